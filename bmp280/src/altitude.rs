@@ -0,0 +1,120 @@
+//! Barometric altitude and sea-level pressure, using the international barometric
+//! formula of the standard atmosphere.
+//!
+//! Pressures are in Pascals as Q24.8 fixed point values, like [`Measurement::pressure`],
+//! and altitudes are in centimetres. The exponentiation is done in single precision
+//! soft-float through `libm`, which is cheap enough for one reading per second
+//! on a Cortex-M0+.
+//!
+//! [`Measurement::pressure`]: crate::Measurement::pressure
+
+/// Mean sea-level pressure of the standard atmosphere (101325 Pa), as Q24.8.
+pub const STANDARD_SEA_LEVEL_PRESSURE: u32 = 101_325 << 8;
+
+/// Height of the standard atmosphere model, in metres.
+const SCALE_HEIGHT: f32 = 44_330.77;
+/// Exponent of the barometric formula.
+const EXPONENT: f32 = 5.255_877;
+
+/// Returns the altitude in centimetres for `pressure`, relative to the reference
+/// `sea_level_pressure` (usually [`STANDARD_SEA_LEVEL_PRESSURE`] or the local QNH).
+///
+/// # Example
+///
+/// ```
+/// use bmp280::altitude::{altitude, STANDARD_SEA_LEVEL_PRESSURE};
+///
+/// // 898.75 hPa is 1000 m in the standard atmosphere.
+/// let cm = altitude(89_875 << 8, STANDARD_SEA_LEVEL_PRESSURE);
+/// assert!((cm - 100_000).abs() < 100);
+/// ```
+pub fn altitude(pressure: u32, sea_level_pressure: u32) -> i32 {
+    if sea_level_pressure == 0 {
+        return 0;
+    }
+
+    let ratio = pressure as f32 / sea_level_pressure as f32;
+    let metres = SCALE_HEIGHT * (1.0 - libm::powf(ratio, 1.0 / EXPONENT));
+    libm::roundf(metres * 100.0) as i32
+}
+
+/// Returns the sea-level pressure, as Q24.8, that corresponds to `pressure`
+/// measured at a known `altitude` in centimetres.
+///
+/// # Example
+///
+/// ```
+/// use bmp280::altitude::sea_level_pressure;
+///
+/// // 898.75 hPa measured at 1000 m is 1013.25 hPa at sea level.
+/// let pa = sea_level_pressure(89_875 << 8, 100_000) >> 8;
+/// assert!((pa as i32 - 101_325).abs() < 5);
+/// ```
+pub fn sea_level_pressure(pressure: u32, altitude: i32) -> u32 {
+    let factor = 1.0 - (altitude as f32 / 100.0) / SCALE_HEIGHT;
+    if factor <= 0.0 {
+        return 0;
+    }
+
+    libm::roundf(pressure as f32 / libm::powf(factor, EXPONENT)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Altitudes in metres and pressures in Pascals of the ICAO standard atmosphere.
+    const STANDARD_ATMOSPHERE: [(i32, u32); 10] = [
+        (-500, 107_478),
+        (0, 101_325),
+        (500, 95_461),
+        (1000, 89_875),
+        (1500, 84_556),
+        (2000, 79_495),
+        (3000, 70_108),
+        (4000, 61_640),
+        (5000, 54_020),
+        (8000, 35_600),
+    ];
+
+    #[test]
+    fn altitude_follows_the_standard_atmosphere() {
+        for (metres, pressure) in STANDARD_ATMOSPHERE {
+            let cm = altitude(pressure << 8, STANDARD_SEA_LEVEL_PRESSURE);
+            assert!((cm - metres * 100).abs() <= 50, "{metres} m: {cm} cm");
+        }
+    }
+
+    #[test]
+    fn sea_level_pressure_follows_the_standard_atmosphere() {
+        for (metres, pressure) in STANDARD_ATMOSPHERE {
+            let pa = (sea_level_pressure(pressure << 8, metres * 100) >> 8) as i32;
+            assert!((pa - 101_325).abs() <= 3, "{metres} m: {pa} Pa");
+        }
+    }
+
+    #[test]
+    fn altitude_decreases_with_the_pressure() {
+        let mut previous = i32::MIN;
+        for pa in (30_000..=110_000).rev().step_by(500) {
+            let cm = altitude(pa << 8, STANDARD_SEA_LEVEL_PRESSURE);
+            assert!(cm > previous);
+            previous = cm;
+        }
+    }
+
+    #[test]
+    fn altitude_is_relative_to_the_reference() {
+        let qnh = 102_000 << 8;
+        assert_eq!(altitude(qnh, qnh), 0);
+        // A higher reference pressure puts the same reading higher
+        let pressure = 95_461 << 8;
+        assert!(altitude(pressure, qnh) > altitude(pressure, STANDARD_SEA_LEVEL_PRESSURE));
+        assert_eq!(altitude(pressure, 0), 0);
+    }
+
+    #[test]
+    fn sea_level_pressure_above_the_atmosphere_is_zero() {
+        assert_eq!(sea_level_pressure(101_325 << 8, 4_500_000), 0);
+    }
+}
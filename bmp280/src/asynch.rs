@@ -0,0 +1,232 @@
+//! Async driver.
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+use embedded_hal_async::spi::SpiDevice;
+
+use crate::interface::{AsyncInterface, I2cInterface, SpiInterface};
+use crate::registers::*;
+use crate::{
+    adc_value, Calibration, Config, Error, Measurement, PowerMode, CALIBRATION_LEN,
+    MAX_STATUS_POLLS, STATUS_POLL_INTERVAL_US,
+};
+
+/// Async BMP280 driver.
+pub struct Bmp280Async<I> {
+    interface: I,
+    calibration: Calibration,
+    config: Config,
+}
+
+impl<SPI> Bmp280Async<SpiInterface<SPI>>
+where
+    SPI: SpiDevice,
+{
+    /// Creates the driver for a sensor on an SPI device.
+    pub async fn new_spi(spi: SPI) -> Result<Self, Error<SPI::Error>> {
+        Self::new(SpiInterface::new(spi)).await
+    }
+}
+
+impl<I2C> Bmp280Async<I2cInterface<I2C>>
+where
+    I2C: I2c,
+{
+    /// Creates the driver for a sensor at `address` on an I2C bus.
+    pub async fn new_i2c(i2c: I2C, address: u8) -> Result<Self, Error<I2C::Error>> {
+        Self::new(I2cInterface::new(i2c, address)).await
+    }
+}
+
+impl<I> Bmp280Async<I>
+where
+    I: AsyncInterface,
+{
+    /// Checks the chip id, reads the calibration block and applies
+    /// the default [`Config`].
+    pub async fn new(interface: I) -> Result<Self, Error<I::Error>> {
+        Self::with_config(interface, Config::default()).await
+    }
+
+    /// Checks the chip id, reads the calibration block and applies `config`.
+    pub async fn with_config(mut interface: I, config: Config) -> Result<Self, Error<I::Error>> {
+        let mut id = [0u8];
+        interface
+            .read_registers(REG_ID, &mut id)
+            .await
+            .map_err(Error::Bus)?;
+        if id[0] != CHIP_ID {
+            return Err(Error::InvalidChipId(id[0]));
+        }
+
+        let mut calib = [0u8; CALIBRATION_LEN];
+        interface
+            .read_registers(REG_CALIB_00, &mut calib)
+            .await
+            .map_err(Error::Bus)?;
+
+        let mut bmp280 = Self {
+            interface,
+            calibration: Calibration::from_bytes(&calib),
+            config,
+        };
+        bmp280.set_config(config).await?;
+
+        Ok(bmp280)
+    }
+
+    /// Returns the active measurement configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Writes a new measurement configuration into the sensor.
+    pub async fn set_config(&mut self, config: Config) -> Result<(), Error<I::Error>> {
+        // Writes to `config` may be ignored in normal mode, so the sensor
+        // is put to sleep first.
+        self.interface
+            .write_register(REG_CTRL_MEAS, config.ctrl_meas(PowerMode::Sleep))
+            .await
+            .map_err(Error::Bus)?;
+        self.interface
+            .write_register(REG_CONFIG, config.config())
+            .await
+            .map_err(Error::Bus)?;
+        if config.mode == PowerMode::Normal {
+            self.interface
+                .write_register(REG_CTRL_MEAS, config.ctrl_meas(PowerMode::Normal))
+                .await
+                .map_err(Error::Bus)?;
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Returns `true` while the sensor is running a conversion.
+    pub async fn is_measuring(&mut self) -> Result<bool, Error<I::Error>> {
+        let mut status = [0u8];
+        self.interface
+            .read_registers(REG_STATUS, &mut status)
+            .await
+            .map_err(Error::Bus)?;
+        Ok(status[0] & STATUS_MEASURING != 0)
+    }
+
+    /// Returns the calibration parameters read from the sensor.
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Reads the pressure and temperature data registers in a single burst
+    /// and returns the compensated values.
+    ///
+    /// In forced mode this triggers a conversion and waits for it to finish with
+    /// `delay`, or returns [`Error::Timeout`] if it does not.
+    pub async fn measure(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<Measurement, Error<I::Error>> {
+        self.wait_for_data(delay).await?;
+        let mut buf = [0u8; 6];
+        self.interface
+            .read_registers(REG_PRESS_MSB, &mut buf)
+            .await
+            .map_err(Error::Bus)?;
+        Ok(self.calibration.measurement(&buf))
+    }
+
+    /// Reads only the temperature, in hundredths of a degree Celsius, like
+    /// [`Bmp280Async::measure`].
+    pub async fn temperature(&mut self, delay: &mut impl DelayNs) -> Result<i32, Error<I::Error>> {
+        self.wait_for_data(delay).await?;
+        let mut buf = [0u8; 3];
+        self.interface
+            .read_registers(REG_TEMP_MSB, &mut buf)
+            .await
+            .map_err(Error::Bus)?;
+        Ok(self.calibration.compensate_temperature(adc_value(&buf)))
+    }
+
+    /// Releases the register interface.
+    pub fn release(self) -> I {
+        self.interface
+    }
+
+    async fn wait_for_data(&mut self, delay: &mut impl DelayNs) -> Result<(), Error<I::Error>> {
+        if self.config.mode != PowerMode::Forced {
+            return Ok(());
+        }
+
+        self.interface
+            .write_register(REG_CTRL_MEAS, self.config.ctrl_meas(PowerMode::Forced))
+            .await
+            .map_err(Error::Bus)?;
+        delay.delay_us(self.config.measurement_time_us()).await;
+        for _ in 0..MAX_STATUS_POLLS {
+            if !self.is_measuring().await? {
+                return Ok(());
+            }
+            delay.delay_us(STATUS_POLL_INTERVAL_US).await;
+        }
+        Err(Error::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{block_on, Bus, Delay};
+    use crate::{Oversampling, MAX_STATUS_POLLS};
+
+    const FORCED: Config = Config {
+        temperature_oversampling: Oversampling::X1,
+        pressure_oversampling: Oversampling::X1,
+        mode: PowerMode::Forced,
+        filter: crate::Filter::Off,
+        standby: crate::Standby::Ms0_5,
+    };
+
+    #[test]
+    fn forced_mode_waits_for_the_measurement() {
+        block_on(async {
+            let mut bus = Bus::datasheet();
+            bus.busy_polls = 1;
+            let mut sensor = Bmp280Async::with_config(SpiInterface::new(bus), FORCED)
+                .await
+                .unwrap();
+            let mut delay = Delay::default();
+            let measurement = sensor.measure(&mut delay).await.unwrap();
+            assert_eq!(measurement.temperature, 2508);
+            assert_eq!(measurement.pressure_pa(), 100653);
+            assert_eq!(delay.waited_ns, (6425 + 500) * 1000);
+            assert_eq!(sensor.release().release().status_polls(), 2);
+        });
+    }
+
+    #[test]
+    fn forced_mode_times_out() {
+        block_on(async {
+            let mut bus = Bus::datasheet();
+            bus.busy_polls = usize::MAX;
+            let mut sensor = Bmp280Async::with_config(SpiInterface::new(bus), FORCED)
+                .await
+                .unwrap();
+            let mut delay = Delay::default();
+            assert_eq!(sensor.measure(&mut delay).await, Err(Error::Timeout));
+            assert_eq!(
+                sensor.release().release().status_polls(),
+                MAX_STATUS_POLLS as usize
+            );
+        });
+    }
+
+    #[test]
+    fn i2c_normal_mode_reads_without_waiting() {
+        block_on(async {
+            let mut sensor = Bmp280Async::new_i2c(Bus::datasheet(), 0x76).await.unwrap();
+            let mut delay = Delay::default();
+            assert_eq!(sensor.temperature(&mut delay).await, Ok(2508));
+            assert_eq!(delay.waited_ns, 0);
+        });
+    }
+}
@@ -0,0 +1,263 @@
+//! Blocking driver.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::SpiDevice;
+
+use crate::interface::{I2cInterface, Interface, SpiInterface};
+use crate::registers::*;
+use crate::{
+    adc_value, Calibration, Config, Error, Measurement, PowerMode, CALIBRATION_LEN,
+    MAX_STATUS_POLLS, STATUS_POLL_INTERVAL_US,
+};
+
+/// Blocking BMP280 driver.
+pub struct Bmp280<I> {
+    interface: I,
+    calibration: Calibration,
+    config: Config,
+}
+
+impl<SPI> Bmp280<SpiInterface<SPI>>
+where
+    SPI: SpiDevice,
+{
+    /// Creates the driver for a sensor on an SPI device.
+    pub fn new_spi(spi: SPI) -> Result<Self, Error<SPI::Error>> {
+        Self::new(SpiInterface::new(spi))
+    }
+}
+
+impl<I2C> Bmp280<I2cInterface<I2C>>
+where
+    I2C: I2c,
+{
+    /// Creates the driver for a sensor at `address` on an I2C bus.
+    pub fn new_i2c(i2c: I2C, address: u8) -> Result<Self, Error<I2C::Error>> {
+        Self::new(I2cInterface::new(i2c, address))
+    }
+}
+
+impl<I> Bmp280<I>
+where
+    I: Interface,
+{
+    /// Checks the chip id, reads the calibration block and applies
+    /// the default [`Config`].
+    pub fn new(interface: I) -> Result<Self, Error<I::Error>> {
+        Self::with_config(interface, Config::default())
+    }
+
+    /// Checks the chip id, reads the calibration block and applies `config`.
+    pub fn with_config(mut interface: I, config: Config) -> Result<Self, Error<I::Error>> {
+        let mut id = [0u8];
+        interface
+            .read_registers(REG_ID, &mut id)
+            .map_err(Error::Bus)?;
+        if id[0] != CHIP_ID {
+            return Err(Error::InvalidChipId(id[0]));
+        }
+
+        let mut calib = [0u8; CALIBRATION_LEN];
+        interface
+            .read_registers(REG_CALIB_00, &mut calib)
+            .map_err(Error::Bus)?;
+
+        let mut bmp280 = Self {
+            interface,
+            calibration: Calibration::from_bytes(&calib),
+            config,
+        };
+        bmp280.set_config(config)?;
+
+        Ok(bmp280)
+    }
+
+    /// Returns the active measurement configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Writes a new measurement configuration into the sensor.
+    pub fn set_config(&mut self, config: Config) -> Result<(), Error<I::Error>> {
+        // Writes to `config` may be ignored in normal mode, so the sensor
+        // is put to sleep first.
+        self.interface
+            .write_register(REG_CTRL_MEAS, config.ctrl_meas(PowerMode::Sleep))
+            .map_err(Error::Bus)?;
+        self.interface
+            .write_register(REG_CONFIG, config.config())
+            .map_err(Error::Bus)?;
+        if config.mode == PowerMode::Normal {
+            self.interface
+                .write_register(REG_CTRL_MEAS, config.ctrl_meas(PowerMode::Normal))
+                .map_err(Error::Bus)?;
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Returns `true` while the sensor is running a conversion.
+    pub fn is_measuring(&mut self) -> Result<bool, Error<I::Error>> {
+        let mut status = [0u8];
+        self.interface
+            .read_registers(REG_STATUS, &mut status)
+            .map_err(Error::Bus)?;
+        Ok(status[0] & STATUS_MEASURING != 0)
+    }
+
+    /// Returns the calibration parameters read from the sensor.
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
+    /// Reads the pressure and temperature data registers in a single burst
+    /// and returns the compensated values.
+    ///
+    /// In forced mode this triggers a conversion and waits for it to finish with
+    /// `delay`, or returns [`Error::Timeout`] if it does not.
+    pub fn measure(&mut self, delay: &mut impl DelayNs) -> Result<Measurement, Error<I::Error>> {
+        self.wait_for_data(delay)?;
+        let mut buf = [0u8; 6];
+        self.interface
+            .read_registers(REG_PRESS_MSB, &mut buf)
+            .map_err(Error::Bus)?;
+        Ok(self.calibration.measurement(&buf))
+    }
+
+    /// Reads only the temperature, in hundredths of a degree Celsius, like
+    /// [`Bmp280::measure`].
+    pub fn temperature(&mut self, delay: &mut impl DelayNs) -> Result<i32, Error<I::Error>> {
+        self.wait_for_data(delay)?;
+        let mut buf = [0u8; 3];
+        self.interface
+            .read_registers(REG_TEMP_MSB, &mut buf)
+            .map_err(Error::Bus)?;
+        Ok(self.calibration.compensate_temperature(adc_value(&buf)))
+    }
+
+    /// Releases the register interface.
+    pub fn release(self) -> I {
+        self.interface
+    }
+
+    fn wait_for_data(&mut self, delay: &mut impl DelayNs) -> Result<(), Error<I::Error>> {
+        if self.config.mode != PowerMode::Forced {
+            return Ok(());
+        }
+
+        self.interface
+            .write_register(REG_CTRL_MEAS, self.config.ctrl_meas(PowerMode::Forced))
+            .map_err(Error::Bus)?;
+        delay.delay_us(self.config.measurement_time_us());
+        for _ in 0..MAX_STATUS_POLLS {
+            if !self.is_measuring()? {
+                return Ok(());
+            }
+            delay.delay_us(STATUS_POLL_INTERVAL_US);
+        }
+        Err(Error::Timeout)
+    }
+}
+
+#[cfg(test)]
+// The bits of `ctrl_meas` are grouped by field: osrs_t, osrs_p and mode
+#[allow(clippy::unusual_byte_groupings)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::*;
+    use crate::mock::{Bus, Delay, Transaction, Transfer};
+    use crate::{Oversampling, MAX_STATUS_POLLS};
+
+    const FORCED: Config = Config {
+        temperature_oversampling: Oversampling::X1,
+        pressure_oversampling: Oversampling::X1,
+        mode: PowerMode::Forced,
+        filter: crate::Filter::Off,
+        standby: crate::Standby::Ms0_5,
+    };
+
+    const DATASHEET: Measurement = Measurement {
+        temperature: 2508,
+        pressure: 25767233,
+    };
+
+    #[test]
+    fn configures_the_sensor_on_init() {
+        let sensor = Bmp280::new_spi(Bus::datasheet()).unwrap();
+        let transactions = sensor.release().release().transactions;
+        assert_eq!(transactions.len(), 5);
+        assert_eq!(
+            transactions[0],
+            Transaction::Spi(vec![
+                Transfer::Write(vec![0xD0]),
+                Transfer::Read(vec![0x58])
+            ])
+        );
+        assert_eq!(
+            transactions[2..],
+            [
+                // Asleep, while the config is written
+                Transaction::Spi(vec![Transfer::Write(vec![0x74, 0b001_001_00])]),
+                Transaction::Spi(vec![Transfer::Write(vec![0x75, 0])]),
+                Transaction::Spi(vec![Transfer::Write(vec![0x74, 0b001_001_11])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_another_chip() {
+        let mut bus = Bus::datasheet();
+        bus.registers[REG_ID as usize] = 0x60;
+        assert!(matches!(
+            Bmp280::new_spi(bus),
+            Err(Error::InvalidChipId(0x60))
+        ));
+    }
+
+    #[test]
+    fn normal_mode_reads_without_waiting() {
+        let mut sensor = Bmp280::new_spi(Bus::datasheet()).unwrap();
+        let mut delay = Delay::default();
+        assert_eq!(sensor.measure(&mut delay), Ok(DATASHEET));
+        assert_eq!(sensor.temperature(&mut delay), Ok(2508));
+        assert_eq!(delay.waited_ns, 0);
+        assert_eq!(sensor.release().release().status_polls(), 0);
+    }
+
+    #[test]
+    fn forced_mode_waits_for_the_measurement() {
+        let mut bus = Bus::datasheet();
+        bus.busy_polls = 2;
+        let mut sensor = Bmp280::with_config(SpiInterface::new(bus), FORCED).unwrap();
+        let mut delay = Delay::default();
+        assert_eq!(sensor.measure(&mut delay), Ok(DATASHEET));
+        // The measurement time, then two polls that find it measuring
+        assert_eq!(delay.waited_ns, (6425 + 2 * 500) * 1000);
+        let bus = sensor.release().release();
+        assert_eq!(bus.status_polls(), 3);
+        assert!(bus
+            .transactions
+            .contains(&Transaction::Spi(vec![Transfer::Write(vec![
+                0x74,
+                0b001_001_01
+            ])])));
+    }
+
+    #[test]
+    fn forced_mode_times_out() {
+        let mut bus = Bus::datasheet();
+        bus.busy_polls = usize::MAX;
+        let mut sensor = Bmp280::with_config(SpiInterface::new(bus), FORCED).unwrap();
+        let mut delay = Delay::default();
+        assert_eq!(sensor.measure(&mut delay), Err(Error::Timeout));
+        assert_eq!(sensor.temperature(&mut delay), Err(Error::Timeout));
+        assert_eq!(
+            sensor.release().release().status_polls(),
+            2 * MAX_STATUS_POLLS as usize
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Factory calibration data and the compensation formulas from the BMP280 datasheet
+//! (section 3.11.3 and 8.2).
+
+use crate::{adc_value, Measurement};
+
+/// Length of the calibration block starting at [`REG_CALIB_00`](crate::registers::REG_CALIB_00).
+pub const CALIBRATION_LEN: usize = 24;
+
+/// The `dig_T1`..`dig_T3` and `dig_P1`..`dig_P9` trimming parameters that are written into
+/// the sensor's non-volatile memory during production.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub dig_t1: u16,
+    pub dig_t2: i16,
+    pub dig_t3: i16,
+    pub dig_p1: u16,
+    pub dig_p2: i16,
+    pub dig_p3: i16,
+    pub dig_p4: i16,
+    pub dig_p5: i16,
+    pub dig_p6: i16,
+    pub dig_p7: i16,
+    pub dig_p8: i16,
+    pub dig_p9: i16,
+}
+
+impl Calibration {
+    /// Parses the calibration block read from registers `0x88`..`0x9F`.
+    ///
+    /// Every parameter is stored as a little endian 16 bit word.
+    pub fn from_bytes(buf: &[u8; CALIBRATION_LEN]) -> Self {
+        let u = |i: usize| u16::from_le_bytes([buf[i], buf[i + 1]]);
+        let s = |i: usize| i16::from_le_bytes([buf[i], buf[i + 1]]);
+
+        Self {
+            dig_t1: u(0),
+            dig_t2: s(2),
+            dig_t3: s(4),
+            dig_p1: u(6),
+            dig_p2: s(8),
+            dig_p3: s(10),
+            dig_p4: s(12),
+            dig_p5: s(14),
+            dig_p6: s(16),
+            dig_p7: s(18),
+            dig_p8: s(20),
+            dig_p9: s(22),
+        }
+    }
+
+    /// Compensates the six data registers read in a single burst starting at
+    /// [`REG_PRESS_MSB`](crate::registers::REG_PRESS_MSB).
+    pub fn measurement(&self, data: &[u8; 6]) -> Measurement {
+        let t_fine = self.t_fine(adc_value(&data[3..]));
+        Measurement {
+            temperature: temperature_from_t_fine(t_fine),
+            pressure: self.compensate_pressure(adc_value(&data[..3]), t_fine),
+        }
+    }
+
+    /// Computes the `t_fine` value that carries the fine temperature into
+    /// the pressure compensation.
+    pub fn t_fine(&self, adc_t: u32) -> i32 {
+        let adc_t = adc_t as i32;
+        let t1 = self.dig_t1 as i32;
+        let t2 = self.dig_t2 as i32;
+        let t3 = self.dig_t3 as i32;
+
+        let var1 = (((adc_t >> 3) - (t1 << 1)) * t2) >> 11;
+        let var2 = (((((adc_t >> 4) - t1) * ((adc_t >> 4) - t1)) >> 12) * t3) >> 14;
+        var1 + var2
+    }
+
+    /// Returns the temperature in hundredths of a degree Celsius
+    /// (a value of `5123` means 51.23 °C).
+    ///
+    /// # Example
+    ///
+    /// Using the worked example from the datasheet:
+    ///
+    /// ```
+    /// # let calibration = bmp280::Calibration {
+    /// #     dig_t1: 27504, dig_t2: 26435, dig_t3: -1000,
+    /// #     dig_p1: 36477, dig_p2: -10685, dig_p3: 3024, dig_p4: 2855, dig_p5: 140,
+    /// #     dig_p6: -7, dig_p7: 15500, dig_p8: -14600, dig_p9: 6000,
+    /// # };
+    /// assert_eq!(calibration.compensate_temperature(519888), 2508);
+    /// ```
+    pub fn compensate_temperature(&self, adc_t: u32) -> i32 {
+        temperature_from_t_fine(self.t_fine(adc_t))
+    }
+
+    /// Returns the pressure in Pascals as an unsigned Q24.8 fixed point value
+    /// (a value of `24674867` means 24674867 / 256 = 96386.2 Pa).
+    ///
+    /// `t_fine` is the value returned by [`Calibration::t_fine`] for the temperature
+    /// sampled together with this pressure.
+    ///
+    /// # Example
+    ///
+    /// Using the worked example from the datasheet:
+    ///
+    /// ```
+    /// # let calibration = bmp280::Calibration {
+    /// #     dig_t1: 27504, dig_t2: 26435, dig_t3: -1000,
+    /// #     dig_p1: 36477, dig_p2: -10685, dig_p3: 3024, dig_p4: 2855, dig_p5: 140,
+    /// #     dig_p6: -7, dig_p7: 15500, dig_p8: -14600, dig_p9: 6000,
+    /// # };
+    /// let t_fine = calibration.t_fine(519888);
+    /// assert_eq!(t_fine, 128422);
+    /// assert_eq!(calibration.compensate_pressure(415148, t_fine) / 256, 100653);
+    /// ```
+    pub fn compensate_pressure(&self, adc_p: u32, t_fine: i32) -> u32 {
+        let p1 = self.dig_p1 as i64;
+        let p2 = self.dig_p2 as i64;
+        let p3 = self.dig_p3 as i64;
+        let p4 = self.dig_p4 as i64;
+        let p5 = self.dig_p5 as i64;
+        let p6 = self.dig_p6 as i64;
+        let p7 = self.dig_p7 as i64;
+        let p8 = self.dig_p8 as i64;
+        let p9 = self.dig_p9 as i64;
+
+        let mut var1 = t_fine as i64 - 128000;
+        let mut var2 = var1 * var1 * p6;
+        var2 += (var1 * p5) << 17;
+        var2 += p4 << 35;
+        var1 = ((var1 * var1 * p3) >> 8) + ((var1 * p2) << 12);
+        var1 = (((1i64 << 47) + var1) * p1) >> 33;
+        if var1 == 0 {
+            // Avoid a division by zero when the calibration block is blank.
+            return 0;
+        }
+
+        let mut p = 1048576 - adc_p as i64;
+        p = (((p << 31) - var2) * 3125) / var1;
+        var1 = (p9 * (p >> 13) * (p >> 13)) >> 25;
+        var2 = (p8 * p) >> 19;
+        p = ((p + var1 + var2) >> 8) + (p7 << 4);
+        p as u32
+    }
+}
+
+/// Converts a `t_fine` value into hundredths of a degree Celsius.
+pub fn temperature_from_t_fine(t_fine: i32) -> i32 {
+    (t_fine * 5 + 128) >> 8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The calibration of the worked example of the datasheet (section 3.12).
+    const DATASHEET: Calibration = Calibration {
+        dig_t1: 27504,
+        dig_t2: 26435,
+        dig_t3: -1000,
+        dig_p1: 36477,
+        dig_p2: -10685,
+        dig_p3: 3024,
+        dig_p4: 2855,
+        dig_p5: 140,
+        dig_p6: -7,
+        dig_p7: 15500,
+        dig_p8: -14600,
+        dig_p9: 6000,
+    };
+
+    const SENSOR_B: Calibration = Calibration {
+        dig_t1: 28009,
+        dig_t2: 25654,
+        dig_t3: 50,
+        dig_p1: 39145,
+        dig_p2: -10750,
+        dig_p3: 3024,
+        dig_p4: 5667,
+        dig_p5: -120,
+        dig_p6: -7,
+        dig_p7: 15500,
+        dig_p8: -14600,
+        dig_p9: 6000,
+    };
+
+    const SENSOR_C: Calibration = Calibration {
+        dig_t1: 27820,
+        dig_t2: 26474,
+        dig_t3: -1000,
+        dig_p1: 36883,
+        dig_p2: -10562,
+        dig_p3: 3024,
+        dig_p4: 3907,
+        dig_p5: -45,
+        dig_p6: -7,
+        dig_p7: 15500,
+        dig_p8: -14600,
+        dig_p9: 6000,
+    };
+
+    /// Calibration, `adc_T`, `adc_P`, the integer results, and the results of the
+    /// double precision formulas of the datasheet (section 8.1), in hundredths of a
+    /// degree and Pascals.
+    #[rustfmt::skip]
+    const REFERENCE: [(Calibration, u32, u32, i32, u32, f64, f64); 11] = [
+        (DATASHEET, 519888, 415148, 2508, 25767233, 2508.25, 100653.27),
+        (DATASHEET, 470000, 380000, 942, 26667740, 942.35, 104170.91),
+        (DATASHEET, 420000, 450000, -633, 23081612, -632.73, 90162.58),
+        (SENSOR_B, 519888, 415148, 2194, 22036920, 2194.36, 86081.76),
+        (SENSOR_B, 470000, 380000, 668, 22873245, 668.43, 89348.63),
+        (SENSOR_B, 560000, 300000, 3421, 27299466, 3421.49, 106638.58),
+        (SENSOR_B, 420000, 450000, -861, 19572266, -860.65, 76454.18),
+        (SENSOR_B, 600000, 350000, 4645, 25723430, 4645.36, 100482.17),
+        (SENSOR_C, 519888, 415148, 2353, 24684883, 2353.28, 96425.36),
+        (SENSOR_C, 470000, 380000, 784, 25556820, 784.50, 99831.37),
+        (SENSOR_C, 420000, 450000, -794, 22008975, -793.49, 85972.60),
+    ];
+
+    const ADC_T: u32 = 519888;
+    const ADC_P: u32 = 415148;
+
+    #[test]
+    fn parses_the_little_endian_block() {
+        let words: [u16; 12] = [
+            27504,
+            26435,
+            -1000i16 as u16,
+            36477,
+            -10685i16 as u16,
+            3024,
+            2855,
+            140,
+            -7i16 as u16,
+            15500,
+            -14600i16 as u16,
+            6000,
+        ];
+        let mut block = [0; CALIBRATION_LEN];
+        for (bytes, word) in block.chunks_exact_mut(2).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        assert_eq!(Calibration::from_bytes(&block), DATASHEET);
+    }
+
+    #[test]
+    fn compensates_the_datasheet_temperature() {
+        assert_eq!(DATASHEET.t_fine(ADC_T), 128422);
+        // 25.08 °C
+        assert_eq!(DATASHEET.compensate_temperature(ADC_T), 2508);
+    }
+
+    #[test]
+    fn compensates_the_datasheet_pressure() {
+        let pressure = DATASHEET.compensate_pressure(ADC_P, DATASHEET.t_fine(ADC_T));
+        assert_eq!(pressure, 25767233);
+        // 100653.27 Pa with the floating point formulas
+        assert!((pressure as i64 - 25767236).abs() < 256);
+    }
+
+    #[test]
+    fn compensates_the_reference_table() {
+        for (calibration, adc_t, adc_p, temperature, pressure, reference_t, reference_p) in
+            REFERENCE
+        {
+            let t_fine = calibration.t_fine(adc_t);
+            assert_eq!(temperature_from_t_fine(t_fine), temperature);
+            assert_eq!(calibration.compensate_pressure(adc_p, t_fine), pressure);
+            // The integer formulas are within a hundredth of a degree and a Pascal
+            assert!((temperature as f64 - reference_t).abs() <= 1.0);
+            assert!((pressure as f64 / 256.0 - reference_p).abs() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn compensates_a_data_burst() {
+        // press_msb, press_lsb, press_xlsb, temp_msb, temp_lsb, temp_xlsb
+        let data = [0x65, 0x5A, 0xC0, 0x7E, 0xED, 0x00];
+        assert_eq!(adc_value(&data[..3]), ADC_P);
+        assert_eq!(adc_value(&data[3..]), ADC_T);
+        assert_eq!(
+            DATASHEET.measurement(&data),
+            Measurement {
+                temperature: 2508,
+                pressure: 25767233,
+            }
+        );
+    }
+
+    #[test]
+    fn blank_calibration_does_not_divide_by_zero() {
+        let blank = Calibration::from_bytes(&[0; CALIBRATION_LEN]);
+        assert_eq!(blank.compensate_pressure(ADC_P, blank.t_fine(ADC_T)), 0);
+    }
+
+    #[test]
+    fn rounds_t_fine_to_hundredths() {
+        assert_eq!(temperature_from_t_fine(0), 0);
+        assert_eq!(temperature_from_t_fine(512), 10);
+        // 5120 is 1 °C
+        assert_eq!(temperature_from_t_fine(5120 * 25), 2500);
+        assert_eq!(temperature_from_t_fine(-5120 * 10), -1000);
+    }
+}
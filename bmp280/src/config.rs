@@ -0,0 +1,149 @@
+//! Measurement configuration written into the `ctrl_meas` (0xF4) and `config` (0xF5)
+//! registers (datasheet section 3.3 to 3.6).
+
+/// Oversampling of a temperature or pressure measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Oversampling {
+    /// The measurement is skipped and its data registers read `0x80000`.
+    Skipped = 0b000,
+    #[default]
+    X1 = 0b001,
+    X2 = 0b010,
+    X4 = 0b011,
+    X8 = 0b100,
+    X16 = 0b101,
+}
+
+/// Power mode of the sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerMode {
+    /// No measurements are performed, lowest power consumption.
+    Sleep = 0b00,
+    /// A single measurement is performed, then the sensor returns to sleep mode.
+    Forced = 0b01,
+    /// Measurements are performed continuously, separated by the standby time.
+    #[default]
+    Normal = 0b11,
+}
+
+/// Coefficient of the IIR filter applied to the pressure and temperature readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    #[default]
+    Off = 0b000,
+    X2 = 0b001,
+    X4 = 0b010,
+    X8 = 0b011,
+    X16 = 0b100,
+}
+
+/// Inactive time between two measurements in normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Standby {
+    #[default]
+    Ms0_5 = 0b000,
+    Ms62_5 = 0b001,
+    Ms125 = 0b010,
+    Ms250 = 0b011,
+    Ms500 = 0b100,
+    Ms1000 = 0b101,
+    Ms2000 = 0b110,
+    Ms4000 = 0b111,
+}
+
+/// Measurement configuration of the sensor.
+///
+/// The default configuration samples temperature and pressure once
+/// in normal mode, with the filter off and the shortest standby time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Config {
+    pub temperature_oversampling: Oversampling,
+    pub pressure_oversampling: Oversampling,
+    pub mode: PowerMode,
+    pub filter: Filter,
+    pub standby: Standby,
+}
+
+impl Config {
+    /// Returns the value of the `ctrl_meas` register for this configuration and `mode`.
+    pub fn ctrl_meas(&self, mode: PowerMode) -> u8 {
+        ((self.temperature_oversampling as u8) << 5)
+            | ((self.pressure_oversampling as u8) << 2)
+            | mode as u8
+    }
+
+    /// Returns the longest time of a measurement in forced mode with this
+    /// oversampling, in microseconds (datasheet appendix B, `t_measure,max`).
+    pub fn measurement_time_us(&self) -> u32 {
+        let samples = |oversampling: Oversampling| match oversampling {
+            Oversampling::Skipped => 0,
+            Oversampling::X1 => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+            Oversampling::X8 => 8,
+            Oversampling::X16 => 16,
+        };
+        let temperature = samples(self.temperature_oversampling);
+        let pressure = samples(self.pressure_oversampling);
+        let mut time = 1250 + 2300 * temperature;
+        if pressure > 0 {
+            time += 2300 * pressure + 575;
+        }
+        time
+    }
+
+    /// Returns the value of the `config` register for this configuration.
+    ///
+    /// The 3-wire SPI interface is always disabled.
+    pub fn config(&self) -> u8 {
+        ((self.standby as u8) << 5) | ((self.filter as u8) << 2)
+    }
+}
+
+#[cfg(test)]
+// The bits of `ctrl_meas` are grouped by field: osrs_t, osrs_p and mode
+#[allow(clippy::unusual_byte_groupings)]
+mod tests {
+    use super::*;
+
+    fn config(temperature: Oversampling, pressure: Oversampling) -> Config {
+        Config {
+            temperature_oversampling: temperature,
+            pressure_oversampling: pressure,
+            mode: PowerMode::Forced,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn packs_the_registers() {
+        let config = Config {
+            filter: Filter::X16,
+            standby: Standby::Ms1000,
+            ..config(Oversampling::X2, Oversampling::X16)
+        };
+        assert_eq!(config.ctrl_meas(PowerMode::Forced), 0b010_101_01);
+        assert_eq!(config.ctrl_meas(PowerMode::Sleep), 0b010_101_00);
+        assert_eq!(config.config(), 0b101_100_00);
+        assert_eq!(Config::default().ctrl_meas(PowerMode::Normal), 0b001_001_11);
+    }
+
+    #[test]
+    fn measurement_time_follows_the_datasheet() {
+        // Ultra low power, 6.4 ms
+        assert_eq!(
+            config(Oversampling::X1, Oversampling::X1).measurement_time_us(),
+            6425
+        );
+        // Ultra high resolution, 43.2 ms
+        assert_eq!(
+            config(Oversampling::X2, Oversampling::X16).measurement_time_us(),
+            43_225
+        );
+        // Temperature only
+        assert_eq!(
+            config(Oversampling::X1, Oversampling::Skipped).measurement_time_us(),
+            3550
+        );
+    }
+}
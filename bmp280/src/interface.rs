@@ -0,0 +1,282 @@
+//! Register access over the buses supported by the BMP280.
+//!
+//! The same driver works on SPI and I2C, both blocking and async. The SPI
+//! interface sets the MSB of the register address for reads and clears it for writes,
+//! while the I2C interface addresses the sensor with its 7 bit slave address.
+
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+use embedded_hal_async::spi::{Operation as AsyncOperation, SpiDevice as AsyncSpiDevice};
+
+/// I2C address when the `SDO` pin is connected to `GND`.
+pub const PRIMARY_ADDRESS: u8 = 0x76;
+/// I2C address when the `SDO` pin is connected to `VDDIO`.
+pub const SECONDARY_ADDRESS: u8 = 0x77;
+
+/// Blocking register access.
+pub trait Interface {
+    type Error;
+
+    /// Reads consecutive registers starting at `reg` into `buf`.
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `value` into `reg`.
+    fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error>;
+}
+
+/// Async register access.
+#[allow(async_fn_in_trait)]
+pub trait AsyncInterface {
+    type Error;
+
+    /// Reads consecutive registers starting at `reg` into `buf`.
+    async fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `value` into `reg`.
+    async fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error>;
+}
+
+/// Register access over an SPI device.
+///
+/// The chip select is handled by the `SpiDevice`, so the sensor can share the bus
+/// with other devices (for example through `SpiDeviceWithConfig`).
+pub struct SpiInterface<SPI> {
+    spi: SPI,
+}
+
+impl<SPI> SpiInterface<SPI> {
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    /// Releases the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI> Interface for SpiInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    type Error = SPI::Error;
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        // The MSB of the control byte is 1 for reads.
+        self.spi
+            .transaction(&mut [Operation::Write(&[reg | 0x80]), Operation::Read(buf)])
+    }
+
+    fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error> {
+        // The MSB of the control byte is 0 for writes.
+        self.spi.write(&[reg & 0x7F, value])
+    }
+}
+
+impl<SPI> AsyncInterface for SpiInterface<SPI>
+where
+    SPI: AsyncSpiDevice,
+{
+    type Error = SPI::Error;
+
+    async fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.spi
+            .transaction(&mut [
+                AsyncOperation::Write(&[reg | 0x80]),
+                AsyncOperation::Read(buf),
+            ])
+            .await
+    }
+
+    async fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.spi.write(&[reg & 0x7F, value]).await
+    }
+}
+
+/// Register access over an I2C bus.
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    /// Creates the interface for the sensor at `address`, usually
+    /// [`PRIMARY_ADDRESS`] or [`SECONDARY_ADDRESS`].
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Releases the I2C bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C> Interface for I2cInterface<I2C>
+where
+    I2C: I2c,
+{
+    type Error = I2C::Error;
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[reg], buf)
+    }
+
+    fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[reg, value])
+    }
+}
+
+impl<I2C> AsyncInterface for I2cInterface<I2C>
+where
+    I2C: AsyncI2c,
+{
+    type Error = I2C::Error;
+
+    async fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[reg], buf).await
+    }
+
+    async fn write_register(&mut self, reg: u8, value: u8) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[reg, value]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::*;
+    use crate::mock::{block_on, Bus, Transaction, Transfer};
+    use crate::registers::{REG_CTRL_MEAS, REG_ID, REG_PRESS_MSB};
+
+    fn sensor() -> Bus {
+        let mut bus = Bus::new();
+        bus.registers[REG_ID as usize] = 0x58;
+        bus.registers[REG_PRESS_MSB as usize..][..6].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        bus
+    }
+
+    #[test]
+    fn spi_reads_with_the_read_bit() {
+        let mut spi = SpiInterface::new(sensor());
+        let mut buf = [0; 6];
+        Interface::read_registers(&mut spi, REG_PRESS_MSB, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            spi.release().transactions,
+            [Transaction::Spi(vec![
+                Transfer::Write(vec![0xF7]),
+                Transfer::Read(vec![1, 2, 3, 4, 5, 6]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn spi_writes_without_the_read_bit() {
+        let mut spi = SpiInterface::new(sensor());
+        Interface::write_register(&mut spi, REG_CTRL_MEAS, 0x27).unwrap();
+        let bus = spi.release();
+        assert_eq!(
+            bus.transactions,
+            [Transaction::Spi(vec![Transfer::Write(vec![0x74, 0x27])])]
+        );
+        assert_eq!(bus.registers[REG_CTRL_MEAS as usize], 0x27);
+    }
+
+    #[test]
+    fn spi_clears_the_read_bit_of_any_register() {
+        let mut spi = SpiInterface::new(sensor());
+        Interface::write_register(&mut spi, 0xFF, 0x01).unwrap();
+        Interface::read_registers(&mut spi, 0x00, &mut [0]).unwrap();
+        assert_eq!(
+            spi.release().transactions,
+            [
+                Transaction::Spi(vec![Transfer::Write(vec![0x7F, 0x01])]),
+                Transaction::Spi(vec![Transfer::Write(vec![0x80]), Transfer::Read(vec![0])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn i2c_reads_with_a_register_write() {
+        let mut i2c = I2cInterface::new(sensor(), PRIMARY_ADDRESS);
+        let mut id = [0];
+        Interface::read_registers(&mut i2c, REG_ID, &mut id).unwrap();
+        assert_eq!(id, [0x58]);
+        assert_eq!(
+            i2c.release().transactions,
+            [Transaction::I2c(
+                0x76,
+                vec![Transfer::Write(vec![0xD0]), Transfer::Read(vec![0x58])]
+            )]
+        );
+    }
+
+    #[test]
+    fn i2c_writes_the_register_and_value() {
+        let mut i2c = I2cInterface::new(sensor(), SECONDARY_ADDRESS);
+        Interface::write_register(&mut i2c, REG_CTRL_MEAS, 0x27).unwrap();
+        let bus = i2c.release();
+        assert_eq!(
+            bus.transactions,
+            [Transaction::I2c(
+                0x77,
+                vec![Transfer::Write(vec![0xF4, 0x27])]
+            )]
+        );
+        assert_eq!(bus.registers[REG_CTRL_MEAS as usize], 0x27);
+    }
+
+    /// Reads the data registers and writes `ctrl_meas`, with the blocking and the
+    /// async interface.
+    fn both<I: Interface>(interface: &mut I) -> [u8; 6] {
+        let mut buf = [0; 6];
+        interface
+            .read_registers(REG_PRESS_MSB, &mut buf)
+            .ok()
+            .unwrap();
+        interface.write_register(REG_CTRL_MEAS, 0x27).ok().unwrap();
+        buf
+    }
+
+    async fn both_async<I: AsyncInterface>(interface: &mut I) -> [u8; 6] {
+        let mut buf = [0; 6];
+        interface
+            .read_registers(REG_PRESS_MSB, &mut buf)
+            .await
+            .ok()
+            .unwrap();
+        interface
+            .write_register(REG_CTRL_MEAS, 0x27)
+            .await
+            .ok()
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn async_spi_writes_the_same_bytes() {
+        let mut blocking = SpiInterface::new(sensor());
+        let mut asynch = SpiInterface::new(sensor());
+        assert_eq!(both(&mut blocking), block_on(both_async(&mut asynch)));
+        assert_eq!(
+            blocking.release().transactions,
+            asynch.release().transactions
+        );
+    }
+
+    #[test]
+    fn async_i2c_writes_the_same_bytes() {
+        let mut blocking = I2cInterface::new(sensor(), PRIMARY_ADDRESS);
+        let mut asynch = I2cInterface::new(sensor(), PRIMARY_ADDRESS);
+        assert_eq!(both(&mut blocking), block_on(both_async(&mut asynch)));
+        assert_eq!(
+            blocking.release().transactions,
+            asynch.release().transactions
+        );
+    }
+}
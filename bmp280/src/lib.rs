@@ -0,0 +1,76 @@
+//! Driver for the Bosch **BMP280** temperature and pressure sensor.
+//!
+//! The driver reads the factory calibration block when it is created and uses it
+//! to turn the raw ADC values into compensated readings:
+//! - temperature in hundredths of a degree Celsius;
+//! - pressure in Pascals, as a Q24.8 fixed point value.
+//!
+//! Register access goes through an [`Interface`] (blocking) or [`AsyncInterface`],
+//! implemented for SPI devices and I2C buses from both `embedded-hal` and
+//! `embedded-hal-async`. [`Bmp280`] is the blocking driver and [`Bmp280Async`] the
+//! async one.
+//!
+//! The oversampling, power mode, IIR filter and standby time are set through a
+//! [`Config`]. In [`PowerMode::Forced`] every read triggers a single conversion,
+//! waits for its measurement time and for the `measuring` bit of the status register
+//! to clear, which keeps the sensor asleep between readings.
+//!
+//! The [`altitude`] and [`trend`] modules derive the altitude, the sea-level pressure
+//! and the barometric tendency from the compensated pressure.
+
+#![no_std]
+
+pub mod altitude;
+mod asynch;
+mod blocking;
+mod calibration;
+pub mod config;
+pub mod interface;
+#[cfg(test)]
+mod mock;
+pub mod registers;
+pub mod trend;
+
+pub use asynch::Bmp280Async;
+pub use blocking::Bmp280;
+pub use calibration::{temperature_from_t_fine, Calibration, CALIBRATION_LEN};
+pub use config::{Config, Filter, Oversampling, PowerMode, Standby};
+pub use interface::{AsyncInterface, I2cInterface, Interface, SpiInterface};
+
+/// Errors returned by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The underlying bus returned an error.
+    Bus(E),
+    /// The device did not answer with the BMP280 chip id.
+    InvalidChipId(u8),
+    /// The `measuring` bit of a forced measurement did not clear.
+    Timeout,
+}
+
+/// Number of reads of the status register after the measurement time of a forced
+/// measurement, before giving up with [`Error::Timeout`].
+const MAX_STATUS_POLLS: u32 = 10;
+/// Time between two reads of the status register, in microseconds.
+const STATUS_POLL_INTERVAL_US: u32 = 500;
+
+/// A compensated temperature and pressure reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    /// Temperature in hundredths of a degree Celsius.
+    pub temperature: i32,
+    /// Pressure in Pascals, as a Q24.8 fixed point value.
+    pub pressure: u32,
+}
+
+impl Measurement {
+    /// Returns the pressure in whole Pascals.
+    pub fn pressure_pa(&self) -> u32 {
+        self.pressure >> 8
+    }
+}
+
+/// Combines the `msb`, `lsb` and `xlsb` data registers into a 20 bit ADC value.
+pub fn adc_value(buf: &[u8]) -> u32 {
+    ((buf[0] as u32) << 12) | ((buf[1] as u32) << 4) | ((buf[2] as u32) >> 4)
+}
@@ -0,0 +1,222 @@
+//! A simulated BMP280 for the tests, on a bus that records every transaction.
+
+extern crate std;
+
+use core::convert::Infallible;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::vec::Vec;
+
+use embedded_hal::{i2c, spi};
+
+use crate::registers::{
+    CHIP_ID, REG_CALIB_00, REG_ID, REG_PRESS_MSB, REG_STATUS, STATUS_MEASURING,
+};
+
+/// A transfer of a transaction, with the bytes written or read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transfer {
+    Write(Vec<u8>),
+    Read(Vec<u8>),
+}
+
+/// A transaction on the bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transaction {
+    I2c(u8, Vec<Transfer>),
+    Spi(Vec<Transfer>),
+}
+
+/// The registers of a sensor, behind an I2C or SPI bus.
+///
+/// Reads auto-increment the register address, like the sensor does.
+pub struct Bus {
+    pub registers: [u8; 256],
+    /// Number of reads of the status register that still have the `measuring` bit.
+    pub busy_polls: usize,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self {
+            registers: [0; 256],
+            busy_polls: 0,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Returns the sensor of the worked example of the datasheet, 25.08 °C and
+    /// 100653 Pa.
+    pub fn datasheet() -> Self {
+        let mut bus = Self::new();
+        bus.registers[REG_ID as usize] = CHIP_ID;
+        let calibration: [i32; 12] = [
+            27504, 26435, -1000, 36477, -10685, 3024, 2855, 140, -7, 15500, -14600, 6000,
+        ];
+        for (i, word) in calibration.iter().enumerate() {
+            let reg = REG_CALIB_00 as usize + 2 * i;
+            bus.registers[reg..reg + 2].copy_from_slice(&(*word as u16).to_le_bytes());
+        }
+        bus.registers[REG_PRESS_MSB as usize..][..6]
+            .copy_from_slice(&[0x65, 0x5A, 0xC0, 0x7E, 0xED, 0x00]);
+        bus
+    }
+
+    /// Returns the number of reads of the status register.
+    pub fn status_polls(&self) -> usize {
+        let status = Transfer::Write(std::vec![REG_STATUS | 0x80]);
+        self.transactions
+            .iter()
+            .filter(|transaction| matches!(transaction, Transaction::Spi(transfers) if transfers[0] == status))
+            .count()
+    }
+
+    fn read(&mut self, reg: &mut u8, buf: &mut [u8]) -> Transfer {
+        for byte in buf.iter_mut() {
+            *byte = self.registers[*reg as usize];
+            if *reg == REG_STATUS && self.busy_polls > 0 {
+                self.busy_polls -= 1;
+                *byte |= STATUS_MEASURING;
+            }
+            *reg = reg.wrapping_add(1);
+        }
+        Transfer::Read(buf.to_vec())
+    }
+
+    fn i2c(&mut self, address: u8, operations: &mut [i2c::Operation<'_>]) {
+        let mut reg = 0;
+        let mut transfers = Vec::new();
+        for operation in operations {
+            match operation {
+                i2c::Operation::Write(bytes) => {
+                    // Register address and data pairs
+                    for pair in bytes.chunks(2) {
+                        reg = pair[0];
+                        if let Some(&value) = pair.get(1) {
+                            self.registers[reg as usize] = value;
+                        }
+                    }
+                    transfers.push(Transfer::Write(bytes.to_vec()));
+                }
+                i2c::Operation::Read(buf) => transfers.push(self.read(&mut reg, buf)),
+            }
+        }
+        self.transactions.push(Transaction::I2c(address, transfers));
+    }
+
+    fn spi(&mut self, operations: &mut [spi::Operation<'_, u8>]) {
+        // The register being read, set by a control byte with the read bit
+        let mut reading = None;
+        let mut transfers = Vec::new();
+        for operation in operations {
+            match operation {
+                spi::Operation::Write([control]) if control & 0x80 != 0 => {
+                    reading = Some(*control);
+                    transfers.push(Transfer::Write(std::vec![*control]));
+                }
+                spi::Operation::Write(bytes) => {
+                    // Control byte, without the read bit, and data pairs
+                    for pair in bytes.chunks_exact(2) {
+                        self.registers[(pair[0] | 0x80) as usize] = pair[1];
+                    }
+                    transfers.push(Transfer::Write(bytes.to_vec()));
+                }
+                spi::Operation::Read(buf) => {
+                    let reg = reading.as_mut().expect("read without a control byte");
+                    transfers.push(self.read(reg, buf));
+                }
+                _ => unimplemented!("the sensor only writes and reads"),
+            }
+        }
+        self.transactions.push(Transaction::Spi(transfers));
+    }
+}
+
+impl i2c::ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl i2c::I2c for Bus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.i2c(address, operations);
+        Ok(())
+    }
+}
+
+impl embedded_hal_async::i2c::I2c for Bus {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.i2c(address, operations);
+        Ok(())
+    }
+}
+
+impl spi::ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl spi::SpiDevice for Bus {
+    fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.spi(operations);
+        Ok(())
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for Bus {
+    async fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.spi(operations);
+        Ok(())
+    }
+}
+
+/// A delay that only adds up the time it waited.
+#[derive(Default)]
+pub struct Delay {
+    pub waited_ns: u64,
+}
+
+impl embedded_hal::delay::DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.waited_ns += ns as u64;
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for Delay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.waited_ns += ns as u64;
+    }
+}
+
+/// Runs a future that never waits, the mock bus never does.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(core::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+    // SAFETY: the functions of the vtable do nothing with the data pointer
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
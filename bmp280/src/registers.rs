@@ -0,0 +1,25 @@
+//! Register map of the BMP280 (datasheet section 4.2).
+
+/// Start of the calibration block (`calib00`..`calib25`).
+pub const REG_CALIB_00: u8 = 0x88;
+/// Chip identification number, always [`CHIP_ID`].
+pub const REG_ID: u8 = 0xD0;
+/// Writing [`RESET_WORD`] here performs a power-on reset.
+pub const REG_RESET: u8 = 0xE0;
+/// Device status (`measuring` and `im_update` bits).
+pub const REG_STATUS: u8 = 0xF3;
+/// Data acquisition options (temperature and pressure oversampling, power mode).
+pub const REG_CTRL_MEAS: u8 = 0xF4;
+/// Rate, filter and interface options.
+pub const REG_CONFIG: u8 = 0xF5;
+/// First of the six data registers (`press_msb`..`temp_xlsb`).
+pub const REG_PRESS_MSB: u8 = 0xF7;
+/// First of the three temperature data registers.
+pub const REG_TEMP_MSB: u8 = 0xFA;
+
+/// Value of the [`REG_ID`] register.
+pub const CHIP_ID: u8 = 0x58;
+/// Set in [`REG_STATUS`] while a conversion is running.
+pub const STATUS_MEASURING: u8 = 1 << 3;
+/// Value that triggers a soft reset when written to [`REG_RESET`].
+pub const RESET_WORD: u8 = 0xB6;
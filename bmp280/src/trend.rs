@@ -0,0 +1,234 @@
+//! Barometric tendency: whether the pressure is rising, falling or steady.
+//!
+//! [`PressureTrend`] keeps the samples of a sliding time window in a fixed size
+//! ring buffer and fits a least squares line through them, so a single noisy
+//! reading does not flip the trend. All the math is done in integers.
+
+/// Direction of the pressure change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// Default threshold for [`PressureTrend`]: 50 Pa (0.5 hPa) per hour.
+pub const DEFAULT_THRESHOLD: u32 = 50;
+
+/// Rolling pressure trend estimator over the last `window` minutes, holding
+/// at most `N` samples.
+///
+/// Timestamps are in seconds from any monotonic clock, for example
+/// `embassy_time::Instant::now().as_secs()`.
+///
+/// # Example
+///
+/// ```
+/// use bmp280::trend::{PressureTrend, Trend, DEFAULT_THRESHOLD};
+///
+/// let mut trend = PressureTrend::<16>::new(60, DEFAULT_THRESHOLD);
+/// // 1 Pa every 60 s is 60 Pa per hour.
+/// for minute in 0..10 {
+///     trend.push(minute * 60, (100_000 + minute) << 8);
+/// }
+/// assert_eq!(trend.rate(), Some(60));
+/// assert_eq!(trend.trend(), Some(Trend::Rising));
+/// ```
+pub struct PressureTrend<const N: usize> {
+    /// `(timestamp, pressure)` pairs, pressure in Pascals as Q24.8.
+    samples: [(u32, u32); N],
+    /// Index of the oldest sample.
+    head: usize,
+    len: usize,
+    /// Length of the window, in seconds.
+    window: u32,
+    /// Pa per hour above which the pressure is considered rising or falling.
+    threshold: u32,
+}
+
+impl<const N: usize> PressureTrend<N> {
+    /// Creates an estimator for the last `window_minutes` minutes that reports
+    /// a change once it exceeds `threshold` Pascals per hour.
+    pub const fn new(window_minutes: u32, threshold: u32) -> Self {
+        Self {
+            samples: [(0, 0); N],
+            head: 0,
+            len: 0,
+            window: window_minutes * 60,
+            threshold,
+        }
+    }
+
+    /// Adds a pressure sample (Pascals, as Q24.8) taken at `time` seconds and
+    /// drops the samples that fell out of the window.
+    pub fn push(&mut self, time: u32, pressure: u32) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
+        self.samples[(self.head + self.len) % N] = (time, pressure);
+        self.len += 1;
+
+        while self.len > 1 && time.wrapping_sub(self.samples[self.head].0) > self.window {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
+    }
+
+    /// Removes all the samples.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Returns the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no samples in the window.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the rate of change in whole Pascals per hour, or `None` until
+    /// there are two samples taken at different times.
+    pub fn rate(&self) -> Option<i32> {
+        if self.len < 2 {
+            return None;
+        }
+
+        // Work relative to the oldest sample to keep the sums small.
+        let (t0, p0) = self.samples[self.head];
+        let n = self.len as i64;
+        let (mut sx, mut sy, mut sxx, mut sxy) = (0i64, 0i64, 0i64, 0i64);
+        for i in 0..self.len {
+            let (t, p) = self.samples[(self.head + i) % N];
+            let x = t.wrapping_sub(t0) as i64;
+            let y = p as i64 - p0 as i64;
+            sx += x;
+            sy += y;
+            sxx += x * x;
+            sxy += x * y;
+        }
+
+        let den = n * sxx - sx * sx;
+        if den == 0 {
+            return None;
+        }
+
+        // The slope is in Q24.8 Pascals per second.
+        let num = (n * sxy - sx * sy) * 3600;
+        Some(((num + den * 128 * num.signum()) / (den * 256)) as i32)
+    }
+
+    /// Returns the direction of the pressure change, or `None` until
+    /// [`rate`](Self::rate) is available.
+    pub fn trend(&self) -> Option<Trend> {
+        let rate = self.rate()?;
+        Some(if rate > self.threshold as i32 {
+            Trend::Rising
+        } else if rate < -(self.threshold as i32) {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pushes a sample every minute from `pressures`, in Pascals.
+    fn trend_of(pressures: &[u32]) -> PressureTrend<16> {
+        let mut trend = PressureTrend::new(180, DEFAULT_THRESHOLD);
+        for (minute, pressure) in pressures.iter().enumerate() {
+            trend.push(minute as u32 * 60, pressure << 8);
+        }
+        trend
+    }
+
+    #[test]
+    fn needs_two_samples_at_different_times() {
+        let mut trend = PressureTrend::<4>::new(60, DEFAULT_THRESHOLD);
+        assert_eq!(trend.rate(), None);
+        trend.push(0, 100_000 << 8);
+        assert_eq!(trend.trend(), None);
+        trend.push(0, 100_100 << 8);
+        assert_eq!(trend.rate(), None);
+        trend.push(60, 100_000 << 8);
+        assert!(trend.rate().is_some());
+    }
+
+    /// Rates in Pascals per hour of linear series, one sample per minute.
+    #[test]
+    fn rate_follows_the_reference_slopes() {
+        // Pascals per minute, Pascals per hour, trend
+        let reference = [
+            (0, 0, Trend::Steady),
+            (1, 60, Trend::Rising),
+            (-1, -60, Trend::Falling),
+            (-3, -180, Trend::Falling),
+            (10, 600, Trend::Rising),
+        ];
+        for (per_minute, per_hour, expected) in reference {
+            let pressures: [u32; 10] =
+                core::array::from_fn(|minute| (100_000 + per_minute * minute as i32) as u32);
+            let trend = trend_of(&pressures);
+            assert_eq!(trend.rate(), Some(per_hour));
+            assert_eq!(trend.trend(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn a_noisy_reading_does_not_flip_the_trend() {
+        let trend = trend_of(&[
+            100_000, 100_000, 100_000, 100_000, 100_040, 100_000, 100_000, 100_000, 100_000,
+            100_000,
+        ]);
+        assert_eq!(trend.trend(), Some(Trend::Steady));
+    }
+
+    #[test]
+    fn half_a_hectopascal_per_hour_is_steady() {
+        // 50 Pa per hour is the threshold
+        let mut trend = PressureTrend::<8>::new(60, DEFAULT_THRESHOLD);
+        trend.push(0, 100_000 << 8);
+        trend.push(3600, 100_050 << 8);
+        assert_eq!(trend.rate(), Some(50));
+        assert_eq!(trend.trend(), Some(Trend::Steady));
+        trend.push(7200, 100_101 << 8);
+        assert_eq!(trend.trend(), Some(Trend::Rising));
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let mut trend = PressureTrend::<16>::new(10, DEFAULT_THRESHOLD);
+        // Falling for an hour, then rising for 10 minutes
+        for minute in 0..60 {
+            trend.push(minute * 60, (100_000 - minute) << 8);
+        }
+        for minute in 60..=70 {
+            trend.push(minute * 60, (100_000 + 2 * minute) << 8);
+        }
+        assert_eq!(trend.len(), 11);
+        assert_eq!(trend.trend(), Some(Trend::Rising));
+        trend.clear();
+        assert!(trend.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_last_samples_when_full() {
+        let mut trend = PressureTrend::<4>::new(600, DEFAULT_THRESHOLD);
+        for minute in 0..10 {
+            trend.push(minute * 60, (100_000 + minute) << 8);
+        }
+        assert_eq!(trend.len(), 4);
+        assert_eq!(trend.rate(), Some(60));
+    }
+}
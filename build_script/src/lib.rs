@@ -11,7 +11,7 @@
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn memory_layout() {
     // Put `memory.x` in our output directory and ensure it's
@@ -39,3 +39,34 @@ pub fn memory_layout() {
     // The `defmt.x` linker script provided by `defmt`.
     println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
 }
+
+/// Name of the environment variable that holds the directory of the CYW43 firmware.
+pub const CYW43_FIRMWARE_DIR: &str = "CYW43_FIRMWARE_DIR";
+
+/// Makes the CYW43 WiFi chip firmware (`43439A0.bin` and `43439A0_clm.bin`)
+/// available to the crate being built, which can embed it with
+/// `include_bytes!(concat!(env!("CYW43_FIRMWARE_DIR"), "/43439A0.bin"))`,
+/// wherever its source files are.
+///
+/// The firmware is taken from the directory in the `CYW43_FIRMWARE_DIR` environment
+/// variable if it is set, and from the `cyw43-firmware` folder of this repository
+/// otherwise.
+pub fn cyw43_firmware() {
+    println!("cargo:rerun-if-env-changed={CYW43_FIRMWARE_DIR}");
+    let dir = env::var_os(CYW43_FIRMWARE_DIR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../cyw43-firmware"));
+    let dir = dir
+        .canonicalize()
+        .unwrap_or_else(|e| panic!("CYW43 firmware directory {}: {e}", dir.display()));
+
+    for file in ["43439A0.bin", "43439A0_clm.bin"] {
+        let path = dir.join(file);
+        if !path.is_file() {
+            panic!("CYW43 firmware file {} is missing", path.display());
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+
+    println!("cargo:rustc-env={CYW43_FIRMWARE_DIR}={}", dir.display());
+}
@@ -0,0 +1,181 @@
+//! Sends a command to a lab08 board and prints its response.
+//!
+//! ```shell
+//! cargo run --target x86_64-unknown-linux-gnu -- <board address>[:port] <command>
+//! ```
+//!
+//! The commands are `ping`, `led on|off`, `rgb <red> <green> <blue>`,
+//! `tone <frequency> <milliseconds>`, `read temperature|pressure|light`, and
+//! `listen`, which prints the button events of the board until it is stopped.
+
+use std::error::Error;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use command_protocol::{Command, Reply, Request, Response, Sensor, EVENT_ID, MAX_FRAME_LEN, PORT};
+
+/// How long to wait for a response before sending the request again.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Number of times a request is sent.
+const ATTEMPTS: usize = 3;
+
+const BUTTONS: [&str; 4] = ["A", "B", "X", "Y"];
+
+const USAGE: &str = "usage: command_cli <board address>[:port] <command>
+
+commands:
+  ping
+  led on|off
+  rgb <red> <green> <blue>
+  tone <frequency> <milliseconds>
+  read temperature|pressure|light
+  listen";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [board, command @ ..] = args else {
+        return Err(USAGE.into());
+    };
+    let board = board_address(board)?;
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(board)?;
+
+    if let ["listen"] = words(command).as_slice() {
+        // The board sends its events to the last host that sent it a request
+        request(&socket, Command::Ping)?;
+        println!("listening to {}", board);
+        return listen(&socket);
+    }
+
+    let reply = request(&socket, parse_command(command)?)?;
+    match reply {
+        Reply::Ack => println!("ok"),
+        Reply::Reading { sensor, value } => println!("{}", format_reading(sensor, value)),
+        Reply::Button(button) => println!("button {}", button_name(button)),
+        Reply::Error(code) => return Err(format!("the board refused: {:?}", code).into()),
+    }
+    Ok(())
+}
+
+/// Returns the address of the board, on [`PORT`] if it has no port.
+fn board_address(board: &str) -> Result<SocketAddr, Box<dyn Error>> {
+    let address = if board.contains(':') {
+        board.to_socket_addrs()?.next()
+    } else {
+        (board, PORT).to_socket_addrs()?.next()
+    };
+    address.ok_or_else(|| format!("unknown board address {}", board).into())
+}
+
+fn words(args: &[String]) -> Vec<&str> {
+    args.iter().map(String::as_str).collect()
+}
+
+fn parse_command(args: &[String]) -> Result<Command, Box<dyn Error>> {
+    let command = match words(args).as_slice() {
+        ["ping"] => Command::Ping,
+        ["led", "on"] => Command::Led(true),
+        ["led", "off"] => Command::Led(false),
+        ["rgb", red, green, blue] => Command::Rgb {
+            red: red.parse()?,
+            green: green.parse()?,
+            blue: blue.parse()?,
+        },
+        ["tone", frequency, duration_ms] => Command::Tone {
+            frequency: frequency.parse()?,
+            duration_ms: duration_ms.parse()?,
+        },
+        ["read", "temperature"] => Command::ReadSensor(Sensor::Temperature),
+        ["read", "pressure"] => Command::ReadSensor(Sensor::Pressure),
+        ["read", "light"] => Command::ReadSensor(Sensor::Light),
+        _ => return Err(USAGE.into()),
+    };
+    Ok(command)
+}
+
+/// Sends a request until its response arrives, up to [`ATTEMPTS`] times.
+fn request(socket: &UdpSocket, command: Command) -> Result<Reply, Box<dyn Error>> {
+    let request = Request {
+        id: request_id(),
+        command,
+    };
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    for _ in 0..ATTEMPTS {
+        socket.send(&request.encode())?;
+        match receive(socket, request.id) {
+            Ok(reply) => return Ok(reply),
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(format!("no response after {} attempts", ATTEMPTS).into())
+}
+
+/// Waits for the response to the request `id`, and ignores the others.
+fn receive(socket: &UdpSocket, id: u16) -> std::io::Result<Reply> {
+    let mut frame = [0; 2 * MAX_FRAME_LEN];
+    loop {
+        let n = socket.recv(&mut frame)?;
+        match Response::decode(&frame[..n]) {
+            Ok(response) if response.id == id => return Ok(response.reply),
+            Ok(_) => {}
+            Err(err) => eprintln!("invalid response: {:?}", err),
+        }
+    }
+}
+
+fn listen(socket: &UdpSocket) -> Result<(), Box<dyn Error>> {
+    socket.set_read_timeout(None)?;
+    let mut frame = [0; 2 * MAX_FRAME_LEN];
+    loop {
+        let n = socket.recv(&mut frame)?;
+        match Response::decode(&frame[..n]) {
+            Ok(Response {
+                id: EVENT_ID,
+                reply: Reply::Button(button),
+            }) => println!("button {} pressed", button_name(button)),
+            Ok(_) => {}
+            Err(err) => eprintln!("invalid event: {:?}", err),
+        }
+    }
+}
+
+/// Returns an id that is not [`EVENT_ID`] and that changes from one run to the next,
+/// so that a late response to a previous run is not taken for this one.
+fn request_id() -> u16 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (now.subsec_micros() as u16).max(1)
+}
+
+fn button_name(button: u8) -> String {
+    BUTTONS
+        .get(button as usize)
+        .map_or_else(|| button.to_string(), |name| name.to_string())
+}
+
+fn format_reading(sensor: Sensor, value: i32) -> String {
+    match sensor {
+        Sensor::Temperature => {
+            let sign = if value < 0 { "-" } else { "" };
+            let value = value.unsigned_abs();
+            format!("{}{}.{:02} °C", sign, value / 100, value % 100)
+        }
+        Sensor::Pressure => format!("{} Pa", value),
+        Sensor::Light => format!("{} / 4095", value),
+    }
+}
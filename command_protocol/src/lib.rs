@@ -0,0 +1,187 @@
+//! Binary command protocol between a host and a lab08 board, over UDP.
+//!
+//! Every datagram holds one frame:
+//!
+//! | byte | content                                       |
+//! |------|-----------------------------------------------|
+//! | 0    | protocol [`VERSION`]                          |
+//! | 1    | message type                                  |
+//! | 2..4 | request id, big endian                        |
+//! | 4..  | payload, its length depends on the type       |
+//!
+//! The host sends [`Request`]s with a [`Command`], and the board answers each one with
+//! a [`Response`] that has the same id: an acknowledgement, a sensor reading or an
+//! [`ErrorCode`]. A request that cannot be decoded gets an error too, so the host
+//! never waits for nothing. The host may send a request again with the same id when
+//! the response is lost: all the commands can be run twice.
+//!
+//! The board also sends unsolicited [`Reply::Button`] events, with the id `0`, to the
+//! last host that sent it a request. Hosts use ids from 1.
+//!
+//! A board that receives a frame of another version answers with
+//! [`ErrorCode::UnsupportedVersion`], in a frame of its own version.
+
+#![no_std]
+
+mod request;
+mod response;
+
+pub use request::{Command, Request, Sensor};
+pub use response::{ErrorCode, Reply, Response};
+
+/// Version of the protocol, the first byte of every frame.
+pub const VERSION: u8 = 1;
+
+/// UDP port of the board.
+pub const PORT: u16 = 1234;
+
+/// Length of the header: version, type and request id.
+pub const HEADER_LEN: usize = 4;
+
+/// Maximum length of a frame.
+pub const MAX_FRAME_LEN: usize = HEADER_LEN + 5;
+
+/// Request id of the unsolicited events of the board.
+pub const EVENT_ID: u16 = 0;
+
+/// Error of [`Request::decode`] and [`Response::decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The frame is shorter than its header or than the payload of its type.
+    TooShort,
+    /// The frame has another protocol version.
+    UnsupportedVersion(u8),
+    /// The message type is unknown.
+    UnknownType(u8),
+    /// The payload has an invalid value.
+    InvalidPayload,
+}
+
+impl DecodeError {
+    /// Returns the error sent back to the host.
+    pub fn code(self) -> ErrorCode {
+        match self {
+            DecodeError::TooShort | DecodeError::InvalidPayload => ErrorCode::Malformed,
+            DecodeError::UnsupportedVersion(_) => ErrorCode::UnsupportedVersion,
+            DecodeError::UnknownType(_) => ErrorCode::UnknownCommand,
+        }
+    }
+}
+
+/// Returns the request id of a frame, or [`EVENT_ID`] if it is too short to have one,
+/// to answer the frames that cannot be decoded.
+pub fn frame_id(frame: &[u8]) -> u16 {
+    match frame {
+        [_, _, high, low, ..] => u16::from_be_bytes([*high, *low]),
+        _ => EVENT_ID,
+    }
+}
+
+/// An encoded frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    bytes: [u8; MAX_FRAME_LEN],
+    len: usize,
+}
+
+impl Frame {
+    fn new(message_type: u8, id: u16) -> Self {
+        let [high, low] = id.to_be_bytes();
+        let mut bytes = [0; MAX_FRAME_LEN];
+        bytes[..HEADER_LEN].copy_from_slice(&[VERSION, message_type, high, low]);
+        Self {
+            bytes,
+            len: HEADER_LEN,
+        }
+    }
+
+    fn push(mut self, payload: &[u8]) -> Self {
+        self.bytes[self.len..self.len + payload.len()].copy_from_slice(payload);
+        self.len += payload.len();
+        self
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl core::ops::Deref for Frame {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Checks the header of a frame and returns its type, its id and its payload.
+fn split(frame: &[u8]) -> Result<(u8, u16, &[u8]), DecodeError> {
+    match frame {
+        [VERSION, message_type, high, low, payload @ ..] => {
+            Ok((*message_type, u16::from_be_bytes([*high, *low]), payload))
+        }
+        [version, _, _, _, ..] => Err(DecodeError::UnsupportedVersion(*version)),
+        _ => Err(DecodeError::TooShort),
+    }
+}
+
+/// Returns the first `N` bytes of a payload.
+fn take<const N: usize>(payload: &[u8]) -> Result<[u8; N], DecodeError> {
+    payload
+        .get(..N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(DecodeError::TooShort)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_id_of_short_frames() {
+        assert_eq!(frame_id(&[]), EVENT_ID);
+        assert_eq!(frame_id(&[VERSION]), EVENT_ID);
+        assert_eq!(frame_id(&[VERSION, 0x01]), EVENT_ID);
+        assert_eq!(frame_id(&[VERSION, 0x01, 0x12]), EVENT_ID);
+        assert_eq!(frame_id(&[VERSION, 0x01, 0x12, 0x34]), 0x1234);
+        // The id is read from any frame, even of another version.
+        assert_eq!(frame_id(b"led:on"), u16::from_be_bytes(*b"d:"));
+    }
+
+    #[test]
+    fn short_headers_are_too_short() {
+        for frame in [&[][..], &[VERSION], &[VERSION, 0x01], &[VERSION, 0x01, 0]] {
+            assert_eq!(Request::decode(frame), Err(DecodeError::TooShort));
+            assert_eq!(Response::decode(frame), Err(DecodeError::TooShort));
+        }
+    }
+
+    #[test]
+    fn other_versions_are_unsupported() {
+        for version in [0, VERSION + 1, 0xff] {
+            let frame = [version, 0x01, 0, 1];
+            assert_eq!(
+                Request::decode(&frame),
+                Err(DecodeError::UnsupportedVersion(version))
+            );
+            assert_eq!(
+                Response::decode(&frame),
+                Err(DecodeError::UnsupportedVersion(version))
+            );
+        }
+    }
+
+    #[test]
+    fn error_codes_of_decode_errors() {
+        assert_eq!(DecodeError::TooShort.code(), ErrorCode::Malformed);
+        assert_eq!(DecodeError::InvalidPayload.code(), ErrorCode::Malformed);
+        assert_eq!(
+            DecodeError::UnsupportedVersion(2).code(),
+            ErrorCode::UnsupportedVersion
+        );
+        assert_eq!(
+            DecodeError::UnknownType(0x42).code(),
+            ErrorCode::UnknownCommand
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Requests of the host.
+
+use crate::{split, take, DecodeError, Frame};
+
+const PING: u8 = 0x01;
+const LED: u8 = 0x02;
+const RGB: u8 = 0x03;
+const TONE: u8 = 0x04;
+const READ_SENSOR: u8 = 0x05;
+
+/// A sensor of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensor {
+    /// BMP280 temperature, in hundredths of a degree Celsius.
+    Temperature = 0,
+    /// BMP280 pressure, in Pascals.
+    Pressure = 1,
+    /// Photoresistor, the raw 12 bit ADC value.
+    Light = 2,
+}
+
+impl Sensor {
+    pub(crate) fn from_u8(value: u8) -> Result<Self, DecodeError> {
+        match value {
+            0 => Ok(Sensor::Temperature),
+            1 => Ok(Sensor::Pressure),
+            2 => Ok(Sensor::Light),
+            _ => Err(DecodeError::InvalidPayload),
+        }
+    }
+}
+
+/// What the host asks the board to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Does nothing, answered with an acknowledgement.
+    Ping,
+    /// Turns the LED of the Pico W on or off.
+    Led(bool),
+    /// Sets the color of the RGB LED.
+    Rgb { red: u8, green: u8, blue: u8 },
+    /// Plays a tone on the buzzer, a frequency of `0` stops it.
+    Tone { frequency: u16, duration_ms: u16 },
+    /// Reads a sensor, answered with a [`Reply::Reading`](crate::Reply::Reading).
+    ReadSensor(Sensor),
+}
+
+/// A command and its id.
+///
+/// # Example
+///
+/// ```
+/// use command_protocol::{Command, DecodeError, Request, Sensor, VERSION};
+///
+/// let request = Request {
+///     id: 7,
+///     command: Command::Rgb { red: 255, green: 64, blue: 0 },
+/// };
+/// let frame = request.encode();
+/// assert_eq!(*frame, [VERSION, 0x03, 0, 7, 255, 64, 0]);
+/// assert_eq!(Request::decode(&frame), Ok(request));
+///
+/// let read = Request { id: 8, command: Command::ReadSensor(Sensor::Light) };
+/// assert_eq!(Request::decode(&read.encode()), Ok(read));
+///
+/// // The text commands of the first version of the lab
+/// assert_eq!(Request::decode(b"led:on"), Err(DecodeError::UnsupportedVersion(b'l')));
+/// assert_eq!(Request::decode(&[VERSION, 0x02, 0, 9]), Err(DecodeError::TooShort));
+/// assert_eq!(Request::decode(&[VERSION, 0x42, 0, 9]), Err(DecodeError::UnknownType(0x42)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request {
+    pub id: u16,
+    pub command: Command,
+}
+
+impl Request {
+    pub fn encode(&self) -> Frame {
+        match self.command {
+            Command::Ping => Frame::new(PING, self.id),
+            Command::Led(on) => Frame::new(LED, self.id).push(&[on as u8]),
+            Command::Rgb { red, green, blue } => Frame::new(RGB, self.id).push(&[red, green, blue]),
+            Command::Tone {
+                frequency,
+                duration_ms,
+            } => Frame::new(TONE, self.id)
+                .push(&frequency.to_be_bytes())
+                .push(&duration_ms.to_be_bytes()),
+            Command::ReadSensor(sensor) => Frame::new(READ_SENSOR, self.id).push(&[sensor as u8]),
+        }
+    }
+
+    /// Decodes a request. Bytes after the payload are ignored, for the extensions of
+    /// the commands in the next versions.
+    pub fn decode(frame: &[u8]) -> Result<Self, DecodeError> {
+        let (message_type, id, payload) = split(frame)?;
+        let command = match message_type {
+            PING => Command::Ping,
+            LED => match take::<1>(payload)? {
+                [0] => Command::Led(false),
+                [1] => Command::Led(true),
+                _ => return Err(DecodeError::InvalidPayload),
+            },
+            RGB => {
+                let [red, green, blue] = take::<3>(payload)?;
+                Command::Rgb { red, green, blue }
+            }
+            TONE => {
+                let [frequency_high, frequency_low, duration_high, duration_low] =
+                    take::<4>(payload)?;
+                Command::Tone {
+                    frequency: u16::from_be_bytes([frequency_high, frequency_low]),
+                    duration_ms: u16::from_be_bytes([duration_high, duration_low]),
+                }
+            }
+            READ_SENSOR => Command::ReadSensor(Sensor::from_u8(take::<1>(payload)?[0])?),
+            _ => return Err(DecodeError::UnknownType(message_type)),
+        };
+        Ok(Self { id, command })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HEADER_LEN, MAX_FRAME_LEN, VERSION};
+
+    const COMMANDS: [Command; 9] = [
+        Command::Ping,
+        Command::Led(false),
+        Command::Led(true),
+        Command::Rgb {
+            red: 255,
+            green: 64,
+            blue: 0,
+        },
+        Command::Tone {
+            frequency: 440,
+            duration_ms: 65_535,
+        },
+        Command::Tone {
+            frequency: 0,
+            duration_ms: 0,
+        },
+        Command::ReadSensor(Sensor::Temperature),
+        Command::ReadSensor(Sensor::Pressure),
+        Command::ReadSensor(Sensor::Light),
+    ];
+
+    #[test]
+    fn every_command_round_trips() {
+        for (id, command) in COMMANDS.into_iter().enumerate() {
+            let request = Request {
+                id: id as u16 * 0x0101,
+                command,
+            };
+            let frame = request.encode();
+            assert!(frame.len() <= MAX_FRAME_LEN);
+            assert_eq!(frame[0], VERSION);
+            assert_eq!(crate::frame_id(&frame), request.id);
+            assert_eq!(Request::decode(&frame), Ok(request));
+        }
+    }
+
+    #[test]
+    fn encoding_of_the_commands() {
+        let encode = |command| {
+            Request {
+                id: 0x0102,
+                command,
+            }
+            .encode()
+        };
+        assert_eq!(*encode(Command::Ping), [VERSION, PING, 1, 2]);
+        assert_eq!(*encode(Command::Led(true)), [VERSION, LED, 1, 2, 1]);
+        assert_eq!(
+            *encode(Command::Tone {
+                frequency: 0x1234,
+                duration_ms: 0x5678
+            }),
+            [VERSION, TONE, 1, 2, 0x12, 0x34, 0x56, 0x78]
+        );
+        assert_eq!(
+            *encode(Command::ReadSensor(Sensor::Light)),
+            [VERSION, READ_SENSOR, 1, 2, 2]
+        );
+    }
+
+    #[test]
+    fn truncated_payloads_are_too_short() {
+        for command in COMMANDS {
+            let frame = Request { id: 1, command }.encode();
+            for len in HEADER_LEN..frame.len() {
+                assert_eq!(
+                    Request::decode(&frame[..len]),
+                    Err(DecodeError::TooShort),
+                    "{:?} cut to {} bytes",
+                    command,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_invalid_payloads() {
+        for value in [2, 0xff] {
+            assert_eq!(
+                Request::decode(&[VERSION, LED, 0, 1, value]),
+                Err(DecodeError::InvalidPayload)
+            );
+        }
+        for sensor in [3, 0xff] {
+            assert_eq!(
+                Request::decode(&[VERSION, READ_SENSOR, 0, 1, sensor]),
+                Err(DecodeError::InvalidPayload)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_types() {
+        for message_type in [0x00, 0x06, 0x80, 0xff] {
+            assert_eq!(
+                Request::decode(&[VERSION, message_type, 0, 1, 0, 0, 0, 0]),
+                Err(DecodeError::UnknownType(message_type))
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes_are_ignored() {
+        for command in COMMANDS {
+            let request = Request { id: 3, command };
+            let mut frame = [0xaa; MAX_FRAME_LEN + 4];
+            let encoded = request.encode();
+            frame[..encoded.len()].copy_from_slice(&encoded);
+            assert_eq!(Request::decode(&frame), Ok(request));
+        }
+    }
+}
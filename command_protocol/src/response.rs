@@ -0,0 +1,213 @@
+//! Responses and events of the board.
+
+use crate::{split, take, DecodeError, Frame, Sensor};
+
+const ACK: u8 = 0x80;
+const READING: u8 = 0x81;
+const BUTTON: u8 = 0x82;
+const ERROR: u8 = 0xff;
+
+/// Why the board did not run a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The frame has another protocol version.
+    UnsupportedVersion = 1,
+    /// The command is unknown.
+    UnknownCommand = 2,
+    /// The frame is too short or has an invalid value.
+    Malformed = 3,
+    /// The board does not have this device, or it does not answer.
+    Unavailable = 4,
+}
+
+impl ErrorCode {
+    fn from_u8(value: u8) -> Result<Self, DecodeError> {
+        match value {
+            1 => Ok(ErrorCode::UnsupportedVersion),
+            2 => Ok(ErrorCode::UnknownCommand),
+            3 => Ok(ErrorCode::Malformed),
+            4 => Ok(ErrorCode::Unavailable),
+            _ => Err(DecodeError::InvalidPayload),
+        }
+    }
+}
+
+/// What the board answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    /// The command was run.
+    Ack,
+    /// The value of a sensor, in the unit of the [`Sensor`].
+    Reading { sensor: Sensor, value: i32 },
+    /// A button of the board was pressed, an event with the id
+    /// [`EVENT_ID`](crate::EVENT_ID). The buttons A, B, X and Y are 0 to 3.
+    Button(u8),
+    /// The request failed.
+    Error(ErrorCode),
+}
+
+/// A reply and the id of its request.
+///
+/// # Example
+///
+/// ```
+/// use command_protocol::{ErrorCode, Reply, Response, Sensor, VERSION};
+///
+/// let response = Response {
+///     id: 8,
+///     reply: Reply::Reading { sensor: Sensor::Temperature, value: -1250 },
+/// };
+/// let frame = response.encode();
+/// assert_eq!(*frame, [VERSION, 0x81, 0, 8, 0, 0xff, 0xff, 0xfb, 0x1e]);
+/// assert_eq!(Response::decode(&frame), Ok(response));
+///
+/// let error = Response { id: 9, reply: Reply::Error(ErrorCode::Unavailable) };
+/// assert_eq!(Response::decode(&error.encode()), Ok(error));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response {
+    pub id: u16,
+    pub reply: Reply,
+}
+
+impl Response {
+    pub fn encode(&self) -> Frame {
+        match self.reply {
+            Reply::Ack => Frame::new(ACK, self.id),
+            Reply::Reading { sensor, value } => Frame::new(READING, self.id)
+                .push(&[sensor as u8])
+                .push(&value.to_be_bytes()),
+            Reply::Button(button) => Frame::new(BUTTON, self.id).push(&[button]),
+            Reply::Error(code) => Frame::new(ERROR, self.id).push(&[code as u8]),
+        }
+    }
+
+    /// Decodes a response. Bytes after the payload are ignored.
+    pub fn decode(frame: &[u8]) -> Result<Self, DecodeError> {
+        let (message_type, id, payload) = split(frame)?;
+        let reply = match message_type {
+            ACK => Reply::Ack,
+            READING => {
+                let [sensor, value @ ..] = take::<5>(payload)?;
+                Reply::Reading {
+                    sensor: Sensor::from_u8(sensor)?,
+                    value: i32::from_be_bytes(value),
+                }
+            }
+            BUTTON => Reply::Button(take::<1>(payload)?[0]),
+            ERROR => Reply::Error(ErrorCode::from_u8(take::<1>(payload)?[0])?),
+            _ => return Err(DecodeError::UnknownType(message_type)),
+        };
+        Ok(Self { id, reply })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HEADER_LEN, MAX_FRAME_LEN, VERSION};
+
+    const REPLIES: [Reply; 11] = [
+        Reply::Ack,
+        Reply::Reading {
+            sensor: Sensor::Temperature,
+            value: -1250,
+        },
+        Reply::Reading {
+            sensor: Sensor::Pressure,
+            value: 101_325,
+        },
+        Reply::Reading {
+            sensor: Sensor::Light,
+            value: i32::MAX,
+        },
+        Reply::Reading {
+            sensor: Sensor::Temperature,
+            value: i32::MIN,
+        },
+        Reply::Button(0),
+        Reply::Button(3),
+        Reply::Error(ErrorCode::UnsupportedVersion),
+        Reply::Error(ErrorCode::UnknownCommand),
+        Reply::Error(ErrorCode::Malformed),
+        Reply::Error(ErrorCode::Unavailable),
+    ];
+
+    #[test]
+    fn every_reply_round_trips() {
+        for (id, reply) in REPLIES.into_iter().enumerate() {
+            let response = Response {
+                id: id as u16 * 0x0101,
+                reply,
+            };
+            let frame = response.encode();
+            assert!(frame.len() <= MAX_FRAME_LEN);
+            assert_eq!(frame[0], VERSION);
+            assert_eq!(crate::frame_id(&frame), response.id);
+            assert_eq!(Response::decode(&frame), Ok(response));
+        }
+    }
+
+    #[test]
+    fn encoding_of_the_replies() {
+        let encode = |reply| Response { id: 0x0102, reply }.encode();
+        assert_eq!(*encode(Reply::Ack), [VERSION, ACK, 1, 2]);
+        assert_eq!(*encode(Reply::Button(2)), [VERSION, BUTTON, 1, 2, 2]);
+        assert_eq!(
+            *encode(Reply::Error(ErrorCode::Malformed)),
+            [VERSION, ERROR, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn truncated_payloads_are_too_short() {
+        for reply in REPLIES {
+            let frame = Response { id: 1, reply }.encode();
+            for len in HEADER_LEN..frame.len() {
+                assert_eq!(
+                    Response::decode(&frame[..len]),
+                    Err(DecodeError::TooShort),
+                    "{:?} cut to {} bytes",
+                    reply,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_invalid_payloads() {
+        assert_eq!(
+            Response::decode(&[VERSION, READING, 0, 1, 3, 0, 0, 0, 0]),
+            Err(DecodeError::InvalidPayload)
+        );
+        for code in [0, 5, 0xff] {
+            assert_eq!(
+                Response::decode(&[VERSION, ERROR, 0, 1, code]),
+                Err(DecodeError::InvalidPayload)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_types() {
+        // The request types are not responses.
+        for message_type in [0x01, 0x05, 0x83, 0xfe] {
+            assert_eq!(
+                Response::decode(&[VERSION, message_type, 0, 1, 0, 0, 0, 0, 0]),
+                Err(DecodeError::UnknownType(message_type))
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes_are_ignored() {
+        for reply in REPLIES {
+            let response = Response { id: 3, reply };
+            let mut frame = [0xaa; MAX_FRAME_LEN + 4];
+            let encoded = response.encode();
+            frame[..encoded.len()].copy_from_slice(&encoded);
+            assert_eq!(Response::decode(&frame), Ok(response));
+        }
+    }
+}
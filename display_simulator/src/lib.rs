@@ -0,0 +1,70 @@
+//! Host-side simulator for the screens of the labs.
+//!
+//! The screens are drawn with the functions from [`utils::display::screens`], the same
+//! ones the lab solutions use, into a [`SimulatorDisplay`] that has the size of the
+//! ST7789 display. The result can be saved as a PNG image and compared against the
+//! golden images in `golden/`.
+
+use std::error::Error;
+use std::path::Path;
+
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use embedded_graphics_simulator::{OutputSettings, SimulatorDisplay};
+use utils::display::{screens, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// The color of the RGB LED, as defined by the lab 05 solution.
+#[derive(Debug)]
+pub enum LedColor {
+    Red,
+    Green,
+    Blue,
+}
+
+/// A lab screen drawn with sample values.
+pub struct Screen {
+    /// Name of the screen, used for the image file name.
+    pub name: &'static str,
+    draw: fn(&mut SimulatorDisplay<Rgb565>) -> Result<(), core::convert::Infallible>,
+}
+
+impl Screen {
+    /// Draws the screen on a display cleared to black, like the one returned by
+    /// `utils::pico_explorer_display!`.
+    pub fn render(&self) -> SimulatorDisplay<Rgb565> {
+        let mut display = new_display();
+        (self.draw)(&mut display).unwrap();
+        display
+    }
+}
+
+/// The screens of the labs.
+pub const SCREENS: &[Screen] = &[
+    Screen {
+        name: "intensity_color",
+        draw: |display| screens::intensity_color(display, 16384, Some(LedColor::Red)),
+    },
+    Screen {
+        name: "temperature_pressure",
+        draw: |display| screens::temperature_pressure(display, 2508, 415148),
+    },
+];
+
+/// Creates a black display with the size of the ST7789 display.
+pub fn new_display() -> SimulatorDisplay<Rgb565> {
+    SimulatorDisplay::with_default_color(
+        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32),
+        Rgb565::BLACK,
+    )
+}
+
+/// Saves the `display` as a PNG image at `path`.
+pub fn save_png(
+    display: &SimulatorDisplay<Rgb565>,
+    path: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
+    display
+        .to_rgb_output_image(&OutputSettings::default())
+        .save_png(path)?;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Saves a PNG snapshot of every lab screen.
+//!
+//! ```shell
+//! cargo run --target x86_64-unknown-linux-gnu -- [output directory]
+//! ```
+//!
+//! The images are written to `snapshots/` if no directory is given.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use display_simulator::{save_png, SCREENS};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let output = PathBuf::from(std::env::args().nth(1).unwrap_or("snapshots".into()));
+    std::fs::create_dir_all(&output)?;
+
+    for screen in SCREENS {
+        let path = output.join(format!("{}.png", screen.name));
+        save_png(&screen.render(), &path)?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Compares the lab screens against the golden images in `golden/`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to replace the golden images after an intended change.
+//! When a screen differs, the rendered image is saved next to the golden one, with
+//! the `.actual.png` extension.
+
+use std::path::PathBuf;
+
+use display_simulator::{save_png, SCREENS};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics_simulator::SimulatorDisplay;
+
+fn golden_path(name: &str, extension: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(format!("{name}.{extension}"))
+}
+
+#[test]
+fn screens_match_golden_images() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatches = Vec::new();
+
+    for screen in SCREENS {
+        let display = screen.render();
+        let golden = golden_path(screen.name, "png");
+        let actual = golden_path(screen.name, "actual.png");
+
+        if update {
+            save_png(&display, &golden).unwrap();
+            continue;
+        }
+
+        let expected = SimulatorDisplay::<Rgb565>::load_png(&golden)
+            .unwrap_or_else(|e| panic!("cannot load {}: {e}", golden.display()));
+        if display.diff(&expected).is_some() {
+            save_png(&display, &actual).unwrap();
+            mismatches.push(actual);
+        } else {
+            let _ = std::fs::remove_file(&actual);
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "screens differ from the golden images, see {mismatches:?}"
+    );
+}
@@ -7,8 +7,8 @@ use embassy_executor::Spawner;
 use embassy_rp::gpio::{Input, Level, Output, Pull};
 use embassy_rp::usb::{Driver, InterruptHandler};
 use embassy_rp::{bind_interrupts, peripherals::USB};
-use embassy_time::Timer;
 use log::info;
+use utils::input::{Button, ButtonConfig, ButtonEvent};
 
 // Use for the serial over USB driver
 bind_interrupts!(struct Irqs {
@@ -32,27 +32,21 @@ async fn main(spawner: Spawner) {
     spawner.spawn(logger_task(driver)).unwrap();
 
     // TODO 2 - initialize button A (pull up)
+    //          the button debounces the contact, a press makes a single event
     let button = Input::new(peripherals.PIN_12, Pull::Up);
+    let mut button = Button::new(button, ButtonConfig::default());
 
     /* Exercise 5 */
     // TODO 4 - initialize the LED
     let mut led = Output::new(peripherals.PIN_25, Level::Low);
 
     loop {
-        // TODO 3 - verify the state of the button
-        if button.is_low() {
-            //          if it is down (the button is pressed)
+        // TODO 3 - wait for the button to be pressed
+        if button.next_event().await == ButtonEvent::Pressed {
             //          - print a message
             info!("The button was pressed");
             //          TODO 5 - Exercise 5 - toggle the LED
             led.toggle();
-            //          - sleep
-            Timer::after_millis(10).await;
-            //          - wait for the button to be released
-            while button.is_low() {
-                //              - sleep
-                Timer::after_millis(10).await;
-            }
         }
     }
 }
@@ -9,7 +9,8 @@ use embassy_executor::Spawner;
 use embassy_rp::gpio::{Input, Pull};
 
 // PWM
-use embassy_rp::pwm::{Config as PwmConfig, Pwm};
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use utils::led::{Polarity, PwmOutput, RgbLed};
 
 #[derive(PartialEq, Copy, Clone)]
 enum LedColor {
@@ -18,36 +19,22 @@ enum LedColor {
     Blue,
 }
 
-static TOP: u16 = 0x8000;
+const TOP: u16 = 0x8000;
 
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
     // TODO 1: Initialize peripherals
     let peripherals = embassy_rp::init(Default::default());
 
-    // TODO 2: Create configuration for red LED
-    let mut config_red: PwmConfig = Default::default();
-    config_red.top = TOP;
-    config_red.compare_b = config_red.top;
-
-    // TODO 3: Create configuration for green LED
-    let mut config_green: PwmConfig = Default::default();
-    config_green.top = TOP;
-    config_green.compare_b = 0;
-
-    // TODO 4: Create configuration for blue LED
-    let mut config_blue: PwmConfig = Default::default();
-    config_blue.top = TOP;
-    config_blue.compare_b = 0;
-
-    // TODO 5: Initialize PWM for red LED
-    let mut pwm_red = Pwm::new_output_b(peripherals.PWM_CH0, peripherals.PIN_1, config_red.clone());
-    // TODO 6: Initialize PWM for green LED
-    let mut pwm_green =
-        Pwm::new_output_a(peripherals.PWM_CH1, peripherals.PIN_2, config_green.clone());
-    // TODO 7: Initialize PWM for blue LED
-    let mut pwm_blue =
-        Pwm::new_output_a(peripherals.PWM_CH2, peripherals.PIN_4, config_blue.clone());
+    // TODO 2-7: Initialize the PWM outputs of the red, green and blue LEDs
+    //           A `PwmOutput` only accepts the pins of its output: PIN_1 is the output B of PWM_CH0
+    let mut led = RgbLed::new(
+        PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+        PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+        PwmOutput::new_a(peripherals.PWM_CH2, peripherals.PIN_4, TOP),
+        Polarity::CommonCathode,
+    );
+    led.set(Rgb888::RED);
 
     // TODO 8: Initialize button
     let mut button = Input::new(peripherals.PIN_12, Pull::Up);
@@ -58,32 +45,22 @@ async fn main(_spawner: Spawner) {
     loop {
         // TODO 9: Wait for button press
         button.wait_for_falling_edge().await;
-        // TODO 10: Check what the current color is and change configurations of PWMs to match next color
+        // TODO 10: Check what the current color is and set the LED to match
+        // TODO 12: Modify variable that keeps track of color
         match color {
             LedColor::Red => {
-                config_red.compare_b = TOP;
-                config_green.compare_a = 0;
-                config_blue.compare_a = 0;
+                led.set(Rgb888::RED);
                 color = LedColor::Yellow;
             }
             LedColor::Yellow => {
-                config_red.compare_b = TOP;
-                config_green.compare_a = TOP;
-                config_blue.compare_a = 0;
+                led.set(Rgb888::YELLOW);
                 color = LedColor::Blue;
             }
             LedColor::Blue => {
-                config_red.compare_b = 0;
-                config_green.compare_a = 0;
-                config_blue.compare_a = TOP;
+                led.set(Rgb888::BLUE);
                 color = LedColor::Red;
             }
         }
-        // TODO 11: Set new configurations for each PWM
-        pwm_red.set_config(&config_red);
-        pwm_green.set_config(&config_green);
-        pwm_blue.set_config(&config_blue);
-        // TODO 12: Modify variable that keeps track of color
     }
 }
 
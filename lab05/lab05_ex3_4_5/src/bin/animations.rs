@@ -0,0 +1,133 @@
+#![no_std]
+#![no_main]
+
+// The RGB LED of exercise 3, animated. The buttons send a `LedCommand`, and a new
+// command stops the animation that is playing:
+// - button A: rainbow cycle
+// - button B: breathe in cyan
+// - button X: blink in orange
+// - button Y: fade out from the current color
+
+use core::panic::PanicInfo;
+use embassy_executor::Spawner;
+
+// PWM
+use utils::led::{Animation, Hsl, Hsv, Polarity, PwmOutput, RgbLed};
+
+// USB
+use embassy_rp::usb::{Driver, InterruptHandler};
+use embassy_rp::{bind_interrupts, peripherals::USB};
+use log::info;
+
+// Channel
+use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use embassy_sync::channel::{Channel, Sender};
+
+// Timer
+use embassy_time::Duration;
+
+// Select futures
+use embassy_futures::select::select;
+use embassy_futures::select::Either::{First, Second};
+
+// Colors
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+
+// Inputs
+use utils::input::{ButtonEvent, ButtonId, ButtonPad};
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => InterruptHandler<USB>;
+});
+
+#[derive(Debug)]
+enum LedCommand {
+    Animate(Animation),
+    Off,
+}
+
+static TOP: u16 = 0x8000;
+
+/// Duration of the fade out of the Y button.
+const FADE_OUT: Duration = Duration::from_millis(1500);
+
+static COMMAND_CHANNEL: Channel<ThreadModeRawMutex, LedCommand, 8> = Channel::new();
+
+#[embassy_executor::task]
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+}
+
+#[embassy_executor::task]
+async fn buttons_pressed(
+    mut buttons: ButtonPad<'static, 4>,
+    channel_sender: Sender<'static, ThreadModeRawMutex, LedCommand, 8>,
+) {
+    loop {
+        let command = match buttons.next_event().await {
+            (ButtonId::A, ButtonEvent::Pressed) => LedCommand::Animate(Animation::Rainbow {
+                period: Duration::from_secs(6),
+                saturation: 255,
+                value: 255,
+            }),
+            (ButtonId::B, ButtonEvent::Pressed) => LedCommand::Animate(Animation::Breathe {
+                color: Hsv::new(180, 255, 255).into(),
+                period: Duration::from_secs(3),
+            }),
+            (ButtonId::X, ButtonEvent::Pressed) => LedCommand::Animate(Animation::Blink {
+                color: Hsl::new(30, 255, 128).into(),
+                on: Duration::from_millis(200),
+                off: Duration::from_millis(800),
+            }),
+            (ButtonId::Y, ButtonEvent::Pressed) => LedCommand::Off,
+            _ => continue,
+        };
+        channel_sender.send(command).await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    let driver = Driver::new(peripherals.USB, Irqs);
+    spawner.spawn(logger_task(driver)).unwrap();
+
+    let buttons = utils::pico_explorer_buttons!(peripherals);
+    spawner
+        .spawn(buttons_pressed(buttons, COMMAND_CHANNEL.sender()))
+        .unwrap();
+
+    let mut led = RgbLed::new(
+        PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+        PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+        PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+        Polarity::CommonCathode,
+    );
+
+    let mut command = COMMAND_CHANNEL.receive().await;
+    loop {
+        info!("{:?}", command);
+        let animation = match command {
+            LedCommand::Animate(animation) => animation,
+            // From the color of the last frame
+            LedCommand::Off => Animation::Fade {
+                from: led.color(),
+                to: Rgb888::BLACK,
+                duration: FADE_OUT,
+            },
+        };
+
+        command = match select(led.play(&animation), COMMAND_CHANNEL.receive()).await {
+            First(()) => COMMAND_CHANNEL.receive().await,
+            // The animation is stopped by the new command
+            Second(command) => command,
+        };
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+use embassy_executor::Spawner;
+
+// USB
+use embassy_rp::usb::{Driver, InterruptHandler};
+use embassy_rp::{bind_interrupts, peripherals::USB};
+use log::info;
+
+// Timer
+use embassy_time::{Instant, Timer};
+
+// Display
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use utils::display::FpsCounter;
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => InterruptHandler<USB>;
+});
+
+#[embassy_executor::task]
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+}
+
+// Runs while the frames are pushed, to show that the executor is not blocked.
+#[embassy_executor::task]
+async fn heartbeat() {
+    let mut last = Instant::now();
+    loop {
+        Timer::after_millis(500).await;
+        let now = Instant::now();
+        info!("heartbeat after {} ms", (now - last).as_millis());
+        last = now;
+    }
+}
+
+// Redraws the full 240x240 screen as fast as possible and logs the frame rate.
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    // The USB driver, for serial debugging
+    let driver = Driver::new(peripherals.USB, Irqs);
+    spawner.spawn(logger_task(driver)).unwrap();
+    spawner.spawn(heartbeat()).unwrap();
+
+    let mut display = utils::pico_explorer_display_async!(peripherals);
+
+    let colors = [Rgb565::RED, Rgb565::GREEN, Rgb565::BLUE];
+    let mut fps = FpsCounter::new();
+
+    for color in colors.iter().cycle() {
+        display.clear(*color).await.unwrap();
+
+        if let Some(fps) = fps.frame() {
+            info!("{fps:.1} FPS");
+        }
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
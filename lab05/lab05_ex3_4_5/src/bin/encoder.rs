@@ -0,0 +1,152 @@
+#![no_std]
+#![no_main]
+
+// The RGB LED of exercise 3, with its intensity set by a rotary encoder instead of
+// the potentiometer. The encoder is connected to `PIN_6` (A) and `PIN_7` (B), with
+// its common pin to the ground. Turning it faster changes the intensity in larger
+// steps.
+
+use core::panic::PanicInfo;
+use embassy_executor::Spawner;
+
+// PWM
+use utils::led::{Polarity, PwmOutput, RgbLed};
+
+// PIO
+use embassy_rp::peripherals::PIO1;
+use embassy_rp::pio::{InterruptHandler as PioInterruptHandler, Pio};
+
+// USB
+use embassy_rp::usb::{Driver, InterruptHandler};
+use embassy_rp::{bind_interrupts, peripherals::USB};
+use log::info;
+
+// Channel
+use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use embassy_sync::channel::{Channel, Sender};
+
+// Select futures
+use embassy_futures::select::select;
+use embassy_futures::select::Either::{First, Second};
+
+// Display
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888};
+use embedded_graphics::prelude::*;
+
+// Inputs
+use utils::input::{ButtonEvent, ButtonId, ButtonPad, Encoder};
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => InterruptHandler<USB>;
+    PIO1_IRQ_0 => PioInterruptHandler<PIO1>;
+});
+
+#[derive(Debug)]
+enum LedColor {
+    Red,
+    Green,
+    Blue,
+}
+
+static TOP: u16 = 0x8000;
+
+/// Steps of the encoder from one detent to the next.
+const STEPS_PER_DETENT: u8 = 4;
+
+static COLOR_CHANNEL: Channel<ThreadModeRawMutex, Option<LedColor>, 64> = Channel::new();
+
+#[embassy_executor::task]
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+}
+
+#[embassy_executor::task]
+async fn buttons_pressed(
+    mut buttons: ButtonPad<'static, 4>,
+    channel_sender: Sender<'static, ThreadModeRawMutex, Option<LedColor>, 64>,
+) {
+    loop {
+        let command = match buttons.next_event().await {
+            (ButtonId::A, ButtonEvent::Pressed) => Some(LedColor::Red),
+            (ButtonId::B, ButtonEvent::Pressed) => Some(LedColor::Green),
+            (ButtonId::X, ButtonEvent::Pressed) => Some(LedColor::Blue),
+            (ButtonId::Y, ButtonEvent::Pressed) => None,
+            _ => continue,
+        };
+        channel_sender.send(command).await;
+    }
+}
+
+/// Returns the change of intensity of a detent, larger when the encoder turns faster.
+fn intensity_step(velocity: i32) -> i32 {
+    match velocity.unsigned_abs() {
+        0..=5 => 1,
+        6..=20 => 4,
+        _ => 16,
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    let driver = Driver::new(peripherals.USB, Irqs);
+    spawner.spawn(logger_task(driver)).unwrap();
+
+    let mut display = utils::pico_explorer_display!(peripherals);
+    display.clear(Rgb565::BLACK).unwrap();
+
+    let buttons = utils::pico_explorer_buttons!(peripherals);
+    spawner
+        .spawn(buttons_pressed(buttons, COLOR_CHANNEL.sender()))
+        .unwrap();
+
+    // PIO0 is free, but it is used for the Wi-Fi chip in the next labs
+    let Pio {
+        mut common, sm0, ..
+    } = Pio::new(peripherals.PIO1, Irqs);
+    let mut encoder = Encoder::new(
+        &mut common,
+        sm0,
+        peripherals.PIN_6,
+        peripherals.PIN_7,
+        STEPS_PER_DETENT,
+    );
+
+    let mut led = RgbLed::new(
+        PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+        PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+        PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+        Polarity::CommonCathode,
+    );
+
+    let mut led_color: Option<LedColor> = None;
+    let mut led_intensity: u8 = u8::MAX / 2;
+
+    loop {
+        match select(COLOR_CHANNEL.receive(), encoder.wait_for_change()).await {
+            First(color) => led_color = color,
+            Second(change) => {
+                let velocity = encoder.velocity();
+                let intensity = led_intensity as i32 + change * intensity_step(velocity);
+                led_intensity = intensity.clamp(0, u8::MAX as i32) as u8;
+                info!("intensity {} at {} detents/s", led_intensity, velocity);
+            }
+        }
+
+        led.set(match led_color {
+            Some(LedColor::Red) => Rgb888::RED,
+            Some(LedColor::Green) => Rgb888::GREEN,
+            Some(LedColor::Blue) => Rgb888::BLUE,
+            None => Rgb888::BLACK,
+        });
+        led.set_brightness(led_intensity);
+
+        utils::display::screens::intensity_color(&mut display, led_intensity, &led_color).unwrap();
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
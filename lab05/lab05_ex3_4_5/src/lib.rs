@@ -1,4 +0,0 @@
-#![no_std]
-
-mod display;
-pub use display::SPIDeviceInterface;
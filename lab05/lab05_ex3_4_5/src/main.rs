@@ -2,13 +2,12 @@
 #![no_main]
 #![allow(unused_imports, dead_code, unused_variables, unused_mut)]
 
-use core::cell::RefCell;
 use core::panic::PanicInfo;
 use embassy_executor::Spawner;
 
 // GPIO
 use embassy_rp::gpio::{Input, Level, Output, Pull};
-use embassy_rp::peripherals::{PIN_0, PIN_12, PIN_13, PIN_14, PIN_15, PIN_16, PIN_17, SPI0};
+use embassy_rp::peripherals::{PIN_12, PIN_13, PIN_14, PIN_15};
 
 // PWM
 use embassy_rp::pwm::{Config as PwmConfig, Pwm};
@@ -28,7 +27,7 @@ use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
 use embassy_sync::channel::{Channel, Receiver, Sender};
 
 // Timer
-use embassy_time::{Delay, Timer};
+use embassy_time::Timer;
 
 // Select futures
 use embassy_futures::select::select;
@@ -36,11 +35,6 @@ use embassy_futures::select::Either::{First, Second};
 
 // Display
 use core::fmt::Write;
-use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
-use embassy_rp::spi;
-use embassy_rp::spi::{Blocking, Spi};
-use embassy_sync::blocking_mutex::raw::NoopRawMutex;
-use embassy_sync::blocking_mutex::Mutex;
 use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
 use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::pixelcolor::Rgb565;
@@ -48,10 +42,6 @@ use embedded_graphics::prelude::*;
 use embedded_graphics::text::renderer::CharacterStyle;
 use embedded_graphics::text::Text;
 use heapless::String;
-use lab05_ex3_4_5::SPIDeviceInterface;
-use st7789::{Orientation, ST7789};
-
-const DISPLAY_FREQ: u32 = 64_000_000;
 
 bind_interrupts!(struct Irqs {
     USBCTRL_IRQ => InterruptHandler<USB>;
@@ -113,37 +103,7 @@ async fn main(spawner: Spawner) {
     style.set_background_color(Some(Rgb565::BLACK));
 
     // ************** Display initialization - DO NOT MODIFY! *****************
-    let miso = peripherals.PIN_4;
-    let display_cs = peripherals.PIN_17;
-    let mosi = peripherals.PIN_19;
-    let clk = peripherals.PIN_18;
-    let rst = peripherals.PIN_0;
-    let dc = peripherals.PIN_16;
-    let mut display_config = spi::Config::default();
-    display_config.frequency = DISPLAY_FREQ;
-    display_config.phase = spi::Phase::CaptureOnSecondTransition;
-    display_config.polarity = spi::Polarity::IdleHigh;
-
-    // Init SPI
-    let spi: Spi<'_, _, Blocking> =
-        Spi::new_blocking(peripherals.SPI0, clk, mosi, miso, display_config.clone());
-    let spi_bus: Mutex<NoopRawMutex, _> = Mutex::new(RefCell::new(spi));
-
-    let display_spi = SpiDeviceWithConfig::new(
-        &spi_bus,
-        Output::new(display_cs, Level::High),
-        display_config,
-    );
-
-    let dc = Output::new(dc, Level::Low);
-    let rst = Output::new(rst, Level::Low);
-    let di = SPIDeviceInterface::new(display_spi, dc);
-
-    // Init ST7789 LCD
-    let mut display = ST7789::new(di, rst, 240, 240);
-    display.init(&mut Delay).unwrap();
-    display.set_orientation(Orientation::Portrait).unwrap();
-    display.clear(Rgb565::BLACK).unwrap();
+    let mut display = utils::pico_explorer_display!(peripherals);
     // ************************************************************************
 
     // Clear display
@@ -29,7 +29,6 @@ use embassy_sync::blocking_mutex::Mutex;
 use embassy_time::Timer;
 
 use core::fmt::Write;
-use embassy_time::Delay;
 use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
 use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::pixelcolor::Rgb565;
@@ -37,16 +36,12 @@ use embedded_graphics::prelude::*;
 use embedded_graphics::text::renderer::CharacterStyle;
 use embedded_graphics::text::Text;
 use heapless::String;
-use lab06_ex5::SPIDeviceInterface;
-use st7789::{Orientation, ST7789};
 
 bind_interrupts!(struct Irqs {
     // Use for the serial over USB driver
     USBCTRL_IRQ => InterruptHandler<USB>;
 });
 
-const DISPLAY_FREQ: u32 = 64_000_000;
-
 #[embassy_executor::task]
 async fn logger_task(driver: Driver<'static, USB>) {
     embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
@@ -71,49 +66,21 @@ async fn main(spawner: Spawner) {
 
     // TODO 1: Initialize the BMP280 SPI config
 
-    // Display SPI config
-    let mut display_config = spi::Config::default();
-    display_config.frequency = DISPLAY_FREQ;
-    display_config.phase = spi::Phase::CaptureOnSecondTransition;
-    display_config.polarity = spi::Polarity::IdleHigh;
-
     // TODO 2: Initialize MISO, MOSI and CLK pins for BMP280 (don't forget to change the circuit too!)
 
-    // Display SPI pins
-    let miso = peripherals.PIN_4;
-    let mosi = peripherals.PIN_19;
-    let clk = peripherals.PIN_18;
-
     // TODO 3: Initialize SPI for the BMP280 on SPI1
 
-    // Display SPI on SPI0
-    let spi_display: Spi<'_, _, Blocking> =
-        Spi::new_blocking(peripherals.SPI0, clk, mosi, miso, display_config.clone());
-    // SPI bus for display
-    let spi_bus: Mutex<NoopRawMutex, _> = Mutex::new(RefCell::new(spi_display));
+    // SPI bus for display (SPI0 on pins 18, 19 and 4)
+    let spi_bus = utils::pico_explorer_spi_bus!(peripherals);
 
     // TODO 4: Initialize CS pin for BMP280
-    let display_cs = Output::new(peripherals.PIN_17, Level::High);
-
-    // Display SPI device initialization
-    let display_spi = SpiDeviceWithConfig::new(&spi_bus, display_cs, display_config);
 
     // TODO 6: Create a bmp280 SpiDeviceWithConfig, similar to the Display device
     //         This is a driver that we can use do make the two SPI devices share the same SPI channel
     // Warning: You will need to change the physical wiring so that the sensor uses the same SPI pins as the screen
 
-    // Other display pins
-    let rst = peripherals.PIN_0;
-    let dc = peripherals.PIN_16;
-    let dc = Output::new(dc, Level::Low);
-    let rst = Output::new(rst, Level::Low);
-    let di = SPIDeviceInterface::new(display_spi, dc);
-
     // Init ST7789 LCD
-    let mut display = ST7789::new(di, rst, 240, 240);
-    display.init(&mut Delay).unwrap();
-    display.set_orientation(Orientation::Portrait).unwrap();
-    display.clear(Rgb565::BLACK).unwrap();
+    let mut display = utils::pico_explorer_display!(peripherals, spi_bus);
 
     // Define style
     let mut style = MonoTextStyle::new(&FONT_10X20, Rgb565::GREEN);
@@ -19,33 +19,12 @@ use embassy_rp::i2c::{Config as I2cConfig, I2c, InterruptHandler as I2CInterrupt
 use embassy_rp::peripherals::I2C0;
 use embedded_hal_async::i2c::{Error, I2c as _};
 
-use core::cell::RefCell;
-use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
-use embassy_rp::spi;
-use embassy_rp::spi::{Async, Blocking, Spi};
-use embassy_sync::blocking_mutex::raw::NoopRawMutex;
-use embassy_sync::blocking_mutex::Mutex;
-
-use core::fmt::Write;
-use embassy_time::Delay;
-use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
-use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::pixelcolor::Rgb565;
-use embedded_graphics::prelude::*;
-use embedded_graphics::text::renderer::CharacterStyle;
-use embedded_graphics::text::Text;
-use heapless::String;
-use lab07_all::SPIDeviceInterface;
-use st7789::{Orientation, ST7789};
-
 bind_interrupts!(struct Irqs {
     // Use for the serial over USB driver
     USBCTRL_IRQ => USBInterruptHandler<USB>;
     I2C0_IRQ => I2CInterruptHandler<I2C0>;
 });
 
-const DISPLAY_FREQ: u32 = 64_000_000;
-
 #[embassy_executor::task]
 async fn logger_task(driver: Driver<'static, USB>) {
     embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
@@ -68,44 +47,8 @@ async fn main(spawner: Spawner) {
     let driver = Driver::new(peripherals.USB, Irqs);
     spawner.spawn(logger_task(driver)).unwrap();
 
-    let mut display_config = spi::Config::default();
-    display_config.frequency = DISPLAY_FREQ;
-    display_config.phase = spi::Phase::CaptureOnSecondTransition;
-    display_config.polarity = spi::Polarity::IdleHigh;
-
-    // Display SPI pins
-    let miso = peripherals.PIN_4;
-    let mosi = peripherals.PIN_19;
-    let clk = peripherals.PIN_18;
-
-    // Display SPI on SPI0
-    let mut spi_display: Spi<'_, _, Blocking> =
-        Spi::new_blocking(peripherals.SPI0, clk, mosi, miso, display_config.clone());
-    // SPI bus for display
-    let spi_bus: Mutex<NoopRawMutex, _> = Mutex::new(RefCell::new(spi_display));
-
-    // TODO 4: Initialize CS pin for BMP280
-    let mut display_cs = Output::new(peripherals.PIN_17, Level::High);
-
-    // Display SPI device initialization
-    let display_spi = SpiDeviceWithConfig::new(&spi_bus, display_cs, display_config);
-
-    // Other display pins
-    let rst = peripherals.PIN_0;
-    let dc = peripherals.PIN_16;
-    let dc = Output::new(dc, Level::Low);
-    let rst = Output::new(rst, Level::Low);
-    let di = SPIDeviceInterface::new(display_spi, dc);
-
     // Init ST7789 LCD
-    let mut display = ST7789::new(di, rst, 240, 240);
-    display.init(&mut Delay).unwrap();
-    display.set_orientation(Orientation::Portrait).unwrap();
-    display.clear(Rgb565::BLACK).unwrap();
-
-    // Define style
-    let mut style = MonoTextStyle::new(&FONT_10X20, Rgb565::GREEN);
-    style.set_background_color(Some(Rgb565::BLACK));
+    let mut display = utils::pico_explorer_display!(peripherals);
 
     // EXERCISE 2 --------------------
     // TODO 1: Declare SDA and SCL pins
@@ -202,22 +145,7 @@ async fn main(spawner: Spawner) {
 
         // END EXERCISE 3 -------------------------
 
-        let mut text_temp = String::<64>::new();
-        let mut text_press = String::<64>::new();
-        write!(
-            text_temp,
-            "Temperature: {temperature_int}.{temperature_frac}"
-        )
-        .unwrap();
-
-        Text::new(&text_temp, Point::new(40, 110), style)
-            .draw(&mut display)
-            .unwrap();
-
-        write!(text_press, "Raw pressure: {pressure_raw}").unwrap();
-
-        Text::new(&text_press, Point::new(40, 150), style)
-            .draw(&mut display)
+        utils::display::screens::temperature_pressure(&mut display, temperature, pressure_raw)
             .unwrap();
 
         // Small delay for yielding
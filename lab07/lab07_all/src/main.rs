@@ -19,15 +19,7 @@ use embassy_rp::i2c::{Config as I2cConfig, I2c, InterruptHandler as I2CInterrupt
 use embassy_rp::peripherals::I2C0;
 use embedded_hal_async::i2c::{Error, I2c as _};
 
-use core::cell::RefCell;
-use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
-use embassy_rp::spi;
-use embassy_rp::spi::{Async, Blocking, Spi};
-use embassy_sync::blocking_mutex::raw::NoopRawMutex;
-use embassy_sync::blocking_mutex::Mutex;
-
 use core::fmt::Write;
-use embassy_time::Delay;
 use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
 use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::pixelcolor::Rgb565;
@@ -35,8 +27,6 @@ use embedded_graphics::prelude::*;
 use embedded_graphics::text::renderer::CharacterStyle;
 use embedded_graphics::text::Text;
 use heapless::String;
-use lab07_all::SPIDeviceInterface;
-use st7789::{Orientation, ST7789};
 
 bind_interrupts!(struct Irqs {
     // Use for the serial over USB driver
@@ -44,8 +34,6 @@ bind_interrupts!(struct Irqs {
     I2C0_IRQ => I2CInterruptHandler<I2C0>;
 });
 
-const DISPLAY_FREQ: u32 = 64_000_000;
-
 #[embassy_executor::task]
 async fn logger_task(driver: Driver<'static, USB>) {
     embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
@@ -68,39 +56,8 @@ async fn main(spawner: Spawner) {
     let driver = Driver::new(peripherals.USB, Irqs);
     spawner.spawn(logger_task(driver)).unwrap();
 
-    let mut display_config = spi::Config::default();
-    display_config.frequency = DISPLAY_FREQ;
-    display_config.phase = spi::Phase::CaptureOnSecondTransition;
-    display_config.polarity = spi::Polarity::IdleHigh;
-
-    // Display SPI pins
-    let miso = peripherals.PIN_4;
-    let mosi = peripherals.PIN_19;
-    let clk = peripherals.PIN_18;
-
-    // Display SPI
-    let mut spi_display: Spi<'_, _, Blocking> =
-        Spi::new_blocking(peripherals.SPI0, clk, mosi, miso, display_config.clone());
-    // SPI bus for display
-    let spi_bus: Mutex<NoopRawMutex, _> = Mutex::new(RefCell::new(spi_display));
-
-    let mut display_cs = Output::new(peripherals.PIN_17, Level::High);
-
-    // Display SPI device initialization
-    let display_spi = SpiDeviceWithConfig::new(&spi_bus, display_cs, display_config);
-
-    // Other display pins
-    let rst = peripherals.PIN_0;
-    let dc = peripherals.PIN_16;
-    let dc = Output::new(dc, Level::Low);
-    let rst = Output::new(rst, Level::Low);
-    let di = SPIDeviceInterface::new(display_spi, dc);
-
     // Init ST7789 LCD
-    let mut display = ST7789::new(di, rst, 240, 240);
-    display.init(&mut Delay).unwrap();
-    display.set_orientation(Orientation::Portrait).unwrap();
-    display.clear(Rgb565::BLACK).unwrap();
+    let mut display = utils::pico_explorer_display!(peripherals);
 
     // Define style
     let mut style = MonoTextStyle::new(&FONT_10X20, Rgb565::GREEN);
@@ -0,0 +1,297 @@
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use core::panic::PanicInfo;
+
+use bmp280::{Bmp280, SpiInterface};
+use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
+use embassy_executor::Spawner;
+use embassy_futures::select::{select, Either};
+use embassy_net::{Config, IpEndpoint, Stack, StackResources};
+use embassy_rp::adc::{Adc, Channel, Config as AdcConfig, InterruptHandler as AdcInterruptHandler};
+use embassy_rp::bind_interrupts;
+use embassy_rp::flash::{Blocking as FlashBlocking, Flash};
+use embassy_rp::gpio::{Level, Output, Pull};
+use embassy_rp::peripherals::{PIO0, PWM_CH0, PWM_CH1, PWM_CH2, SPI0};
+use embassy_rp::pio::InterruptHandler;
+use embassy_rp::spi::{self, Blocking, Spi};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Delay, Duration, Ticker, Timer};
+use embedded_graphics::pixelcolor::Rgb888;
+use heapless::String;
+use log::{info, warn};
+use static_cell::StaticCell;
+use utils::led::{Polarity, PwmOutput, RgbLed};
+use utils::mqtt::{Buffers, Connect, Error, Event, MqttClient, QoS};
+use utils::time::{sntp_task, timestamp, SntpConfig};
+use utils::wifi::config::parse_address;
+use utils::wifi::{
+    connection_task, Backoff, ConfigStore, ConnectionManager, SharedControl, WifiPins, CONNECTION,
+};
+
+// USB driver
+use embassy_rp::peripherals::USB;
+use embassy_rp::usb::{Driver, InterruptHandler as USBInterruptHandler};
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => USBInterruptHandler<USB>;
+    // PIO interrupt for CYW SPI communication
+    PIO0_IRQ_0 => InterruptHandler<PIO0>;
+    ADC_IRQ_FIFO => AdcInterruptHandler;
+});
+
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
+
+/// The address of the broker, given at build time in MQTT_BROKER
+const BROKER: &str = match option_env!("MQTT_BROKER") {
+    Some(broker) => broker,
+    None => "192.168.1.2",
+};
+const BROKER_PORT: u16 = 1883;
+
+/// The address of the NTP server, given at build time in NTP_SERVER
+const NTP_SERVER: &str = match option_env!("NTP_SERVER") {
+    Some(server) => server,
+    // time.cloudflare.com
+    None => "162.159.200.1",
+};
+
+/// Time between two readings of the sensors
+const TELEMETRY_PERIOD: Duration = Duration::from_secs(10);
+
+/// Sets the color of the RGB LED, as `r,g,b`, like `255,128,0`
+const LED_TOPIC: &str = "board/led/set";
+
+static TOP: u16 = 0x8000;
+
+type SpiBus =
+    embassy_sync::blocking_mutex::Mutex<NoopRawMutex, RefCell<Spi<'static, SPI0, Blocking>>>;
+type Sensor = Bmp280<
+    SpiInterface<
+        SpiDeviceWithConfig<'static, NoopRawMutex, Spi<'static, SPI0, Blocking>, Output<'static>>,
+    >,
+>;
+
+struct Board {
+    sensor: Sensor,
+    adc: Adc<'static, embassy_rp::adc::Async>,
+    light_sensor: Channel<'static>,
+    led: RgbLed<'static, PWM_CH0, PWM_CH1, PWM_CH2>,
+}
+
+impl Board {
+    /// Publishes the temperature, the pressure and the light level, with the time of
+    /// the reading once the clock is synchronized
+    async fn publish_telemetry(&mut self, client: &mut MqttClient<'_>) -> Result<(), Error> {
+        let mut values: [(&str, String<16>); 3] = Default::default();
+        values[0].0 = "board/temperature";
+        values[1].0 = "board/pressure";
+        values[2].0 = "board/light";
+
+        match self.sensor.measure(&mut Delay) {
+            Ok(measurement) => {
+                let temperature = measurement.temperature;
+                let sign = if temperature < 0 { "-" } else { "" };
+                let _ = write!(
+                    values[0].1,
+                    "{}{}.{:02}",
+                    sign,
+                    temperature.unsigned_abs() / 100,
+                    temperature.unsigned_abs() % 100
+                );
+                let _ = write!(values[1].1, "{}", measurement.pressure_pa());
+            }
+            Err(err) => warn!("BMP280 error: {:?}", err),
+        }
+        match self.adc.read(&mut self.light_sensor).await {
+            Ok(level) => {
+                let _ = write!(values[2].1, "{}", level);
+            }
+            Err(err) => warn!("ADC error: {:?}", err),
+        }
+
+        let time = utils::time::now();
+        for (topic, value) in values.iter().filter(|(_, value)| !value.is_empty()) {
+            let mut payload: String<64> = String::new();
+            let _ = match time {
+                Some(time) => write!(payload, "{{\"value\":{},\"time\":\"{}\"}}", value, time),
+                None => write!(payload, "{{\"value\":{}}}", value),
+            };
+            match client
+                .publish(topic, payload.as_bytes(), QoS::AtLeastOnce, false)
+                .await
+            {
+                // The broker is slow, this reading is skipped
+                Err(Error::InFlightFull) => warn!("{} not sent, waiting for the broker", topic),
+                result => {
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_message(&mut self, topic: &str, payload: &[u8]) {
+        if topic != LED_TOPIC {
+            return;
+        }
+        let mut channels = core::str::from_utf8(payload)
+            .unwrap_or_default()
+            .split(',')
+            .map(|value| value.trim().parse::<u8>().ok());
+        match (
+            channels.next(),
+            channels.next(),
+            channels.next(),
+            channels.next(),
+        ) {
+            (Some(Some(r)), Some(Some(g)), Some(Some(b)), None) => {
+                info!("LED color {} {} {}", r, g, b);
+                self.led.set(Rgb888::new(r, g, b));
+            }
+            _ => warn!("{}: expected r,g,b", LED_TOPIC),
+        }
+    }
+}
+
+/// Connects to the broker and exchanges messages until the connection is lost
+async fn run_client(
+    stack: &Stack<cyw43::NetDriver<'static>>,
+    buffers: &mut Buffers<512>,
+    broker: IpEndpoint,
+    board: &mut Board,
+    backoff: &mut Backoff,
+) -> Result<(), Error> {
+    let mut client = MqttClient::connect(stack, buffers, broker, &Connect::new("pico-w")).await?;
+    info!("connected to the broker");
+    backoff.reset();
+    client.subscribe(&[(LED_TOPIC, QoS::AtLeastOnce)]).await?;
+
+    let mut ticker = Ticker::every(TELEMETRY_PERIOD);
+    loop {
+        match select(client.next_event(), ticker.next()).await {
+            Either::First(event) => match event? {
+                Event::Message(message) => board.handle_message(message.topic, message.payload),
+                Event::Subscribed { granted, .. } => info!("subscribed, QoS {:?}", granted),
+                Event::Acked(_) => {}
+            },
+            Either::Second(()) => board.publish_telemetry(&mut client).await?,
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+}
+
+#[embassy_executor::task]
+async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
+    stack.run().await
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    // USB logger driver
+    let driver = Driver::new(peripherals.USB, Irqs);
+    spawner.spawn(logger_task(driver)).unwrap();
+
+    // BMP280 on the SPI bus of the Pico Explorer
+    let spi_bus: &'static SpiBus = utils::pico_explorer_spi_bus!(peripherals);
+    let mut bmp280_config = spi::Config::default();
+    bmp280_config.frequency = 2_000_000;
+    let bmp280_cs = Output::new(peripherals.PIN_3, Level::High);
+    let sensor =
+        Bmp280::new_spi(SpiDeviceWithConfig::new(spi_bus, bmp280_cs, bmp280_config)).unwrap();
+
+    // RGB LED, off
+    let mut board = Board {
+        sensor,
+        adc: Adc::new(peripherals.ADC, Irqs, AdcConfig::default()),
+        light_sensor: Channel::new_pin(peripherals.PIN_26, Pull::None),
+        led: RgbLed::new(
+            PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+            PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+            PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+            Polarity::CommonCathode,
+        ),
+    };
+
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
+        peripherals.DMA_CH0,
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
+
+    // Read the network configuration written with the `provision` binary
+    let flash = Flash::<_, FlashBlocking, FLASH_SIZE>::new_blocking(peripherals.FLASH);
+    let network = ConfigStore::new(flash).load_or_fallback();
+    let mut config = Config::default();
+    config.ipv4 = network.ipv4.clone();
+    let manager = ConnectionManager::new(network.credentials, network.ipv4);
+
+    // Generate random seed
+    let seed = 0x0123_4567_89ab_cdef;
+
+    // Init network stack, with sockets for DHCP, SNTP and MQTT
+    static STACK: StaticCell<Stack<cyw43::NetDriver<'static>>> = StaticCell::new();
+    static RESOURCES: StaticCell<StackResources<3>> = StaticCell::new();
+    let stack = &*STACK.init(Stack::new(
+        net_device,
+        config,
+        RESOURCES.init(StackResources::<3>::new()),
+        seed,
+    ));
+    spawner.spawn(net_task(stack)).unwrap();
+
+    static CONTROL: StaticCell<SharedControl> = StaticCell::new();
+    let control = &*CONTROL.init(Mutex::new(control));
+    spawner
+        .spawn(connection_task(manager, control, stack))
+        .unwrap();
+
+    // Synchronize the clock, for the time of the readings
+    let ntp_server = parse_address(NTP_SERVER).expect("NTP_SERVER is not an IPv4 address");
+    spawner
+        .spawn(sntp_task(stack, SntpConfig::new(ntp_server.into())))
+        .unwrap();
+
+    let broker = IpEndpoint::new(
+        parse_address(BROKER)
+            .expect("MQTT_BROKER is not an IPv4 address")
+            .into(),
+        BROKER_PORT,
+    );
+    let mut buffers = Buffers::<512>::new();
+    let mut backoff = Backoff::default();
+    loop {
+        CONNECTION.wait_up().await;
+        info!("{} connecting to the broker {:?}", timestamp(), broker);
+        if let Err(err) = run_client(stack, &mut buffers, broker, &mut board, &mut backoff).await {
+            warn!("{} MQTT error: {:?}", timestamp(), err);
+        }
+        Timer::after(backoff.next_delay()).await;
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
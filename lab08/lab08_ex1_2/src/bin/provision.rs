@@ -0,0 +1,38 @@
+//! Writes the network configuration used by the lab08 solutions into flash.
+//!
+//! Flash it with `cargo run --bin provision`, open the USB serial port with a
+//! terminal and type `help`. The configuration stays in flash when other programs
+//! are flashed.
+
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+
+use embassy_executor::Spawner;
+use embassy_rp::bind_interrupts;
+use embassy_rp::flash::{Blocking, Flash};
+use embassy_rp::peripherals::USB;
+use embassy_rp::usb::{Driver, InterruptHandler as USBInterruptHandler};
+use utils::wifi::ConfigStore;
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => USBInterruptHandler<USB>;
+});
+
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    let flash = Flash::<_, Blocking, FLASH_SIZE>::new_blocking(peripherals.FLASH);
+    let driver = Driver::new(peripherals.USB, Irqs);
+
+    utils::wifi::provision::usb_serial(driver, ConfigStore::new(flash)).await
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
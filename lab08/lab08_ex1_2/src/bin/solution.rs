@@ -5,21 +5,26 @@ use core::panic::PanicInfo;
 use core::str::from_utf8;
 
 use byte_slice_cast::AsByteSlice;
-use cyw43_pio::PioSpi;
 use embassy_executor::Spawner;
 use embassy_futures::select;
 use embassy_net::tcp::TcpSocket;
 use embassy_net::udp::{PacketMetadata, UdpSocket};
 use embassy_net::{Config, IpAddress, IpEndpoint, Ipv4Address, Ipv4Cidr, Stack, StackResources};
 use embassy_rp::bind_interrupts;
-use embassy_rp::gpio::{Input, Level, Output, Pull};
-use embassy_rp::peripherals::{DMA_CH0, PIO0};
-use embassy_rp::pio::{InterruptHandler, Pio};
-use embassy_time::{Duration, Timer};
+use embassy_rp::flash::{Blocking, Flash};
+use embassy_rp::gpio::{Input, Pull};
+use embassy_rp::peripherals::PIO0;
+use embassy_rp::pio::InterruptHandler;
+use embassy_sync::mutex::Mutex;
+use embassy_time::Duration;
 use embedded_io_async::Write;
 use heapless::Vec;
 use log::{info, warn};
 use static_cell::StaticCell;
+use utils::wifi::{
+    connection_task, ConfigStore, ConnectionManager, ScanConfig, SharedControl, WifiPins,
+    CONNECTION,
+};
 
 // USB driver
 use embassy_rp::peripherals::USB;
@@ -31,21 +36,13 @@ bind_interrupts!(struct Irqs {
     PIO0_IRQ_0 => InterruptHandler<PIO0>;
 });
 
-const WIFI_NETWORK: &str = "TEST";
-const WIFI_PASSWORD: &str = "test";
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
 
 #[embassy_executor::task]
 async fn logger_task(driver: Driver<'static, USB>) {
     embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
 }
 
-#[embassy_executor::task]
-async fn wifi_task(
-    runner: cyw43::Runner<'static, Output<'static>, PioSpi<'static, PIO0, 0, DMA_CH0>>,
-) -> ! {
-    runner.run().await
-}
-
 #[embassy_executor::task]
 async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
     stack.run().await
@@ -61,54 +58,46 @@ async fn main(spawner: Spawner) {
 
     let mut button_a = Input::new(peripherals.PIN_12, Pull::Up);
 
-    // Link CYW43 firmware
-    let fw = include_bytes!("../../../../cyw43-firmware/43439A0.bin");
-    let clm = include_bytes!("../../../../cyw43-firmware/43439A0_clm.bin");
-
-    // Init SPI for communication with CYW43
-    let pwr = Output::new(peripherals.PIN_23, Level::Low);
-    let cs = Output::new(peripherals.PIN_25, Level::High);
-    let mut pio = Pio::new(peripherals.PIO0, Irqs);
-    let spi = PioSpi::new(
-        &mut pio.common,
-        pio.sm0,
-        pio.irq0,
-        cs,
-        peripherals.PIN_24,
-        peripherals.PIN_29,
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, mut control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
         peripherals.DMA_CH0,
-    );
-
-    // Start Wi-Fi task
-    static STATE: StaticCell<cyw43::State> = StaticCell::new();
-    let state = STATE.init(cyw43::State::new());
-    let (net_device, mut control, runner) = cyw43::new(state, pwr, spi, fw).await;
-    spawner.spawn(wifi_task(runner)).unwrap();
-
-    // Init the device
-    control.init(clm).await;
-    control
-        .set_power_management(cyw43::PowerManagementMode::PowerSave)
-        .await;
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
 
     // TODO 1: Scan for Wi-Fi access points.
-    // let mut scanner = control.scan(Default::default()).await;
-    let mut number = 10;
-    loop {
-        while let Some(bss) = control.scan(Default::default()).await.next().await {
-            if let Ok(ssid_str) = from_utf8(&bss.ssid) {
-                info!("Scanned {}", ssid_str);
-                number -= 1;
-            }
-        }
-        if number == 0 {
-            break;
-        }
+    let access_points = utils::wifi::scan::<16>(&mut control, ScanConfig::default()).await;
+    for ap in access_points.iter() {
+        info!(
+            "Scanned {} ({}) on channel {}, {} dBm",
+            ap.ssid,
+            ap.bssid(),
+            ap.channel,
+            ap.rssi
+        );
     }
 
-    // TODO 3: Remove this line and create a configuration for a static IP instead
-    //         Use the IPv4 address and default gateway previously determined through DHCP
-    let config = Config::dhcpv4(Default::default());
+    // Read the network and the IPv4 configuration written with the `provision` binary
+    // of lab08_ex1_2, or use the one given at build time in WIFI_NETWORK, WIFI_PASSWORD
+    // and WIFI_IPV4
+    let flash = Flash::<_, Blocking, FLASH_SIZE>::new_blocking(peripherals.FLASH);
+    let network = ConfigStore::new(flash).load_or_fallback();
+    let mut config = Config::default();
+    config.ipv4 = network.ipv4.clone();
+
+    // The connection manager applies the configuration again after every join
+    let manager = ConnectionManager::new(network.credentials, network.ipv4);
 
     // Generate random seed
     let seed = 0x0123_4567_89ab_cdef;
@@ -126,24 +115,19 @@ async fn main(spawner: Spawner) {
     // Start network stack task
     spawner.spawn(net_task(stack)).unwrap();
 
-    loop {
-        // Join WPA2 access point
-        // TODO 2: Modify WIFI_NETWORK and WIFI_PASSWORD if you're connecting to a WPA AP
-        //         Use `join_open` instead if you're connecting to an open AP
-        match control.join_wpa2(WIFI_NETWORK, WIFI_PASSWORD).await {
-            Ok(_) => break,
-            Err(err) => {
-                info!("join failed with status {}", err.status);
-            }
-        }
-    }
+    // Share the control handle between the connection manager and the application
+    static CONTROL: StaticCell<SharedControl> = StaticCell::new();
+    let control = &*CONTROL.init(Mutex::new(control));
 
-    // Wait for DHCP (not necessary when using static IP)
-    info!("waiting for DHCP...");
-    while !stack.is_config_up() {
-        Timer::after_millis(100).await;
-    }
-    info!("DHCP is now up!");
+    // Join the access point, and re-join it if the connection is lost
+    spawner
+        .spawn(connection_task(manager, control, stack))
+        .unwrap();
+
+    // Wait for the connection and for the IP configuration
+    info!("waiting for the connection...");
+    CONNECTION.wait_up().await;
+    info!("Connection is up {:?}!", stack.config_v4());
 
     let mut rx_buffer = [0; 4096];
     let mut tx_buffer = [0; 4096];
@@ -153,7 +137,7 @@ async fn main(spawner: Spawner) {
         let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
         socket.set_timeout(Some(Duration::from_secs(10)));
 
-        control.gpio_set(0, false).await;
+        control.lock().await.gpio_set(0, false).await;
         info!("Listening on TCP:1234...");
 
         if let Err(e) = socket.accept(1234).await {
@@ -162,7 +146,7 @@ async fn main(spawner: Spawner) {
         }
 
         info!("Received connection from {:?}", socket.remote_endpoint());
-        control.gpio_set(0, true).await;
+        control.lock().await.gpio_set(0, true).await;
 
         loop {
             let n = match socket.read(&mut buf).await {
@@ -1,49 +1,146 @@
 #![no_std]
 #![no_main]
 
+use core::cell::RefCell;
 use core::panic::PanicInfo;
-use core::str::from_utf8;
 
-use byte_slice_cast::AsByteSlice;
-use cyw43_pio::PioSpi;
+use bmp280::{Bmp280, SpiInterface};
+use command_protocol::{
+    frame_id, Command, ErrorCode, Reply, Request, Response, Sensor, EVENT_ID, PORT,
+};
+use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
 use embassy_executor::Spawner;
-use embassy_futures::select;
-use embassy_net::tcp::TcpSocket;
+use embassy_futures::select::{select, Either};
 use embassy_net::udp::{PacketMetadata, UdpSocket};
-use embassy_net::{Config, IpAddress, IpEndpoint, Ipv4Address, Ipv4Cidr, Stack, StackResources};
+use embassy_net::{Config, IpEndpoint, Stack, StackResources};
+use embassy_rp::adc::{Adc, Channel, Config as AdcConfig, InterruptHandler as AdcInterruptHandler};
 use embassy_rp::bind_interrupts;
+use embassy_rp::flash::{Blocking as FlashBlocking, Flash};
 use embassy_rp::gpio::{Input, Level, Output, Pull};
-use embassy_rp::peripherals::{DMA_CH0, PIO0};
-use embassy_rp::pio::{InterruptHandler, Pio};
-use embassy_time::{Duration, Timer};
-use embedded_io_async::Write;
-use heapless::Vec;
+use embassy_rp::peripherals::{PIO0, PWM_CH0, PWM_CH1, PWM_CH2, PWM_CH3, SPI0};
+use embassy_rp::pio::InterruptHandler;
+use embassy_rp::pwm::{Config as PwmConfig, Pwm};
+use embassy_rp::spi::{self, Blocking, Spi};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Delay, Timer};
+use embedded_graphics::pixelcolor::Rgb888;
 use log::{info, warn};
 use static_cell::StaticCell;
+use utils::led::{Polarity, PwmOutput, RgbLed};
+use utils::wifi::{
+    connection_task, ConfigStore, ConnectionManager, SharedControl, WifiPins, CONNECTION,
+};
 
 // USB driver
 use embassy_rp::peripherals::USB;
-use embassy_rp::usb::{Driver, Endpoint, InterruptHandler as USBInterruptHandler};
+use embassy_rp::usb::{Driver, InterruptHandler as USBInterruptHandler};
 
 bind_interrupts!(struct Irqs {
     USBCTRL_IRQ => USBInterruptHandler<USB>;
     // PIO interrupt for CYW SPI communication
     PIO0_IRQ_0 => InterruptHandler<PIO0>;
+    ADC_IRQ_FIFO => AdcInterruptHandler;
 });
 
-const WIFI_NETWORK: &str = "TEST";
-const WIFI_PASSWORD: &str = "test";
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
 
+static TOP: u16 = 0x8000;
+
+/// Frequency of the buzzer PWM counter: the 125 MHz system clock divided by 64
+const BUZZER_DIVIDER: u8 = 64;
+const BUZZER_CLOCK: u32 = 125_000_000 / BUZZER_DIVIDER as u32;
+
+/// The tones to play, as (frequency, duration in ms)
+static TONES: Signal<CriticalSectionRawMutex, (u16, u16)> = Signal::new();
+
+type SpiBus =
+    embassy_sync::blocking_mutex::Mutex<NoopRawMutex, RefCell<Spi<'static, SPI0, Blocking>>>;
+type Bmp280Sensor = Bmp280<
+    SpiInterface<
+        SpiDeviceWithConfig<'static, NoopRawMutex, Spi<'static, SPI0, Blocking>, Output<'static>>,
+    >,
+>;
+
+struct Board {
+    control: &'static SharedControl,
+    bmp280: Option<Bmp280Sensor>,
+    adc: Adc<'static, embassy_rp::adc::Async>,
+    light_sensor: Channel<'static>,
+    led: RgbLed<'static, PWM_CH0, PWM_CH1, PWM_CH2>,
+}
+
+impl Board {
+    /// Runs a command and returns the reply for the host
+    async fn run(&mut self, command: Command) -> Reply {
+        match command {
+            Command::Ping => Reply::Ack,
+            Command::Led(on) => {
+                self.control.lock().await.gpio_set(0, on).await;
+                Reply::Ack
+            }
+            Command::Rgb { red, green, blue } => {
+                self.led.set(Rgb888::new(red, green, blue));
+                Reply::Ack
+            }
+            Command::Tone {
+                frequency,
+                duration_ms,
+            } => {
+                TONES.signal((frequency, duration_ms));
+                Reply::Ack
+            }
+            Command::ReadSensor(sensor) => match self.read(sensor).await {
+                Some(value) => Reply::Reading { sensor, value },
+                None => Reply::Error(ErrorCode::Unavailable),
+            },
+        }
+    }
+
+    async fn read(&mut self, sensor: Sensor) -> Option<i32> {
+        match sensor {
+            Sensor::Temperature => {
+                Some(self.bmp280.as_mut()?.measure(&mut Delay).ok()?.temperature)
+            }
+            Sensor::Pressure => {
+                let measurement = self.bmp280.as_mut()?.measure(&mut Delay).ok()?;
+                Some(measurement.pressure_pa() as i32)
+            }
+            Sensor::Light => Some(self.adc.read(&mut self.light_sensor).await.ok()? as i32),
+        }
+    }
+}
+
+/// Plays the tones of `TONES`, a new tone replaces the one that is playing
 #[embassy_executor::task]
-async fn logger_task(driver: Driver<'static, USB>) {
-    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+async fn buzzer_task(mut buzzer: Pwm<'static, PWM_CH3>) -> ! {
+    let mut tone = TONES.wait().await;
+    loop {
+        let (frequency, duration_ms) = tone;
+        let mut config: PwmConfig = Default::default();
+        config.divider = BUZZER_DIVIDER.into();
+        if frequency > 0 {
+            // The lowest frequency that fits in the 16 bit counter is 30 Hz
+            config.top = (BUZZER_CLOCK / frequency.max(30) as u32 - 1) as u16;
+            config.compare_a = config.top / 2;
+        }
+        buzzer.set_config(&config);
+
+        if frequency == 0 {
+            tone = TONES.wait().await;
+            continue;
+        }
+        tone = match select(Timer::after_millis(duration_ms as u64), TONES.wait()).await {
+            Either::First(()) => (0, 0),
+            Either::Second(next) => next,
+        };
+    }
 }
 
 #[embassy_executor::task]
-async fn wifi_task(
-    runner: cyw43::Runner<'static, Output<'static>, PioSpi<'static, PIO0, 0, DMA_CH0>>,
-) -> ! {
-    runner.run().await
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
 }
 
 #[embassy_executor::task]
@@ -61,37 +158,48 @@ async fn main(spawner: Spawner) {
 
     let mut button_a = Input::new(peripherals.PIN_12, Pull::Up);
 
-    // Link CYW43 firmware
-    let fw = include_bytes!("../../../../cyw43-firmware/43439A0.bin");
-    let clm = include_bytes!("../../../../cyw43-firmware/43439A0_clm.bin");
-
-    // Init SPI for communication with CYW43
-    let pwr = Output::new(peripherals.PIN_23, Level::Low);
-    let cs = Output::new(peripherals.PIN_25, Level::High);
-    let mut pio = Pio::new(peripherals.PIO0, Irqs);
-    let spi = PioSpi::new(
-        &mut pio.common,
-        pio.sm0,
-        pio.irq0,
-        cs,
-        peripherals.PIN_24,
-        peripherals.PIN_29,
-        peripherals.DMA_CH0,
-    );
+    // BMP280 on the SPI bus of the Pico Explorer, the board works without it
+    let spi_bus: &'static SpiBus = utils::pico_explorer_spi_bus!(peripherals);
+    let mut bmp280_config = spi::Config::default();
+    bmp280_config.frequency = 2_000_000;
+    let bmp280_cs = Output::new(peripherals.PIN_3, Level::High);
+    let bmp280 = Bmp280::new_spi(SpiDeviceWithConfig::new(spi_bus, bmp280_cs, bmp280_config));
+    if let Err(err) = &bmp280 {
+        warn!("BMP280 not found: {:?}", err);
+    }
+
+    // Buzzer, wired to GP6
+    let buzzer = Pwm::new_output_a(peripherals.PWM_CH3, peripherals.PIN_6, Default::default());
+    spawner.spawn(buzzer_task(buzzer)).unwrap();
 
-    // Start Wi-Fi task
-    static STATE: StaticCell<cyw43::State> = StaticCell::new();
-    let state = STATE.init(cyw43::State::new());
-    let (net_device, mut control, runner) = cyw43::new(state, pwr, spi, fw).await;
-    spawner.spawn(wifi_task(runner)).unwrap();
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
+        peripherals.DMA_CH0,
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
 
-    // Init the device
-    control.init(clm).await;
-    control
-        .set_power_management(cyw43::PowerManagementMode::PowerSave)
-        .await;
+    // Read the network and the IPv4 configuration written with the `provision` binary
+    // of lab08_ex1_2, or use the one given at build time in WIFI_NETWORK, WIFI_PASSWORD
+    // and WIFI_IPV4
+    let flash = Flash::<_, FlashBlocking, FLASH_SIZE>::new_blocking(peripherals.FLASH);
+    let network = ConfigStore::new(flash).load_or_fallback();
+    let mut config = Config::default();
+    config.ipv4 = network.ipv4.clone();
 
-    let config = Config::dhcpv4(Default::default());
+    // The connection manager applies the configuration again after every join
+    let manager = ConnectionManager::new(network.credentials, network.ipv4);
 
     // Generate random seed
     let seed = 0x0123_4567_89ab_cdef;
@@ -109,107 +217,102 @@ async fn main(spawner: Spawner) {
     // Start network stack task
     spawner.spawn(net_task(stack)).unwrap();
 
-    loop {
-        // Join WPA2 access point
-        // TODO 2: Modify WIFI_NETWORK and WIFI_PASSWORD if you're connecting to a WPA AP
-        //         Use `join_open` instead if you're connecting to an open AP
-        match control.join_wpa2(WIFI_NETWORK, WIFI_PASSWORD).await {
-            Ok(_) => break,
-            Err(err) => {
-                info!("join failed with status {}", err.status);
-            }
-        }
-    }
+    // Share the control handle between the connection manager and the application
+    static CONTROL: StaticCell<SharedControl> = StaticCell::new();
+    let control = &*CONTROL.init(Mutex::new(control));
 
-    // Wait for DHCP (not necessary when using static IP)
-    info!("waiting for DHCP...");
-    while !stack.is_config_up() {
-        Timer::after_millis(100).await;
-    }
-    info!("DHCP is now up {:?}!", stack.config_v4());
+    // Join the access point, and re-join it if the connection is lost
+    spawner
+        .spawn(connection_task(manager, control, stack))
+        .unwrap();
 
-    // And now we can use it!
+    // RGB LED, off
+    let mut board = Board {
+        control,
+        bmp280: bmp280.ok(),
+        adc: Adc::new(peripherals.ADC, Irqs, AdcConfig::default()),
+        light_sensor: Channel::new_pin(peripherals.PIN_26, Pull::None),
+        led: RgbLed::new(
+            PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+            PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+            PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+            Polarity::CommonCathode,
+        ),
+    };
 
-    // TODO 1: Create buffers
-    let mut rx_buffer = [0; 4096];
-    let mut rx_metadata_buffer = [PacketMetadata::EMPTY; 3];
-    let mut tx_buffer = [0; 4096];
-    let mut tx_metadata_buffer = [PacketMetadata::EMPTY; 3];
+    // Wait for the connection and for the IP configuration
+    info!("waiting for the connection...");
+    CONNECTION.wait_up().await;
+    info!("Connection is up {:?}!", stack.config_v4());
 
-    let mut buf = [0u8; 4096];
+    let mut rx_buffer = [0; 1024];
+    let mut rx_metadata_buffer = [PacketMetadata::EMPTY; 8];
+    let mut tx_buffer = [0; 1024];
+    let mut tx_metadata_buffer = [PacketMetadata::EMPTY; 8];
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_metadata_buffer,
+        &mut rx_buffer,
+        &mut tx_metadata_buffer,
+        &mut tx_buffer,
+    );
+    // Cannot fail, the socket is not bound and the port is not 0
+    socket.bind(PORT).unwrap();
+    info!("Command server on UDP:{}", PORT);
 
+    // The button events go to the last host that sent a request
+    let mut host: Option<IpEndpoint> = None;
+    let mut frame = [0u8; 64];
     loop {
-        // TODO 2: Initialize UDP socket
-        let mut socket = UdpSocket::new(
-            stack,
-            &mut rx_metadata_buffer,
-            &mut rx_buffer,
-            &mut tx_metadata_buffer,
-            &mut tx_buffer,
-        );
-
-        info!("Starting server on UDP:1234...");
-
-        // TODO 3: Bind socket to port
-        if let Err(e) = socket.bind(1234) {
-            warn!("accept error: {:?}", e);
-            continue;
-        }
-
-        // TODO 4: Wait for button press
-        // button_a.wait_for_falling_edge().await;
-
-        // TODO 6: Remove the line in which you wait for the button press
-        //         Instead, move the `wait_for_falling_edge` future inside a `select`
-        //         The second future inside `select` will be the `recv_from` future
-
-        control.gpio_set(0, true).await;
-        let buffer = "Button was pressed!\n".as_bytes();
-        match socket
-            .send_to(
-                &buffer,
-                IpEndpoint::new(IpAddress::v4(192, 168, 1, 132), 1234),
-            )
-            .await
+        let (response, destination) = match select(
+            button_a.wait_for_falling_edge(),
+            socket.recv_from(&mut frame),
+        )
+        .await
         {
-            Ok(()) => {
-                info!("sent")
+            Either::First(()) => {
+                let Some(host) = host else {
+                    info!("Button A pressed, no host to tell");
+                    continue;
+                };
+                let event = Response {
+                    id: EVENT_ID,
+                    reply: Reply::Button(0),
+                };
+                (event, host)
             }
-            Err(e) => {
-                warn!("send error: {:?}", e);
+            Either::Second(Ok((n, endpoint))) => {
+                let reply = match Request::decode(&frame[..n]) {
+                    Ok(request) => {
+                        info!(
+                            "Request {} from {:?}: {:?}",
+                            request.id, endpoint, request.command
+                        );
+                        host = Some(endpoint);
+                        let reply = board.run(request.command).await;
+                        Response {
+                            id: request.id,
+                            reply,
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Invalid request from {:?}: {:?}", endpoint, err);
+                        Response {
+                            id: frame_id(&frame[..n]),
+                            reply: Reply::Error(err.code()),
+                        }
+                    }
+                };
+                (reply, endpoint)
             }
-        }
-        control.gpio_set(0, false).await;
-        match select::select(button_a.wait_for_falling_edge(), socket.recv_from(&mut buf)).await {
-            // TODO 7: Move the code for sending the message to be run when the `button_a` future completes
-            select::Either::First(_res_btn) => {
-                // TODO 5: Send message to the correct IP and port
+            Either::Second(Err(err)) => {
+                warn!("receive error: {:?}", err);
+                continue;
             }
-            // TODO 8: Handle the `recv_from` future accordingly: the LED on the Pico should light up/turn off
-            //         according to the received command
-            // Hint: To light the LED on the Pico W, use:
-            // ```rust
-            // control.gpio_set(0, true).await; // turn on LED
-            // control.gpio_set(0, false).await; // turn off LED
-            // ```
-            select::Either::Second(res_read) => match res_read {
-                Ok((n, endpoint)) => {
-                    info!(
-                        "Received from {:?}: {:?}",
-                        endpoint,
-                        from_utf8(&buf[..n]).unwrap().trim()
-                    );
-                    let command = from_utf8(&buf[..n]).unwrap().trim();
-                    match command {
-                        "led:on" => control.gpio_set(0, true).await,
-                        "led:off" => control.gpio_set(0, false).await,
-                        _ => {}
-                    }
-                }
-                Err(_) => {
-                    info!("An error occurred when receiving the packet!");
-                }
-            },
+        };
+
+        if let Err(err) = socket.send_to(&response.encode(), destination).await {
+            warn!("send error: {:?}", err);
         }
     }
 }
@@ -6,21 +6,21 @@ use core::panic::PanicInfo;
 use core::str::from_utf8;
 
 use byte_slice_cast::AsByteSlice;
-use cyw43_pio::PioSpi;
 use embassy_executor::Spawner;
 use embassy_futures::select;
 use embassy_net::tcp::TcpSocket;
 use embassy_net::udp::{PacketMetadata, UdpSocket};
 use embassy_net::{Config, IpAddress, IpEndpoint, Ipv4Address, Ipv4Cidr, Stack, StackResources};
 use embassy_rp::bind_interrupts;
-use embassy_rp::gpio::{Input, Level, Output, Pull};
-use embassy_rp::peripherals::{DMA_CH0, PIO0};
-use embassy_rp::pio::{InterruptHandler, Pio};
+use embassy_rp::gpio::{Input, Pull};
+use embassy_rp::peripherals::PIO0;
+use embassy_rp::pio::InterruptHandler;
 use embassy_time::{Duration, Timer};
 use embedded_io_async::Write;
 use heapless::Vec;
 use log::{info, warn};
 use static_cell::StaticCell;
+use utils::wifi::WifiPins;
 
 // USB driver
 use embassy_rp::peripherals::USB;
@@ -40,13 +40,6 @@ async fn logger_task(driver: Driver<'static, USB>) {
     embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
 }
 
-#[embassy_executor::task]
-async fn wifi_task(
-    runner: cyw43::Runner<'static, Output<'static>, PioSpi<'static, PIO0, 0, DMA_CH0>>,
-) -> ! {
-    runner.run().await
-}
-
 #[embassy_executor::task]
 async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
     stack.run().await
@@ -60,35 +53,23 @@ async fn main(spawner: Spawner) {
     let driver = Driver::new(peripherals.USB, Irqs);
     spawner.spawn(logger_task(driver)).unwrap();
 
-    // Link CYW43 firmware
-    let fw = include_bytes!("../../../cyw43-firmware/43439A0.bin");
-    let clm = include_bytes!("../../../cyw43-firmware/43439A0_clm.bin");
-
-    // Init SPI for communication with CYW43
-    let pwr = Output::new(peripherals.PIN_23, Level::Low);
-    let cs = Output::new(peripherals.PIN_25, Level::High);
-    let mut pio = Pio::new(peripherals.PIO0, Irqs);
-    let spi = PioSpi::new(
-        &mut pio.common,
-        pio.sm0,
-        pio.irq0,
-        cs,
-        peripherals.PIN_24,
-        peripherals.PIN_29,
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, mut control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
         peripherals.DMA_CH0,
-    );
-
-    // Start Wi-Fi task
-    static STATE: StaticCell<cyw43::State> = StaticCell::new();
-    let state = STATE.init(cyw43::State::new());
-    let (net_device, mut control, runner) = cyw43::new(state, pwr, spi, fw).await;
-    spawner.spawn(wifi_task(runner)).unwrap();
-
-    // Init the device
-    control.init(clm).await;
-    control
-        .set_power_management(cyw43::PowerManagementMode::PowerSave)
-        .await;
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
 
     let config = Config::dhcpv4(Default::default());
 
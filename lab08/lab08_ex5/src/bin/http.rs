@@ -0,0 +1,223 @@
+#![no_std]
+#![no_main]
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use core::panic::PanicInfo;
+
+use bmp280::{Bmp280, SpiInterface};
+use embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig;
+use embassy_executor::Spawner;
+use embassy_net::{Config, Ipv4Address, Ipv4Cidr, Stack, StackResources, StaticConfigV4};
+use embassy_rp::bind_interrupts;
+use embassy_rp::gpio::{Level, Output};
+use embassy_rp::peripherals::{PIO0, PWM_CH0, PWM_CH1, PWM_CH2, SPI0};
+use embassy_rp::pio::InterruptHandler;
+use embassy_rp::spi::{self, Blocking, Spi};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_time::Delay;
+use embedded_graphics::pixelcolor::Rgb888;
+use heapless::String;
+use log::info;
+use static_cell::StaticCell;
+use utils::http::{Error, Handler, Method, Request, Response, Router, Sent, Status};
+use utils::led::{Polarity, PwmOutput, RgbLed};
+use utils::wifi::ap::{self, ApConfig};
+use utils::wifi::dhcp::{dhcp_server_task, DhcpServerConfig};
+use utils::wifi::WifiPins;
+
+// USB driver
+use embassy_rp::peripherals::USB;
+use embassy_rp::usb::{Driver, InterruptHandler as USBInterruptHandler};
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => USBInterruptHandler<USB>;
+    // PIO interrupt for CYW SPI communication
+    PIO0_IRQ_0 => InterruptHandler<PIO0>;
+});
+
+static TOP: u16 = 0x8000;
+
+type SpiBus =
+    embassy_sync::blocking_mutex::Mutex<NoopRawMutex, RefCell<Spi<'static, SPI0, Blocking>>>;
+type Sensor = Bmp280<
+    SpiInterface<
+        SpiDeviceWithConfig<'static, NoopRawMutex, Spi<'static, SPI0, Blocking>, Output<'static>>,
+    >,
+>;
+
+/// The page shown by the browser: a form for the LED and a link to the sensor.
+const INDEX: [&str; 3] = [
+    "<!DOCTYPE html><html><head><title>Pico W</title></head><body>",
+    "<h1>Pico W</h1><p><a href=\"/sensor\">Sensor</a></p>",
+    "<form method=\"post\" action=\"/led\">\
+     R <input name=\"r\" type=\"number\" min=\"0\" max=\"255\" value=\"0\"> \
+     G <input name=\"g\" type=\"number\" min=\"0\" max=\"255\" value=\"0\"> \
+     B <input name=\"b\" type=\"number\" min=\"0\" max=\"255\" value=\"0\"> \
+     <button>Set</button></form></body></html>",
+];
+
+#[derive(Clone, Copy)]
+enum Route {
+    Index,
+    Sensor,
+    Led,
+}
+
+struct App {
+    sensor: Sensor,
+    led: RgbLed<'static, PWM_CH0, PWM_CH1, PWM_CH2>,
+}
+
+impl Handler<Route> for App {
+    async fn handle(
+        &mut self,
+        route: Route,
+        request: &Request<'_>,
+        response: Response<'_, '_>,
+    ) -> Result<Sent, Error> {
+        match route {
+            Route::Index => {
+                // The page is sent in pieces, as it is written
+                let mut body = response.chunked(Status::Ok, "text/html").await?;
+                for part in INDEX {
+                    body.write_str(part).await?;
+                }
+                body.finish().await
+            }
+            Route::Sensor => {
+                let Ok(measurement) = self.sensor.measure(&mut Delay) else {
+                    return response.error(Status::ServiceUnavailable).await;
+                };
+                let temperature = measurement.temperature;
+                let sign = if temperature < 0 { "-" } else { "" };
+                let mut json: String<96> = String::new();
+                let _ = write!(
+                    json,
+                    "{{\"temperature\":{}{}.{:02},\"pressure\":{}}}",
+                    sign,
+                    temperature.unsigned_abs() / 100,
+                    temperature.unsigned_abs() % 100,
+                    measurement.pressure_pa()
+                );
+                response
+                    .send(Status::Ok, "application/json", json.as_bytes())
+                    .await
+            }
+            Route::Led => {
+                // The color comes from the form, or from the query string: /led?r=255
+                let fields = request.form().unwrap_or(request.query);
+                let (Some(r), Some(g), Some(b)) = (
+                    fields.parse::<u8>("r"),
+                    fields.parse::<u8>("g"),
+                    fields.parse::<u8>("b"),
+                ) else {
+                    return response.error(Status::UnprocessableContent).await;
+                };
+                info!("LED color {} {} {}", r, g, b);
+                self.led.set(Rgb888::new(r, g, b));
+                // Back to the form
+                response
+                    .send_with_headers(Status::SeeOther, "text/plain", &[("Location", "/")], b"")
+                    .await
+            }
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn logger_task(driver: Driver<'static, USB>) {
+    embassy_usb_logger::run!(1024, log::LevelFilter::Info, driver);
+}
+
+#[embassy_executor::task]
+async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
+    stack.run().await
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let peripherals = embassy_rp::init(Default::default());
+
+    // Start the USB logger driver
+    let driver = Driver::new(peripherals.USB, Irqs);
+    spawner.spawn(logger_task(driver)).unwrap();
+
+    // BMP280 on the SPI bus of the Pico Explorer
+    let spi_bus: &'static SpiBus = utils::pico_explorer_spi_bus!(peripherals);
+    let mut bmp280_config = spi::Config::default();
+    bmp280_config.frequency = 2_000_000;
+    let bmp280_cs = Output::new(peripherals.PIN_3, Level::High);
+    let sensor =
+        Bmp280::new_spi(SpiDeviceWithConfig::new(spi_bus, bmp280_cs, bmp280_config)).unwrap();
+
+    // RGB LED, off
+    let mut app = App {
+        sensor,
+        led: RgbLed::new(
+            PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+            PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+            PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+            Polarity::CommonCathode,
+        ),
+    };
+
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, mut control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
+        peripherals.DMA_CH0,
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
+
+    // The access point has a static address and gives addresses to its clients
+    let config = Config::ipv4_static(StaticConfigV4 {
+        address: Ipv4Cidr::new(Ipv4Address::new(192, 168, 4, 1), 24),
+        dns_servers: heapless::Vec::new(),
+        gateway: None,
+    });
+
+    // Generate random seed
+    let seed = 0x0123_4567_89ab_cdef; // chosen by fair dice roll. guarenteed to be random.
+
+    // Init network stack, with sockets for the DHCP and the HTTP servers
+    static STACK: StaticCell<Stack<cyw43::NetDriver<'static>>> = StaticCell::new();
+    static RESOURCES: StaticCell<StackResources<3>> = StaticCell::new();
+    let stack = &*STACK.init(Stack::new(
+        net_device,
+        config,
+        RESOURCES.init(StackResources::<3>::new()),
+        seed,
+    ));
+
+    spawner.spawn(net_task(stack)).unwrap();
+
+    let ap_config = ApConfig::wpa2("Pico", "embassy-rs", 5).unwrap();
+    ap::start(&mut control, &ap_config).await;
+
+    let dhcp = DhcpServerConfig::new([192, 168, 4, 1], 24, [192, 168, 4, 100]);
+    spawner.spawn(dhcp_server_task(stack, dhcp)).unwrap();
+
+    // Connect to the Pico Wi-Fi and open http://192.168.4.1 in a browser
+    let router = Router::<Route, 3>::new()
+        .route(Method::Get, "/", Route::Index)
+        .route(Method::Get, "/sensor", Route::Sensor)
+        .route(Method::Post, "/led", Route::Led);
+    info!("HTTP server on port 80");
+    utils::http::serve(stack, 80, &router, &mut app).await;
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
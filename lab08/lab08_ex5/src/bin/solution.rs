@@ -5,21 +5,24 @@ use core::panic::PanicInfo;
 use core::str::from_utf8;
 
 use byte_slice_cast::AsByteSlice;
-use cyw43_pio::PioSpi;
 use embassy_executor::Spawner;
 use embassy_futures::select;
 use embassy_net::tcp::TcpSocket;
 use embassy_net::udp::{PacketMetadata, UdpSocket};
 use embassy_net::{Config, IpAddress, IpEndpoint, Ipv4Address, Ipv4Cidr, Stack, StackResources};
 use embassy_rp::bind_interrupts;
-use embassy_rp::gpio::{Input, Level, Output, Pull};
-use embassy_rp::peripherals::{DMA_CH0, PIO0};
-use embassy_rp::pio::{InterruptHandler, Pio};
+use embassy_rp::gpio::{Input, Pull};
+use embassy_rp::peripherals::PIO0;
+use embassy_rp::pio::InterruptHandler;
+use embassy_sync::mutex::Mutex;
 use embassy_time::{Duration, Timer};
 use embedded_io_async::Write;
 use heapless::Vec;
 use log::{info, warn};
 use static_cell::StaticCell;
+use utils::wifi::ap::{self, ApConfig, StationEvent, StationMonitor};
+use utils::wifi::dhcp::{dhcp_server_task, DhcpServerConfig};
+use utils::wifi::{SharedControl, WifiPins};
 
 // USB driver
 use embassy_rp::peripherals::USB;
@@ -37,15 +40,25 @@ async fn logger_task(driver: Driver<'static, USB>) {
 }
 
 #[embassy_executor::task]
-async fn wifi_task(
-    runner: cyw43::Runner<'static, Output<'static>, PioSpi<'static, PIO0, 0, DMA_CH0>>,
-) -> ! {
-    runner.run().await
+async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
+    stack.run().await
 }
 
 #[embassy_executor::task]
-async fn net_task(stack: &'static Stack<cyw43::NetDriver<'static>>) -> ! {
-    stack.run().await
+async fn station_task() -> ! {
+    let mut monitor = StationMonitor::new().unwrap();
+    loop {
+        let change = monitor.next_change().await;
+        match change.event {
+            StationEvent::Joined(mac) => info!("Station {:02x?} joined", mac),
+            StationEvent::Left(mac) => info!("Station {:02x?} left", mac),
+        }
+        if change.is_last_left() {
+            info!("No more stations");
+        } else {
+            info!("{} stations connected", change.count);
+        }
+    }
 }
 
 #[embassy_executor::main]
@@ -58,39 +71,27 @@ async fn main(spawner: Spawner) {
 
     let mut button_a = Input::new(peripherals.PIN_12, Pull::Up);
 
-    // Link CYW43 firmware
-    let fw = include_bytes!("../../../../cyw43-firmware/43439A0.bin");
-    let clm = include_bytes!("../../../../cyw43-firmware/43439A0_clm.bin");
-
-    // Init SPI for communication with CYW43
-    let pwr = Output::new(peripherals.PIN_23, Level::Low);
-    let cs = Output::new(peripherals.PIN_25, Level::High);
-    let mut pio = Pio::new(peripherals.PIO0, Irqs);
-    let spi = PioSpi::new(
-        &mut pio.common,
-        pio.sm0,
-        pio.irq0,
-        cs,
-        peripherals.PIN_24,
-        peripherals.PIN_29,
+    // Init the CYW43 Wi-Fi chip and start its task
+    let pins = WifiPins {
+        pwr: peripherals.PIN_23,
+        dio: peripherals.PIN_24,
+        cs: peripherals.PIN_25,
+        clk: peripherals.PIN_29,
+    };
+    let (net_device, mut control) = utils::wifi::init(
+        spawner,
+        pins,
+        peripherals.PIO0,
         peripherals.DMA_CH0,
-    );
-
-    // Start Wi-Fi task
-    static STATE: StaticCell<cyw43::State> = StaticCell::new();
-    let state = STATE.init(cyw43::State::new());
-    let (net_device, mut control, runner) = cyw43::new(state, pwr, spi, fw).await;
-    spawner.spawn(wifi_task(runner)).unwrap();
+        Irqs,
+        cyw43::PowerManagementMode::PowerSave,
+    )
+    .await
+    .unwrap();
 
-    // Init the device
-    control.init(clm).await;
-    control
-        .set_power_management(cyw43::PowerManagementMode::PowerSave)
-        .await;
-
-    // Use a link-local address for communication without DHCP server
+    // The access point has a static address and gives addresses to its clients
     let config = Config::ipv4_static(embassy_net::StaticConfigV4 {
-        address: embassy_net::Ipv4Cidr::new(embassy_net::Ipv4Address::new(169, 254, 1, 1), 16),
+        address: embassy_net::Ipv4Cidr::new(embassy_net::Ipv4Address::new(192, 168, 4, 1), 24),
         dns_servers: heapless::Vec::new(),
         gateway: None,
     });
@@ -110,7 +111,20 @@ async fn main(spawner: Spawner) {
 
     spawner.spawn(net_task(stack)).unwrap();
 
-    control.start_ap_open("Pico", 5).await;
+    let ap_config = ApConfig::wpa2("Pico", "embassy-rs", 5).unwrap();
+    ap::start(&mut control, &ap_config).await;
+
+    // The LED is on while at least one station is connected
+    static CONTROL: StaticCell<SharedControl> = StaticCell::new();
+    let control = &*CONTROL.init(Mutex::new(control));
+    control.lock().await.gpio_set(0, false).await;
+    spawner.spawn(ap::station_led_task(control)).unwrap();
+
+    // Give the clients an address from 192.168.4.100. The default lease is short, so
+    // the server notices the stations that leave without releasing their address.
+    let dhcp = DhcpServerConfig::new([192, 168, 4, 1], 24, [192, 168, 4, 100]);
+    spawner.spawn(dhcp_server_task(stack, dhcp)).unwrap();
+    spawner.spawn(station_task()).unwrap();
 
     // And now we can use it!
 
@@ -122,7 +136,6 @@ async fn main(spawner: Spawner) {
         let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
         socket.set_timeout(Some(Duration::from_secs(10)));
 
-        control.gpio_set(0, false).await;
         info!("Listening on TCP:1234...");
         if let Err(e) = socket.accept(1234).await {
             warn!("accept error: {:?}", e);
@@ -130,7 +143,6 @@ async fn main(spawner: Spawner) {
         }
 
         info!("Received connection from {:?}", socket.remote_endpoint());
-        control.gpio_set(0, true).await;
 
         loop {
             let n = match socket.read(&mut buf).await {
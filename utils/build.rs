@@ -0,0 +1,7 @@
+//! Sets the location of the CYW43 firmware embedded by `utils::wifi`.
+
+fn main() {
+    if std::env::var_os("CARGO_FEATURE_RP2040").is_some() {
+        build_script::cyw43_firmware();
+    }
+}
@@ -0,0 +1,217 @@
+//! Async display interface, for SPI buses driven by DMA.
+//!
+//! `display-interface` 0.4 only defines a blocking [`WriteOnlyDataCommand`], so
+//! [`AsyncWriteOnlyDataCommand`] is its async counterpart. While a transfer is
+//! running, the executor is free to poll the other tasks.
+//!
+//! [`WriteOnlyDataCommand`]: display_interface::WriteOnlyDataCommand
+
+use display_interface::{DataFormat, DisplayError};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiDevice;
+
+/// Size of the scratch buffer used to convert 16 bit words and to collect iterators.
+///
+/// It is larger than the blocking one, as every chunk is a separate DMA transfer.
+const SCRATCH_LEN: usize = 1024;
+
+/// Async version of [`WriteOnlyDataCommand`](display_interface::WriteOnlyDataCommand).
+#[allow(async_fn_in_trait)]
+pub trait AsyncWriteOnlyDataCommand {
+    /// Send a batch of commands to the display.
+    async fn send_commands(&mut self, cmds: DataFormat<'_>) -> Result<(), DisplayError>;
+
+    /// Send pixel data to the display.
+    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError>;
+}
+
+/// Async SPI display interface.
+///
+/// This combines an async SPI device and a data/command pin.
+pub struct SPIDeviceInterfaceAsync<SPI, DC> {
+    spi: SPI,
+    dc: DC,
+}
+
+impl<SPI, DC> SPIDeviceInterfaceAsync<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    /// Create new async SPI interface for communication with a display driver
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        Self { spi, dc }
+    }
+}
+
+impl<SPI, DC> AsyncWriteOnlyDataCommand for SPIDeviceInterfaceAsync<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    async fn send_commands(&mut self, cmds: DataFormat<'_>) -> Result<(), DisplayError> {
+        // 1 = data, 0 = command
+        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
+
+        send_u8(&mut self.spi, cmds).await
+    }
+
+    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        // 1 = data, 0 = command
+        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+
+        send_u8(&mut self.spi, buf).await
+    }
+}
+
+async fn send_u8<T: SpiDevice>(spi: &mut T, words: DataFormat<'_>) -> Result<(), DisplayError> {
+    match words {
+        DataFormat::U8(slice) => spi.write(slice).await,
+        DataFormat::U16(slice) => {
+            use byte_slice_cast::*;
+            spi.write(slice.as_byte_slice()).await
+        }
+        DataFormat::U16LE(slice) => {
+            write_bytes(spi, slice.iter().flat_map(|v| v.to_le_bytes())).await
+        }
+        DataFormat::U16BE(slice) => {
+            write_bytes(spi, slice.iter().flat_map(|v| v.to_be_bytes())).await
+        }
+        DataFormat::U8Iter(iter) => write_bytes(spi, iter).await,
+        DataFormat::U16LEIter(iter) => write_bytes(spi, iter.flat_map(u16::to_le_bytes)).await,
+        DataFormat::U16BEIter(iter) => write_bytes(spi, iter.flat_map(u16::to_be_bytes)).await,
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    }
+    .map_err(|_| DisplayError::BusWriteError)
+}
+
+/// Writes the bytes of `iter` in chunks of [`SCRATCH_LEN`].
+async fn write_bytes<T: SpiDevice>(
+    spi: &mut T,
+    iter: impl Iterator<Item = u8>,
+) -> Result<(), T::Error> {
+    let mut buf = [0; SCRATCH_LEN];
+    let mut i = 0;
+
+    for v in iter {
+        buf[i] = v;
+        i += 1;
+
+        if i == buf.len() {
+            spi.write(&buf).await?;
+            i = 0;
+        }
+    }
+
+    if i > 0 {
+        spi.write(&buf[..i]).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::mock::{block_on, display_bus, Event};
+
+    /// Sends `data` after a command, and returns what the display received.
+    fn send(data: DataFormat<'_>) -> Vec<Event> {
+        let (spi, dc, log) = display_bus();
+        let mut interface = SPIDeviceInterfaceAsync::new(spi, dc);
+        block_on(async {
+            interface
+                .send_commands(DataFormat::U8(&[0x2C]))
+                .await
+                .unwrap();
+            interface.send_data(data).await.unwrap();
+        });
+        log.take()
+    }
+
+    /// Returns what the display receives for `bytes` of data after a command.
+    fn expected(bytes: &[u8]) -> Vec<Event> {
+        vec![
+            Event::Dc(false),
+            Event::Write(vec![0x2C]),
+            Event::Dc(true),
+            Event::Write(bytes.to_vec()),
+        ]
+    }
+
+    #[test]
+    fn u8() {
+        assert_eq!(send(DataFormat::U8(&[1, 2, 3])), expected(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn u16_is_native_endian() {
+        let bytes = [0x1234u16.to_ne_bytes(), 0xABCDu16.to_ne_bytes()].concat();
+        assert_eq!(send(DataFormat::U16(&[0x1234, 0xABCD])), expected(&bytes));
+    }
+
+    #[test]
+    fn u16le() {
+        let mut words = [0x1234, 0xABCD];
+        assert_eq!(
+            send(DataFormat::U16LE(&mut words)),
+            expected(&[0x34, 0x12, 0xCD, 0xAB])
+        );
+        // The words of the caller are left as they were
+        assert_eq!(words, [0x1234, 0xABCD]);
+    }
+
+    #[test]
+    fn u16be() {
+        let mut words = [0x1234, 0xABCD];
+        assert_eq!(
+            send(DataFormat::U16BE(&mut words)),
+            expected(&[0x12, 0x34, 0xAB, 0xCD])
+        );
+        assert_eq!(words, [0x1234, 0xABCD]);
+    }
+
+    #[test]
+    fn u8_iter() {
+        let mut iter = [1u8, 2, 3].into_iter();
+        assert_eq!(send(DataFormat::U8Iter(&mut iter)), expected(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn u16le_iter() {
+        let mut iter = [0x1234u16, 0xABCD].into_iter();
+        assert_eq!(
+            send(DataFormat::U16LEIter(&mut iter)),
+            expected(&[0x34, 0x12, 0xCD, 0xAB])
+        );
+    }
+
+    #[test]
+    fn u16be_iter() {
+        let mut iter = [0x1234u16, 0xABCD].into_iter();
+        assert_eq!(
+            send(DataFormat::U16BEIter(&mut iter)),
+            expected(&[0x12, 0x34, 0xAB, 0xCD])
+        );
+    }
+
+    #[test]
+    fn long_data_is_written_in_chunks() {
+        let mut iter = 0..SCRATCH_LEN as u16;
+        let bytes: Vec<u8> = (0..SCRATCH_LEN as u16).flat_map(u16::to_be_bytes).collect();
+        let events = send(DataFormat::U16BEIter(&mut iter));
+        assert_eq!(
+            events[2..],
+            [
+                Event::Dc(true),
+                Event::Write(bytes[..SCRATCH_LEN].to_vec()),
+                Event::Write(bytes[SCRATCH_LEN..].to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Frame rate measurement for display benchmarks.
+
+use embassy_time::{Duration, Instant};
+
+/// Counts the frames drawn and reports the frame rate about once per second.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut fps = utils::display::FpsCounter::new();
+///
+/// loop {
+///     display.clear(Rgb565::RED).await.unwrap();
+///     if let Some(fps) = fps.frame() {
+///         info!("{fps:.1} FPS");
+///     }
+/// }
+/// ```
+pub struct FpsCounter {
+    frames: u32,
+    start: Instant,
+}
+
+impl FpsCounter {
+    /// Interval over which the frames are counted.
+    const INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            frames: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Registers a drawn frame and returns the frame rate once [`Self::INTERVAL`]
+    /// has passed since the last report.
+    pub fn frame(&mut self) -> Option<f32> {
+        self.frames += 1;
+
+        let elapsed = self.start.elapsed();
+        if elapsed < Self::INTERVAL {
+            return None;
+        }
+
+        let fps = self.frames as f32 * 1_000_000.0 / elapsed.as_micros() as f32;
+        self.frames = 0;
+        self.start = Instant::now();
+        Some(fps)
+    }
+}
+
+impl Default for FpsCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
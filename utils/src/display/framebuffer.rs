@@ -0,0 +1,517 @@
+//! In-RAM RGB565 framebuffer that only sends the changed regions to the display.
+//!
+//! Drawing into a [`Framebuffer`] does not touch the SPI bus. Pixels that change
+//! value are collected into a short list of dirty rectangles, and [`Framebuffer::flush`]
+//! writes only those rectangles to the display with `set_pixels`. Redrawing the same
+//! text every iteration of the main loop costs nothing, and redrawing a shorter value
+//! over a cleared area erases the stale characters without flickering.
+//!
+//! A full 240x240 frame takes 115200 bytes, almost half of the RP2040's RAM. The
+//! framebuffer can also cover only a band of the screen, placed at `origin`, for
+//! example the lines where the values change.
+
+use display_interface::DisplayError;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, Point, Size};
+use embedded_graphics::pixelcolor::raw::RawU16;
+use embedded_graphics::pixelcolor::{IntoStorage, Rgb565};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+use embedded_hal::digital::OutputPin;
+use heapless::Vec;
+
+use super::{AsyncWriteOnlyDataCommand, St7789Async};
+
+/// Maximum number of dirty rectangles that are tracked separately. When more regions
+/// change between two flushes, the closest ones are merged.
+pub const MAX_DIRTY_REGIONS: usize = 8;
+
+/// Rectangle in framebuffer coordinates, with both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Area {
+    x0: u16,
+    y0: u16,
+    x1: u16,
+    y1: u16,
+}
+
+impl Area {
+    fn point(x: u16, y: u16) -> Self {
+        Self {
+            x0: x,
+            y0: y,
+            x1: x,
+            y1: y,
+        }
+    }
+
+    fn union(&self, other: &Area) -> Area {
+        Area {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    /// Returns `true` if the two areas overlap or are next to each other, so that
+    /// merging them does not add any pixels that have not changed.
+    fn touches(&self, other: &Area) -> bool {
+        self.x0 <= other.x1 + 1
+            && other.x0 <= self.x1 + 1
+            && self.y0 <= other.y1 + 1
+            && other.y0 <= self.y1 + 1
+    }
+
+    fn pixels(&self) -> u32 {
+        (self.x1 - self.x0 + 1) as u32 * (self.y1 - self.y0 + 1) as u32
+    }
+}
+
+/// RGB565 framebuffer of `W` x `H` pixels with dirty rectangle tracking.
+///
+/// The framebuffer starts black, like the screen after
+/// [`pico_explorer_display!`](crate::pico_explorer_display), with nothing to flush.
+/// [`Framebuffer::new`] is `const`, so large framebuffers can be placed in a `static`
+/// instead of on the stack.
+///
+/// # Example
+///
+/// ```
+/// use embedded_graphics::pixelcolor::Rgb565;
+/// use embedded_graphics::prelude::*;
+/// use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+/// use utils::display::Framebuffer;
+///
+/// // A 240 x 40 band, in the middle of the screen.
+/// let mut framebuffer = Framebuffer::<240, 40>::new(Point::new(0, 100));
+///
+/// Rectangle::new(Point::new(10, 110), Size::new(20, 5))
+///     .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+///     .draw(&mut framebuffer)
+///     .unwrap();
+///
+/// let mut windows = [(0, 0, 0, 0); 2];
+/// let mut count = 0;
+/// framebuffer
+///     .flush(|sx, sy, ex, ey, colors| {
+///         for color in colors {
+///             assert_eq!(color, Rgb565::RED.into_storage());
+///         }
+///         windows[count] = (sx, sy, ex, ey);
+///         count += 1;
+///         Ok::<(), ()>(())
+///     })
+///     .unwrap();
+/// assert_eq!(&windows[..count], &[(10, 110, 29, 114)]);
+///
+/// // Drawing the same pixels again leaves nothing to flush.
+/// Rectangle::new(Point::new(10, 110), Size::new(20, 5))
+///     .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+///     .draw(&mut framebuffer)
+///     .unwrap();
+/// assert!(!framebuffer.is_dirty());
+/// ```
+pub struct Framebuffer<const W: usize, const H: usize> {
+    pixels: [[u16; W]; H],
+    origin: Point,
+    dirty: Vec<Area, MAX_DIRTY_REGIONS>,
+}
+
+impl<const W: usize, const H: usize> Framebuffer<W, H> {
+    /// Creates a black framebuffer whose top left corner is at `origin` on the screen.
+    ///
+    /// `origin` must not be negative and the framebuffer must fit on the screen.
+    pub const fn new(origin: Point) -> Self {
+        Self {
+            pixels: [[0; W]; H],
+            origin,
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if some pixels changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Returns the regions that will be written by the next flush, in screen coordinates.
+    pub fn dirty_regions(&self) -> impl Iterator<Item = Rectangle> + '_ {
+        self.dirty.iter().map(|area| {
+            Rectangle::with_corners(
+                self.origin + Point::new(area.x0 as i32, area.y0 as i32),
+                self.origin + Point::new(area.x1 as i32, area.y1 as i32),
+            )
+        })
+    }
+
+    /// Marks the whole framebuffer as changed, so that the next flush rewrites it.
+    ///
+    /// Useful after the screen has been drawn without the framebuffer.
+    pub fn invalidate(&mut self) {
+        if W == 0 || H == 0 {
+            return;
+        }
+        self.dirty.clear();
+        self.mark_dirty(Area {
+            x0: 0,
+            y0: 0,
+            x1: W as u16 - 1,
+            y1: H as u16 - 1,
+        });
+    }
+
+    /// Returns the color of the pixel at `point` (in screen coordinates), or `None` if
+    /// the point is outside of the framebuffer.
+    pub fn pixel(&self, point: Point) -> Option<Rgb565> {
+        self.local(point)
+            .map(|(x, y)| RawU16::new(self.pixels[y as usize][x as usize]).into())
+    }
+
+    /// Writes the dirty regions with `set_pixels`, which receives the window
+    /// `(sx, sy, ex, ey)` (both corners inclusive, in screen coordinates) and the
+    /// raw RGB565 values of its pixels, row by row.
+    ///
+    /// The arguments match `st7789::ST7789::set_pixels`:
+    ///
+    /// ```ignore
+    /// framebuffer.flush(|sx, sy, ex, ey, colors| display.set_pixels(sx, sy, ex, ey, colors))?;
+    /// ```
+    ///
+    /// If `set_pixels` fails, the regions that were not written are kept for the next flush.
+    pub fn flush<F, E>(&mut self, mut set_pixels: F) -> Result<(), E>
+    where
+        F: FnMut(u16, u16, u16, u16, &mut dyn Iterator<Item = u16>) -> Result<(), E>,
+    {
+        while let Some(area) = self.dirty.last().copied() {
+            let (sx, sy, ex, ey) = self.window(&area);
+            set_pixels(sx, sy, ex, ey, &mut self.area_pixels(area))?;
+            self.dirty.pop();
+        }
+        Ok(())
+    }
+
+    /// Async version of [`Framebuffer::flush`], writing the dirty regions to a
+    /// [`St7789Async`] display.
+    pub async fn flush_async<DI, RST>(
+        &mut self,
+        display: &mut St7789Async<DI, RST>,
+    ) -> Result<(), DisplayError>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+        RST: OutputPin,
+    {
+        while let Some(area) = self.dirty.last().copied() {
+            let (sx, sy, ex, ey) = self.window(&area);
+            display
+                .set_pixels(sx, sy, ex, ey, self.area_pixels(area))
+                .await?;
+            self.dirty.pop();
+        }
+        Ok(())
+    }
+
+    /// Converts a point in screen coordinates into framebuffer coordinates.
+    fn local(&self, point: Point) -> Option<(u16, u16)> {
+        let point = point - self.origin;
+        if point.x < 0 || point.y < 0 || point.x >= W as i32 || point.y >= H as i32 {
+            return None;
+        }
+        Some((point.x as u16, point.y as u16))
+    }
+
+    fn window(&self, area: &Area) -> (u16, u16, u16, u16) {
+        let x = self.origin.x as u16;
+        let y = self.origin.y as u16;
+        (x + area.x0, y + area.y0, x + area.x1, y + area.y1)
+    }
+
+    fn area_pixels(&self, area: Area) -> impl Iterator<Item = u16> + '_ {
+        (area.y0..=area.y1).flat_map(move |y| {
+            self.pixels[y as usize][area.x0 as usize..=area.x1 as usize]
+                .iter()
+                .copied()
+        })
+    }
+
+    /// Adds `area` to the dirty regions, merging it with the regions it touches.
+    /// When the list is full, it is merged with the region that grows the least.
+    fn mark_dirty(&mut self, mut area: Area) {
+        loop {
+            let mut i = 0;
+            while i < self.dirty.len() {
+                if self.dirty[i].touches(&area) {
+                    area = area.union(&self.dirty.swap_remove(i));
+                    // The merged area may now touch regions that were already checked.
+                    i = 0;
+                } else {
+                    i += 1;
+                }
+            }
+
+            match self.dirty.push(area) {
+                Ok(()) => return,
+                Err(full) => {
+                    let closest = self
+                        .dirty
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, other)| full.union(other).pixels() - other.pixels())
+                        .map(|(i, _)| i)
+                        .unwrap_or_default();
+                    area = full.union(&self.dirty.swap_remove(closest));
+                }
+            }
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Dimensions for Framebuffer<W, H> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(self.origin, Size::new(W as u32, H as u32))
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for Framebuffer<W, H> {
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        // Only the bounding box of the pixels that really change is marked as dirty.
+        let mut changed: Option<Area> = None;
+
+        for Pixel(point, color) in pixels {
+            let Some((x, y)) = self.local(point) else {
+                continue;
+            };
+
+            let raw = color.into_storage();
+            let pixel = &mut self.pixels[y as usize][x as usize];
+            if *pixel != raw {
+                *pixel = raw;
+                let point = Area::point(x, y);
+                changed = Some(changed.map_or(point, |area| area.union(&point)));
+            }
+        }
+
+        if let Some(area) = changed {
+            self.mark_dirty(area);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::PrimitiveStyle;
+
+    use super::*;
+
+    type Band = Framebuffer<16, 8>;
+
+    fn band() -> Band {
+        Framebuffer::new(Point::new(10, 20))
+    }
+
+    fn set(framebuffer: &mut Band, x: i32, y: i32, color: Rgb565) {
+        Pixel(Point::new(x, y), color).draw(framebuffer).unwrap();
+    }
+
+    fn regions(framebuffer: &Band) -> Vec<Rectangle> {
+        let mut regions: Vec<_> = framebuffer.dirty_regions().collect();
+        regions.sort_by_key(|region| (region.top_left.y, region.top_left.x));
+        regions
+    }
+
+    fn corners(x0: i32, y0: i32, x1: i32, y1: i32) -> Rectangle {
+        Rectangle::with_corners(Point::new(x0, y0), Point::new(x1, y1))
+    }
+
+    /// Flushes the framebuffer and returns the windows that were written.
+    fn flush(framebuffer: &mut Band) -> Vec<(u16, u16, u16, u16)> {
+        let mut windows = Vec::new();
+        framebuffer
+            .flush(|sx, sy, ex, ey, colors| {
+                assert_eq!(
+                    colors.count(),
+                    (ex - sx + 1) as usize * (ey - sy + 1) as usize
+                );
+                windows.push((sx, sy, ex, ey));
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        windows
+    }
+
+    #[test]
+    fn touching_regions_are_merged() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        set(&mut framebuffer, 11, 20, Rgb565::RED);
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 11, 20)]);
+
+        // Diagonally next to the region
+        set(&mut framebuffer, 12, 21, Rgb565::RED);
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 12, 21)]);
+    }
+
+    #[test]
+    fn overlapping_regions_are_merged() {
+        let mut framebuffer = band();
+        Rectangle::new(Point::new(10, 20), Size::new(4, 4))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut framebuffer)
+            .unwrap();
+        Rectangle::new(Point::new(12, 22), Size::new(4, 4))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+            .draw(&mut framebuffer)
+            .unwrap();
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 15, 25)]);
+    }
+
+    #[test]
+    fn a_region_that_joins_two_others_merges_them_all() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        set(&mut framebuffer, 14, 20, Rgb565::RED);
+        assert_eq!(regions(&framebuffer).len(), 2);
+        Rectangle::new(Point::new(11, 20), Size::new(3, 1))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut framebuffer)
+            .unwrap();
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 14, 20)]);
+    }
+
+    #[test]
+    fn separate_regions_are_kept_apart() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        set(&mut framebuffer, 20, 25, Rgb565::RED);
+        assert_eq!(
+            regions(&framebuffer),
+            [corners(10, 20, 10, 20), corners(20, 25, 20, 25)]
+        );
+    }
+
+    #[test]
+    fn too_many_regions_are_merged_with_the_closest() {
+        let mut framebuffer = band();
+        // A pixel every other column of the first line
+        for i in 0..MAX_DIRTY_REGIONS as i32 {
+            set(&mut framebuffer, 10 + 2 * i, 20, Rgb565::RED);
+        }
+        assert_eq!(framebuffer.dirty_regions().count(), MAX_DIRTY_REGIONS);
+
+        // Far below the last one, it grows it the least
+        set(&mut framebuffer, 24, 27, Rgb565::RED);
+        let regions = regions(&framebuffer);
+        assert_eq!(regions.len(), MAX_DIRTY_REGIONS);
+        assert!(regions.contains(&corners(24, 20, 24, 27)));
+
+        // Every changed pixel is still written
+        let windows = flush(&mut framebuffer);
+        for i in 0..MAX_DIRTY_REGIONS as u16 {
+            let x = 10 + 2 * i;
+            assert!(windows
+                .iter()
+                .any(|&(sx, sy, ex, ey)| (sx..=ex).contains(&x) && (sy..=ey).contains(&20)));
+        }
+        assert!(!framebuffer.is_dirty());
+    }
+
+    #[test]
+    fn failed_flush_keeps_the_unwritten_regions() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        set(&mut framebuffer, 20, 25, Rgb565::GREEN);
+        set(&mut framebuffer, 15, 27, Rgb565::BLUE);
+
+        let mut written = 0;
+        let result = framebuffer.flush(|_, _, _, _, _| {
+            if written == 1 {
+                return Err("bus error");
+            }
+            written += 1;
+            Ok(())
+        });
+        assert_eq!(result, Err("bus error"));
+        assert_eq!(framebuffer.dirty_regions().count(), 2);
+
+        assert_eq!(flush(&mut framebuffer).len(), 2);
+        assert!(!framebuffer.is_dirty());
+    }
+
+    #[test]
+    fn flush_writes_the_pixels_row_by_row() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 12, 21, Rgb565::RED);
+        set(&mut framebuffer, 13, 22, Rgb565::BLUE);
+        let mut colors = Vec::new();
+        framebuffer
+            .flush(|sx, sy, ex, ey, pixels| {
+                assert_eq!((sx, sy, ex, ey), (12, 21, 13, 22));
+                colors.extend(pixels);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        let (red, blue) = (Rgb565::RED.into_storage(), Rgb565::BLUE.into_storage());
+        assert_eq!(colors, [red, 0, 0, blue]);
+    }
+
+    #[test]
+    fn draws_outside_the_band_are_clipped() {
+        let mut framebuffer = band();
+        // Over the top left corner of the band
+        Rectangle::new(Point::new(5, 15), Size::new(8, 8))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut framebuffer)
+            .unwrap();
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 12, 22)]);
+        assert_eq!(framebuffer.pixel(Point::new(10, 20)), Some(Rgb565::RED));
+        assert_eq!(framebuffer.pixel(Point::new(9, 20)), None);
+        flush(&mut framebuffer);
+
+        // Completely outside
+        for (x, y) in [(9, 20), (26, 20), (10, 19), (10, 28), (-1, -1)] {
+            set(&mut framebuffer, x, y, Rgb565::RED);
+        }
+        assert!(!framebuffer.is_dirty());
+    }
+
+    #[test]
+    fn unchanged_pixels_are_not_dirty() {
+        let mut framebuffer = band();
+        // The framebuffer starts black
+        set(&mut framebuffer, 10, 20, Rgb565::BLACK);
+        assert!(!framebuffer.is_dirty());
+
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        flush(&mut framebuffer);
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        assert!(!framebuffer.is_dirty());
+
+        // Only the pixels that change count in the bounding box
+        Rectangle::new(Point::new(10, 20), Size::new(3, 1))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut framebuffer)
+            .unwrap();
+        assert_eq!(regions(&framebuffer), [corners(11, 20, 12, 20)]);
+    }
+
+    #[test]
+    fn invalidate_marks_the_whole_band() {
+        let mut framebuffer = band();
+        set(&mut framebuffer, 10, 20, Rgb565::RED);
+        framebuffer.invalidate();
+        assert_eq!(regions(&framebuffer), [corners(10, 20, 25, 27)]);
+        assert_eq!(flush(&mut framebuffer), [(10, 20, 25, 27)]);
+    }
+}
@@ -0,0 +1,196 @@
+//! Blocking display interface.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::SpiDevice;
+
+/// SPI display interface.
+///
+/// This combines the SPI peripheral and a data/command pin.
+pub struct SPIDeviceInterface<SPI, DC> {
+    spi: SPI,
+    dc: DC,
+}
+
+impl<SPI, DC> SPIDeviceInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    /// Create new SPI interface for communciation with a display driver
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        Self { spi, dc }
+    }
+}
+
+impl<SPI, DC> WriteOnlyDataCommand for SPIDeviceInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    fn send_commands(&mut self, cmds: DataFormat<'_>) -> Result<(), DisplayError> {
+        // 1 = data, 0 = command
+        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
+
+        send_u8(&mut self.spi, cmds)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        // 1 = data, 0 = command
+        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+
+        send_u8(&mut self.spi, buf)
+    }
+}
+
+/// Size of the scratch buffer used to convert 16 bit words and to collect iterators.
+const SCRATCH_LEN: usize = 128;
+
+fn send_u8<T: SpiDevice>(spi: &mut T, words: DataFormat<'_>) -> Result<(), DisplayError> {
+    match words {
+        DataFormat::U8(slice) => spi.write(slice),
+        DataFormat::U16(slice) => {
+            use byte_slice_cast::*;
+            spi.write(slice.as_byte_slice())
+        }
+        // The caller's words are converted in a scratch buffer, as the
+        // slice might be reused by the driver after this call.
+        DataFormat::U16LE(slice) => write_bytes(spi, slice.iter().flat_map(|v| v.to_le_bytes())),
+        DataFormat::U16BE(slice) => write_bytes(spi, slice.iter().flat_map(|v| v.to_be_bytes())),
+        DataFormat::U8Iter(iter) => write_bytes(spi, iter),
+        DataFormat::U16LEIter(iter) => write_bytes(spi, iter.flat_map(u16::to_le_bytes)),
+        DataFormat::U16BEIter(iter) => write_bytes(spi, iter.flat_map(u16::to_be_bytes)),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    }
+    .map_err(|_| DisplayError::BusWriteError)
+}
+
+/// Writes the bytes of `iter` in chunks of [`SCRATCH_LEN`].
+fn write_bytes<T: SpiDevice>(spi: &mut T, iter: impl Iterator<Item = u8>) -> Result<(), T::Error> {
+    let mut buf = [0; SCRATCH_LEN];
+    let mut i = 0;
+
+    for v in iter {
+        buf[i] = v;
+        i += 1;
+
+        if i == buf.len() {
+            spi.write(&buf)?;
+            i = 0;
+        }
+    }
+
+    if i > 0 {
+        spi.write(&buf[..i])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::mock::{display_bus, Event};
+
+    /// Sends `data` after a command, and returns what the display received.
+    fn send(data: DataFormat<'_>) -> Vec<Event> {
+        let (spi, dc, log) = display_bus();
+        let mut interface = SPIDeviceInterface::new(spi, dc);
+        interface.send_commands(DataFormat::U8(&[0x2C])).unwrap();
+        interface.send_data(data).unwrap();
+        log.take()
+    }
+
+    /// Returns what the display receives for `bytes` of data after a command.
+    fn expected(bytes: &[u8]) -> Vec<Event> {
+        vec![
+            Event::Dc(false),
+            Event::Write(vec![0x2C]),
+            Event::Dc(true),
+            Event::Write(bytes.to_vec()),
+        ]
+    }
+
+    #[test]
+    fn u8() {
+        assert_eq!(send(DataFormat::U8(&[1, 2, 3])), expected(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn u16_is_native_endian() {
+        let bytes = [0x1234u16.to_ne_bytes(), 0xABCDu16.to_ne_bytes()].concat();
+        assert_eq!(send(DataFormat::U16(&[0x1234, 0xABCD])), expected(&bytes));
+    }
+
+    #[test]
+    fn u16le() {
+        let mut words = [0x1234, 0xABCD];
+        assert_eq!(
+            send(DataFormat::U16LE(&mut words)),
+            expected(&[0x34, 0x12, 0xCD, 0xAB])
+        );
+        // The words of the caller are left as they were
+        assert_eq!(words, [0x1234, 0xABCD]);
+    }
+
+    #[test]
+    fn u16be() {
+        let mut words = [0x1234, 0xABCD];
+        assert_eq!(
+            send(DataFormat::U16BE(&mut words)),
+            expected(&[0x12, 0x34, 0xAB, 0xCD])
+        );
+        assert_eq!(words, [0x1234, 0xABCD]);
+    }
+
+    #[test]
+    fn u8_iter() {
+        let mut iter = [1u8, 2, 3].into_iter();
+        assert_eq!(send(DataFormat::U8Iter(&mut iter)), expected(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn u16le_iter() {
+        let mut iter = [0x1234u16, 0xABCD].into_iter();
+        assert_eq!(
+            send(DataFormat::U16LEIter(&mut iter)),
+            expected(&[0x34, 0x12, 0xCD, 0xAB])
+        );
+    }
+
+    #[test]
+    fn u16be_iter() {
+        let mut iter = [0x1234u16, 0xABCD].into_iter();
+        assert_eq!(
+            send(DataFormat::U16BEIter(&mut iter)),
+            expected(&[0x12, 0x34, 0xAB, 0xCD])
+        );
+    }
+
+    #[test]
+    fn long_data_is_written_in_chunks() {
+        let mut iter = 0..SCRATCH_LEN as u16;
+        let bytes: Vec<u8> = (0..SCRATCH_LEN as u16).flat_map(u16::to_be_bytes).collect();
+        let events = send(DataFormat::U16BEIter(&mut iter));
+        assert_eq!(
+            events[2..],
+            [
+                Event::Dc(true),
+                Event::Write(bytes[..SCRATCH_LEN].to_vec()),
+                Event::Write(bytes[SCRATCH_LEN..].to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_data_is_not_written() {
+        let mut iter = core::iter::empty();
+        let events = send(DataFormat::U8Iter(&mut iter));
+        assert_eq!(events[2..], [Event::Dc(true)]);
+    }
+}
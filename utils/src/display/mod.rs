@@ -0,0 +1,232 @@
+//! Utilities for the ST7789 display of the Pimoroni Pico Explorer base.
+//!
+//! [`SPIDeviceInterface`] implements the `display-interface` traits on top of an
+//! `embedded-hal` SPI device, so the `st7789` driver can share the SPI bus with
+//! other devices. The [`pico_explorer_display!`] macro builds a ready to use `ST7789`
+//! handle on the Pico Explorer's pins.
+//!
+//! [`SPIDeviceInterfaceAsync`] and [`St7789Async`] are the async counterparts, for
+//! the DMA driven `Spi` and the async shared bus: pushing a full frame yields to
+//! the other tasks instead of blocking the executor. They are created by
+//! [`pico_explorer_display_async!`]. [`FpsCounter`] measures the frame rate.
+//!
+//! [`Framebuffer`] keeps the image in RAM and sends only the regions that changed.
+//! The [`widgets`] build sensor dashboards on top of any `DrawTarget`, and [`screens`]
+//! holds the screens drawn by the lab solutions.
+
+mod asynch;
+mod benchmark;
+mod framebuffer;
+mod interface;
+pub mod screens;
+mod st7789;
+pub mod widgets;
+
+pub use asynch::{AsyncWriteOnlyDataCommand, SPIDeviceInterfaceAsync};
+pub use benchmark::FpsCounter;
+pub use framebuffer::{Framebuffer, MAX_DIRTY_REGIONS};
+pub use interface::SPIDeviceInterface;
+pub use st7789::St7789Async;
+
+/// Frequency of the display's SPI clock.
+pub const DISPLAY_FREQ: u32 = 64_000_000;
+
+/// Width of the display, in pixels.
+pub const DISPLAY_WIDTH: u16 = 240;
+
+/// Height of the display, in pixels.
+pub const DISPLAY_HEIGHT: u16 = 240;
+
+/// Creates the SPI configuration of the display: [`DISPLAY_FREQ`], SPI mode 3.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pico_explorer_display_config {
+    () => {{
+        let mut config = embassy_rp::spi::Config::default();
+        config.frequency = $crate::display::DISPLAY_FREQ;
+        config.phase = embassy_rp::spi::Phase::CaptureOnSecondTransition;
+        config.polarity = embassy_rp::spi::Polarity::IdleHigh;
+        config
+    }};
+}
+
+/// Creates the blocking SPI bus of the Pico Explorer (`SPI0` on pins 18, 19 and 4)
+/// and returns a `&'static` reference to it, so that the display and other devices
+/// (like the BMP280) can share it through `SpiDeviceWithConfig`.
+///
+/// Should be invoked on the RP2040 `Peripherals`.
+#[macro_export]
+macro_rules! pico_explorer_spi_bus {
+    ($p:expr) => {{
+        type Bus = embassy_sync::blocking_mutex::Mutex<
+            embassy_sync::blocking_mutex::raw::NoopRawMutex,
+            core::cell::RefCell<
+                embassy_rp::spi::Spi<
+                    'static,
+                    embassy_rp::peripherals::SPI0,
+                    embassy_rp::spi::Blocking,
+                >,
+            >,
+        >;
+
+        let spi = embassy_rp::spi::Spi::new_blocking(
+            $p.SPI0,
+            $p.PIN_18,
+            $p.PIN_19,
+            $p.PIN_4,
+            $crate::__pico_explorer_display_config!(),
+        );
+
+        static SPI_BUS: static_cell::StaticCell<Bus> = static_cell::StaticCell::new();
+        &*SPI_BUS.init(embassy_sync::blocking_mutex::Mutex::new(
+            core::cell::RefCell::new(spi),
+        ))
+    }};
+}
+
+/// Creates an initialized `ST7789` handle for the display of the Pico Explorer,
+/// in portrait orientation and cleared to black.
+///
+/// Should be invoked on the RP2040 `Peripherals` and, optionally, on the SPI bus
+/// returned by [`pico_explorer_spi_bus!`] when other devices share it. The display
+/// uses CS on pin 17, DC on pin 16 and RST on pin 0.
+///
+/// # Example
+///
+/// ```ignore
+/// use embassy_executor::Spawner;
+/// use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
+/// use embedded_graphics::mono_font::MonoTextStyle;
+/// use embedded_graphics::pixelcolor::Rgb565;
+/// use embedded_graphics::prelude::*;
+/// use embedded_graphics::text::Text;
+///
+/// #[embassy_executor::main]
+/// async fn main(_spawner: Spawner) {
+///     let peripherals = embassy_rp::init(Default::default());
+///     let mut display = utils::pico_explorer_display!(peripherals);
+///
+///     let style = MonoTextStyle::new(&FONT_10X20, Rgb565::GREEN);
+///     Text::new("Hello!", Point::new(40, 110), style)
+///         .draw(&mut display)
+///         .unwrap();
+/// }
+/// ```
+#[macro_export]
+macro_rules! pico_explorer_display {
+    ($p:expr) => {{
+        let spi_bus = $crate::pico_explorer_spi_bus!($p);
+        $crate::pico_explorer_display!($p, spi_bus)
+    }};
+    ($p:expr, $bus:expr) => {{
+        use embedded_graphics::draw_target::DrawTarget as _;
+        use embedded_graphics::pixelcolor::RgbColor as _;
+
+        let display_spi = embassy_embedded_hal::shared_bus::blocking::spi::SpiDeviceWithConfig::new(
+            $bus,
+            embassy_rp::gpio::Output::new($p.PIN_17, embassy_rp::gpio::Level::High),
+            $crate::__pico_explorer_display_config!(),
+        );
+        let dc = embassy_rp::gpio::Output::new($p.PIN_16, embassy_rp::gpio::Level::Low);
+        let rst = embassy_rp::gpio::Output::new($p.PIN_0, embassy_rp::gpio::Level::Low);
+        let di = $crate::display::SPIDeviceInterface::new(display_spi, dc);
+
+        let mut display = st7789::ST7789::new(
+            di,
+            rst,
+            $crate::display::DISPLAY_WIDTH,
+            $crate::display::DISPLAY_HEIGHT,
+        );
+        display.init(&mut embassy_time::Delay).unwrap();
+        display
+            .set_orientation(st7789::Orientation::Portrait)
+            .unwrap();
+        display
+            .clear(embedded_graphics::pixelcolor::Rgb565::BLACK)
+            .unwrap();
+
+        display
+    }};
+}
+
+/// Creates the async SPI bus of the Pico Explorer (`SPI0` on pins 18, 19 and 4, with
+/// `DMA_CH1` and `DMA_CH2`) and returns a `&'static` reference to it, so that the
+/// display and other devices can share it through the async `SpiDeviceWithConfig`.
+///
+/// `DMA_CH0` is left free for the WiFi chip.
+///
+/// Should be invoked on the RP2040 `Peripherals`.
+#[macro_export]
+macro_rules! pico_explorer_spi_bus_async {
+    ($p:expr) => {{
+        type Bus = embassy_sync::mutex::Mutex<
+            embassy_sync::blocking_mutex::raw::NoopRawMutex,
+            embassy_rp::spi::Spi<'static, embassy_rp::peripherals::SPI0, embassy_rp::spi::Async>,
+        >;
+
+        let spi = embassy_rp::spi::Spi::new(
+            $p.SPI0,
+            $p.PIN_18,
+            $p.PIN_19,
+            $p.PIN_4,
+            $p.DMA_CH1,
+            $p.DMA_CH2,
+            $crate::__pico_explorer_display_config!(),
+        );
+
+        static SPI_BUS: static_cell::StaticCell<Bus> = static_cell::StaticCell::new();
+        &*SPI_BUS.init(embassy_sync::mutex::Mutex::new(spi))
+    }};
+}
+
+/// Creates an initialized [`St7789Async`](crate::display::St7789Async) handle for the
+/// display of the Pico Explorer, cleared to black. Must be invoked from an async context.
+///
+/// Should be invoked on the RP2040 `Peripherals` and, optionally, on the SPI bus
+/// returned by [`pico_explorer_spi_bus_async!`] when other devices share it.
+///
+/// # Example
+///
+/// ```ignore
+/// use embassy_executor::Spawner;
+/// use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+///
+/// #[embassy_executor::main]
+/// async fn main(_spawner: Spawner) {
+///     let peripherals = embassy_rp::init(Default::default());
+///     let mut display = utils::pico_explorer_display_async!(peripherals);
+///
+///     display.clear(Rgb565::BLUE).await.unwrap();
+/// }
+/// ```
+#[macro_export]
+macro_rules! pico_explorer_display_async {
+    ($p:expr) => {{
+        let spi_bus = $crate::pico_explorer_spi_bus_async!($p);
+        $crate::pico_explorer_display_async!($p, spi_bus)
+    }};
+    ($p:expr, $bus:expr) => {{
+        let display_spi = embassy_embedded_hal::shared_bus::asynch::spi::SpiDeviceWithConfig::new(
+            $bus,
+            embassy_rp::gpio::Output::new($p.PIN_17, embassy_rp::gpio::Level::High),
+            $crate::__pico_explorer_display_config!(),
+        );
+        let dc = embassy_rp::gpio::Output::new($p.PIN_16, embassy_rp::gpio::Level::Low);
+        let rst = embassy_rp::gpio::Output::new($p.PIN_0, embassy_rp::gpio::Level::Low);
+        let di = $crate::display::SPIDeviceInterfaceAsync::new(display_spi, dc);
+
+        let mut display = $crate::display::St7789Async::new(
+            di,
+            rst,
+            $crate::display::DISPLAY_WIDTH,
+            $crate::display::DISPLAY_HEIGHT,
+        );
+        display.init(&mut embassy_time::Delay).await.unwrap();
+        display
+            .clear(<embedded_graphics::pixelcolor::Rgb565 as embedded_graphics::pixelcolor::RgbColor>::BLACK)
+            .await
+            .unwrap();
+
+        display
+    }};
+}
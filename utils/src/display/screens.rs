@@ -0,0 +1,72 @@
+//! The screens drawn by the lab solutions.
+//!
+//! They only need a `DrawTarget`, so the same functions draw on the ST7789 and on the
+//! host, in the `display_simulator` crate.
+
+use core::fmt::{Debug, Display, Write};
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::Point;
+use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
+use embedded_graphics::mono_font::{MonoTextStyle, MonoTextStyleBuilder};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::text::Text;
+use embedded_graphics::Drawable;
+use heapless::String;
+
+/// Green text on black, used by all the screens.
+fn text_style() -> MonoTextStyle<'static, Rgb565> {
+    MonoTextStyleBuilder::new()
+        .font(&FONT_10X20)
+        .text_color(Rgb565::GREEN)
+        .background_color(Rgb565::BLACK)
+        .build()
+}
+
+/// Draws the `Intensity` and `Color` of the RGB LED (lab 05).
+///
+/// `color` is printed with its `Debug` representation.
+pub fn intensity_color<D>(
+    target: &mut D,
+    intensity: impl Display,
+    color: impl Debug,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let mut text = String::<64>::new();
+    // The text is truncated if it does not fit.
+    let _ = write!(text, "Intensity: {} \n Color: {:?}", intensity, color);
+
+    Text::new(&text, Point::new(40, 110), text_style()).draw(target)?;
+    Ok(())
+}
+
+/// Draws the `Temperature`, in hundredths of a degree Celsius, and the `Raw pressure`
+/// read from the BMP280 (labs 06 and 07).
+pub fn temperature_pressure<D>(
+    target: &mut D,
+    temperature: i32,
+    pressure_raw: u32,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let sign = if temperature < 0 { "-" } else { "" };
+    let temperature = temperature.unsigned_abs();
+
+    let mut text = String::<64>::new();
+    let _ = write!(
+        text,
+        "Temperature: {sign}{}.{:02}",
+        temperature / 100,
+        temperature % 100
+    );
+    Text::new(&text, Point::new(40, 110), text_style()).draw(target)?;
+
+    text.clear();
+    let _ = write!(text, "Raw pressure: {pressure_raw}");
+    Text::new(&text, Point::new(40, 150), text_style()).draw(target)?;
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! Minimal async driver for the ST7789 controller.
+//!
+//! It covers what the `st7789` crate's blocking driver is used for in the labs:
+//! initialization in portrait orientation, clearing the screen and writing pixels
+//! into a window, on top of an [`AsyncWriteOnlyDataCommand`].
+
+use display_interface::{DataFormat, DisplayError};
+use embedded_graphics::pixelcolor::{IntoStorage, Rgb565};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+use super::AsyncWriteOnlyDataCommand;
+
+/// ST7789 commands (datasheet section 9.1).
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum Instruction {
+    SwReset = 0x01,
+    SlpOut = 0x11,
+    NorOn = 0x13,
+    InvOff = 0x20,
+    InvOn = 0x21,
+    DispOn = 0x29,
+    CaSet = 0x2A,
+    RaSet = 0x2B,
+    RamWr = 0x2C,
+    VScrDef = 0x33,
+    MadCtl = 0x36,
+    ColMod = 0x3A,
+}
+
+/// Async ST7789 driver.
+pub struct St7789Async<DI, RST> {
+    di: DI,
+    rst: RST,
+    width: u16,
+    height: u16,
+}
+
+impl<DI, RST> St7789Async<DI, RST>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    RST: OutputPin,
+{
+    /// Creates the driver for a `width` x `height` panel.
+    pub fn new(di: DI, rst: RST, width: u16, height: u16) -> Self {
+        Self {
+            di,
+            rst,
+            width,
+            height,
+        }
+    }
+
+    /// Resets the controller and sets it up for RGB565 pixels in portrait orientation,
+    /// the same way `st7789::ST7789::init` does.
+    pub async fn init(&mut self, delay: &mut impl DelayNs) -> Result<(), DisplayError> {
+        self.hard_reset(delay).await?;
+
+        self.write_command(Instruction::SwReset).await?;
+        delay.delay_ms(150).await;
+        self.write_command(Instruction::SlpOut).await?;
+        delay.delay_ms(10).await;
+        self.write_command(Instruction::InvOff).await?;
+        self.write_command(Instruction::VScrDef).await?;
+        self.write_data(&[0, 0, 0x14, 0, 0, 0]).await?;
+        self.write_command(Instruction::MadCtl).await?;
+        self.write_data(&[0b0000_0000]).await?;
+        self.write_command(Instruction::ColMod).await?;
+        self.write_data(&[0b0101_0101]).await?;
+        self.write_command(Instruction::InvOn).await?;
+        self.write_command(Instruction::NorOn).await?;
+        delay.delay_ms(10).await;
+        self.write_command(Instruction::DispOn).await?;
+        delay.delay_ms(10).await;
+
+        Ok(())
+    }
+
+    /// Returns the size of the panel as `(width, height)`.
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Writes `colors` (raw RGB565 values) into the window between
+    /// `(sx, sy)` and `(ex, ey)`, both inclusive.
+    pub async fn set_pixels<T>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: T,
+    ) -> Result<(), DisplayError>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.set_address_window(sx, sy, ex, ey).await?;
+        self.write_command(Instruction::RamWr).await?;
+        self.di
+            .send_data(DataFormat::U16BEIter(&mut colors.into_iter()))
+            .await
+    }
+
+    /// Fills the whole screen with `color`.
+    pub async fn clear(&mut self, color: Rgb565) -> Result<(), DisplayError> {
+        let raw = color.into_storage();
+        let count = self.width as usize * self.height as usize;
+        self.set_pixels(
+            0,
+            0,
+            self.width - 1,
+            self.height - 1,
+            core::iter::repeat(raw).take(count),
+        )
+        .await
+    }
+
+    /// Releases the display interface and the reset pin.
+    pub fn release(self) -> (DI, RST) {
+        (self.di, self.rst)
+    }
+
+    async fn hard_reset(&mut self, delay: &mut impl DelayNs) -> Result<(), DisplayError> {
+        self.rst.set_high().map_err(|_| DisplayError::RSError)?;
+        delay.delay_us(10).await;
+        self.rst.set_low().map_err(|_| DisplayError::RSError)?;
+        delay.delay_us(10).await;
+        self.rst.set_high().map_err(|_| DisplayError::RSError)?;
+        delay.delay_us(10).await;
+        Ok(())
+    }
+
+    async fn set_address_window(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+    ) -> Result<(), DisplayError> {
+        self.write_command(Instruction::CaSet).await?;
+        self.write_data(&[(sx >> 8) as u8, sx as u8, (ex >> 8) as u8, ex as u8])
+            .await?;
+        self.write_command(Instruction::RaSet).await?;
+        self.write_data(&[(sy >> 8) as u8, sy as u8, (ey >> 8) as u8, ey as u8])
+            .await
+    }
+
+    async fn write_command(&mut self, command: Instruction) -> Result<(), DisplayError> {
+        self.di
+            .send_commands(DataFormat::U8(&[command as u8]))
+            .await
+    }
+
+    async fn write_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.di.send_data(DataFormat::U8(data)).await
+    }
+}
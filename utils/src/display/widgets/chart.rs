@@ -0,0 +1,197 @@
+//! Scrolling line chart.
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::primitives::{Line, Primitive, PrimitiveStyle, Rectangle};
+use embedded_graphics::Drawable;
+use heapless::Deque;
+
+use super::{clear_below, Widget, WidgetStyle};
+
+/// Plots the last `N` samples pushed into it, oldest on the left. When the chart is
+/// full, every new sample scrolls the line to the left.
+///
+/// Without a fixed range, the vertical axis is scaled to the samples shown.
+///
+/// # Example
+///
+/// Temperature history, in hundredths of a degree, as returned by the BMP280 driver:
+///
+/// ```
+/// use embedded_graphics::prelude::*;
+/// use embedded_graphics::primitives::Rectangle;
+/// use utils::display::widgets::{LineChart, Widget, WidgetStyle};
+/// use utils::display::Framebuffer;
+///
+/// let mut display = Framebuffer::<240, 100>::new(Point::zero());
+/// let mut history = LineChart::<60>::new(WidgetStyle::default());
+///
+/// for temperature in [2508, 2512, 2515, 2511] {
+///     history.push(temperature);
+/// }
+/// history.draw(display.bounding_box(), &mut display).unwrap();
+/// ```
+pub struct LineChart<'a, const N: usize> {
+    samples: Deque<i32, N>,
+    range: Option<(i32, i32)>,
+    height: u32,
+    style: WidgetStyle<'a>,
+}
+
+impl<'a, const N: usize> LineChart<'a, N> {
+    /// Height of the chart, unless changed with [`LineChart::set_height`].
+    const DEFAULT_HEIGHT: u32 = 80;
+
+    pub fn new(style: WidgetStyle<'a>) -> Self {
+        Self {
+            samples: Deque::new(),
+            range: None,
+            height: Self::DEFAULT_HEIGHT,
+            style,
+        }
+    }
+
+    /// Adds a sample, dropping the oldest one if the chart is full.
+    pub fn push(&mut self, sample: i32) {
+        if self.samples.is_full() {
+            self.samples.pop_front();
+        }
+        // There is room after dropping the oldest sample.
+        let _ = self.samples.push_back(sample);
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns the samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = i32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    /// Fixes the vertical axis between `min` and `max`, or scales it to the samples
+    /// shown if `None`.
+    pub fn set_range(&mut self, range: Option<(i32, i32)>) {
+        self.range = range;
+    }
+
+    /// Sets the height of the chart, in pixels.
+    pub fn set_height(&mut self, height: u32) {
+        self.height = height;
+    }
+
+    /// Returns the range of the vertical axis.
+    fn range(&self) -> (i32, i32) {
+        if let Some(range) = self.range {
+            return range;
+        }
+        let min = self.samples().min().unwrap_or_default();
+        let max = self.samples().max().unwrap_or_default();
+        (min, max)
+    }
+}
+
+impl<const N: usize> Widget for LineChart<'_, N> {
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let mut target = target.clipped(&area);
+
+        let frame = Rectangle::new(area.top_left, Size::new(area.size.width, self.height));
+        let plot = frame.offset(-1);
+        target.fill_solid(&plot, self.style.background)?;
+        frame
+            .into_styled(PrimitiveStyle::with_stroke(self.style.foreground, 1))
+            .draw(&mut target)?;
+
+        let (min, max) = self.range();
+        let span = (max as i64 - min as i64).max(1);
+        let width = plot.size.width.saturating_sub(1) as i64;
+        let height = plot.size.height.saturating_sub(1) as i64;
+        let steps = (N as i64 - 1).max(1);
+
+        let point = |i: usize, sample: i32| {
+            let sample = (sample as i64).clamp(min as i64, max as i64);
+            let x = i as i64 * width / steps;
+            let y = height - (sample - min as i64) * height / span;
+            plot.top_left + Point::new(x as i32, y as i32)
+        };
+
+        let line_style = PrimitiveStyle::with_stroke(self.style.accent, 1);
+        let mut previous = None;
+        for (i, sample) in self.samples().enumerate() {
+            let current = point(i, sample);
+            Line::new(previous.unwrap_or(current), current)
+                .into_styled(line_style)
+                .draw(&mut target)?;
+            previous = Some(current);
+        }
+
+        clear_below(&mut target, area, self.height, self.style.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    fn draw<const N: usize>(chart: &LineChart<'_, N>) -> MockDisplay<Rgb565> {
+        let mut display = MockDisplay::new();
+        // The line is drawn over the cleared plot
+        display.set_allow_overdraw(true);
+        let area = Rectangle::new(Point::zero(), Size::new(6, 6));
+        chart.draw(area, &mut display).unwrap();
+        display
+    }
+
+    #[test]
+    fn samples_are_plotted_in_the_range() {
+        let mut chart = LineChart::<4>::new(WidgetStyle::default());
+        chart.set_height(5);
+        chart.set_range(Some((0, 3)));
+        for sample in [0, 1, 2, 3] {
+            chart.push(sample);
+        }
+        draw(&chart).assert_pattern(&["WWWWWW", "WKKKGW", "WKKGKW", "WGGKKW", "WWWWWW", "KKKKKK"]);
+    }
+
+    #[test]
+    fn samples_out_of_the_range_are_clamped() {
+        let mut chart = LineChart::<4>::new(WidgetStyle::default());
+        chart.set_height(5);
+        chart.set_range(Some((0, 3)));
+        for sample in [-10, 10, 10, -10] {
+            chart.push(sample);
+        }
+        draw(&chart).assert_pattern(&["WWWWWW", "WKGGKW", "WGKGKW", "WGKKGW", "WWWWWW", "KKKKKK"]);
+    }
+
+    #[test]
+    fn new_samples_scroll_to_the_left() {
+        let mut chart = LineChart::<4>::new(WidgetStyle::default());
+        chart.set_height(5);
+        for sample in [9, 0, 0, 0, 2] {
+            chart.push(sample);
+        }
+        assert!(chart.samples().eq([0, 0, 0, 2]));
+        // Scaled to the samples shown, from 0 to 2
+        draw(&chart).assert_pattern(&["WWWWWW", "WKKKGW", "WKKGKW", "WGGGKW", "WWWWWW", "KKKKKK"]);
+    }
+
+    #[test]
+    fn empty_chart_is_only_the_frame() {
+        let mut chart = LineChart::<4>::new(WidgetStyle::default());
+        chart.set_height(5);
+        draw(&chart).assert_pattern(&["WWWWWW", "WKKKKW", "WKKKKW", "WKKKKW", "WWWWWW", "KKKKKK"]);
+    }
+}
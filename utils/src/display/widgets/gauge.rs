@@ -0,0 +1,165 @@
+//! Horizontal bar gauge.
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::primitives::{Primitive, PrimitiveStyle, Rectangle};
+use embedded_graphics::Drawable;
+
+use super::{clear_below, Widget, WidgetStyle};
+
+/// Shows a value between `min` and `max` as a horizontal bar inside a frame.
+pub struct BarGauge<'a> {
+    min: i32,
+    max: i32,
+    value: i32,
+    height: u32,
+    style: WidgetStyle<'a>,
+}
+
+impl<'a> BarGauge<'a> {
+    /// Height of the gauge, unless changed with [`BarGauge::set_height`].
+    const DEFAULT_HEIGHT: u32 = 16;
+
+    /// Creates a gauge for values between `min` and `max`, showing `min`.
+    pub fn new(min: i32, max: i32, style: WidgetStyle<'a>) -> Self {
+        Self {
+            min,
+            max,
+            value: min,
+            height: Self::DEFAULT_HEIGHT,
+            style,
+        }
+    }
+
+    /// Sets the value shown, which is clamped to the range of the gauge.
+    pub fn set_value(&mut self, value: i32) {
+        self.value = value.clamp(self.min.min(self.max), self.max.max(self.min));
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// Sets the height of the gauge, in pixels.
+    pub fn set_height(&mut self, height: u32) {
+        self.height = height;
+    }
+
+    /// Returns the width of the bar for an inner width of `width` pixels.
+    fn bar_width(&self, width: u32) -> u32 {
+        if self.max == self.min {
+            return 0;
+        }
+        let filled = (self.value - self.min) as i64 * width as i64 / (self.max - self.min) as i64;
+        filled.clamp(0, width as i64) as u32
+    }
+}
+
+impl Widget for BarGauge<'_> {
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let mut target = target.clipped(&area);
+
+        let frame = Rectangle::new(area.top_left, Size::new(area.size.width, self.height));
+        frame
+            .into_styled(PrimitiveStyle::with_stroke(self.style.foreground, 1))
+            .draw(&mut target)?;
+
+        // The bar and the empty part are drawn separately, so the display does not flicker
+        // when the gauge is drawn without a framebuffer.
+        let inner = frame.offset(-1);
+        let filled = self.bar_width(inner.size.width);
+        target.fill_solid(
+            &Rectangle::new(inner.top_left, Size::new(filled, inner.size.height)),
+            self.style.accent,
+        )?;
+        target.fill_solid(
+            &Rectangle::new(
+                inner.top_left + Point::new(filled as i32, 0),
+                Size::new(inner.size.width - filled, inner.size.height),
+            ),
+            self.style.background,
+        )?;
+
+        clear_below(&mut target, area, self.height, self.style.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    fn draw(gauge: &BarGauge<'_>) -> MockDisplay<Rgb565> {
+        let mut display = MockDisplay::new();
+        let area = Rectangle::new(Point::zero(), Size::new(10, 6));
+        gauge.draw(area, &mut display).unwrap();
+        display
+    }
+
+    #[test]
+    fn bar_is_proportional_to_the_value() {
+        let mut gauge = BarGauge::new(0, 8, WidgetStyle::default());
+        gauge.set_height(4);
+        gauge.set_value(3);
+        draw(&gauge).assert_pattern(&[
+            "WWWWWWWWWW",
+            "WGGGKKKKKW",
+            "WGGGKKKKKW",
+            "WWWWWWWWWW",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+        ]);
+    }
+
+    #[test]
+    fn value_is_clamped_to_the_range() {
+        let mut gauge = BarGauge::new(-4, 4, WidgetStyle::default());
+        gauge.set_height(3);
+        gauge.set_value(100);
+        assert_eq!(gauge.value(), 4);
+        draw(&gauge).assert_pattern(&[
+            "WWWWWWWWWW",
+            "WGGGGGGGGW",
+            "WWWWWWWWWW",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+        ]);
+
+        gauge.set_value(-100);
+        assert_eq!(gauge.value(), -4);
+        draw(&gauge).assert_pattern(&[
+            "WWWWWWWWWW",
+            "WKKKKKKKKW",
+            "WWWWWWWWWW",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+        ]);
+    }
+
+    #[test]
+    fn empty_range_shows_an_empty_bar() {
+        let mut gauge = BarGauge::new(5, 5, WidgetStyle::default());
+        gauge.set_height(3);
+        gauge.set_value(5);
+        draw(&gauge).assert_pattern(&[
+            "WWWWWWWWWW",
+            "WKKKKKKKKW",
+            "WWWWWWWWWW",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+            "KKKKKKKKKK",
+        ]);
+    }
+}
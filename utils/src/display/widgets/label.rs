@@ -0,0 +1,141 @@
+//! A label followed by a value, on a single line.
+
+use core::fmt::{Display, Write};
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::Size;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use heapless::String;
+
+use super::{clear_below, Widget, WidgetStyle};
+
+/// Maximum length of the value text, in bytes. Longer values are truncated.
+const VALUE_LEN: usize = 32;
+
+/// Shows `label: value`, with the label in the foreground color and the value in the
+/// accent color. The rest of the line is cleared, so a shorter value replaces a
+/// longer one completely.
+pub struct LabeledValue<'a> {
+    label: &'a str,
+    value: String<VALUE_LEN>,
+    style: WidgetStyle<'a>,
+}
+
+impl<'a> LabeledValue<'a> {
+    pub fn new(label: &'a str, style: WidgetStyle<'a>) -> Self {
+        Self {
+            label,
+            value: String::new(),
+            style,
+        }
+    }
+
+    /// Replaces the value with the formatted `value`, for example
+    /// `format_args!("{} Pa", pressure)`.
+    pub fn set_value(&mut self, value: impl Display) {
+        self.value.clear();
+        // A value that does not fit is truncated.
+        let _ = write!(self.value, "{value}");
+    }
+
+    /// Returns the value text.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Widget for LabeledValue<'_> {
+    fn height(&self) -> u32 {
+        self.style.line_height()
+    }
+
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let mut target = target.clipped(&area);
+
+        let label_style = self.style.text(self.style.foreground);
+        let value_style = self.style.text(self.style.accent);
+
+        let mut next = Text::with_baseline(self.label, area.top_left, label_style, Baseline::Top)
+            .draw(&mut target)?;
+        next = Text::with_baseline(": ", next, label_style, Baseline::Top).draw(&mut target)?;
+        next =
+            Text::with_baseline(&self.value, next, value_style, Baseline::Top).draw(&mut target)?;
+
+        // Clear the rest of the line, where a longer value might have been.
+        let line_height = self.height();
+        let right = area.top_left.x + area.size.width as i32;
+        if next.x < right {
+            target.fill_solid(
+                &Rectangle::new(next, Size::new((right - next.x) as u32, line_height)),
+                self.style.background,
+            )?;
+        }
+
+        clear_below(&mut target, area, line_height, self.style.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::mono_font::ascii::FONT_4X6;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    fn style() -> WidgetStyle<'static> {
+        WidgetStyle {
+            font: &FONT_4X6,
+            ..WidgetStyle::default()
+        }
+    }
+
+    const AREA: Rectangle = Rectangle::new(Point::zero(), Size::new(24, 8));
+
+    #[test]
+    fn label_and_value_fill_the_area() {
+        let mut label = LabeledValue::new("T", style());
+        label.set_value(12);
+
+        let mut display = MockDisplay::new();
+        label.draw(AREA, &mut display).unwrap();
+        display.assert_pattern(&[
+            "WWWKKKKKKKKKKGKKKGKKKKKK",
+            "KWKKKWKKKKKKGGKKGKGKKKKK",
+            "KWKKKKKKKKKKKGKKKKGKKKKK",
+            "KWKKKKKKKKKKKGKKKGKKKKKK",
+            "KWKKKWKKKKKKGGGKGGGKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKK",
+        ]);
+    }
+
+    #[test]
+    fn shorter_value_covers_the_previous_one() {
+        let mut label = LabeledValue::new("T", style());
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        label.set_value(12345);
+        label.draw(AREA, &mut display).unwrap();
+        label.set_value(1);
+        label.draw(AREA, &mut display).unwrap();
+
+        let mut expected = MockDisplay::new();
+        label.draw(AREA, &mut expected).unwrap();
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn long_values_are_truncated() {
+        let mut label = LabeledValue::new("T", style());
+        label.set_value(format_args!("{:040}", 7));
+        assert_eq!(label.value().len(), VALUE_LEN);
+    }
+}
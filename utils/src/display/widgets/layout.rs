@@ -0,0 +1,133 @@
+//! Vertical stacking of widgets.
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::primitives::Rectangle;
+
+use super::Widget;
+
+/// Places widgets one under the other inside `area`, each one as wide as the area,
+/// with `spacing` pixels between them.
+///
+/// A layout is created for every redraw. The gaps between the widgets and, after
+/// [`VerticalLayout::finish`], the area below the last one are cleared with the
+/// background color, so nothing from a previous screen is left behind.
+pub struct VerticalLayout {
+    area: Rectangle,
+    spacing: u32,
+    background: Rgb565,
+    /// Offset of the next widget from the top of `area`.
+    offset: u32,
+}
+
+impl VerticalLayout {
+    /// Creates a layout with a black background.
+    pub fn new(area: Rectangle, spacing: u32) -> Self {
+        Self {
+            area,
+            spacing,
+            background: Rgb565::BLACK,
+            offset: 0,
+        }
+    }
+
+    /// Sets the color of the gaps between the widgets.
+    pub fn with_background(mut self, background: Rgb565) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Returns the rectangle that the next widget of `height` pixels gets, clipped to
+    /// the area of the layout, and moves past it.
+    pub fn allocate(&mut self, height: u32) -> Rectangle {
+        let top = self.offset.min(self.area.size.height);
+        let height = height.min(self.area.size.height - top);
+        self.offset = top + height;
+        Rectangle::new(
+            self.area.top_left + Point::new(0, top as i32),
+            Size::new(self.area.size.width, height),
+        )
+    }
+
+    /// Draws `widget` under the previous one.
+    pub fn draw<W, D>(&mut self, widget: &W, target: &mut D) -> Result<(), D::Error>
+    where
+        W: Widget,
+        D: DrawTarget<Color = Rgb565>,
+    {
+        if self.offset > 0 {
+            let gap = self.allocate(self.spacing);
+            target.fill_solid(&gap, self.background)?;
+        }
+
+        let area = self.allocate(widget.height());
+        if area.size.height == 0 {
+            return Ok(());
+        }
+        widget.draw(area, target)
+    }
+
+    /// Clears the area below the last widget.
+    pub fn finish<D>(mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let rest = self.allocate(self.area.size.height);
+        target.fill_solid(&rest, self.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+    use crate::display::widgets::{BarGauge, WidgetStyle};
+
+    #[test]
+    fn widgets_are_stacked_with_gaps() {
+        let mut empty = BarGauge::new(0, 3, WidgetStyle::default());
+        empty.set_height(3);
+        let mut full = BarGauge::new(0, 3, WidgetStyle::default());
+        full.set_height(3);
+        full.set_value(3);
+
+        let mut display = MockDisplay::new();
+        let area = Rectangle::new(Point::new(1, 1), Size::new(5, 10));
+        let mut layout = VerticalLayout::new(area, 1).with_background(Rgb565::BLUE);
+        layout.draw(&empty, &mut display).unwrap();
+        layout.draw(&full, &mut display).unwrap();
+        layout.finish(&mut display).unwrap();
+
+        display.assert_pattern(&[
+            "      ", " WWWWW", " WKKKW", " WWWWW", " BBBBB", " WWWWW", " WGGGW", " WWWWW",
+            " BBBBB", " BBBBB", " BBBBB",
+        ]);
+    }
+
+    #[test]
+    fn widgets_are_clipped_to_the_area() {
+        let mut gauge = BarGauge::new(0, 3, WidgetStyle::default());
+        gauge.set_height(3);
+
+        let mut display = MockDisplay::new();
+        let area = Rectangle::new(Point::zero(), Size::new(5, 5));
+        let mut layout = VerticalLayout::new(area, 1);
+        layout.draw(&gauge, &mut display).unwrap();
+        layout.draw(&gauge, &mut display).unwrap();
+        // Nothing is left for a third one
+        layout.draw(&gauge, &mut display).unwrap();
+        layout.finish(&mut display).unwrap();
+
+        #[rustfmt::skip]
+        display.assert_pattern(&[
+            "WWWWW",
+            "WKKKW",
+            "WWWWW",
+            "KKKKK",
+            "WWWWW",
+        ]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Small widget layer for sensor dashboards on the 240x240 display.
+//!
+//! Every widget implements [`Widget`]: it reports the height it needs and draws itself
+//! into a rectangle of any `DrawTarget`, covering the whole rectangle so that values
+//! which get shorter do not leave stale characters behind. [`VerticalLayout`] stacks
+//! widgets from top to bottom, so their positions do not have to be picked by hand.
+//!
+//! Drawing into a [`Framebuffer`](super::Framebuffer) and flushing it afterwards sends
+//! only the pixels that changed to the display.
+//!
+//! # Example
+//!
+//! ```
+//! use embedded_graphics::prelude::*;
+//! use utils::display::widgets::{BarGauge, LabeledValue, VerticalLayout, WidgetStyle};
+//! use utils::display::Framebuffer;
+//!
+//! let mut display = Framebuffer::<240, 240>::new(Point::zero());
+//! let style = WidgetStyle::default();
+//!
+//! let mut temperature = LabeledValue::new("Temperature", style);
+//! let mut intensity = BarGauge::new(0, 100, style);
+//!
+//! temperature.set_value(format_args!("{}.{:02} C", 2508 / 100, 2508 % 100));
+//! intensity.set_value(40);
+//!
+//! let mut layout = VerticalLayout::new(display.bounding_box(), 8);
+//! layout.draw(&temperature, &mut display).unwrap();
+//! layout.draw(&intensity, &mut display).unwrap();
+//! layout.finish(&mut display).unwrap();
+//! ```
+
+mod chart;
+mod gauge;
+mod label;
+mod layout;
+mod networks;
+mod status;
+
+pub use chart::LineChart;
+pub use gauge::BarGauge;
+pub use label::LabeledValue;
+pub use layout::VerticalLayout;
+pub use networks::AccessPointList;
+pub use status::{Status, StatusIcon, StatusRow};
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::mono_font::iso_8859_16::FONT_10X20;
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle, MonoTextStyleBuilder};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::primitives::Rectangle;
+
+/// A piece of the user interface that can be placed in a rectangle.
+pub trait Widget {
+    /// Returns the height the widget needs, in pixels.
+    fn height(&self) -> u32;
+
+    /// Draws the widget in `area`. Every pixel of `area` is overwritten, nothing is
+    /// drawn outside of it.
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>;
+}
+
+/// Font and colors shared by the widgets.
+#[derive(Debug, Clone, Copy)]
+pub struct WidgetStyle<'a> {
+    /// Font of the labels and values.
+    pub font: &'a MonoFont<'a>,
+    /// Color of the labels, frames and axes.
+    pub foreground: Rgb565,
+    /// Color of the values, gauge bars and chart lines.
+    pub accent: Rgb565,
+    /// Color every widget clears its area with.
+    pub background: Rgb565,
+}
+
+impl<'a> WidgetStyle<'a> {
+    /// Returns the text style for `color` on the background color.
+    pub fn text(&self, color: Rgb565) -> MonoTextStyle<'a, Rgb565> {
+        MonoTextStyleBuilder::new()
+            .font(self.font)
+            .text_color(color)
+            .background_color(self.background)
+            .build()
+    }
+
+    /// Returns the height of a line of text.
+    pub fn line_height(&self) -> u32 {
+        self.font.character_size.height
+    }
+}
+
+impl Default for WidgetStyle<'static> {
+    /// The style used by the labs: [`FONT_10X20`], white labels and green values on black.
+    fn default() -> Self {
+        Self {
+            font: &FONT_10X20,
+            foreground: Rgb565::WHITE,
+            accent: Rgb565::GREEN,
+            background: Rgb565::BLACK,
+        }
+    }
+}
+
+/// Fills the part of `area` below the first `height` lines with `color`.
+fn clear_below<D>(
+    target: &mut D,
+    area: Rectangle,
+    height: u32,
+    color: Rgb565,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    if area.size.height <= height {
+        return Ok(());
+    }
+    target.fill_solid(
+        &Rectangle::new(
+            area.top_left + Point::new(0, height as i32),
+            Size::new(area.size.width, area.size.height - height),
+        ),
+        color,
+    )
+}
@@ -0,0 +1,196 @@
+//! List of the access points found by a WiFi scan.
+
+use core::fmt::Write;
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use heapless::String;
+
+use super::{clear_below, Status, Widget, WidgetStyle};
+use crate::wifi::{AccessPoint, Security};
+
+/// Maximum number of characters of a line.
+const LINE_LEN: usize = 48;
+
+/// Characters used by the channel, the signal and the lock after the SSID.
+const INFO_LEN: usize = 9;
+
+/// Shows one access point per line: the SSID, the channel, the signal strength in dBm
+/// and `*` for protected networks.
+///
+/// The signal is green above -60 dBm, yellow above -75 dBm and red below. The SSID is
+/// truncated to fit the line, and hidden networks are shown as `<hidden>`.
+///
+/// # Example
+///
+/// ```
+/// use embedded_graphics::prelude::*;
+/// use utils::display::widgets::{AccessPointList, Widget, WidgetStyle};
+/// use utils::display::Framebuffer;
+/// use utils::wifi::{AccessPoint, ScanResults, Security};
+///
+/// let mut results = ScanResults::<8>::new();
+/// results.insert(AccessPoint::new(b"lab", [2, 0, 0, 0, 0, 1], 6, -48, Security::Protected));
+/// results.insert(AccessPoint::new(b"guests", [2, 0, 0, 0, 0, 2], 11, -81, Security::Open));
+///
+/// let mut display = Framebuffer::<240, 240>::new(Point::zero());
+/// let list = AccessPointList::new(results.as_slice(), WidgetStyle::default());
+/// assert_eq!(list.height(), 40);
+/// list.draw(display.bounding_box(), &mut display).unwrap();
+/// ```
+pub struct AccessPointList<'a> {
+    access_points: &'a [AccessPoint],
+    max_rows: usize,
+    style: WidgetStyle<'a>,
+}
+
+impl<'a> AccessPointList<'a> {
+    pub fn new(access_points: &'a [AccessPoint], style: WidgetStyle<'a>) -> Self {
+        Self {
+            access_points,
+            max_rows: usize::MAX,
+            style,
+        }
+    }
+
+    /// Shows at most `rows` access points, the first ones of the list.
+    pub fn with_max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = rows;
+        self
+    }
+
+    /// Replaces the access points, for example after a new scan.
+    pub fn set_access_points(&mut self, access_points: &'a [AccessPoint]) {
+        self.access_points = access_points;
+    }
+
+    fn rows(&self) -> usize {
+        self.access_points.len().min(self.max_rows)
+    }
+}
+
+/// Returns the color of the signal strength.
+fn signal_status(rssi: i16) -> Status {
+    match rssi {
+        -60.. => Status::Ok,
+        -75..=-61 => Status::Warning,
+        _ => Status::Error,
+    }
+}
+
+impl Widget for AccessPointList<'_> {
+    fn height(&self) -> u32 {
+        self.rows() as u32 * self.style.line_height()
+    }
+
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let mut target = target.clipped(&area);
+        let line_height = self.style.line_height();
+        let columns = (area.size.width / self.style.font.character_size.width) as usize;
+        let ssid_len = columns.min(LINE_LEN).saturating_sub(INFO_LEN);
+        let name_style = self.style.text(self.style.foreground);
+
+        let mut position = area.top_left;
+        for access_point in &self.access_points[..self.rows()] {
+            let ssid = match access_point.ssid.as_str() {
+                "" => "<hidden>",
+                ssid => ssid,
+            };
+            let lock = match access_point.security {
+                Security::Open => ' ',
+                Security::Protected => '*',
+            };
+
+            // Padding the fields keeps the columns aligned and covers the previous text.
+            let mut name: String<LINE_LEN> = String::new();
+            let mut channel: String<4> = String::new();
+            let mut signal: String<6> = String::new();
+            let _ = write!(name, "{:<width$.width$}", ssid, width = ssid_len);
+            let _ = write!(channel, "{:>3}", access_point.channel);
+            let _ = write!(signal, "{:>5}{}", access_point.rssi, lock);
+
+            let signal_style = self.style.text(signal_status(access_point.rssi).color());
+            let mut next = Text::with_baseline(&name, position, name_style, Baseline::Top)
+                .draw(&mut target)?;
+            next =
+                Text::with_baseline(&channel, next, name_style, Baseline::Top).draw(&mut target)?;
+            next = Text::with_baseline(&signal, next, signal_style, Baseline::Top)
+                .draw(&mut target)?;
+
+            let right = area.top_left.x + area.size.width as i32;
+            if next.x < right {
+                target.fill_solid(
+                    &Rectangle::new(next, Size::new((right - next.x) as u32, line_height)),
+                    self.style.background,
+                )?;
+            }
+            position += Point::new(0, line_height as i32);
+        }
+
+        let height = (position.y - area.top_left.y) as u32;
+        clear_below(&mut target, area, height, self.style.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::mono_font::ascii::FONT_4X6;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    fn style() -> WidgetStyle<'static> {
+        WidgetStyle {
+            font: &FONT_4X6,
+            ..WidgetStyle::default()
+        }
+    }
+
+    #[test]
+    fn one_access_point_per_line() {
+        let access_points = [
+            AccessPoint::new(b"lab", [2, 0, 0, 0, 0, 1], 6, -48, Security::Protected),
+            AccessPoint::new(b"", [2, 0, 0, 0, 0, 2], 11, -81, Security::Open),
+            AccessPoint::new(b"hall", [2, 0, 0, 0, 0, 3], 1, -70, Security::Open),
+        ];
+        let list = AccessPointList::new(&access_points, style()).with_max_rows(2);
+        assert_eq!(list.height(), 12);
+
+        let mut display = MockDisplay::new();
+        let area = Rectangle::new(Point::zero(), Size::new(60, 14));
+        list.draw(area, &mut display).unwrap();
+        display.assert_pattern(&[
+            "WWKKKKKKWKKKKKKKKKKKKKKKKKKKKKKKKWWKKKKKKKKKKKKKGKGKKGGKGKGK",
+            "KWKKKWWKWWKKKKKKKKKKKKKKKKKKKKKKWKKKKKKKKKKKKKKKGKGKGKGKKGKK",
+            "KWKKWKWKWKWKKKKKKKKKKKKKKKKKKKKKWWKKKKKKKKKKGGGKGGGKKGKKGGGK",
+            "KWKKWKWKWKWKKKKKKKKKKKKKKKKKKKKKWKWKKKKKKKKKKKKKKKGKGKGKKGKK",
+            "WWWKKWWKWWKKKKKKKKKKKKKKKKKKKKKKKWKKKKKKKKKKKKKKKKGKGGKKGKGK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKWKWKKKKWKKKKWKKKWKKKKKKKKKKWKKKWKKKKKKKKKKKKKKKRRKKRKKKKKK",
+            "KWKKWWKKKKKKKWWKKWWKKWKKKKKKWWKKWWKKKKKKKKKKKKKKRKRKRRKKKKKK",
+            "WKKKWKWKWWKKWKWKWKWKWKWKKKKKKWKKKWKKKKKKKKKKRRRKKRKKKRKKKKKK",
+            "KWKKWKWKKWKKWKWKWKWKWWKKKKKKKWKKKWKKKKKKKKKKKKKKRKRKKRKKKKKK",
+            "KKWKWKWKWWWKKWWKKWWKKWWKKKKKWWWKWWWKKKKKKKKKKKKKRRKKRRRKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+        ]);
+    }
+
+    #[test]
+    fn signal_colors() {
+        assert_eq!(signal_status(-40), Status::Ok);
+        assert_eq!(signal_status(-60), Status::Ok);
+        assert_eq!(signal_status(-61), Status::Warning);
+        assert_eq!(signal_status(-75), Status::Warning);
+        assert_eq!(signal_status(-76), Status::Error);
+    }
+}
@@ -0,0 +1,184 @@
+//! Row of status indicators.
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor, WebColors};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+
+use super::{clear_below, Widget, WidgetStyle};
+
+/// Horizontal space between two icons, in pixels.
+const ICON_SPACING: u32 = 10;
+
+/// Space around the indicator of an icon, in pixels.
+const ICON_MARGIN: u32 = 3;
+
+/// State shown by a [`StatusIcon`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The feature is disabled.
+    Off,
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Status {
+    /// Returns the color of the indicator.
+    pub fn color(self) -> Rgb565 {
+        match self {
+            Status::Off => Rgb565::CSS_DIM_GRAY,
+            Status::Ok => Rgb565::GREEN,
+            Status::Warning => Rgb565::YELLOW,
+            Status::Error => Rgb565::RED,
+        }
+    }
+}
+
+/// A colored square followed by a short label, like `WiFi` or `BMP`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusIcon<'a> {
+    pub label: &'a str,
+    pub status: Status,
+}
+
+impl<'a> StatusIcon<'a> {
+    pub fn new(label: &'a str, status: Status) -> Self {
+        Self { label, status }
+    }
+}
+
+/// Shows `N` [`StatusIcon`]s from left to right.
+pub struct StatusRow<'a, const N: usize> {
+    /// The icons, which can be updated directly.
+    pub icons: [StatusIcon<'a>; N],
+    style: WidgetStyle<'a>,
+}
+
+impl<'a, const N: usize> StatusRow<'a, N> {
+    pub fn new(icons: [StatusIcon<'a>; N], style: WidgetStyle<'a>) -> Self {
+        Self { icons, style }
+    }
+
+    /// Sets the status of the icon at `index`.
+    pub fn set_status(&mut self, index: usize, status: Status) {
+        if let Some(icon) = self.icons.get_mut(index) {
+            icon.status = status;
+        }
+    }
+}
+
+impl<const N: usize> Widget for StatusRow<'_, N> {
+    fn height(&self) -> u32 {
+        self.style.line_height()
+    }
+
+    fn draw<D>(&self, area: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let mut target = target.clipped(&area);
+        let height = self.height();
+        let size = height.saturating_sub(2 * ICON_MARGIN);
+        let label_style = self.style.text(self.style.foreground);
+        let background = self.style.background;
+
+        // Every pixel is drawn once, so redrawing an unchanged row does not
+        // change a framebuffer.
+        let mut position = area.top_left;
+        for icon in &self.icons {
+            let cell = Rectangle::new(position, Size::new(size + ICON_MARGIN, height));
+            let dot = Rectangle::new(
+                position + Point::new(0, ICON_MARGIN as i32),
+                Size::new(size, size),
+            );
+            target.fill_solid(
+                &Rectangle::new(cell.top_left, Size::new(cell.size.width, ICON_MARGIN)),
+                background,
+            )?;
+            target.fill_solid(
+                &Rectangle::new(
+                    dot.top_left + Point::new(size as i32, 0),
+                    Size::new(ICON_MARGIN, size),
+                ),
+                background,
+            )?;
+            target.fill_solid(
+                &Rectangle::new(
+                    dot.top_left + Point::new(0, size as i32),
+                    Size::new(cell.size.width, ICON_MARGIN),
+                ),
+                background,
+            )?;
+            target.fill_solid(&dot, icon.status.color())?;
+
+            position.x += cell.size.width as i32;
+            position = Text::with_baseline(icon.label, position, label_style, Baseline::Top)
+                .draw(&mut target)?;
+            target.fill_solid(
+                &Rectangle::new(position, Size::new(ICON_SPACING, height)),
+                background,
+            )?;
+            position.x += ICON_SPACING as i32;
+        }
+
+        let right = area.top_left.x + area.size.width as i32;
+        if position.x < right {
+            target.fill_solid(
+                &Rectangle::new(position, Size::new((right - position.x) as u32, height)),
+                background,
+            )?;
+        }
+
+        clear_below(&mut target, area, height, self.style.background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::mono_font::ascii::FONT_6X10;
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    fn style() -> WidgetStyle<'static> {
+        WidgetStyle {
+            font: &FONT_6X10,
+            ..WidgetStyle::default()
+        }
+    }
+
+    #[test]
+    fn icons_are_drawn_from_left_to_right() {
+        let mut row = StatusRow::new(
+            [
+                StatusIcon::new("A", Status::Ok),
+                StatusIcon::new("B", Status::Warning),
+            ],
+            style(),
+        );
+        row.set_status(1, Status::Error);
+        // Out of the row
+        row.set_status(2, Status::Warning);
+
+        let mut display = MockDisplay::new();
+        let area = Rectangle::new(Point::zero(), Size::new(50, 11));
+        row.draw(area, &mut display).unwrap();
+        display.assert_pattern(&[
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKWKKKKKKKKKKKKKKKKKKKKWWWWKKKKKKKKKKKKKKKK",
+            "KKKKKKKKWKWKKKKKKKKKKKKKKKKKKKKWKKWKKKKKKKKKKKKKKK",
+            "GGGGKKKWKKKWKKKKKKKKKKKRRRRKKKKWKKWKKKKKKKKKKKKKKK",
+            "GGGGKKKWKKKWKKKKKKKKKKKRRRRKKKKWWWKKKKKKKKKKKKKKKK",
+            "GGGGKKKWWWWWKKKKKKKKKKKRRRRKKKKWKKWKKKKKKKKKKKKKKK",
+            "GGGGKKKWKKKWKKKKKKKKKKKRRRRKKKKWKKWKKKKKKKKKKKKKKK",
+            "KKKKKKKWKKKWKKKKKKKKKKKKKKKKKKWWWWKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+            "KKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKKK",
+        ]);
+    }
+}
@@ -0,0 +1,27 @@
+//! A small HTTP/1.1 server, to control the board from a browser.
+//!
+//! [`Request::parse`] reads a request from the bytes received on a connection, and a
+//! [`Router`] finds the route of its method and path. [`Query`] decodes the query
+//! string and the bodies of HTML forms, and [`Head`] writes the status line and the
+//! headers of the response.
+//!
+//! With the `rp2040` feature, [`serve`] runs the server on an `embassy-net` TCP
+//! socket and gives the requests to a [`Handler`], which answers them through a
+//! [`Response`], in one piece or with the chunked encoding.
+//!
+//! The parser, the router and the encoding do not allocate and do not depend on the
+//! network stack, so they are also available on the host.
+
+mod query;
+mod request;
+mod response;
+mod router;
+#[cfg(feature = "rp2040")]
+mod server;
+
+pub use query::{percent_decode, DecodeError, Query};
+pub use request::{Method, ParseError, Request, Version};
+pub use response::{chunk_header, Head, Length, Status, CHUNK_END, LAST_CHUNK};
+pub use router::{RouteError, Router};
+#[cfg(feature = "rp2040")]
+pub use server::{serve, ChunkedBody, Error, Handler, Response, Sent, MAX_REQUEST_LEN};
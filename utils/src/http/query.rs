@@ -0,0 +1,209 @@
+//! Query strings and `application/x-www-form-urlencoded` bodies.
+
+use heapless::{String, Vec};
+
+/// Error returned by [`percent_decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The decoded value does not fit in the string.
+    TooLong,
+    /// The decoded bytes are not valid UTF-8.
+    InvalidUtf8,
+}
+
+/// Decodes `+` as a space and `%XX` as the byte `XX`. A `%` that is not followed by
+/// two hexadecimal digits is kept as it is.
+///
+/// # Example
+///
+/// ```
+/// use utils::http::percent_decode;
+///
+/// let color = percent_decode::<16>("%23ff8000+now").unwrap();
+/// assert_eq!(color, "#ff8000 now");
+/// ```
+pub fn percent_decode<const N: usize>(raw: &str) -> Result<String<N>, DecodeError> {
+    let mut bytes: Vec<u8, N> = Vec::new();
+    for byte in decode(raw) {
+        bytes.push(byte).map_err(|_| DecodeError::TooLong)?;
+    }
+    String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// Returns the decoded bytes of `raw`.
+fn decode(raw: &str) -> impl Iterator<Item = u8> + '_ {
+    let bytes = raw.as_bytes();
+    let mut index = 0;
+    core::iter::from_fn(move || {
+        let byte = *bytes.get(index)?;
+        index += 1;
+        match byte {
+            b'+' => Some(b' '),
+            b'%' => {
+                // `from_str_radix` accepts a sign, so the digits are checked first.
+                let escaped = bytes
+                    .get(index..index + 2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| core::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                if escaped.is_some() {
+                    index += 2;
+                }
+                Some(escaped.unwrap_or(b'%'))
+            }
+            byte => Some(byte),
+        }
+    })
+}
+
+/// A query string or a form body: `name=value` pairs separated by `&`, with the names
+/// and the values percent-encoded.
+///
+/// # Example
+///
+/// ```
+/// use utils::http::Query;
+///
+/// let query = Query::new("r=255&g=128&name=Pico+W&debug");
+/// assert_eq!(query.get("g"), Some("128"));
+/// assert_eq!(query.get("debug"), Some(""));
+/// assert_eq!(query.get("b"), None);
+/// assert_eq!(query.decoded::<16>("name").unwrap(), "Pico W");
+/// assert_eq!(query.parse::<u8>("r"), Some(255));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Query<'a> {
+    raw: &'a str,
+}
+
+impl<'a> Query<'a> {
+    pub const fn new(raw: &'a str) -> Self {
+        Self { raw }
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.raw
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Returns the pairs, still percent-encoded. A name without `=` has an empty value.
+    pub fn pairs(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.raw
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+    }
+
+    /// Returns the value of the first pair called `name`, still percent-encoded.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.pairs()
+            .find(|(key, _)| decode(key).eq(name.bytes()))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the decoded value of `name`, or `None` if there is no such pair or if
+    /// the value cannot be decoded in `N` bytes.
+    pub fn decoded<const N: usize>(&self, name: &str) -> Option<String<N>> {
+        percent_decode(self.get(name)?).ok()
+    }
+
+    /// Returns the value of `name` parsed as a `T`, like a number.
+    pub fn parse<T: core::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.decoded::<32>(name)?.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(percent_decode::<16>("a%20b+c").unwrap(), "a b c");
+        assert_eq!(percent_decode::<16>("%2B%2b%25").unwrap(), "++%");
+        assert_eq!(percent_decode::<16>("caf%C3%A9").unwrap(), "café");
+        assert_eq!(percent_decode::<16>("").unwrap(), "");
+    }
+
+    #[test]
+    fn incomplete_escapes_are_kept() {
+        assert_eq!(percent_decode::<16>("100%").unwrap(), "100%");
+        assert_eq!(percent_decode::<16>("%4").unwrap(), "%4");
+        assert_eq!(percent_decode::<16>("%G1").unwrap(), "%G1");
+        assert_eq!(percent_decode::<16>("%1G").unwrap(), "%1G");
+        assert_eq!(percent_decode::<16>("%+1%-1").unwrap(), "% 1%-1");
+        assert_eq!(percent_decode::<16>("%%41").unwrap(), "%A");
+        // A multibyte character after `%` is not split.
+        assert_eq!(percent_decode::<16>("%é").unwrap(), "%é");
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        assert_eq!(percent_decode::<16>("%FF"), Err(DecodeError::InvalidUtf8));
+        assert_eq!(
+            percent_decode::<16>("caf%C3"),
+            Err(DecodeError::InvalidUtf8)
+        );
+    }
+
+    #[test]
+    fn too_long_is_an_error() {
+        assert_eq!(percent_decode::<4>("abcd").unwrap(), "abcd");
+        assert_eq!(percent_decode::<4>("abcde"), Err(DecodeError::TooLong));
+        // The limit is on the decoded bytes.
+        assert_eq!(percent_decode::<4>("%41%42%43%44").unwrap(), "ABCD");
+        assert_eq!(percent_decode::<1>("%C3%A9"), Err(DecodeError::TooLong));
+    }
+
+    #[test]
+    fn names_are_compared_decoded() {
+        let query = Query::new("my+name=1&led%20color=red&%61=2");
+        assert_eq!(query.get("my name"), Some("1"));
+        assert_eq!(query.get("led color"), Some("red"));
+        assert_eq!(query.get("a"), Some("2"));
+        assert_eq!(query.get("my+name"), None);
+    }
+
+    #[test]
+    fn values_stay_encoded_until_decoded() {
+        let query = Query::new("name=Pico+W&color=%23ff0000&bad=%FF");
+        assert_eq!(query.get("name"), Some("Pico+W"));
+        assert_eq!(query.decoded::<16>("color").unwrap(), "#ff0000");
+        assert_eq!(query.decoded::<16>("bad"), None);
+        assert_eq!(query.decoded::<2>("name"), None);
+    }
+
+    #[test]
+    fn empty_pairs_are_skipped() {
+        let query = Query::new("&a=1&&b=&c&");
+        let mut pairs = query.pairs();
+        assert_eq!(pairs.next(), Some(("a", "1")));
+        assert_eq!(pairs.next(), Some(("b", "")));
+        assert_eq!(pairs.next(), Some(("c", "")));
+        assert_eq!(pairs.next(), None);
+        assert_eq!(query.get(""), None);
+        assert_eq!(Query::new("").pairs().count(), 0);
+        assert_eq!(Query::new("&&").pairs().count(), 0);
+    }
+
+    #[test]
+    fn first_pair_wins_and_values_keep_their_equals_signs() {
+        let query = Query::new("x=1&x=2&eq=a=b&=empty");
+        assert_eq!(query.get("x"), Some("1"));
+        assert_eq!(query.get("eq"), Some("a=b"));
+        assert_eq!(query.get(""), Some("empty"));
+    }
+
+    #[test]
+    fn values_are_parsed() {
+        let query = Query::new("r=255&g=256&b=-1&t=%2B20");
+        assert_eq!(query.parse::<u8>("r"), Some(255));
+        assert_eq!(query.parse::<u8>("g"), None);
+        assert_eq!(query.parse::<i8>("b"), Some(-1));
+        assert_eq!(query.parse::<i32>("t"), Some(20));
+        assert_eq!(query.parse::<u8>("missing"), None);
+    }
+}
@@ -0,0 +1,362 @@
+//! Parser of HTTP/1.0 and HTTP/1.1 requests.
+
+use super::{Query, Status};
+
+/// The request methods known by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Options,
+}
+
+impl Method {
+    /// Parses a method name. Method names are case-sensitive.
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "GET" => Method::Get,
+            "HEAD" => Method::Head,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "PATCH" => Method::Patch,
+            "DELETE" => Method::Delete,
+            "OPTIONS" => Method::Options,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+            Method::Options => "OPTIONS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    Http10,
+    Http11,
+}
+
+/// Error returned by [`Request::parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The headers or the body have not been completely received.
+    Incomplete,
+    /// The request line or a header is malformed, or the head is not ASCII.
+    BadRequest,
+    /// The method is not one of [`Method`].
+    UnknownMethod,
+    /// The version is not HTTP/1.0 or HTTP/1.1.
+    UnsupportedVersion,
+    /// The body uses a `Transfer-Encoding`, like chunked, instead of a
+    /// `Content-Length`.
+    UnsupportedTransferEncoding,
+}
+
+impl ParseError {
+    /// Returns the status of the response, or `None` for [`ParseError::Incomplete`].
+    pub fn status(self) -> Option<Status> {
+        match self {
+            ParseError::Incomplete => None,
+            ParseError::BadRequest => Some(Status::BadRequest),
+            ParseError::UnknownMethod => Some(Status::NotImplemented),
+            ParseError::UnsupportedVersion => Some(Status::HttpVersionNotSupported),
+            ParseError::UnsupportedTransferEncoding => Some(Status::NotImplemented),
+        }
+    }
+
+    /// Returns the status of the response to a request that the server gives up
+    /// on, with `received` the bytes of the request that fit in its buffer.
+    ///
+    /// A request that is still [`ParseError::Incomplete`] when the buffer is full
+    /// is too large: its body if the head has been received, otherwise its
+    /// headers.
+    pub fn rejection_status(self, received: &[u8]) -> Status {
+        self.status().unwrap_or_else(|| {
+            if head_len(received).is_some() {
+                Status::PayloadTooLarge
+            } else {
+                Status::RequestHeaderFieldsTooLarge
+            }
+        })
+    }
+}
+
+/// Returns the length of the head, up to the empty line that ends it.
+fn head_len(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+/// A request, borrowed from the buffer it was parsed from.
+///
+/// # Example
+///
+/// ```
+/// use utils::http::{Method, Request};
+///
+/// let buffer = b"POST /led?fade=1 HTTP/1.1\r\n\
+///                Host: 192.168.4.1\r\n\
+///                Content-Type: application/x-www-form-urlencoded\r\n\
+///                Content-Length: 15\r\n\r\n\
+///                r=255&g=0&b=128\
+///                GET /sensor HTTP/1.1\r\n";
+///
+/// let (request, len) = Request::parse(buffer).unwrap();
+/// assert_eq!(request.method, Method::Post);
+/// assert_eq!(request.path, "/led");
+/// assert_eq!(request.query.get("fade"), Some("1"));
+/// assert_eq!(request.header("host"), Some("192.168.4.1"));
+/// assert_eq!(request.form().unwrap().parse::<u8>("b"), Some(128));
+/// assert!(request.keep_alive());
+///
+/// // The next request has not been completely received.
+/// assert!(Request::parse(&buffer[len..]).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Request<'a> {
+    pub method: Method,
+    /// The path of the target, without the query string and still percent-encoded.
+    pub path: &'a str,
+    pub query: Query<'a>,
+    pub version: Version,
+    headers: &'a str,
+    pub body: &'a [u8],
+}
+
+impl<'a> Request<'a> {
+    /// Parses the request at the start of `buffer`.
+    ///
+    /// Returns the request and the number of bytes it uses, the head and the body,
+    /// so that the next request of the connection starts right after it.
+    pub fn parse(buffer: &'a [u8]) -> Result<(Self, usize), ParseError> {
+        let head_len = head_len(buffer).ok_or(ParseError::Incomplete)?;
+        let head = core::str::from_utf8(&buffer[..head_len])
+            .ok()
+            .filter(|head| head.is_ascii())
+            .ok_or(ParseError::BadRequest)?;
+
+        let (request_line, headers) = head.split_once("\r\n").unwrap_or((head, ""));
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ParseError::BadRequest);
+        };
+
+        let method = Method::parse(method).ok_or(ParseError::UnknownMethod)?;
+        let version = match version {
+            "HTTP/1.1" => Version::Http11,
+            "HTTP/1.0" => Version::Http10,
+            version if version.starts_with("HTTP/") => return Err(ParseError::UnsupportedVersion),
+            _ => return Err(ParseError::BadRequest),
+        };
+        if !target.starts_with('/') {
+            return Err(ParseError::BadRequest);
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        let mut body_len = 0;
+        for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once(':').ok_or(ParseError::BadRequest)?;
+            if name.is_empty() || name.contains(|c: char| c.is_ascii_whitespace()) {
+                return Err(ParseError::BadRequest);
+            }
+            if name.eq_ignore_ascii_case("transfer-encoding") {
+                return Err(ParseError::UnsupportedTransferEncoding);
+            }
+            if name.eq_ignore_ascii_case("content-length") {
+                body_len = value.trim().parse().map_err(|_| ParseError::BadRequest)?;
+            }
+        }
+
+        let body_start = head_len + 4;
+        let len = body_start
+            .checked_add(body_len)
+            .ok_or(ParseError::BadRequest)?;
+        let body = buffer.get(body_start..len).ok_or(ParseError::Incomplete)?;
+
+        let request = Self {
+            method,
+            path,
+            query: Query::new(query),
+            version,
+            headers,
+            body,
+        };
+        Ok((request, len))
+    }
+
+    /// Returns the headers, with the whitespace around the values removed.
+    pub fn headers(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.headers
+            .split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name, value.trim()))
+    }
+
+    /// Returns the value of the first header called `name`, ignoring the case.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns `true` if the client wants to send more requests on the connection:
+    /// HTTP/1.1 clients that did not send `Connection: close`.
+    pub fn keep_alive(&self) -> bool {
+        self.version == Version::Http11
+            && !self
+                .header("connection")
+                .is_some_and(|value| value.eq_ignore_ascii_case("close"))
+    }
+
+    /// Returns the fields of a form, for bodies of type
+    /// `application/x-www-form-urlencoded`.
+    pub fn form(&self) -> Option<Query<'a>> {
+        let content_type = self.header("content-type")?;
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if !media_type.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+            return None;
+        }
+        core::str::from_utf8(self.body).ok().map(Query::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(buffer: &[u8]) -> ParseError {
+        Request::parse(buffer).unwrap_err()
+    }
+
+    #[test]
+    fn malformed_request_lines_are_bad_requests() {
+        for line in [
+            &b"GET /\r\n\r\n"[..],
+            b"GET / HTTP/1.1 extra\r\n\r\n",
+            b"GET  / HTTP/1.1\r\n\r\n",
+            b"GET index.html HTTP/1.1\r\n\r\n",
+            b"GET / FTP/1.0\r\n\r\n",
+            b"\r\n\r\n",
+        ] {
+            assert_eq!(parse_err(line), ParseError::BadRequest, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn malformed_headers_are_bad_requests() {
+        for head in [
+            &b"GET / HTTP/1.1\r\nHost 192.168.4.1\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\n: empty\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent Length: 1\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: many\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: 99999999999999999999999\r\n\r\n",
+        ] {
+            assert_eq!(parse_err(head), ParseError::BadRequest, "{head:?}");
+        }
+    }
+
+    #[test]
+    fn non_ascii_heads_are_bad_requests() {
+        assert_eq!(
+            parse_err("GET /caf\u{e9} HTTP/1.1\r\n\r\n".as_bytes()),
+            ParseError::BadRequest
+        );
+        assert_eq!(
+            parse_err(b"GET /\xff HTTP/1.1\r\n\r\n"),
+            ParseError::BadRequest
+        );
+    }
+
+    #[test]
+    fn unsupported_requests() {
+        assert_eq!(
+            parse_err(b"get / HTTP/1.1\r\n\r\n"),
+            ParseError::UnknownMethod
+        );
+        assert_eq!(
+            parse_err(b"TRACE / HTTP/1.1\r\n\r\n"),
+            ParseError::UnknownMethod
+        );
+        assert_eq!(
+            parse_err(b"GET / HTTP/2\r\n\r\n"),
+            ParseError::UnsupportedVersion
+        );
+        assert_eq!(
+            parse_err(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"),
+            ParseError::UnsupportedTransferEncoding
+        );
+    }
+
+    #[test]
+    fn incomplete_requests() {
+        assert_eq!(parse_err(b""), ParseError::Incomplete);
+        assert_eq!(
+            parse_err(b"GET / HTTP/1.1\r\nHost: a\r\n"),
+            ParseError::Incomplete
+        );
+        assert_eq!(
+            parse_err(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nab"),
+            ParseError::Incomplete
+        );
+    }
+
+    #[test]
+    fn rejection_status_of_the_errors() {
+        assert_eq!(
+            ParseError::BadRequest.rejection_status(b""),
+            Status::BadRequest
+        );
+        assert_eq!(
+            ParseError::UnknownMethod.rejection_status(b"\r\n\r\n"),
+            Status::NotImplemented
+        );
+        assert_eq!(
+            ParseError::UnsupportedVersion.rejection_status(b""),
+            Status::HttpVersionNotSupported
+        );
+    }
+
+    #[test]
+    fn full_buffer_is_too_large() {
+        assert_eq!(
+            ParseError::Incomplete.rejection_status(b"GET / HTTP/1.1\r\nCookie: aaaa"),
+            Status::RequestHeaderFieldsTooLarge
+        );
+        assert_eq!(
+            ParseError::Incomplete
+                .rejection_status(b"POST / HTTP/1.1\r\nContent-Length: 4096\r\n\r\nabcd"),
+            Status::PayloadTooLarge
+        );
+    }
+
+    #[test]
+    fn only_the_received_bytes_are_scanned() {
+        // The end of a previous request is still in the buffer after the
+        // received bytes of a head that does not fit.
+        let mut buffer = *b"GET /a HTTP/1.1\r\nHost: 192.168.4.1\r\n\r\n";
+        let received = b"GET / HTTP/1.1\r\nCookie: ";
+        buffer[..received.len()].copy_from_slice(received);
+        assert_eq!(
+            ParseError::Incomplete.rejection_status(&buffer[..received.len()]),
+            Status::RequestHeaderFieldsTooLarge
+        );
+        assert_eq!(
+            ParseError::Incomplete.rejection_status(&buffer),
+            Status::PayloadTooLarge
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Status line and headers of HTTP/1.1 responses, and chunked transfer encoding.
+
+use core::fmt::{self, Write};
+
+use heapless::String;
+
+/// The status codes used by the server and the usual handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Created,
+    NoContent,
+    SeeOther,
+    BadRequest,
+    NotFound,
+    MethodNotAllowed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    UnprocessableContent,
+    RequestHeaderFieldsTooLarge,
+    InternalServerError,
+    NotImplemented,
+    ServiceUnavailable,
+    HttpVersionNotSupported,
+}
+
+impl Status {
+    pub fn code(self) -> u16 {
+        match self {
+            Status::Ok => 200,
+            Status::Created => 201,
+            Status::NoContent => 204,
+            Status::SeeOther => 303,
+            Status::BadRequest => 400,
+            Status::NotFound => 404,
+            Status::MethodNotAllowed => 405,
+            Status::PayloadTooLarge => 413,
+            Status::UnsupportedMediaType => 415,
+            Status::UnprocessableContent => 422,
+            Status::RequestHeaderFieldsTooLarge => 431,
+            Status::InternalServerError => 500,
+            Status::NotImplemented => 501,
+            Status::ServiceUnavailable => 503,
+            Status::HttpVersionNotSupported => 505,
+        }
+    }
+
+    pub fn reason(self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Created => "Created",
+            Status::NoContent => "No Content",
+            Status::SeeOther => "See Other",
+            Status::BadRequest => "Bad Request",
+            Status::NotFound => "Not Found",
+            Status::MethodNotAllowed => "Method Not Allowed",
+            Status::PayloadTooLarge => "Payload Too Large",
+            Status::UnsupportedMediaType => "Unsupported Media Type",
+            Status::UnprocessableContent => "Unprocessable Content",
+            Status::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Status::InternalServerError => "Internal Server Error",
+            Status::NotImplemented => "Not Implemented",
+            Status::ServiceUnavailable => "Service Unavailable",
+            Status::HttpVersionNotSupported => "HTTP Version Not Supported",
+        }
+    }
+}
+
+/// How the client finds the end of the body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// `Content-Length`, the body has this number of bytes.
+    Fixed(usize),
+    /// `Transfer-Encoding: chunked`, the body is sent in chunks of any size.
+    Chunked,
+    /// The body ends when the connection is closed, for HTTP/1.0 clients that do not
+    /// know the chunked encoding.
+    UntilClose,
+}
+
+/// The status line and the headers of a response.
+///
+/// # Example
+///
+/// ```
+/// use heapless::String;
+/// use utils::http::{Head, Length, Status};
+///
+/// let head = Head {
+///     status: Status::Ok,
+///     content_type: Some("application/json"),
+///     length: Length::Fixed(2),
+///     keep_alive: false,
+///     headers: &[("Cache-Control", "no-store")],
+/// };
+/// let mut text = String::<256>::new();
+/// head.write(&mut text).unwrap();
+/// assert_eq!(
+///     text,
+///     "HTTP/1.1 200 OK\r\n\
+///      Content-Type: application/json\r\n\
+///      Content-Length: 2\r\n\
+///      Connection: close\r\n\
+///      Cache-Control: no-store\r\n\r\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Head<'a> {
+    pub status: Status,
+    pub content_type: Option<&'a str>,
+    pub length: Length,
+    /// Keep the connection open for the next request. Ignored with
+    /// [`Length::UntilClose`].
+    pub keep_alive: bool,
+    /// Other headers, like `Allow` or `Location`.
+    pub headers: &'a [(&'a str, &'a str)],
+}
+
+impl Head<'_> {
+    pub fn write<W: Write>(&self, out: &mut W) -> fmt::Result {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\n",
+            self.status.code(),
+            self.status.reason()
+        )?;
+        if let Some(content_type) = self.content_type {
+            write!(out, "Content-Type: {}\r\n", content_type)?;
+        }
+        match self.length {
+            Length::Fixed(len) => write!(out, "Content-Length: {}\r\n", len)?,
+            Length::Chunked => out.write_str("Transfer-Encoding: chunked\r\n")?,
+            Length::UntilClose => {}
+        }
+        if !self.keep_alive || self.length == Length::UntilClose {
+            out.write_str("Connection: close\r\n")?;
+        }
+        for (name, value) in self.headers {
+            write!(out, "{}: {}\r\n", name, value)?;
+        }
+        out.write_str("\r\n")
+    }
+}
+
+/// Ends a chunk.
+pub const CHUNK_END: &[u8] = b"\r\n";
+
+/// The chunk of size 0 that ends a chunked body.
+pub const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
+
+/// Returns the line that starts a chunk of `len` bytes, which are followed by
+/// [`CHUNK_END`]. A body ends with [`LAST_CHUNK`], so `len` should not be 0.
+///
+/// # Example
+///
+/// ```
+/// use utils::http::chunk_header;
+///
+/// assert_eq!(chunk_header(26), "1A\r\n");
+/// ```
+pub fn chunk_header(len: usize) -> String<18> {
+    let mut header = String::new();
+    // Cannot fail, a `usize` has at most 16 hexadecimal digits.
+    let _ = write!(header, "{:X}\r\n", len);
+    header
+}
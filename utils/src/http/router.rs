@@ -0,0 +1,262 @@
+//! Routing table: finds the handler of a request from its method and path.
+
+use heapless::Vec;
+
+use super::{Method, Status};
+
+/// Error returned by [`Router::find`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteError {
+    /// No route has this path.
+    NotFound,
+    /// A route has this path, but not for this method.
+    MethodNotAllowed,
+}
+
+impl RouteError {
+    pub fn status(self) -> Status {
+        match self {
+            RouteError::NotFound => Status::NotFound,
+            RouteError::MethodNotAllowed => Status::MethodNotAllowed,
+        }
+    }
+}
+
+struct Route<R> {
+    method: Method,
+    path: &'static str,
+    route: R,
+}
+
+/// Up to `N` routes, each one a method, a path and a value `R` that tells the
+/// application which handler to run, usually an enum.
+///
+/// A path that ends with `/*` matches all the paths that start with it, like
+/// `/files/*` for `/files/index.html`. The routes are tried in the order they were
+/// added. `HEAD` requests use the `GET` routes.
+///
+/// # Example
+///
+/// ```
+/// use utils::http::{Method, RouteError, Router};
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Route {
+///     Index,
+///     Sensor,
+///     Led,
+///     Files,
+/// }
+///
+/// let router = Router::<Route, 4>::new()
+///     .route(Method::Get, "/", Route::Index)
+///     .route(Method::Get, "/sensor", Route::Sensor)
+///     .route(Method::Post, "/led", Route::Led)
+///     .route(Method::Get, "/files/*", Route::Files);
+///
+/// assert_eq!(router.find(Method::Get, "/sensor"), Ok(Route::Sensor));
+/// assert_eq!(router.find(Method::Head, "/sensor"), Ok(Route::Sensor));
+/// assert_eq!(router.find(Method::Get, "/files/app.js"), Ok(Route::Files));
+/// assert_eq!(router.find(Method::Get, "/led"), Err(RouteError::MethodNotAllowed));
+/// assert_eq!(router.find(Method::Get, "/motor"), Err(RouteError::NotFound));
+///
+/// let allowed: Vec<_> = router.allowed("/led").collect();
+/// assert_eq!(allowed, [Method::Post]);
+/// ```
+pub struct Router<R, const N: usize> {
+    routes: Vec<Route<R>, N>,
+}
+
+impl<R: Copy, const N: usize> Router<R, N> {
+    pub const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Adds a route.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the router already has `N` routes.
+    pub fn route(mut self, method: Method, path: &'static str, route: R) -> Self {
+        let route = Route {
+            method,
+            path,
+            route,
+        };
+        if self.routes.push(route).is_err() {
+            panic!("too many routes, the router has room for {}", N);
+        }
+        self
+    }
+
+    /// Returns the route of a request.
+    pub fn find(&self, method: Method, path: &str) -> Result<R, RouteError> {
+        let method = match method {
+            Method::Head => Method::Get,
+            method => method,
+        };
+        let mut found = false;
+        for route in self.routes.iter().filter(|route| matches(route.path, path)) {
+            if route.method == method {
+                return Ok(route.route);
+            }
+            found = true;
+        }
+        Err(if found {
+            RouteError::MethodNotAllowed
+        } else {
+            RouteError::NotFound
+        })
+    }
+
+    /// Returns the methods of the routes that match `path`, for the `Allow` header, in
+    /// the order the routes were added and without duplicates.
+    pub fn allowed<'a>(&'a self, path: &'a str) -> impl Iterator<Item = Method> + 'a {
+        let matching = move |route: &&Route<R>| matches(route.path, path);
+        self.routes
+            .iter()
+            .enumerate()
+            .filter(move |(index, route)| {
+                matching(route)
+                    && !self.routes[..*index]
+                        .iter()
+                        .filter(matching)
+                        .any(|earlier| earlier.method == route.method)
+            })
+            .map(|(_, route)| route.method)
+    }
+}
+
+impl<R: Copy, const N: usize> Default for Router<R, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `true` if `pattern` matches `path`.
+fn matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) if prefix.ends_with('/') => path.starts_with(prefix),
+        _ => pattern == path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Page {
+        Index,
+        Files,
+        Readme,
+        Upload,
+        Remove,
+    }
+
+    fn router() -> Router<Page, 8> {
+        Router::new()
+            .route(Method::Get, "/", Page::Index)
+            .route(Method::Get, "/files/README", Page::Readme)
+            .route(Method::Get, "/files/*", Page::Files)
+            .route(Method::Post, "/files/*", Page::Upload)
+            .route(Method::Delete, "/files/*", Page::Remove)
+    }
+
+    fn allowed<const N: usize>(router: &Router<Page, N>, path: &str) -> Vec<Method, 8> {
+        router.allowed(path).collect()
+    }
+
+    #[test]
+    fn wildcard_matches_below_its_directory() {
+        let router = router();
+        assert_eq!(router.find(Method::Get, "/files/"), Ok(Page::Files));
+        assert_eq!(router.find(Method::Get, "/files/a/b.txt"), Ok(Page::Files));
+        assert_eq!(
+            router.find(Method::Get, "/files"),
+            Err(RouteError::NotFound)
+        );
+        assert_eq!(
+            router.find(Method::Get, "/filesystem"),
+            Err(RouteError::NotFound)
+        );
+    }
+
+    #[test]
+    fn star_without_slash_is_a_plain_path() {
+        let router = Router::<Page, 1>::new().route(Method::Get, "/files*", Page::Files);
+        assert_eq!(router.find(Method::Get, "/files*"), Ok(Page::Files));
+        assert_eq!(
+            router.find(Method::Get, "/files/a"),
+            Err(RouteError::NotFound)
+        );
+    }
+
+    #[test]
+    fn first_matching_route_wins() {
+        let router = router();
+        assert_eq!(router.find(Method::Get, "/files/README"), Ok(Page::Readme));
+        assert_eq!(router.find(Method::Get, "/"), Ok(Page::Index));
+    }
+
+    #[test]
+    fn head_falls_back_to_get() {
+        let router = router();
+        assert_eq!(router.find(Method::Head, "/"), Ok(Page::Index));
+        assert_eq!(router.find(Method::Head, "/files/a"), Ok(Page::Files));
+
+        let router = Router::<Page, 1>::new().route(Method::Post, "/upload", Page::Upload);
+        assert_eq!(
+            router.find(Method::Head, "/upload"),
+            Err(RouteError::MethodNotAllowed)
+        );
+    }
+
+    #[test]
+    fn method_not_allowed_when_another_method_matches() {
+        let router = router();
+        assert_eq!(router.find(Method::Post, "/files/a"), Ok(Page::Upload));
+        assert_eq!(router.find(Method::Delete, "/files/a"), Ok(Page::Remove));
+        assert_eq!(
+            router.find(Method::Put, "/files/a"),
+            Err(RouteError::MethodNotAllowed)
+        );
+        assert_eq!(
+            router.find(Method::Post, "/"),
+            Err(RouteError::MethodNotAllowed)
+        );
+        assert_eq!(
+            router.find(Method::Put, "/other"),
+            Err(RouteError::NotFound)
+        );
+        assert_eq!(RouteError::NotFound.status(), Status::NotFound);
+        assert_eq!(
+            RouteError::MethodNotAllowed.status(),
+            Status::MethodNotAllowed
+        );
+    }
+
+    #[test]
+    fn allowed_methods_in_route_order() {
+        let router = router();
+        assert_eq!(
+            allowed(&router, "/files/a"),
+            [Method::Get, Method::Post, Method::Delete]
+        );
+        // `/files/README` matches two GET routes, GET is only listed once.
+        assert_eq!(
+            allowed(&router, "/files/README"),
+            [Method::Get, Method::Post, Method::Delete]
+        );
+        assert_eq!(allowed(&router, "/"), [Method::Get]);
+        assert!(allowed(&router, "/files").is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "too many routes")]
+    fn too_many_routes_panic() {
+        Router::<Page, 1>::new()
+            .route(Method::Get, "/", Page::Index)
+            .route(Method::Get, "/files/*", Page::Files);
+    }
+}
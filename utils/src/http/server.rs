@@ -0,0 +1,355 @@
+//! HTTP server on an `embassy-net` TCP socket.
+
+use core::fmt::Write;
+
+use embassy_net::driver::Driver;
+use embassy_net::tcp::{self, TcpSocket};
+use embassy_net::Stack;
+use embassy_time::Duration;
+use heapless::String;
+use log::warn;
+
+use super::{
+    chunk_header, Head, Length, Method, ParseError, Request, Router, Status, Version, CHUNK_END,
+    LAST_CHUNK,
+};
+
+/// Maximum size of a request, the head and the body.
+pub const MAX_REQUEST_LEN: usize = 2048;
+
+/// Maximum size of the status line and the headers of a response.
+const MAX_HEAD_LEN: usize = 512;
+
+/// Size of the transmit and receive buffers of the socket.
+const SOCKET_BUFFER_LEN: usize = 1024;
+
+/// The connection is closed when the client sends nothing for this time.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error of a handler or of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The connection was reset or closed by the client.
+    Network(tcp::Error),
+    /// The status line and the headers of the response are longer than 512 bytes.
+    HeadTooLong,
+}
+
+impl From<tcp::Error> for Error {
+    fn from(err: tcp::Error) -> Self {
+        Error::Network(err)
+    }
+}
+
+/// Proof that a response has been sent, returned to the server by the handlers.
+pub struct Sent(());
+
+/// Runs the handler of the route of a request.
+///
+/// The trait is only used by [`serve`] with a concrete type, so the future of
+/// `handle` does not need to be `Send`.
+#[allow(async_fn_in_trait)]
+pub trait Handler<R> {
+    async fn handle(
+        &mut self,
+        route: R,
+        request: &Request<'_>,
+        response: Response<'_, '_>,
+    ) -> Result<Sent, Error>;
+}
+
+/// The response to a request. It is consumed by the method that sends it, so a
+/// handler sends exactly one response.
+pub struct Response<'s, 'd> {
+    socket: &'s mut TcpSocket<'d>,
+    head_only: bool,
+    version: Version,
+    keep_alive: bool,
+}
+
+impl<'s, 'd> Response<'s, 'd> {
+    fn new(socket: &'s mut TcpSocket<'d>, request: &Request<'_>) -> Self {
+        Self {
+            socket,
+            head_only: request.method == Method::Head,
+            version: request.version,
+            keep_alive: request.keep_alive(),
+        }
+    }
+
+    /// Sends a response with a body of known length.
+    pub async fn send(
+        self,
+        status: Status,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<Sent, Error> {
+        self.send_with_headers(status, content_type, &[], body)
+            .await
+    }
+
+    /// Sends a response with other headers, like `Location` or `Cache-Control`.
+    pub async fn send_with_headers(
+        self,
+        status: Status,
+        content_type: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<Sent, Error> {
+        let head = Head {
+            status,
+            content_type: Some(content_type),
+            length: Length::Fixed(body.len()),
+            keep_alive: self.keep_alive,
+            headers,
+        };
+        write_head(self.socket, &head).await?;
+        if !self.head_only {
+            write_all(self.socket, body).await?;
+        }
+        Ok(Sent(()))
+    }
+
+    /// Sends a response without body, like [`Status::NoContent`].
+    pub async fn empty(self, status: Status) -> Result<Sent, Error> {
+        let head = Head {
+            status,
+            content_type: None,
+            length: Length::Fixed(0),
+            keep_alive: self.keep_alive,
+            headers: &[],
+        };
+        write_head(self.socket, &head).await?;
+        Ok(Sent(()))
+    }
+
+    /// Sends the reason of `status` as a plain text body.
+    pub async fn error(self, status: Status) -> Result<Sent, Error> {
+        let reason = status.reason();
+        self.send(status, "text/plain", reason.as_bytes()).await
+    }
+
+    /// Sends the head of a response whose body is written in pieces, when its length
+    /// is not known in advance. The body is sent with the chunked encoding, or until
+    /// the connection is closed for HTTP/1.0 clients.
+    pub async fn chunked(
+        self,
+        status: Status,
+        content_type: &str,
+    ) -> Result<ChunkedBody<'s, 'd>, Error> {
+        let chunked = self.version == Version::Http11;
+        let head = Head {
+            status,
+            content_type: Some(content_type),
+            length: if chunked {
+                Length::Chunked
+            } else {
+                Length::UntilClose
+            },
+            keep_alive: self.keep_alive,
+            headers: &[],
+        };
+        write_head(self.socket, &head).await?;
+        Ok(ChunkedBody {
+            socket: self.socket,
+            head_only: self.head_only,
+            chunked,
+        })
+    }
+}
+
+/// The body of a [`Response::chunked`], ended by [`ChunkedBody::finish`].
+pub struct ChunkedBody<'s, 'd> {
+    socket: &'s mut TcpSocket<'d>,
+    head_only: bool,
+    chunked: bool,
+}
+
+impl ChunkedBody<'_, '_> {
+    /// Sends `data` as one chunk.
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        // An empty chunk would end the body.
+        if self.head_only || data.is_empty() {
+            return Ok(());
+        }
+        if self.chunked {
+            write_all(self.socket, chunk_header(data.len()).as_bytes()).await?;
+        }
+        write_all(self.socket, data).await?;
+        if self.chunked {
+            write_all(self.socket, CHUNK_END).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn write_str(&mut self, text: &str) -> Result<(), Error> {
+        self.write(text.as_bytes()).await
+    }
+
+    /// Ends the body.
+    pub async fn finish(self) -> Result<Sent, Error> {
+        if self.chunked && !self.head_only {
+            write_all(self.socket, LAST_CHUNK).await?;
+        }
+        Ok(Sent(()))
+    }
+}
+
+async fn write_head(socket: &mut TcpSocket<'_>, head: &Head<'_>) -> Result<(), Error> {
+    let mut text: String<MAX_HEAD_LEN> = String::new();
+    head.write(&mut text).map_err(|_| Error::HeadTooLong)?;
+    write_all(socket, text.as_bytes()).await
+}
+
+async fn write_all(socket: &mut TcpSocket<'_>, mut data: &[u8]) -> Result<(), Error> {
+    while !data.is_empty() {
+        let n = socket.write(data).await?;
+        if n == 0 {
+            return Err(Error::Network(tcp::Error::ConnectionReset));
+        }
+        data = &data[n..];
+    }
+    Ok(())
+}
+
+/// Accepts connections on `port` and answers their requests, one connection at a
+/// time, forever.
+///
+/// The requests are parsed and routed by the server, which answers the malformed
+/// requests and the paths or methods without route. The other requests are given
+/// to the handler, with the route found by the `router`. Requests may be up to
+/// [`MAX_REQUEST_LEN`] bytes long, and HTTP/1.1 connections are kept open for more
+/// requests.
+///
+/// Several tasks can run `serve` on the same port to answer several clients at the
+/// same time.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Clone, Copy)]
+/// enum Route {
+///     Hello,
+/// }
+///
+/// struct App;
+///
+/// impl Handler<Route> for App {
+///     async fn handle(
+///         &mut self,
+///         route: Route,
+///         request: &Request<'_>,
+///         response: Response<'_, '_>,
+///     ) -> Result<Sent, Error> {
+///         match route {
+///             Route::Hello => response.send(Status::Ok, "text/plain", b"Hello!").await,
+///         }
+///     }
+/// }
+///
+/// let router = Router::<Route, 1>::new().route(Method::Get, "/", Route::Hello);
+/// utils::http::serve(stack, 80, &router, &mut App).await;
+/// ```
+pub async fn serve<D, R, H, const N: usize>(
+    stack: &Stack<D>,
+    port: u16,
+    router: &Router<R, N>,
+    handler: &mut H,
+) -> !
+where
+    D: Driver,
+    R: Copy,
+    H: Handler<R>,
+{
+    let mut rx_buffer = [0; SOCKET_BUFFER_LEN];
+    let mut tx_buffer = [0; SOCKET_BUFFER_LEN];
+    let mut buffer = [0; MAX_REQUEST_LEN];
+
+    loop {
+        let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
+        socket.set_timeout(Some(IDLE_TIMEOUT));
+        if let Err(err) = socket.accept(port).await {
+            warn!("HTTP accept error: {:?}", err);
+            continue;
+        }
+
+        if let Err(err) = serve_connection(&mut socket, &mut buffer, router, handler).await {
+            warn!("HTTP connection error: {:?}", err);
+        }
+        socket.close();
+        let _ = socket.flush().await;
+        socket.abort();
+    }
+}
+
+async fn serve_connection<R, H, const N: usize>(
+    socket: &mut TcpSocket<'_>,
+    buffer: &mut [u8],
+    router: &Router<R, N>,
+    handler: &mut H,
+) -> Result<(), Error>
+where
+    R: Copy,
+    H: Handler<R>,
+{
+    let mut len = 0;
+    loop {
+        let used = match Request::parse(&buffer[..len]) {
+            Ok((request, used)) => {
+                let response = Response::new(socket, &request);
+                match router.find(request.method, request.path) {
+                    Ok(route) => {
+                        handler.handle(route, &request, response).await?;
+                    }
+                    Err(err) => {
+                        let mut allow: String<64> = String::new();
+                        for method in router.allowed(request.path) {
+                            let separator = if allow.is_empty() { "" } else { ", " };
+                            let _ = write!(allow, "{}{}", separator, method.as_str());
+                        }
+                        let status = err.status();
+                        let headers = [("Allow", allow.as_str())];
+                        let headers = if allow.is_empty() { &[][..] } else { &headers };
+                        response
+                            .send_with_headers(
+                                status,
+                                "text/plain",
+                                headers,
+                                status.reason().as_bytes(),
+                            )
+                            .await?;
+                    }
+                }
+                if !request.keep_alive() {
+                    return Ok(());
+                }
+                used
+            }
+            Err(ParseError::Incomplete) if len < buffer.len() => {
+                let n = socket.read(&mut buffer[len..]).await?;
+                if n == 0 {
+                    return Ok(());
+                }
+                len += n;
+                continue;
+            }
+            Err(err) => {
+                let status = err.rejection_status(&buffer[..len]);
+                let head = Head {
+                    status,
+                    content_type: Some("text/plain"),
+                    length: Length::Fixed(status.reason().len()),
+                    keep_alive: false,
+                    headers: &[],
+                };
+                write_head(socket, &head).await?;
+                write_all(socket, status.reason().as_bytes()).await?;
+                return Ok(());
+            }
+        };
+
+        // The next request may already be in the buffer.
+        buffer.copy_within(used..len, 0);
+        len -= used;
+    }
+}
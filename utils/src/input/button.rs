@@ -0,0 +1,105 @@
+//! Button on a GPIO input, driving [`ButtonEvents`].
+
+use embassy_futures::select::select;
+use embassy_rp::gpio::{Input, Level};
+use embassy_time::{Instant, Timer};
+use heapless::Deque;
+
+use super::{ButtonConfig, ButtonEvent, ButtonEvents};
+
+/// A debounced button on a GPIO input.
+///
+/// The buttons of the Pico Explorer connect their pin to the ground, with the pull
+/// up of the input: they are active low, see [`Button::new`].
+///
+/// ```ignore
+/// let button_a = Input::new(peripherals.PIN_12, Pull::Up);
+/// let mut button_a = Button::new(button_a, ButtonConfig::default());
+/// loop {
+///     match button_a.next_event().await {
+///         ButtonEvent::Click => info!("click"),
+///         ButtonEvent::LongPress(held) => info!("held for {} ms", held.as_millis()),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub struct Button<'d> {
+    input: Input<'d>,
+    /// The level of the pin when the button is down.
+    active: Level,
+    events: ButtonEvents,
+    pending: Deque<ButtonEvent, 3>,
+}
+
+impl<'d> Button<'d> {
+    /// Returns a button that pulls its input low when it is pressed.
+    pub fn new(input: Input<'d>, config: ButtonConfig) -> Self {
+        Self::with_active_level(input, Level::Low, config)
+    }
+
+    /// Returns a button that drives its input to `active` when it is pressed.
+    pub fn with_active_level(input: Input<'d>, active: Level, config: ButtonConfig) -> Self {
+        let mut events = ButtonEvents::new(config);
+        // A button held at startup is pressed, without waiting for its edge
+        let pressed = input.get_level() == active;
+        events.update(pressed, Instant::MIN);
+        Self {
+            input,
+            active,
+            events,
+            pending: Deque::new(),
+        }
+    }
+
+    /// Returns the debounced state of the button.
+    pub fn is_pressed(&self) -> bool {
+        self.events.is_pressed()
+    }
+
+    /// Returns when the contact went down for the last [`ButtonEvent::Pressed`]
+    /// returned, see [`ButtonEvents::pressed_at`].
+    pub fn pressed_at(&self) -> Instant {
+        self.events.pressed_at()
+    }
+
+    /// Returns when the contact went up for the last [`ButtonEvent::Released`]
+    /// returned, see [`ButtonEvents::released_at`].
+    pub fn released_at(&self) -> Instant {
+        self.events.released_at()
+    }
+
+    /// Waits for the next event of the button.
+    ///
+    /// It is cancel safe: dropping it, in a `select` for example, does not lose any
+    /// event.
+    pub async fn next_event(&mut self) -> ButtonEvent {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return event;
+            }
+            let pressed = self.input.get_level() == self.active;
+            for event in self.events.update(pressed, Instant::now()) {
+                // Cannot fail, the queue is empty and an update has at most 3 events
+                let _ = self.pending.push_back(event);
+            }
+            if !self.pending.is_empty() {
+                continue;
+            }
+
+            // Waiting for a level rather than an edge does not miss the edges that
+            // happened since the pin was read
+            let edge = async {
+                match (self.events.contact(), self.active) {
+                    (true, Level::Low) | (false, Level::High) => self.input.wait_for_high().await,
+                    (true, Level::High) | (false, Level::Low) => self.input.wait_for_low().await,
+                }
+            };
+            match self.events.deadline() {
+                Some(deadline) => {
+                    select(edge, Timer::at(deadline)).await;
+                }
+                None => edge.await,
+            }
+        }
+    }
+}
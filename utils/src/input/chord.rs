@@ -0,0 +1,300 @@
+//! Buttons of a pad, and chords of buttons held together.
+
+use core::fmt;
+use core::ops::BitOr;
+
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+use super::ButtonEvent;
+
+/// Maximum number of chords of a [`Chords`].
+pub const MAX_CHORDS: usize = 4;
+
+/// Maximum number of buttons of a pad, the bits of a [`ButtonId`].
+pub const MAX_BUTTONS: usize = 8;
+
+/// A button of a pad, or a chord of several buttons.
+///
+/// It is a set of buttons: the button `n` of the pad is [`ButtonId::new(n)`](ButtonId::new),
+/// and a chord is the union of its buttons, `ButtonId::A | ButtonId::B`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ButtonId(u8);
+
+impl ButtonId {
+    /// Button A of the Pico Explorer, the first button of
+    /// [`pico_explorer_buttons!`](crate::pico_explorer_buttons).
+    pub const A: Self = Self::new(0);
+    /// Button B of the Pico Explorer.
+    pub const B: Self = Self::new(1);
+    /// Button X of the Pico Explorer.
+    pub const X: Self = Self::new(2);
+    /// Button Y of the Pico Explorer.
+    pub const Y: Self = Self::new(3);
+
+    /// Returns the button `index` of a pad.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is [`MAX_BUTTONS`] or more.
+    pub const fn new(index: usize) -> Self {
+        assert!(index < MAX_BUTTONS, "a pad has at most 8 buttons");
+        Self(1 << index)
+    }
+
+    /// Returns `true` if all the buttons of `other` are in this set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if it has more than one button.
+    pub const fn is_chord(self) -> bool {
+        self.0.count_ones() > 1
+    }
+
+    /// Returns the index of the button, `None` for a chord.
+    pub const fn index(self) -> Option<usize> {
+        if self.0.count_ones() == 1 {
+            Some(self.0.trailing_zeros() as usize)
+        } else {
+            None
+        }
+    }
+
+    const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl BitOr for ButtonId {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for ButtonId {
+    /// Writes the buttons, `A+B` with the names of the Pico Explorer.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [&str; MAX_BUTTONS] = ["A", "B", "X", "Y", "4", "5", "6", "7"];
+        let mut first = true;
+        for (index, name) in NAMES.iter().enumerate() {
+            if self.contains(Self::new(index)) {
+                if !first {
+                    f.write_str("+")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An event of a button or a chord of a pad.
+pub type PadEvent = (ButtonId, ButtonEvent);
+
+/// The events of one [`Chords::update`]: the event of the button, and those of the
+/// chords it starts or ends.
+pub type PadEvents = Vec<PadEvent, { 1 + 2 * MAX_CHORDS }>;
+
+#[derive(Debug, Clone, Copy)]
+struct Chord {
+    buttons: ButtonId,
+    pressed_at: Option<Instant>,
+}
+
+/// Chords of the buttons of a pad.
+///
+/// A chord is pressed when all its buttons are down, whatever their order, and
+/// released with the first of them. Its events are [`ButtonEvent::Pressed`],
+/// [`ButtonEvent::Released`], then [`ButtonEvent::Click`] or
+/// [`ButtonEvent::LongPress`]. The buttons of a chord still report their presses
+/// and releases, but not their clicks and long presses.
+///
+/// # Example
+///
+/// ```
+/// use embassy_time::{Duration, Instant};
+/// use utils::input::{ButtonEvent, ButtonId, Chords};
+///
+/// let ms = Instant::from_millis;
+/// let mut chords = Chords::new(Duration::from_millis(800));
+/// chords.add(ButtonId::A | ButtonId::B).unwrap();
+///
+/// let a = ButtonId::A;
+/// let b = ButtonId::B;
+/// let chord = a | b;
+/// assert_eq!(chords.update(a, ButtonEvent::Pressed, ms(0)), [(a, ButtonEvent::Pressed)]);
+/// let events = chords.update(b, ButtonEvent::Pressed, ms(30));
+/// assert_eq!(events, [(b, ButtonEvent::Pressed), (chord, ButtonEvent::Pressed)]);
+/// assert_eq!(format!("{:?}", chord), "A+B");
+///
+/// let events = chords.update(a, ButtonEvent::Released, ms(2030));
+/// let held = Duration::from_secs(2);
+/// assert_eq!(
+///     events,
+///     [
+///         (a, ButtonEvent::Released),
+///         (chord, ButtonEvent::Released),
+///         (chord, ButtonEvent::LongPress(held)),
+///     ]
+/// );
+/// // The long press of A is the one of the chord
+/// assert!(chords.update(a, ButtonEvent::LongPress(held), ms(2030)).is_empty());
+/// chords.update(b, ButtonEvent::Released, ms(2050));
+///
+/// // A alone
+/// chords.update(a, ButtonEvent::Pressed, ms(3000));
+/// chords.update(a, ButtonEvent::Released, ms(3100));
+/// assert_eq!(chords.update(a, ButtonEvent::Click, ms(3400)), [(a, ButtonEvent::Click)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Chords {
+    chords: Vec<Chord, MAX_CHORDS>,
+    long_press: Duration,
+    held: ButtonId,
+    /// The buttons that were part of a chord since their last click or long press.
+    in_chord: ButtonId,
+}
+
+impl Chords {
+    /// Returns a set of chords without any, with the long press time of the buttons.
+    pub const fn new(long_press: Duration) -> Self {
+        Self {
+            chords: Vec::new(),
+            long_press,
+            held: ButtonId(0),
+            in_chord: ButtonId(0),
+        }
+    }
+
+    /// Adds a chord, returns it back if there are already [`MAX_CHORDS`].
+    pub fn add(&mut self, chord: ButtonId) -> Result<(), ButtonId> {
+        self.chords
+            .push(Chord {
+                buttons: chord,
+                pressed_at: None,
+            })
+            .map_err(|chord| chord.buttons)
+    }
+
+    /// Returns the buttons that are down.
+    pub fn held(&self) -> ButtonId {
+        self.held
+    }
+
+    /// Takes an `event` of the `button` and returns the events to report.
+    ///
+    /// `at` is the time of the debounced edge of a press or a release, which times
+    /// the chords. It is not used by the other events.
+    pub fn update(&mut self, button: ButtonId, event: ButtonEvent, at: Instant) -> PadEvents {
+        let mut events = PadEvents::new();
+        match event {
+            ButtonEvent::Pressed => {
+                self.held = self.held | button;
+                push(&mut events, (button, event));
+                for chord in self.chords.iter_mut() {
+                    if chord.pressed_at.is_none() && self.held.contains(chord.buttons) {
+                        chord.pressed_at = Some(at);
+                        self.in_chord = self.in_chord | chord.buttons;
+                        push(&mut events, (chord.buttons, ButtonEvent::Pressed));
+                    }
+                }
+            }
+            ButtonEvent::Released => {
+                self.held = self.held.without(button);
+                push(&mut events, (button, event));
+                for chord in self.chords.iter_mut() {
+                    let Some(pressed_at) = chord.pressed_at else {
+                        continue;
+                    };
+                    if chord.buttons.contains(button) {
+                        chord.pressed_at = None;
+                        let held = at - pressed_at;
+                        push(&mut events, (chord.buttons, ButtonEvent::Released));
+                        let gesture = if held >= self.long_press {
+                            ButtonEvent::LongPress(held)
+                        } else {
+                            ButtonEvent::Click
+                        };
+                        push(&mut events, (chord.buttons, gesture));
+                    }
+                }
+            }
+            ButtonEvent::Click | ButtonEvent::DoubleClick | ButtonEvent::LongPress(_) => {
+                if self.in_chord.contains(button) {
+                    self.in_chord = self.in_chord.without(button);
+                } else {
+                    push(&mut events, (button, event));
+                }
+            }
+        }
+        events
+    }
+}
+
+fn push(events: &mut PadEvents, event: PadEvent) {
+    // Cannot fail, a button event ends at most all the chords.
+    let _ = events.push(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{ButtonConfig, ButtonEvents};
+
+    #[test]
+    fn every_button_of_a_pad_has_an_id() {
+        let all = (0..MAX_BUTTONS).fold(ButtonId::default(), |all, index| {
+            let button = ButtonId::new(index);
+            assert_eq!(button.index(), Some(index));
+            all | button
+        });
+        assert_eq!(all, ButtonId(u8::MAX));
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_pad_has_at_most_8_buttons() {
+        ButtonId::new(MAX_BUTTONS);
+    }
+
+    #[test]
+    fn chords_are_timed_with_the_debounced_edges() {
+        let ms = Instant::from_millis;
+        let config = ButtonConfig::default();
+        let mut chords = Chords::new(config.long_press);
+        let chord = ButtonId::A | ButtonId::B;
+        chords.add(chord).unwrap();
+        let mut a = ButtonEvents::new(config);
+        let mut b = ButtonEvents::new(config);
+
+        // The events are handled late, after the debounce time.
+        a.update(true, ms(0));
+        b.update(true, ms(100));
+        assert_eq!(a.update(true, ms(500)), [ButtonEvent::Pressed]);
+        chords.update(ButtonId::A, ButtonEvent::Pressed, a.pressed_at());
+        assert_eq!(b.update(true, ms(500)), [ButtonEvent::Pressed]);
+        chords.update(ButtonId::B, ButtonEvent::Pressed, b.pressed_at());
+        assert_eq!(b.pressed_at(), ms(100));
+
+        a.update(false, ms(400));
+        // Held for 1 s by the time it is handled, but 400 ms between the edges.
+        assert_eq!(
+            a.update(false, ms(1500)),
+            [ButtonEvent::Released, ButtonEvent::Click]
+        );
+        assert_eq!(a.released_at(), ms(400));
+        let events = chords.update(ButtonId::A, ButtonEvent::Released, a.released_at());
+        assert_eq!(
+            events,
+            [
+                (ButtonId::A, ButtonEvent::Released),
+                (chord, ButtonEvent::Released),
+                (chord, ButtonEvent::Click),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Quadrature encoder read by a PIO state machine.
+
+use embassy_rp::gpio::Pull;
+use embassy_rp::pio::{
+    Common, Config, Direction, FifoJoin, Instance, PioPin, ShiftDirection, StateMachine,
+};
+use embassy_time::Instant;
+use fixed::traits::ToFixed;
+
+use super::Quadrature;
+
+/// Divider of the system clock for the state machine, it reads the signals every 5
+/// cycles, 25 000 times per second at 125 MHz.
+const CLOCK_DIVIDER: u16 = 1_000;
+
+/// A rotary encoder, or any quadrature signal, on two consecutive pins.
+///
+/// A PIO state machine reads the signals and pushes their state to its FIFO each time
+/// it changes, and the states are decoded by a [`Quadrature`]. The FIFO holds 8 states,
+/// so the steps are not missed while the executor runs other tasks for a short time.
+/// When the FIFO is full, the state machine waits until a state is read and the steps
+/// made meanwhile can be lost: [`Encoder::errors`] counts these stalls.
+///
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     PIO1_IRQ_0 => embassy_rp::pio::InterruptHandler<PIO1>;
+/// });
+///
+/// let Pio { mut common, sm0, .. } = Pio::new(peripherals.PIO1, Irqs);
+/// let mut encoder = Encoder::new(&mut common, sm0, peripherals.PIN_6, peripherals.PIN_7, 4);
+/// loop {
+///     let change = encoder.wait_for_change().await;
+///     info!("{} at {} detents/s", change, encoder.velocity());
+/// }
+/// ```
+pub struct Encoder<'d, T: Instance, const SM: usize> {
+    sm: StateMachine<'d, T, SM>,
+    quadrature: Quadrature,
+    /// The position returned by the last [`Encoder::wait_for_change`].
+    reported: i32,
+    /// Number of times the state machine waited on a full FIFO.
+    stalls: u32,
+}
+
+impl<'d, T: Instance, const SM: usize> Encoder<'d, T, SM> {
+    /// Starts reading the encoder with the pins A and B, that pull their signal low,
+    /// and that makes `steps_per_detent` steps from one detent to the next.
+    ///
+    /// # Panics
+    ///
+    /// Panics if B is not the pin after A.
+    pub fn new(
+        common: &mut Common<'d, T>,
+        mut sm: StateMachine<'d, T, SM>,
+        pin_a: impl PioPin,
+        pin_b: impl PioPin,
+        steps_per_detent: u8,
+    ) -> Self {
+        let mut pin_a = common.make_pio_pin(pin_a);
+        let mut pin_b = common.make_pio_pin(pin_b);
+        // The program reads both signals with a single `in pins, 2`
+        assert_eq!(pin_b.pin(), pin_a.pin() + 1, "B must be the pin after A");
+        pin_a.set_pull(Pull::Up);
+        pin_b.set_pull(Pull::Up);
+        sm.set_pin_dirs(Direction::In, &[&pin_a, &pin_b]);
+
+        // Y holds the last state, initialized to a value the pins cannot have so that
+        // the first state is pushed
+        let program = pio_proc::pio_asm!(
+            "    mov y, ~null",
+            ".wrap_target",
+            "sample:",
+            "    mov isr, null",
+            "    in pins, 2",
+            "    mov x, isr",
+            "    jmp x!=y changed",
+            "    jmp sample",
+            "changed:",
+            "    mov y, x",
+            "    push block",
+            ".wrap",
+        );
+
+        let mut config = Config::default();
+        config.use_program(&common.load_program(&program.program), &[]);
+        config.set_in_pins(&[&pin_a, &pin_b]);
+        config.shift_in.direction = ShiftDirection::Left;
+        config.fifo_join = FifoJoin::RxOnly;
+        config.clock_divider = CLOCK_DIVIDER.to_fixed();
+        sm.set_config(&config);
+        sm.set_enable(true);
+
+        Self {
+            sm,
+            quadrature: Quadrature::new(steps_per_detent),
+            reported: 0,
+            stalls: 0,
+        }
+    }
+
+    /// Returns the position, in detents from the start.
+    pub fn position(&mut self) -> i32 {
+        self.read();
+        self.quadrature.position()
+    }
+
+    /// Returns the velocity, in detents per second, positive clockwise.
+    pub fn velocity(&mut self) -> i32 {
+        self.read();
+        self.quadrature.velocity(Instant::now())
+    }
+
+    /// Returns the number of times steps may have been missed: the invalid transitions
+    /// of [`Quadrature::errors`], and the times the state machine waited on a full
+    /// FIFO because the states were not read fast enough.
+    pub fn errors(&self) -> u32 {
+        self.quadrature.errors().saturating_add(self.stalls)
+    }
+
+    /// Waits for the position to change, and returns the change since the last call,
+    /// in detents.
+    ///
+    /// It is cancel safe, the states read by the state machine stay in its FIFO.
+    pub async fn wait_for_change(&mut self) -> i32 {
+        loop {
+            let change = self.quadrature.position().wrapping_sub(self.reported);
+            if change != 0 {
+                self.reported = self.quadrature.position();
+                return change;
+            }
+            let state = self.sm.rx().wait_pull().await;
+            self.decode(state);
+        }
+    }
+
+    /// Decodes the states in the FIFO.
+    fn read(&mut self) {
+        while let Some(state) = self.sm.rx().try_pull() {
+            self.decode(state);
+        }
+    }
+
+    fn decode(&mut self, state: u32) {
+        // The flag is set when the state machine waited for room in the FIFO, and
+        // cleared when it is read.
+        if self.sm.rx().stalled() {
+            self.stalls = self.stalls.saturating_add(1);
+        }
+        self.quadrature.update(state as u8, Instant::now());
+    }
+}
@@ -0,0 +1,474 @@
+//! Debouncing and click detection, driven by the level of the contact and the time.
+
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+/// Timings of a [`ButtonEvents`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonConfig {
+    /// How long the contact has to stay at a level for it to be taken into account.
+    /// The bounces of the contact are shorter than this.
+    pub debounce: Duration,
+    /// Maximum time between the release of a click and the next press for them to
+    /// be a double click. [`Duration::MIN`] reports every click at once, without
+    /// double clicks.
+    pub double_click: Duration,
+    /// Minimum time a button is held for a long press.
+    pub long_press: Duration,
+}
+
+impl Default for ButtonConfig {
+    /// Debounces for 20 ms, double clicks within 300 ms, long presses from 800 ms.
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(20),
+            double_click: Duration::from_millis(300),
+            long_press: Duration::from_millis(800),
+        }
+    }
+}
+
+/// Something the button did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// The button went down.
+    Pressed,
+    /// The button went up.
+    Released,
+    /// The button was pressed and released once, shorter than a long press. It is
+    /// reported when the double click delay is over without a second press.
+    Click,
+    /// The button was clicked twice in a row.
+    DoubleClick,
+    /// The button was released after being held for this long, at least the long
+    /// press time. It replaces the click.
+    LongPress(Duration),
+}
+
+/// The events of one [`update`](ButtonEvents::update), at most a release, the click
+/// before it and its long press.
+pub type Events = Vec<ButtonEvent, 3>;
+
+/// The state machine of a button: debounces the level of its contact and turns the
+/// presses into [`ButtonEvent`]s.
+///
+/// It does not read the pin or the time itself: [`ButtonEvents::update`] is called
+/// with the level of the contact when it changes, and at the time returned by
+/// [`ButtonEvents::deadline`], which is how [`Button`](crate::input::Button) drives it.
+/// The events carry the time of the edges, so an update that comes late does not
+/// change them.
+///
+/// # Example
+///
+/// A click with bounces, then a double click:
+///
+/// ```
+/// use embassy_time::{Duration, Instant};
+/// use utils::input::{ButtonConfig, ButtonEvent, ButtonEvents};
+///
+/// let ms = Instant::from_millis;
+/// let mut button = ButtonEvents::new(ButtonConfig::default());
+///
+/// // Bounces, then the contact stays down from 3 ms
+/// assert!(button.update(true, ms(0)).is_empty());
+/// assert!(button.update(false, ms(1)).is_empty());
+/// assert!(button.update(true, ms(3)).is_empty());
+/// assert_eq!(button.deadline(), Some(ms(23)));
+/// assert_eq!(button.update(true, ms(23)), [ButtonEvent::Pressed]);
+/// assert!(button.is_pressed());
+///
+/// button.update(false, ms(100));
+/// assert_eq!(button.update(false, ms(120)), [ButtonEvent::Released]);
+/// // The click waits for a second one
+/// assert_eq!(button.deadline(), Some(ms(400)));
+/// assert_eq!(button.update(false, ms(400)), [ButtonEvent::Click]);
+/// assert_eq!(button.deadline(), None);
+///
+/// // Two clicks 200 ms apart
+/// button.update(true, ms(1000));
+/// button.update(true, ms(1020));
+/// button.update(false, ms(1100));
+/// button.update(false, ms(1120));
+/// button.update(true, ms(1300));
+/// assert_eq!(button.update(true, ms(1320)), [ButtonEvent::Pressed]);
+/// button.update(false, ms(1400));
+/// let events = button.update(false, ms(1420));
+/// assert_eq!(events, [ButtonEvent::Released, ButtonEvent::DoubleClick]);
+/// ```
+///
+/// A long press, and a glitch shorter than the debounce time:
+///
+/// ```
+/// # use embassy_time::{Duration, Instant};
+/// # use utils::input::{ButtonConfig, ButtonEvent, ButtonEvents};
+/// # let ms = Instant::from_millis;
+/// let mut button = ButtonEvents::new(ButtonConfig::default());
+///
+/// button.update(true, ms(0));
+/// button.update(true, ms(20));
+/// button.update(false, ms(1500));
+/// let events = button.update(false, ms(1520));
+/// let held = Duration::from_millis(1500);
+/// assert_eq!(events, [ButtonEvent::Released, ButtonEvent::LongPress(held)]);
+///
+/// button.update(true, ms(2000));
+/// assert!(button.update(false, ms(2005)).is_empty());
+/// assert_eq!(button.deadline(), None);
+/// assert!(!button.is_pressed());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ButtonEvents {
+    config: ButtonConfig,
+    /// The debounced level.
+    pressed: bool,
+    /// The last level of the contact, and when it changed.
+    raw: bool,
+    changed_at: Instant,
+    pressed_at: Instant,
+    released_at: Instant,
+    /// A click was released, a second one would make a double click.
+    click_pending: bool,
+}
+
+impl ButtonEvents {
+    /// Returns the state machine of a button that is up.
+    pub const fn new(config: ButtonConfig) -> Self {
+        Self {
+            config,
+            pressed: false,
+            raw: false,
+            changed_at: Instant::MIN,
+            pressed_at: Instant::MIN,
+            released_at: Instant::MIN,
+            click_pending: false,
+        }
+    }
+
+    /// Returns the debounced state of the button.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Returns when the contact went down for the last [`ButtonEvent::Pressed`], at
+    /// the start of its debounce time.
+    pub fn pressed_at(&self) -> Instant {
+        self.pressed_at
+    }
+
+    /// Returns when the contact went up for the last [`ButtonEvent::Released`], at
+    /// the start of its debounce time.
+    pub fn released_at(&self) -> Instant {
+        self.released_at
+    }
+
+    /// Returns the level of the contact the last time it changed, `true` when it is
+    /// down.
+    pub fn contact(&self) -> bool {
+        self.raw
+    }
+
+    /// Takes the level of the contact at `now`, `true` when it is down, and returns
+    /// the events that happened since the last update.
+    pub fn update(&mut self, pressed: bool, now: Instant) -> Events {
+        let mut events = Events::new();
+        if pressed != self.raw {
+            self.raw = pressed;
+            self.changed_at = now;
+        }
+
+        if self.raw != self.pressed && now >= self.changed_at + self.config.debounce {
+            self.pressed = self.raw;
+            let at = self.changed_at;
+            if self.pressed {
+                // The update at the end of the double click delay did not happen
+                if self.click_pending && at > self.click_deadline() {
+                    self.click_pending = false;
+                    push(&mut events, ButtonEvent::Click);
+                }
+                self.pressed_at = at;
+                push(&mut events, ButtonEvent::Pressed);
+            } else {
+                self.released_at = at;
+                push(&mut events, ButtonEvent::Released);
+                self.released(&mut events, at - self.pressed_at);
+            }
+        }
+
+        if self.click_timeout().is_some_and(|timeout| now >= timeout) {
+            self.click_pending = false;
+            push(&mut events, ButtonEvent::Click);
+        }
+        events
+    }
+
+    /// Returns when [`ButtonEvents::update`] has to be called again if the contact
+    /// does not change: at the end of the debounce time or of the double click delay.
+    pub fn deadline(&self) -> Option<Instant> {
+        let debounce = (self.raw != self.pressed).then(|| self.changed_at + self.config.debounce);
+        let click = self.click_timeout();
+        match (debounce, click) {
+            (Some(debounce), Some(click)) => Some(debounce.min(click)),
+            (debounce, click) => debounce.or(click),
+        }
+    }
+
+    fn released(&mut self, events: &mut Events, held: Duration) {
+        if held >= self.config.long_press {
+            if self.click_pending {
+                self.click_pending = false;
+                push(events, ButtonEvent::Click);
+            }
+            push(events, ButtonEvent::LongPress(held));
+        } else if self.click_pending {
+            self.click_pending = false;
+            push(events, ButtonEvent::DoubleClick);
+        } else if self.config.double_click == Duration::MIN {
+            push(events, ButtonEvent::Click);
+        } else {
+            self.click_pending = true;
+        }
+    }
+
+    fn click_deadline(&self) -> Instant {
+        self.released_at + self.config.double_click
+    }
+
+    /// Returns when the pending click is reported, unless the button is down or the
+    /// contact went down before the end of the double click delay and is being
+    /// debounced: it may be the second click.
+    fn click_timeout(&self) -> Option<Instant> {
+        let deadline = self.click_deadline();
+        let second_press = self.pressed || (self.raw && self.changed_at <= deadline);
+        (self.click_pending && !second_press).then_some(deadline)
+    }
+}
+
+fn push(events: &mut Events, event: ButtonEvent) {
+    // Cannot fail, an update has at most 3 events.
+    let _ = events.push(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    fn button() -> ButtonEvents {
+        ButtonEvents::new(ButtonConfig::default())
+    }
+
+    /// Moves the contact at `at` and updates at the end of the debounce time.
+    fn settle(button: &mut ButtonEvents, pressed: bool, at: u64) -> Events {
+        assert!(button.update(pressed, ms(at)).is_empty());
+        assert_eq!(button.deadline(), Some(ms(at + 20)));
+        button.update(pressed, ms(at + 20))
+    }
+
+    /// A click released at `at + 100`, still waiting for a second one.
+    fn click(button: &mut ButtonEvents, at: u64) {
+        assert_eq!(settle(button, true, at), [ButtonEvent::Pressed]);
+        assert_eq!(settle(button, false, at + 100), [ButtonEvent::Released]);
+    }
+
+    #[test]
+    fn glitches_are_ignored() {
+        let mut button = button();
+        assert!(button.update(true, ms(0)).is_empty());
+        assert!(button.update(false, ms(19)).is_empty());
+        assert_eq!(button.deadline(), None);
+        assert!(button.update(false, ms(100)).is_empty());
+        assert!(!button.is_pressed());
+
+        // A glitch while the button is held.
+        settle(&mut button, true, 200);
+        assert!(button.update(false, ms(300)).is_empty());
+        assert!(button.update(true, ms(310)).is_empty());
+        assert_eq!(button.deadline(), None);
+        assert!(button.update(true, ms(400)).is_empty());
+        assert!(button.is_pressed());
+        assert!(button.contact());
+    }
+
+    #[test]
+    fn debounce_restarts_at_every_bounce() {
+        let mut button = button();
+        button.update(true, ms(0));
+        button.update(false, ms(10));
+        button.update(true, ms(15));
+        assert_eq!(button.deadline(), Some(ms(35)));
+        assert!(button.update(true, ms(34)).is_empty());
+        assert_eq!(button.update(true, ms(35)), [ButtonEvent::Pressed]);
+        assert_eq!(button.pressed_at(), ms(15));
+    }
+
+    #[test]
+    fn click_is_reported_at_the_double_click_deadline() {
+        let mut button = button();
+        click(&mut button, 0);
+        assert_eq!(button.released_at(), ms(100));
+        assert_eq!(button.deadline(), Some(ms(400)));
+        assert!(button.update(false, ms(399)).is_empty());
+        assert_eq!(button.update(false, ms(400)), [ButtonEvent::Click]);
+        assert_eq!(button.deadline(), None);
+        assert!(button.update(false, ms(1000)).is_empty());
+    }
+
+    #[test]
+    fn second_press_at_the_deadline_is_a_double_click() {
+        let mut button = button();
+        click(&mut button, 0);
+        // The contact goes down exactly at the end of the double click delay.
+        assert!(button.update(true, ms(400)).is_empty());
+        assert_eq!(button.deadline(), Some(ms(420)));
+        assert_eq!(button.update(true, ms(420)), [ButtonEvent::Pressed]);
+        let events = settle(&mut button, false, 500);
+        assert_eq!(events, [ButtonEvent::Released, ButtonEvent::DoubleClick]);
+    }
+
+    #[test]
+    fn second_press_after_the_deadline_is_another_click() {
+        let mut button = button();
+        click(&mut button, 0);
+        // No update at the deadline, the contact goes down just after it.
+        let events = button.update(true, ms(401));
+        assert_eq!(events, [ButtonEvent::Click]);
+        assert_eq!(button.update(true, ms(421)), [ButtonEvent::Pressed]);
+        assert_eq!(settle(&mut button, false, 500), [ButtonEvent::Released]);
+        assert_eq!(button.deadline(), Some(ms(800)));
+    }
+
+    #[test]
+    fn second_press_being_debounced_holds_the_click() {
+        let mut button = button();
+        click(&mut button, 0);
+        // Down 10 ms before the deadline, debounced 10 ms after it.
+        assert!(button.update(true, ms(390)).is_empty());
+        assert_eq!(button.deadline(), Some(ms(410)));
+        assert!(button.update(true, ms(400)).is_empty());
+        assert_eq!(button.update(true, ms(410)), [ButtonEvent::Pressed]);
+        let events = settle(&mut button, false, 450);
+        assert_eq!(events, [ButtonEvent::Released, ButtonEvent::DoubleClick]);
+    }
+
+    #[test]
+    fn second_press_that_bounces_away_releases_the_click() {
+        let mut button = button();
+        click(&mut button, 0);
+        button.update(true, ms(390));
+        // A glitch: the contact is up again before the debounce time.
+        assert!(button.update(false, ms(395)).is_empty());
+        assert_eq!(button.deadline(), Some(ms(400)));
+        assert_eq!(button.update(false, ms(400)), [ButtonEvent::Click]);
+    }
+
+    #[test]
+    fn late_update_keeps_the_time_of_the_edges() {
+        let mut button = button();
+        button.update(true, ms(0));
+        // The update at the end of the debounce time comes 2 s late.
+        assert_eq!(button.update(true, ms(2000)), [ButtonEvent::Pressed]);
+        assert_eq!(button.pressed_at(), ms(0));
+        button.update(false, ms(2500));
+        let events = button.update(false, ms(3000));
+        let held = Duration::from_millis(2500);
+        assert_eq!(
+            events,
+            [ButtonEvent::Released, ButtonEvent::LongPress(held)]
+        );
+    }
+
+    #[test]
+    fn late_update_reports_the_click_before_the_press() {
+        let mut button = ButtonEvents::new(ButtonConfig {
+            debounce: Duration::MIN,
+            ..ButtonConfig::default()
+        });
+        button.update(true, ms(0));
+        button.update(false, ms(100));
+        assert_eq!(button.deadline(), Some(ms(400)));
+        // Down after the deadline, and debounced by the same update.
+        assert_eq!(
+            button.update(true, ms(500)),
+            [ButtonEvent::Click, ButtonEvent::Pressed]
+        );
+        assert_eq!(button.update(false, ms(600)), [ButtonEvent::Released]);
+    }
+
+    #[test]
+    fn late_update_reports_a_missed_deadline() {
+        let mut button = button();
+        click(&mut button, 0);
+        // Nothing happened since the release, the update comes long after the deadline.
+        assert_eq!(button.update(false, ms(5000)), [ButtonEvent::Click]);
+    }
+
+    #[test]
+    fn clicks_are_reported_at_once_without_double_clicks() {
+        let mut button = ButtonEvents::new(ButtonConfig {
+            double_click: Duration::MIN,
+            ..ButtonConfig::default()
+        });
+        for at in [0, 200] {
+            assert_eq!(settle(&mut button, true, at), [ButtonEvent::Pressed]);
+            let events = settle(&mut button, false, at + 100);
+            assert_eq!(events, [ButtonEvent::Released, ButtonEvent::Click]);
+            assert_eq!(button.deadline(), None);
+        }
+    }
+
+    #[test]
+    fn long_press_after_a_pending_click() {
+        let mut button = button();
+        click(&mut button, 0);
+        assert_eq!(settle(&mut button, true, 300), [ButtonEvent::Pressed]);
+        let events = settle(&mut button, false, 1300);
+        let held = Duration::from_millis(1000);
+        assert_eq!(
+            events,
+            [
+                ButtonEvent::Released,
+                ButtonEvent::Click,
+                ButtonEvent::LongPress(held)
+            ]
+        );
+        assert_eq!(button.deadline(), None);
+    }
+
+    #[test]
+    fn long_press_starts_at_the_long_press_time() {
+        let mut button = button();
+        settle(&mut button, true, 0);
+        let events = settle(&mut button, false, 799);
+        assert_eq!(events, [ButtonEvent::Released]);
+        assert_eq!(button.update(false, ms(1119)), [ButtonEvent::Click]);
+
+        settle(&mut button, true, 2000);
+        let events = settle(&mut button, false, 2800);
+        let held = Duration::from_millis(800);
+        assert_eq!(
+            events,
+            [ButtonEvent::Released, ButtonEvent::LongPress(held)]
+        );
+    }
+
+    #[test]
+    fn button_held_at_startup() {
+        let mut button = button();
+        // The first update comes with the button already down.
+        assert!(button.update(true, ms(5)).is_empty());
+        assert!(!button.is_pressed());
+        assert_eq!(button.deadline(), Some(ms(25)));
+        assert_eq!(button.update(true, ms(25)), [ButtonEvent::Pressed]);
+        assert_eq!(button.pressed_at(), ms(5));
+        // It is held from the first update.
+        let events = settle(&mut button, false, 1005);
+        let held = Duration::from_millis(1000);
+        assert_eq!(
+            events,
+            [ButtonEvent::Released, ButtonEvent::LongPress(held)]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Debounced buttons.
+//!
+//! The contacts of a button bounce for a few milliseconds when it is pressed and
+//! released, so a single press makes several edges on its pin. A [`Button`] waits
+//! for the pin to stay at a level for [`ButtonConfig::debounce`] before taking it
+//! into account, and turns the presses into [`ButtonEvent`]s: press, release, click,
+//! double click and long press.
+//!
+//! A [`ButtonPad`] watches several buttons from a single task, and reports their
+//! [`Chords`], like A and B held together.
+//!
+//! An [`Encoder`] reads the signals of a rotary encoder with a PIO state machine,
+//! and decodes them with a [`Quadrature`].
+//!
+//! The state machines, [`ButtonEvents`], [`Chords`] and [`Quadrature`], do not
+//! depend on the chip and are also available without the `rp2040` feature, to test
+//! them with simulated timelines.
+
+#[cfg(feature = "rp2040")]
+mod button;
+mod chord;
+#[cfg(feature = "rp2040")]
+mod encoder;
+mod events;
+#[cfg(feature = "rp2040")]
+mod pad;
+pub mod quadrature;
+
+#[cfg(feature = "rp2040")]
+pub use button::Button;
+pub use chord::{ButtonId, Chords, PadEvent, PadEvents, MAX_BUTTONS, MAX_CHORDS};
+#[cfg(feature = "rp2040")]
+pub use encoder::Encoder;
+pub use events::{ButtonConfig, ButtonEvent, ButtonEvents, Events};
+#[cfg(feature = "rp2040")]
+pub use pad::ButtonPad;
+pub use quadrature::Quadrature;
@@ -0,0 +1,135 @@
+//! Several buttons watched by a single task.
+
+use embassy_futures::select::select_array;
+use embassy_rp::gpio::Input;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Sender;
+use heapless::Deque;
+
+use super::{
+    Button, ButtonConfig, ButtonEvent, ButtonId, Chords, PadEvent, MAX_BUTTONS, MAX_CHORDS,
+};
+
+/// Returns the [`ButtonPad`] of the buttons A, B, X and Y of the Pico Explorer, on
+/// `PIN_12` to `PIN_15`, with an optional [`ButtonConfig`].
+///
+/// ```ignore
+/// let mut buttons = utils::pico_explorer_buttons!(peripherals);
+/// buttons.add_chord(ButtonId::A | ButtonId::B).unwrap();
+/// ```
+#[macro_export]
+macro_rules! pico_explorer_buttons {
+    ($p:expr) => {
+        $crate::pico_explorer_buttons!($p, $crate::input::ButtonConfig::default())
+    };
+    ($p:expr, $config:expr) => {
+        $crate::input::ButtonPad::new(
+            [
+                embassy_rp::gpio::Input::new($p.PIN_12, embassy_rp::gpio::Pull::Up),
+                embassy_rp::gpio::Input::new($p.PIN_13, embassy_rp::gpio::Pull::Up),
+                embassy_rp::gpio::Input::new($p.PIN_14, embassy_rp::gpio::Pull::Up),
+                embassy_rp::gpio::Input::new($p.PIN_15, embassy_rp::gpio::Pull::Up),
+            ],
+            $config,
+        )
+    };
+}
+
+/// `N` active low buttons, the button `n` being [`ButtonId::new(n)`](ButtonId::new),
+/// and their [`Chords`]. A pad has at most [`MAX_BUTTONS`] buttons.
+///
+/// A single task waits for the events of all the buttons and sends them on a
+/// channel, with [`ButtonPad::run`]:
+///
+/// ```ignore
+/// static BUTTONS: Channel<ThreadModeRawMutex, PadEvent, 8> = Channel::new();
+///
+/// #[embassy_executor::task]
+/// async fn buttons_task(mut buttons: ButtonPad<'static, 4>) {
+///     buttons.run(BUTTONS.sender()).await
+/// }
+///
+/// // In main
+/// let mut buttons = utils::pico_explorer_buttons!(peripherals);
+/// buttons.add_chord(ButtonId::A | ButtonId::B).unwrap();
+/// spawner.spawn(buttons_task(buttons)).unwrap();
+///
+/// loop {
+///     match BUTTONS.receive().await {
+///         (ButtonId::A, ButtonEvent::Click) => info!("A"),
+///         (button, ButtonEvent::LongPress(_)) if button == ButtonId::A | ButtonId::B => {
+///             info!("reset")
+///         }
+///         _ => {}
+///     }
+/// }
+/// ```
+pub struct ButtonPad<'d, const N: usize> {
+    buttons: [Button<'d>; N],
+    chords: Chords,
+    pending: Deque<PadEvent, { 1 + 2 * MAX_CHORDS }>,
+}
+
+impl<'d, const N: usize> ButtonPad<'d, N> {
+    /// Fails the build of a pad of more than [`MAX_BUTTONS`] buttons.
+    const FITS: () = assert!(N <= MAX_BUTTONS, "a pad has at most 8 buttons");
+
+    /// Returns a pad of buttons that pull their input low when they are pressed.
+    pub fn new(inputs: [Input<'d>; N], config: ButtonConfig) -> Self {
+        let () = Self::FITS;
+        Self {
+            buttons: inputs.map(|input| Button::new(input, config)),
+            chords: Chords::new(config.long_press),
+            pending: Deque::new(),
+        }
+    }
+
+    /// Adds a chord, returns it back if the pad already has [`MAX_CHORDS`].
+    pub fn add_chord(&mut self, chord: ButtonId) -> Result<(), ButtonId> {
+        self.chords.add(chord)
+    }
+
+    /// Returns the buttons that are down.
+    pub fn held(&self) -> ButtonId {
+        self.chords.held()
+    }
+
+    /// Waits for the next event of a button or a chord.
+    ///
+    /// It is cancel safe, like [`Button::next_event`].
+    pub async fn next_event(&mut self) -> PadEvent {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return event;
+            }
+            let mut buttons = self.buttons.iter_mut();
+            let events = core::array::from_fn::<_, N, _>(|_| {
+                // Cannot fail, there are N buttons
+                buttons.next().unwrap().next_event()
+            });
+            let (event, index) = select_array(events).await;
+            // The chords are timed with the edges, not with when the events are
+            // handled. The clicks and long presses follow a release.
+            let button = &self.buttons[index];
+            let at = match event {
+                ButtonEvent::Pressed => button.pressed_at(),
+                _ => button.released_at(),
+            };
+            for event in self.chords.update(ButtonId::new(index), event, at) {
+                // Cannot fail, the queue is empty and has room for all the events
+                let _ = self.pending.push_back(event);
+            }
+        }
+    }
+
+    /// Sends the events of the buttons and the chords on a channel, forever.
+    pub async fn run<M: RawMutex, const CAP: usize>(
+        &mut self,
+        sender: Sender<'_, M, PadEvent, CAP>,
+    ) -> ! {
+        loop {
+            let event = self.next_event().await;
+            sender.send(event).await;
+        }
+    }
+}
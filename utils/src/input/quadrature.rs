@@ -0,0 +1,250 @@
+//! Decoding of the two signals of a quadrature encoder.
+
+use embassy_time::Instant;
+
+/// The steps of the transitions between two states, indexed by
+/// `previous << 2 | current`, a state being `B << 1 | A`.
+///
+/// Turning clockwise, A leads: `00 -> 01 -> 11 -> 10 -> 00`. The transitions that
+/// change both signals are `None`: a state was missed, the direction is unknown.
+const TRANSITIONS: [Option<i8>; 16] = [
+    Some(0),  // 00 -> 00
+    Some(1),  // 00 -> 01
+    Some(-1), // 00 -> 10
+    None,     // 00 -> 11
+    Some(-1), // 01 -> 00
+    Some(0),  // 01 -> 01
+    None,     // 01 -> 10
+    Some(1),  // 01 -> 11
+    Some(1),  // 10 -> 00
+    None,     // 10 -> 01
+    Some(0),  // 10 -> 10
+    Some(-1), // 10 -> 11
+    None,     // 11 -> 00
+    Some(-1), // 11 -> 01
+    Some(1),  // 11 -> 10
+    Some(0),  // 11 -> 11
+];
+
+/// Returns the step between two states `B << 1 | A` of the signals, `1` clockwise
+/// and `-1` counterclockwise, or `None` if both signals changed.
+///
+/// ```
+/// use utils::input::quadrature::step;
+///
+/// assert_eq!(step(0b00, 0b01), Some(1));
+/// assert_eq!(step(0b01, 0b00), Some(-1));
+/// assert_eq!(step(0b11, 0b11), Some(0));
+/// assert_eq!(step(0b01, 0b10), None);
+/// ```
+pub const fn step(previous: u8, current: u8) -> Option<i8> {
+    TRANSITIONS[(((previous & 0b11) << 2) | (current & 0b11)) as usize]
+}
+
+/// Position and velocity of a quadrature encoder, from the successive states of its
+/// signals.
+///
+/// Mechanical encoders with detents make several steps from one detent to the next,
+/// usually 4 (a full cycle of the signals) or 2. The position is counted in detents,
+/// and changes half way between two of them so that the bounces of the contacts at
+/// a detent do not change it.
+///
+/// # Example
+///
+/// ```
+/// use embassy_time::Instant;
+/// use utils::input::Quadrature;
+///
+/// let ms = Instant::from_millis;
+/// let mut encoder = Quadrature::new(4);
+/// // The encoder rests at 11 between the detents
+/// assert_eq!(encoder.update(0b11, ms(0)), 0);
+///
+/// // One detent clockwise, a step every 10 ms
+/// assert_eq!(encoder.update(0b10, ms(10)), 0);
+/// assert_eq!(encoder.update(0b00, ms(20)), 1);
+/// assert_eq!(encoder.update(0b01, ms(30)), 0);
+/// assert_eq!(encoder.update(0b11, ms(40)), 0);
+/// assert_eq!(encoder.position(), 1);
+/// assert_eq!(encoder.steps(), 4);
+/// // 100 steps per second
+/// assert_eq!(encoder.velocity(ms(40)), 25);
+/// assert_eq!(encoder.velocity(ms(3000)), 0);
+///
+/// // A bounce at the detent does not move it
+/// encoder.update(0b01, ms(3001));
+/// encoder.update(0b11, ms(3002));
+/// assert_eq!(encoder.position(), 1);
+///
+/// // A missed state is an error
+/// encoder.update(0b00, ms(3010));
+/// assert_eq!(encoder.errors(), 1);
+/// assert_eq!(encoder.steps(), 4);
+///
+/// // Back one detent, from 00
+/// assert_eq!(encoder.update(0b10, ms(4000)), 0);
+/// assert_eq!(encoder.update(0b11, ms(4010)), 0);
+/// assert_eq!(encoder.update(0b01, ms(4020)), -1);
+/// assert_eq!(encoder.update(0b00, ms(4030)), 0);
+/// assert_eq!(encoder.position(), 0);
+/// assert!(encoder.velocity(ms(4030)) < 0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Quadrature {
+    state: Option<u8>,
+    steps: i32,
+    steps_per_detent: i32,
+    errors: u32,
+    last_step: Option<Instant>,
+    /// Smoothed velocity, in steps per second.
+    rate: i32,
+}
+
+impl Quadrature {
+    /// Returns the decoder of an encoder that makes `steps_per_detent` steps from
+    /// one detent to the next, `1` to count every step.
+    pub const fn new(steps_per_detent: u8) -> Self {
+        Self {
+            state: None,
+            steps: 0,
+            steps_per_detent: if steps_per_detent == 0 {
+                1
+            } else {
+                steps_per_detent as i32
+            },
+            errors: 0,
+            last_step: None,
+            rate: 0,
+        }
+    }
+
+    /// Takes the state of the signals `B << 1 | A` at `now`, and returns the change of
+    /// the position, in detents.
+    pub fn update(&mut self, state: u8, now: Instant) -> i32 {
+        let state = state & 0b11;
+        let Some(previous) = self.state.replace(state) else {
+            return 0;
+        };
+        match step(previous, state) {
+            Some(0) => 0,
+            Some(step) => {
+                let position = self.position();
+                self.steps = self.steps.wrapping_add(step as i32);
+                self.update_rate(step as i32, now);
+                self.position() - position
+            }
+            None => {
+                self.errors = self.errors.wrapping_add(1);
+                0
+            }
+        }
+    }
+
+    /// Returns the position, in detents from the start.
+    pub fn position(&self) -> i32 {
+        (self.steps + self.steps_per_detent / 2).div_euclid(self.steps_per_detent)
+    }
+
+    /// Returns the position, in steps from the start.
+    pub fn steps(&self) -> i32 {
+        self.steps
+    }
+
+    /// Returns the number of transitions where both signals changed, because the
+    /// signals were not read fast enough.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Returns the velocity at `now`, in detents per second, positive clockwise.
+    ///
+    /// It is averaged over the last steps, and decreases when the encoder stops.
+    pub fn velocity(&self, now: Instant) -> i32 {
+        let Some(last_step) = self.last_step else {
+            return 0;
+        };
+        // The encoder is not turning faster than a step since the last one
+        let elapsed = (now - last_step).as_micros().max(1);
+        let max = (1_000_000 / elapsed).min(i32::MAX as u64) as i32;
+        self.rate.clamp(-max, max) / self.steps_per_detent
+    }
+
+    fn update_rate(&mut self, step: i32, now: Instant) {
+        if let Some(last_step) = self.last_step {
+            let elapsed = (now - last_step).as_micros().max(1);
+            let rate = step * (1_000_000 / elapsed).min(i32::MAX as u64) as i32;
+            self.rate = if self.rate.signum() == step {
+                // Average of the last 4 steps
+                (self.rate / 4) * 3 + rate / 4
+            } else {
+                rate
+            };
+        }
+        self.last_step = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The states of the signals clockwise, `B << 1 | A`.
+    const CLOCKWISE: [u8; 4] = [0b00, 0b01, 0b11, 0b10];
+
+    #[test]
+    fn every_transition() {
+        for previous in 0..4 {
+            for current in 0..4 {
+                let from = CLOCKWISE
+                    .iter()
+                    .position(|&state| state == previous)
+                    .unwrap();
+                let to = CLOCKWISE
+                    .iter()
+                    .position(|&state| state == current)
+                    .unwrap();
+                let expected = match (to + 4 - from) % 4 {
+                    0 => Some(0),
+                    1 => Some(1),
+                    3 => Some(-1),
+                    // Both signals changed
+                    _ => None,
+                };
+                assert_eq!(
+                    step(previous, current),
+                    expected,
+                    "{previous:02b} -> {current:02b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_transitions_change_both_signals() {
+        let invalid: usize = (0..4)
+            .flat_map(|previous| (0..4).map(move |current| (previous, current)))
+            .filter(|&(previous, current)| step(previous, current).is_none())
+            .inspect(|&(previous, current)| assert_eq!(previous ^ current, 0b11))
+            .count();
+        assert_eq!(invalid, 4);
+    }
+
+    #[test]
+    fn only_the_two_low_bits_are_read() {
+        assert_eq!(step(0b1100, 0b0101), step(0b00, 0b01));
+        assert_eq!(step(0xff, 0xfd), step(0b11, 0b01));
+    }
+
+    #[test]
+    fn invalid_transitions_are_errors() {
+        let ms = Instant::from_millis;
+        let mut encoder = Quadrature::new(1);
+        encoder.update(0b00, ms(0));
+        for (at, state) in [0b11, 0b00, 0b01, 0b10, 0b01].into_iter().enumerate() {
+            encoder.update(state, ms(at as u64 + 1));
+        }
+        // 00 -> 11, 11 -> 00, 01 -> 10 and 10 -> 01 are errors, 00 -> 01 is a step
+        assert_eq!(encoder.errors(), 4);
+        assert_eq!(encoder.steps(), 1);
+    }
+}
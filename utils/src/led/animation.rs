@@ -0,0 +1,268 @@
+//! Animations of the color of a LED.
+
+use embassy_time::Duration;
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+
+use super::{scale, Hsv};
+
+/// A color that changes with the time.
+///
+/// [`Animation::color_at`] returns the color at a time since the start, so that the
+/// animations do not depend on how often they are drawn. The levels change linearly,
+/// and look linear once gamma corrected by the LED.
+///
+/// # Example
+///
+/// ```
+/// use embassy_time::Duration;
+/// use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+/// use utils::led::Animation;
+///
+/// let ms = Duration::from_millis;
+///
+/// let fade = Animation::Fade {
+///     from: Rgb888::BLACK,
+///     to: Rgb888::new(200, 100, 0),
+///     duration: ms(1000),
+/// };
+/// assert_eq!(fade.color_at(ms(0)), Rgb888::BLACK);
+/// assert_eq!(fade.color_at(ms(500)), Rgb888::new(100, 50, 0));
+/// assert_eq!(fade.color_at(ms(1000)), Rgb888::new(200, 100, 0));
+/// assert_eq!(fade.color_at(ms(5000)), Rgb888::new(200, 100, 0));
+/// assert_eq!(fade.duration(), Some(ms(1000)));
+/// for t in 0..1000 {
+///     assert!(fade.color_at(ms(t)).r() <= fade.color_at(ms(t + 1)).r());
+/// }
+///
+/// let breathe = Animation::Breathe {
+///     color: Rgb888::CYAN,
+///     period: ms(2000),
+/// };
+/// assert_eq!(breathe.color_at(ms(0)), Rgb888::BLACK);
+/// assert_eq!(breathe.color_at(ms(1000)), Rgb888::CYAN);
+/// assert_eq!(breathe.color_at(ms(2000)), Rgb888::BLACK);
+/// assert_eq!(breathe.duration(), None);
+/// for t in 0..1000 {
+///     assert!(breathe.color_at(ms(t)).g() <= breathe.color_at(ms(t + 1)).g());
+///     assert!(breathe.color_at(ms(1000 + t)).g() >= breathe.color_at(ms(1001 + t)).g());
+/// }
+///
+/// let rainbow = Animation::Rainbow {
+///     period: ms(3600),
+///     saturation: 255,
+///     value: 255,
+/// };
+/// assert_eq!(rainbow.color_at(ms(0)), Rgb888::RED);
+/// assert_eq!(rainbow.color_at(ms(1200)), Rgb888::GREEN);
+/// assert_eq!(rainbow.color_at(ms(2400)), Rgb888::BLUE);
+/// assert_eq!(rainbow.color_at(ms(3600)), Rgb888::RED);
+///
+/// let blink = Animation::Blink {
+///     color: Rgb888::RED,
+///     on: ms(100),
+///     off: ms(300),
+/// };
+/// assert_eq!(blink.color_at(ms(0)), Rgb888::RED);
+/// assert_eq!(blink.color_at(ms(99)), Rgb888::RED);
+/// assert_eq!(blink.color_at(ms(100)), Rgb888::BLACK);
+/// assert_eq!(blink.color_at(ms(399)), Rgb888::BLACK);
+/// assert_eq!(blink.color_at(ms(400)), Rgb888::RED);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Animation {
+    /// Goes from a color to another in `duration`, then stays at the last one.
+    Fade {
+        from: Rgb888,
+        to: Rgb888,
+        duration: Duration,
+    },
+    /// Lights the color up from black and dims it back, every `period`.
+    Breathe { color: Rgb888, period: Duration },
+    /// Goes around the color wheel every `period`, starting at red, with the
+    /// saturation and value of [`Hsv`].
+    Rainbow {
+        period: Duration,
+        saturation: u8,
+        value: u8,
+    },
+    /// Shows the color for `on`, then black for `off`.
+    Blink {
+        color: Rgb888,
+        on: Duration,
+        off: Duration,
+    },
+}
+
+impl Animation {
+    /// Returns the color at `elapsed` since the start of the animation.
+    ///
+    /// The products of the times are computed on 128 bits, so any duration works,
+    /// up to `Duration::MAX`.
+    pub fn color_at(&self, elapsed: Duration) -> Rgb888 {
+        let elapsed = elapsed.as_micros();
+        match *self {
+            Animation::Fade { from, to, duration } => {
+                let duration = duration.as_micros();
+                if elapsed >= duration {
+                    return to;
+                }
+                let (elapsed, duration) = (elapsed as u128, duration as u128);
+                let mix = |from: u8, to: u8| {
+                    ((from as u128 * (duration - elapsed) + to as u128 * elapsed) / duration) as u8
+                };
+                Rgb888::new(
+                    mix(from.r(), to.r()),
+                    mix(from.g(), to.g()),
+                    mix(from.b(), to.b()),
+                )
+            }
+            Animation::Breathe { color, period } => {
+                let period = period.as_micros().max(1) as u128;
+                let time = elapsed as u128 % period;
+                let half = period / 2;
+                let level = if time < half {
+                    255 * time / half
+                } else {
+                    255 * (period - time) / (period - half)
+                };
+                let level = level as u8;
+                Rgb888::new(
+                    scale(color.r(), level),
+                    scale(color.g(), level),
+                    scale(color.b(), level),
+                )
+            }
+            Animation::Rainbow {
+                period,
+                saturation,
+                value,
+            } => {
+                let period = period.as_micros().max(1);
+                let hue = 360 * (elapsed % period) as u128 / period as u128;
+                Hsv::new(hue as u16, saturation, value).into()
+            }
+            Animation::Blink { color, on, off } => {
+                let on = on.as_micros();
+                let period = on.saturating_add(off.as_micros()).max(1);
+                if elapsed % period < on {
+                    color
+                } else {
+                    Rgb888::BLACK
+                }
+            }
+        }
+    }
+
+    /// Returns the duration of the animation, or `None` if it repeats forever.
+    pub fn duration(&self) -> Option<Duration> {
+        match *self {
+            Animation::Fade { duration, .. } => Some(duration),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: fn(u64) -> Duration = Duration::from_millis;
+
+    /// A color with different levels on the three channels.
+    const COLOR: Rgb888 = Rgb888::new(200, 100, 50);
+
+    #[test]
+    fn fade_mixes_every_channel() {
+        let fade = Animation::Fade {
+            from: Rgb888::new(0, 255, 100),
+            to: COLOR,
+            duration: MS(1000),
+        };
+        assert_eq!(fade.color_at(MS(0)), Rgb888::new(0, 255, 100));
+        assert_eq!(fade.color_at(MS(250)), Rgb888::new(50, 216, 87));
+        assert_eq!(fade.color_at(MS(500)), Rgb888::new(100, 177, 75));
+        assert_eq!(fade.color_at(MS(1000)), COLOR);
+        assert_eq!(fade.color_at(Duration::MAX), COLOR);
+    }
+
+    #[test]
+    fn fade_without_duration_is_the_last_color() {
+        let fade = Animation::Fade {
+            from: Rgb888::WHITE,
+            to: COLOR,
+            duration: Duration::from_ticks(0),
+        };
+        assert_eq!(fade.color_at(Duration::from_ticks(0)), COLOR);
+    }
+
+    #[test]
+    fn breathe_scales_every_channel() {
+        let breathe = Animation::Breathe {
+            color: COLOR,
+            period: MS(2000),
+        };
+        assert_eq!(breathe.color_at(MS(0)), Rgb888::BLACK);
+        assert_eq!(breathe.color_at(MS(500)), Rgb888::new(100, 50, 25));
+        assert_eq!(breathe.color_at(MS(1000)), COLOR);
+        assert_eq!(breathe.color_at(MS(1500)), Rgb888::new(100, 50, 25));
+    }
+
+    #[test]
+    fn breathe_ends_black_at_the_period() {
+        for period in [MS(1), MS(999), MS(2000), Duration::MAX] {
+            let breathe = Animation::Breathe {
+                color: COLOR,
+                period,
+            };
+            assert_eq!(breathe.color_at(period), Rgb888::BLACK);
+            if let Some(twice) = period.checked_mul(2) {
+                assert_eq!(breathe.color_at(twice), Rgb888::BLACK);
+            }
+        }
+    }
+
+    #[test]
+    fn rainbow_ends_red_at_the_period() {
+        for period in [MS(1), MS(3600), Duration::MAX] {
+            let rainbow = Animation::Rainbow {
+                period,
+                saturation: 255,
+                value: 255,
+            };
+            assert_eq!(rainbow.color_at(Duration::from_ticks(0)), Rgb888::RED);
+            assert_eq!(rainbow.color_at(period), Rgb888::RED);
+        }
+    }
+
+    #[test]
+    fn extreme_durations_do_not_overflow() {
+        let half = Duration::from_ticks(u64::MAX / 2);
+        let fade = Animation::Fade {
+            from: Rgb888::BLACK,
+            to: Rgb888::WHITE,
+            duration: Duration::MAX,
+        };
+        assert_eq!(fade.color_at(half), Rgb888::new(127, 127, 127));
+
+        let breathe = Animation::Breathe {
+            color: Rgb888::WHITE,
+            period: Duration::MAX,
+        };
+        assert_eq!(breathe.color_at(half), Rgb888::WHITE);
+
+        let rainbow = Animation::Rainbow {
+            period: Duration::MAX,
+            saturation: 255,
+            value: 255,
+        };
+        // Just before the middle of the wheel, 180 degrees
+        assert_eq!(rainbow.color_at(half), Hsv::new(179, 255, 255).into());
+
+        let blink = Animation::Blink {
+            color: COLOR,
+            on: Duration::MAX,
+            off: Duration::MAX,
+        };
+        assert_eq!(blink.color_at(half), COLOR);
+    }
+}
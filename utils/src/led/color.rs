@@ -0,0 +1,243 @@
+//! HSV and HSL colors, and the gamma correction of the LEDs.
+
+use embedded_graphics::pixelcolor::Rgb888;
+
+/// A color from its hue, saturation and value.
+///
+/// The hue is the angle on the color wheel, in degrees: 0 is red, 120 green and 240
+/// blue. The saturation and the value are from 0 to 255: the value is the level of
+/// the brightest channel, the saturation the share of the other ones.
+///
+/// ```
+/// use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+/// use utils::led::Hsv;
+///
+/// assert_eq!(Rgb888::from(Hsv::new(0, 255, 255)), Rgb888::new(255, 0, 0));
+/// assert_eq!(Rgb888::from(Hsv::new(120, 255, 255)), Rgb888::new(0, 255, 0));
+/// assert_eq!(Rgb888::from(Hsv::new(240, 255, 255)), Rgb888::new(0, 0, 255));
+/// assert_eq!(Rgb888::from(Hsv::new(60, 255, 255)), Rgb888::new(255, 255, 0));
+/// assert_eq!(Rgb888::from(Hsv::new(360 + 30, 255, 128)), Rgb888::new(128, 64, 0));
+/// assert_eq!(Rgb888::from(Hsv::new(200, 0, 100)), Rgb888::new(100, 100, 100));
+/// assert_eq!(Rgb888::from(Hsv::new(200, 255, 0)), Rgb888::new(0, 0, 0));
+///
+/// // Around the wheel, a single channel changes at a time, in one direction
+/// for hue in 0..60 {
+///     let color = Rgb888::from(Hsv::new(hue, 255, 255));
+///     let next = Rgb888::from(Hsv::new(hue + 1, 255, 255));
+///     assert_eq!((color.r(), color.b()), (255, 0));
+///     assert!(next.g() >= color.g());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hsv {
+    pub hue: u16,
+    pub saturation: u8,
+    pub value: u8,
+}
+
+impl Hsv {
+    pub const fn new(hue: u16, saturation: u8, value: u8) -> Self {
+        Self {
+            hue,
+            saturation,
+            value,
+        }
+    }
+}
+
+impl From<Hsv> for Rgb888 {
+    fn from(hsv: Hsv) -> Self {
+        let chroma = hsv.value as u32 * hsv.saturation as u32 / 255;
+        from_chroma(hsv.hue, chroma, hsv.value as u32 - chroma)
+    }
+}
+
+/// A color from its hue, saturation and lightness.
+///
+/// The hue is the one of [`Hsv`]. The lightness is from 0 (black) to 255 (white),
+/// the pure colors are half way. The saturation is from 0 (gray) to 255.
+///
+/// ```
+/// use embedded_graphics::pixelcolor::Rgb888;
+/// use utils::led::Hsl;
+///
+/// assert_eq!(Rgb888::from(Hsl::new(0, 255, 255)), Rgb888::new(255, 255, 255));
+/// assert_eq!(Rgb888::from(Hsl::new(0, 255, 0)), Rgb888::new(0, 0, 0));
+/// assert_eq!(Rgb888::from(Hsl::new(240, 255, 128)), Rgb888::new(1, 1, 255));
+/// assert_eq!(Rgb888::from(Hsl::new(120, 255, 192)), Rgb888::new(129, 255, 129));
+/// assert_eq!(Rgb888::from(Hsl::new(120, 0, 192)), Rgb888::new(192, 192, 192));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hsl {
+    pub hue: u16,
+    pub saturation: u8,
+    pub lightness: u8,
+}
+
+impl Hsl {
+    pub const fn new(hue: u16, saturation: u8, lightness: u8) -> Self {
+        Self {
+            hue,
+            saturation,
+            lightness,
+        }
+    }
+}
+
+impl From<Hsl> for Rgb888 {
+    fn from(hsl: Hsl) -> Self {
+        let lightness = hsl.lightness as u32;
+        let chroma = (255 - (2 * lightness).abs_diff(255)) * hsl.saturation as u32 / 255;
+        from_chroma(hsl.hue, chroma, lightness - chroma.div_ceil(2))
+    }
+}
+
+/// Returns the color with the `hue`, the difference between its brightest and its
+/// darkest channels, and the level of its darkest channel.
+fn from_chroma(hue: u16, chroma: u32, min: u32) -> Rgb888 {
+    let hue = (hue % 360) as u32;
+    // The middle channel, rising and falling in turns every 60 degrees
+    let middle = chroma * (60 - (hue % 120).abs_diff(60)) / 60;
+    let (red, green, blue) = match hue / 60 {
+        0 => (chroma, middle, 0),
+        1 => (middle, chroma, 0),
+        2 => (0, chroma, middle),
+        3 => (0, middle, chroma),
+        4 => (middle, 0, chroma),
+        _ => (chroma, 0, middle),
+    };
+    let channel = |value: u32| (value + min).min(255) as u8;
+    Rgb888::new(channel(red), channel(green), channel(blue))
+}
+
+/// The luminance of the levels from 0 to 255, from 0 to 65535.
+///
+/// The eye does not see the light linearly: a LED at half of its duty cycle looks
+/// much brighter than half. The levels are lightnesses (CIE L\*), so that the same
+/// change of level looks like the same change of brightness.
+pub static GAMMA: [u16; 256] = gamma_table();
+
+/// Returns the luminance of a perceptual `level`, see [`GAMMA`].
+///
+/// ```
+/// use utils::led::gamma;
+///
+/// assert_eq!(gamma(0), 0);
+/// assert_eq!(gamma(255), u16::MAX);
+/// // The middle is less than a fifth of the light
+/// assert!((11_000..15_000).contains(&gamma(128)));
+/// for level in 0..255 {
+///     assert!(gamma(level) < gamma(level + 1));
+/// }
+/// ```
+pub fn gamma(level: u8) -> u16 {
+    GAMMA[level as usize]
+}
+
+const fn gamma_table() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut level = 0;
+    while level < 256 {
+        // The lightness from 0 to 100, times 255
+        let lightness = level as u64 * 100;
+        table[level] = if lightness <= 8 * 255 {
+            // Y = L* / 903.3 in the dark
+            (lightness * 65535 * 10 / (255 * 9033)) as u16
+        } else {
+            // Y = ((L* + 16) / 116)^3
+            let n = lightness + 16 * 255;
+            let d = 116 * 255;
+            (n * n * n * 65535 / (d * d * d)) as u16
+        };
+        level += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::RgbColor;
+
+    use super::*;
+
+    fn hsv(hue: u16) -> Rgb888 {
+        Hsv::new(hue, 255, 255).into()
+    }
+
+    #[test]
+    fn every_sextant_of_the_wheel() {
+        // The start, the middle and the end of each sextant
+        let expected = [
+            (0, (255, 0, 0)),
+            (30, (255, 127, 0)),
+            (59, (255, 250, 0)),
+            (60, (255, 255, 0)),
+            (90, (127, 255, 0)),
+            (119, (4, 255, 0)),
+            (120, (0, 255, 0)),
+            (150, (0, 255, 127)),
+            (179, (0, 255, 250)),
+            (180, (0, 255, 255)),
+            (210, (0, 127, 255)),
+            (239, (0, 4, 255)),
+            (240, (0, 0, 255)),
+            (270, (127, 0, 255)),
+            (299, (250, 0, 255)),
+            (300, (255, 0, 255)),
+            (330, (255, 0, 127)),
+            (359, (255, 0, 4)),
+        ];
+        for (hue, (r, g, b)) in expected {
+            assert_eq!(hsv(hue), Rgb888::new(r, g, b), "hue {hue}");
+        }
+    }
+
+    #[test]
+    fn one_channel_changes_in_each_sextant() {
+        for sextant in 0..6 {
+            for hue in sextant * 60..sextant * 60 + 59 {
+                let (color, next) = (hsv(hue), hsv(hue + 1));
+                let changed = [
+                    color.r() != next.r(),
+                    color.g() != next.g(),
+                    color.b() != next.b(),
+                ];
+                assert!(
+                    changed.iter().filter(|&&changed| changed).count() <= 1,
+                    "hue {hue}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hue_wraps_around() {
+        for hue in [0, 45, 200, 359] {
+            assert_eq!(hsv(hue + 360), hsv(hue));
+            assert_eq!(hsv(hue + 720), hsv(hue));
+        }
+        assert_eq!(hsv(u16::MAX), hsv(u16::MAX % 360));
+    }
+
+    #[test]
+    fn every_sextant_of_hsl() {
+        for (hue, color) in [
+            (0, Rgb888::RED),
+            (60, Rgb888::YELLOW),
+            (120, Rgb888::GREEN),
+            (180, Rgb888::CYAN),
+            (240, Rgb888::BLUE),
+            (300, Rgb888::MAGENTA),
+        ] {
+            let hsl = Rgb888::from(Hsl::new(hue, 255, 128));
+            // 128 is one step above the middle of the lightness, the off channels
+            // are at 1
+            let pure = |level: u8| level.max(1);
+            assert_eq!(
+                hsl,
+                Rgb888::new(pure(color.r()), pure(color.g()), pure(color.b())),
+                "hue {hue}"
+            );
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Duty cycle of the PWM outputs of a LED.
+
+/// How the LED is wired to its PWM outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Polarity {
+    /// The common pin is the ground, an output lights its LED when it is high.
+    #[default]
+    CommonCathode,
+    /// The common pin is the supply, an output lights its LED when it is low. The
+    /// outputs are inverted.
+    CommonAnode,
+}
+
+impl Polarity {
+    /// Returns `true` if the PWM output has to be inverted.
+    pub fn inverted(self) -> bool {
+        self == Polarity::CommonAnode
+    }
+}
+
+/// Returns a LED `level` scaled by the `brightness`, both from 0 to 255, rounded.
+///
+/// ```
+/// use utils::led::scale;
+///
+/// assert_eq!(scale(255, 255), 255);
+/// assert_eq!(scale(200, 0), 0);
+/// assert_eq!(scale(128, 128), 64);
+/// ```
+pub fn scale(level: u8, brightness: u8) -> u8 {
+    ((level as u32 * brightness as u32 + 127) / 255) as u8
+}
+
+/// Highest `top` value with which an output can be kept high, see [`compare`].
+pub const MAX_TOP: u16 = u16::MAX - 1;
+
+/// Returns the compare value of a PWM output with a `top` value, for a `luminance`
+/// from 0 to 65535, see [`gamma`](super::gamma).
+///
+/// The output is high while the counter, from 0 to `top`, is lower than the compare
+/// value: `top + 1` keeps it high. With a `top` of `u16::MAX`, `top + 1` does not fit
+/// in the compare register, so the output is low for one count of the period at
+/// full luminance. `PwmOutput` limits its `top` to [`MAX_TOP`] for this reason.
+///
+/// ```
+/// use utils::led::{compare, gamma};
+///
+/// assert_eq!(compare(u16::MAX, 0x8000), 0x8001);
+/// assert_eq!(compare(0, 0x8000), 0);
+/// assert_eq!(compare(u16::MAX, u16::MAX), u16::MAX);
+/// assert_eq!(compare(0x8000, 999), 500);
+///
+/// // Half of the lightness is less than a fifth of the duty cycle
+/// assert_eq!(compare(gamma(128), 999), 186);
+/// ```
+pub fn compare(luminance: u16, top: u16) -> u16 {
+    let period = top as u32 + 1;
+    let compare = (period as u64 * luminance as u64 + u16::MAX as u64 / 2) / u16::MAX as u64;
+    compare.min(u16::MAX as u64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_luminance_keeps_the_output_high() {
+        for top in [0, 1, 999, 0x8000, MAX_TOP] {
+            assert_eq!(compare(u16::MAX, top) as u32, top as u32 + 1);
+            assert_eq!(compare(0, top), 0);
+        }
+    }
+
+    #[test]
+    fn full_luminance_with_the_largest_top_is_not_always_high() {
+        assert_eq!(compare(u16::MAX, u16::MAX), u16::MAX);
+    }
+}
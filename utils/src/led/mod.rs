@@ -0,0 +1,26 @@
+//! RGB LEDs driven by PWM.
+//!
+//! An [`RgbLed`] sets the compare values of its three [`PwmOutput`]s from an
+//! [`Rgb888`](embedded_graphics::pixelcolor::Rgb888) color and a brightness. The
+//! outputs know if they are the output A or B of their slice, and only accept the
+//! pins of that output.
+//!
+//! The levels of the colors are perceptual: they are gamma corrected, with
+//! [`gamma`], before the duty cycles are computed. The colors can also be given as
+//! [`Hsv`] or [`Hsl`], and animated with an [`Animation`] played by
+//! [`RgbLed::play`].
+//!
+//! The color and duty cycle computations do not depend on the chip and are also
+//! available without the `rp2040` feature.
+
+mod animation;
+mod color;
+mod duty;
+#[cfg(feature = "rp2040")]
+mod rgb;
+
+pub use animation::Animation;
+pub use color::{gamma, Hsl, Hsv, GAMMA};
+pub use duty::{compare, scale, Polarity, MAX_TOP};
+#[cfg(feature = "rp2040")]
+pub use rgb::{PwmOutput, RgbLed};
@@ -0,0 +1,208 @@
+//! RGB LED on three PWM outputs.
+
+use embassy_rp::pwm::{Channel, Config, Pwm, PwmPinA, PwmPinB};
+use embassy_rp::Peripheral;
+use embassy_time::{Duration, Instant, Ticker};
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+
+use super::{compare, gamma, scale, Animation, Polarity, MAX_TOP};
+
+/// Time between two frames of an [`Animation`], 50 per second.
+const FRAME: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    A,
+    B,
+}
+
+/// One output of a PWM slice.
+///
+/// The pins are wired to the output A or B of a slice: `PIN_1` is the output B of
+/// `PWM_CH0`. [`PwmOutput::new_a`] and [`PwmOutput::new_b`] only accept the pins of
+/// their output, and it sets the compare value of that output.
+///
+/// The `top` value is at most [`MAX_TOP`], so that the output can be kept high.
+pub struct PwmOutput<'d, T: Channel> {
+    pwm: Pwm<'d, T>,
+    config: Config,
+    output: Output,
+}
+
+impl<'d, T: Channel> PwmOutput<'d, T> {
+    /// Returns the output A of the `slice` on `pin`, low, with a `top` value, limited
+    /// to [`MAX_TOP`].
+    pub fn new_a(
+        slice: impl Peripheral<P = T> + 'd,
+        pin: impl Peripheral<P = impl PwmPinA<T>> + 'd,
+        top: u16,
+    ) -> Self {
+        let config = Self::config(top);
+        let pwm = Pwm::new_output_a(slice, pin, config.clone());
+        Self {
+            pwm,
+            config,
+            output: Output::A,
+        }
+    }
+
+    /// Returns the output B of the `slice` on `pin`, low, with a `top` value, limited
+    /// to [`MAX_TOP`].
+    pub fn new_b(
+        slice: impl Peripheral<P = T> + 'd,
+        pin: impl Peripheral<P = impl PwmPinB<T>> + 'd,
+        top: u16,
+    ) -> Self {
+        let config = Self::config(top);
+        let pwm = Pwm::new_output_b(slice, pin, config.clone());
+        Self {
+            pwm,
+            config,
+            output: Output::B,
+        }
+    }
+
+    fn config(top: u16) -> Config {
+        let mut config: Config = Default::default();
+        config.top = top.min(MAX_TOP);
+        config
+    }
+
+    /// Returns the top value of the counter.
+    pub fn top(&self) -> u16 {
+        self.config.top
+    }
+
+    /// Sets the compare value, the output is high while the counter is lower.
+    pub fn set_compare(&mut self, compare: u16) {
+        match self.output {
+            Output::A => self.config.compare_a = compare,
+            Output::B => self.config.compare_b = compare,
+        }
+        self.pwm.set_config(&self.config);
+    }
+
+    /// Inverts the output, it is low while the counter is lower than the compare
+    /// value.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        match self.output {
+            Output::A => self.config.invert_a = inverted,
+            Output::B => self.config.invert_b = inverted,
+        }
+        self.pwm.set_config(&self.config);
+    }
+}
+
+/// An RGB LED with its red, green and blue LEDs on three [`PwmOutput`]s.
+///
+/// The color is scaled by the brightness and gamma corrected, and the outputs are
+/// inverted for a common anode LED.
+///
+/// ```ignore
+/// const TOP: u16 = 0x8000;
+///
+/// let mut led = RgbLed::new(
+///     PwmOutput::new_b(peripherals.PWM_CH0, peripherals.PIN_1, TOP),
+///     PwmOutput::new_a(peripherals.PWM_CH1, peripherals.PIN_2, TOP),
+///     PwmOutput::new_b(peripherals.PWM_CH2, peripherals.PIN_5, TOP),
+///     Polarity::CommonCathode,
+/// );
+/// led.set(Rgb888::new(255, 128, 0));
+/// led.set_brightness(64);
+/// ```
+pub struct RgbLed<'d, R: Channel, G: Channel, B: Channel> {
+    red: PwmOutput<'d, R>,
+    green: PwmOutput<'d, G>,
+    blue: PwmOutput<'d, B>,
+    color: Rgb888,
+    brightness: u8,
+}
+
+impl<'d, R: Channel, G: Channel, B: Channel> RgbLed<'d, R, G, B> {
+    /// Returns the LED, off, at full brightness.
+    pub fn new(
+        mut red: PwmOutput<'d, R>,
+        mut green: PwmOutput<'d, G>,
+        mut blue: PwmOutput<'d, B>,
+        polarity: Polarity,
+    ) -> Self {
+        red.set_inverted(polarity.inverted());
+        green.set_inverted(polarity.inverted());
+        blue.set_inverted(polarity.inverted());
+        let mut led = Self {
+            red,
+            green,
+            blue,
+            color: Rgb888::BLACK,
+            brightness: u8::MAX,
+        };
+        led.update();
+        led
+    }
+
+    /// Sets the color.
+    pub fn set(&mut self, color: Rgb888) {
+        self.color = color;
+        self.update();
+    }
+
+    /// Returns the color, without the brightness.
+    pub fn color(&self) -> Rgb888 {
+        self.color
+    }
+
+    /// Turns the LED off, [`RgbLed::set`] with black.
+    pub fn off(&mut self) {
+        self.set(Rgb888::BLACK);
+    }
+
+    /// Sets the brightness, from 0 (off) to 255 (the color as it is). Like the levels
+    /// of the colors, it is perceptual: 128 looks half as bright.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.update();
+    }
+
+    /// Returns the brightness.
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Plays an animation, and returns when it ends. The animations that repeat forever
+    /// only end when the future is dropped, the LED keeps the color of the last frame.
+    ///
+    /// ```ignore
+    /// // A new command stops the animation
+    /// match select(led.play(&animation), COMMANDS.receive()).await {
+    ///     First(()) => info!("done"),
+    ///     Second(command) => info!("{:?}", command),
+    /// }
+    /// ```
+    pub async fn play(&mut self, animation: &Animation) {
+        let start = Instant::now();
+        let mut ticker = Ticker::every(FRAME);
+        loop {
+            let elapsed = Instant::now() - start;
+            self.set(animation.color_at(elapsed));
+            if animation
+                .duration()
+                .is_some_and(|duration| elapsed >= duration)
+            {
+                return;
+            }
+            ticker.next().await;
+        }
+    }
+
+    fn update(&mut self) {
+        let duty = |level: u8, top: u16| compare(gamma(scale(level, self.brightness)), top);
+        let (red, green, blue) = (
+            duty(self.color.r(), self.red.top()),
+            duty(self.color.g(), self.green.top()),
+            duty(self.color.b(), self.blue.top()),
+        );
+        self.red.set_compare(red);
+        self.green.set_compare(green);
+        self.blue.set_compare(blue);
+    }
+}
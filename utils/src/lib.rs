@@ -4,4 +4,12 @@
 #![no_std]
 
 #[macro_use]
+pub mod display;
+pub mod http;
+pub mod input;
+pub mod led;
+#[cfg(test)]
+mod mock;
+pub mod mqtt;
+pub mod time;
 pub mod wifi;
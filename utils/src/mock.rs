@@ -0,0 +1,109 @@
+//! Test doubles for the tests of the modules.
+
+extern crate std;
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::rc::Rc;
+use std::vec::Vec;
+
+use embedded_hal::{digital, spi};
+
+/// What happened on the pins and the bus of a display, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The data/command pin was set, high for data.
+    Dc(bool),
+    /// A write on the SPI bus.
+    Write(Vec<u8>),
+}
+
+/// The events shared by a [`Spi`] and a [`Dc`].
+pub type Log = Rc<RefCell<Vec<Event>>>;
+
+/// A SPI device that records the writes.
+pub struct Spi(pub Log);
+
+/// A data/command pin that records its levels.
+pub struct Dc(pub Log);
+
+/// Returns a SPI device and a data/command pin that record into the same log.
+pub fn display_bus() -> (Spi, Dc, Log) {
+    let log = Log::default();
+    (Spi(log.clone()), Dc(log.clone()), log)
+}
+
+impl Spi {
+    fn record(&mut self, operations: &mut [spi::Operation<'_, u8>]) {
+        for operation in operations {
+            match operation {
+                spi::Operation::Write(bytes) => {
+                    self.0.borrow_mut().push(Event::Write(bytes.to_vec()))
+                }
+                _ => unimplemented!("the displays only write"),
+            }
+        }
+    }
+}
+
+impl spi::ErrorType for Spi {
+    type Error = Infallible;
+}
+
+impl spi::SpiDevice for Spi {
+    fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.record(operations);
+        Ok(())
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for Spi {
+    async fn transaction(
+        &mut self,
+        operations: &mut [spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.record(operations);
+        Ok(())
+    }
+}
+
+impl digital::ErrorType for Dc {
+    type Error = Infallible;
+}
+
+impl digital::OutputPin for Dc {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().push(Event::Dc(false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().push(Event::Dc(true));
+        Ok(())
+    }
+}
+
+/// Runs a future that never waits, the mocks never do.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(core::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+    // SAFETY: the functions of the vtable do nothing with the data pointer
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
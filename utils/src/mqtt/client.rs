@@ -0,0 +1,413 @@
+//! MQTT client on an `embassy-net` TCP socket.
+
+use embassy_net::driver::Driver;
+use embassy_net::tcp::{self, ConnectError, TcpSocket};
+use embassy_net::{IpEndpoint, Stack};
+use embassy_time::{with_timeout, Duration, Instant};
+use heapless::Vec;
+
+use super::{Connect, ConnectReturnCode, DecodeError, EncodeError, Filters, Packet, Publish, QoS};
+
+/// Maximum number of QoS 1 messages sent and not acknowledged yet.
+pub const MAX_IN_FLIGHT: usize = 4;
+
+/// How long to wait for the CONNACK of the broker.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error of the [`MqttClient`]. The connection cannot be used anymore after an error,
+/// except after [`Error::InFlightFull`] and [`Error::Encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The TCP connection to the broker could not be opened.
+    Connect(ConnectError),
+    /// The TCP connection was reset or closed.
+    Network(tcp::Error),
+    /// The broker refused the connection.
+    Refused(ConnectReturnCode),
+    /// The broker did not answer in time: no CONNACK, or no answer to a PINGREQ
+    /// within the keep alive.
+    Timeout,
+    /// The broker sent an unexpected or a malformed packet.
+    Protocol(Option<DecodeError>),
+    /// A packet does not fit in the buffers.
+    Encode(EncodeError),
+    /// [`MAX_IN_FLIGHT`] QoS 1 messages wait for their acknowledgement.
+    InFlightFull,
+}
+
+impl From<tcp::Error> for Error {
+    fn from(err: tcp::Error) -> Self {
+        Error::Network(err)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(err: EncodeError) -> Self {
+        Error::Encode(err)
+    }
+}
+
+/// Something received from the broker, returned by [`MqttClient::next_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A message of a subscribed topic. The PUBACK of a QoS 1 message is sent by the
+    /// next call of the client, once the application has the message.
+    Message(Publish<'a>),
+    /// The broker acknowledged the QoS 1 message with this packet identifier.
+    Acked(u16),
+    /// The broker answered a SUBSCRIBE, with the granted QoS of every filter, or
+    /// `0x80` for the refused ones.
+    Subscribed { packet_id: u16, granted: &'a [u8] },
+}
+
+/// The memory of an [`MqttClient`]: the socket buffers and the buffers of the
+/// packets, each `N` bytes long. A packet longer than `N` cannot be sent or received.
+pub struct Buffers<const N: usize> {
+    socket_rx: [u8; N],
+    socket_tx: [u8; N],
+    rx: [u8; N],
+    tx: [u8; N],
+}
+
+impl<const N: usize> Buffers<N> {
+    pub const fn new() -> Self {
+        Self {
+            socket_rx: [0; N],
+            socket_tx: [0; N],
+            rx: [0; N],
+            tx: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> Default for Buffers<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A connection to an MQTT 3.1.1 broker.
+///
+/// The client sends a PINGREQ when it has sent nothing during the keep alive, while
+/// the application waits in [`MqttClient::next_event`], so the application should
+/// always be waiting for the next event when it does not publish. A lost connection
+/// is reported as an error, and the application connects again, usually after a
+/// [`Backoff`](crate::wifi::Backoff) delay. Sessions are not resumed: the messages
+/// in flight are lost and the subscriptions are made again. QoS 1 messages are never
+/// sent again, [`MqttClient::in_flight`] tells how many are still unacknowledged.
+///
+/// The futures of the client can be cancelled, for example by a `select` with a
+/// timer. A packet that was being sent is completed by the next call, so the
+/// connection is never left with half a packet, and a received packet stays in the
+/// buffer until its event is returned.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut buffers = Buffers::<512>::new();
+/// let broker = IpEndpoint::new(IpAddress::v4(192, 168, 1, 2), 1883);
+/// let mut client = MqttClient::connect(stack, &mut buffers, broker, &Connect::new("pico")).await?;
+/// client.subscribe(&[("board/led/set", QoS::AtLeastOnce)]).await?;
+///
+/// let mut ticker = Ticker::every(Duration::from_secs(10));
+/// loop {
+///     match select(client.next_event(), ticker.next()).await {
+///         Either::First(event) => {
+///             if let Event::Message(message) = event? {
+///                 info!("{}: {:?}", message.topic, message.payload);
+///             }
+///         }
+///         Either::Second(()) => {
+///             client.publish("board/light", b"1234", QoS::AtMostOnce, false).await?;
+///         }
+///     }
+/// }
+/// ```
+pub struct MqttClient<'a> {
+    socket: TcpSocket<'a>,
+    rx: &'a mut [u8],
+    /// Number of bytes received in `rx`.
+    rx_len: usize,
+    /// Length of the packet returned by the last event, removed before the next one.
+    consumed: usize,
+    tx: &'a mut [u8],
+    /// Length of the packet in `tx`, not completely sent yet.
+    tx_len: usize,
+    /// Number of bytes of the packet in `tx` already sent.
+    tx_sent: usize,
+    keep_alive: Option<Duration>,
+    last_sent: Instant,
+    ping_sent: Option<Instant>,
+    next_packet_id: u16,
+    in_flight: Vec<u16, MAX_IN_FLIGHT>,
+}
+
+impl<'a> MqttClient<'a> {
+    /// Opens a TCP connection to the `broker`, sends the CONNECT and waits for the
+    /// CONNACK.
+    pub async fn connect<D: Driver, const N: usize>(
+        stack: &'a Stack<D>,
+        buffers: &'a mut Buffers<N>,
+        broker: IpEndpoint,
+        options: &Connect<'_>,
+    ) -> Result<MqttClient<'a>, Error> {
+        let Buffers {
+            socket_rx,
+            socket_tx,
+            rx,
+            tx,
+        } = buffers;
+        let mut socket = TcpSocket::new(stack, socket_rx, socket_tx);
+        socket.connect(broker).await.map_err(Error::Connect)?;
+
+        let keep_alive = match options.keep_alive {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds as u64)),
+        };
+        let mut client = MqttClient {
+            socket,
+            rx,
+            rx_len: 0,
+            consumed: 0,
+            tx,
+            tx_len: 0,
+            tx_sent: 0,
+            keep_alive,
+            last_sent: Instant::now(),
+            ping_sent: None,
+            next_packet_id: 1,
+            in_flight: Vec::new(),
+        };
+        client.send(&Packet::Connect(*options)).await?;
+
+        let code = with_timeout(CONNECT_TIMEOUT, client.connack())
+            .await
+            .map_err(|_| Error::Timeout)??;
+        if code != ConnectReturnCode::Accepted {
+            return Err(Error::Refused(code));
+        }
+        client.consume();
+        Ok(client)
+    }
+
+    /// Waits for the first packet, which should be a CONNACK.
+    async fn connack(&mut self) -> Result<ConnectReturnCode, Error> {
+        loop {
+            match Packet::decode(&self.rx[..self.rx_len]) {
+                Ok((Packet::ConnAck { code, .. }, len)) => {
+                    self.consumed = len;
+                    return Ok(code);
+                }
+                Ok(_) => return Err(Error::Protocol(None)),
+                Err(DecodeError::Incomplete) => self.fill().await?,
+                Err(err) => return Err(Error::Protocol(Some(err))),
+            }
+        }
+    }
+
+    /// Sends a message. Returns the packet identifier of a QoS 1 message, given back
+    /// by [`Event::Acked`] when the broker receives it.
+    ///
+    /// If the future is cancelled after the message was encoded, the message is still
+    /// sent by the next call of the client.
+    pub async fn publish(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: QoS,
+        retain: bool,
+    ) -> Result<Option<u16>, Error> {
+        let packet_id = match qos {
+            QoS::AtMostOnce => None,
+            QoS::AtLeastOnce => {
+                if self.in_flight.is_full() {
+                    return Err(Error::InFlightFull);
+                }
+                Some(self.packet_id())
+            }
+        };
+        let publish = Publish {
+            topic,
+            payload,
+            qos,
+            retain,
+            dup: false,
+            packet_id,
+        };
+        self.flush().await?;
+        self.queue(&Packet::Publish(publish))?;
+        if let Some(id) = packet_id {
+            // Cannot fail, there is room for one more.
+            let _ = self.in_flight.push(id);
+        }
+        self.flush().await?;
+        Ok(packet_id)
+    }
+
+    /// Subscribes to topic filters. Returns the packet identifier given back by
+    /// [`Event::Subscribed`].
+    pub async fn subscribe(&mut self, filters: &[(&str, QoS)]) -> Result<u16, Error> {
+        let packet_id = self.packet_id();
+        let subscribe = Packet::Subscribe {
+            packet_id,
+            filters: Filters::new(filters),
+        };
+        self.send(&subscribe).await?;
+        Ok(packet_id)
+    }
+
+    /// Returns the number of QoS 1 messages not acknowledged yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Waits for the next event, and keeps the connection alive meanwhile.
+    ///
+    /// The future can be cancelled, for example by a `select` with a timer, without
+    /// losing data: see [`MqttClient`].
+    pub async fn next_event(&mut self) -> Result<Event<'_>, Error> {
+        self.consume();
+        // The PUBACK of the message of the last event, or the rest of a cancelled
+        // packet.
+        self.flush().await?;
+        let len = loop {
+            match Packet::decode(&self.rx[..self.rx_len]) {
+                Ok((packet, len)) => match packet {
+                    Packet::PingResp => {
+                        self.ping_sent = None;
+                        self.consumed = len;
+                        self.consume();
+                    }
+                    Packet::PubAck(id) => {
+                        self.in_flight.retain(|known| *known != id);
+                        self.consumed = len;
+                        break len;
+                    }
+                    Packet::SubAck { .. } => {
+                        self.consumed = len;
+                        break len;
+                    }
+                    Packet::Publish(publish) => {
+                        // The buffer was flushed and nothing has been sent since, so
+                        // the PUBACK fits. It is sent with the next call, so that
+                        // a cancelled call cannot send it twice.
+                        if let Some(id) = publish.packet_id {
+                            self.queue(&Packet::PubAck(id))?;
+                        }
+                        self.consumed = len;
+                        break len;
+                    }
+                    _ => return Err(Error::Protocol(None)),
+                },
+                Err(DecodeError::Incomplete) => self.wait_for_data().await?,
+                Err(err) => return Err(Error::Protocol(Some(err))),
+            }
+        };
+
+        // The packet is decoded again, so that the event borrows the buffer only when
+        // it is returned.
+        Ok(match Packet::decode(&self.rx[..len]) {
+            Ok((Packet::PubAck(id), _)) => Event::Acked(id),
+            Ok((Packet::SubAck { packet_id, granted }, _)) => {
+                Event::Subscribed { packet_id, granted }
+            }
+            Ok((Packet::Publish(publish), _)) => Event::Message(publish),
+            _ => return Err(Error::Protocol(None)),
+        })
+    }
+
+    /// Sends a DISCONNECT and closes the connection.
+    pub async fn disconnect(mut self) {
+        let _ = self.send(&Packet::Disconnect).await;
+        self.socket.close();
+        let _ = self.socket.flush().await;
+    }
+
+    /// Receives more data, and sends a PINGREQ when nothing has been sent during the
+    /// keep alive.
+    async fn wait_for_data(&mut self) -> Result<(), Error> {
+        let Some(keep_alive) = self.keep_alive else {
+            return self.fill().await;
+        };
+        let deadline = match self.ping_sent {
+            // The broker has the keep alive to answer the PINGREQ.
+            Some(ping_sent) => ping_sent + keep_alive,
+            None => self.last_sent + keep_alive,
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if let Ok(filled) = with_timeout(remaining, self.fill()).await {
+            return filled;
+        }
+        if self.ping_sent.is_some() {
+            return Err(Error::Timeout);
+        }
+        self.queue(&Packet::PingReq)?;
+        self.ping_sent = Some(Instant::now());
+        self.flush().await
+    }
+
+    /// Receives more data in the buffer.
+    async fn fill(&mut self) -> Result<(), Error> {
+        if self.rx_len == self.rx.len() {
+            return Err(Error::Encode(EncodeError::BufferTooSmall));
+        }
+        match self.socket.read(&mut self.rx[self.rx_len..]).await? {
+            0 => Err(Error::Network(tcp::Error::ConnectionReset)),
+            n => {
+                self.rx_len += n;
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the packet of the last event from the buffer.
+    fn consume(&mut self) {
+        self.rx.copy_within(self.consumed..self.rx_len, 0);
+        self.rx_len -= self.consumed;
+        self.consumed = 0;
+    }
+
+    async fn send(&mut self, packet: &Packet<'_>) -> Result<(), Error> {
+        self.flush().await?;
+        self.queue(packet)?;
+        self.flush().await
+    }
+
+    /// Encodes a packet in the transmit buffer, which must have been flushed.
+    fn queue(&mut self, packet: &Packet<'_>) -> Result<(), Error> {
+        debug_assert_eq!(self.tx_len, 0);
+        self.tx_len = packet.encode(self.tx)?;
+        self.tx_sent = 0;
+        Ok(())
+    }
+
+    /// Sends the rest of the packet in the transmit buffer.
+    ///
+    /// Writing to the socket is cancel-safe, so the packet is resumed where a
+    /// cancelled call left it.
+    async fn flush(&mut self) -> Result<(), Error> {
+        if self.tx_len == 0 {
+            return Ok(());
+        }
+        while self.tx_sent < self.tx_len {
+            let n = self
+                .socket
+                .write(&self.tx[self.tx_sent..self.tx_len])
+                .await?;
+            if n == 0 {
+                return Err(Error::Network(tcp::Error::ConnectionReset));
+            }
+            self.tx_sent += n;
+        }
+        self.tx_len = 0;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Returns a packet identifier, never 0.
+    fn packet_id(&mut self) -> u16 {
+        let id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.checked_add(1).unwrap_or(1);
+        id
+    }
+}
@@ -0,0 +1,21 @@
+//! MQTT 3.1.1 client, to publish telemetry to a broker and receive commands.
+//!
+//! [`Packet`] encodes and decodes the control packets used by a client: CONNECT,
+//! PUBLISH at QoS 0 and 1, SUBSCRIBE, the keep alive PINGREQ and their answers. It
+//! also encodes the answers of a broker, so the codec can be tested without one,
+//! and it is available without the `rp2040` feature.
+//!
+//! With the `rp2040` feature, [`MqttClient`] connects to a broker over an
+//! `embassy-net` TCP socket, keeps the connection alive and returns the messages of
+//! the subscribed topics as [`Event`]s.
+
+#[cfg(feature = "rp2040")]
+mod client;
+mod packet;
+
+#[cfg(feature = "rp2040")]
+pub use client::{Buffers, Error, Event, MqttClient, MAX_IN_FLIGHT};
+pub use packet::{
+    topic_matches, Connect, ConnectReturnCode, DecodeError, EncodeError, Filters, Packet, Publish,
+    QoS,
+};
@@ -0,0 +1,803 @@
+//! Encoding and decoding of the MQTT 3.1.1 control packets.
+//!
+//! Both sides are implemented: the packets sent by a client can be decoded and the
+//! packets sent by a broker can be encoded, so a broker can be stood in for in tests.
+
+/// Quality of service of a message. QoS 2 is not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QoS {
+    /// Sent once, without acknowledgement.
+    AtMostOnce = 0,
+    /// Acknowledged by the receiver with a PUBACK.
+    ///
+    /// MQTT 3.1.1 only sends a message again when a session is resumed after a
+    /// reconnection. The client does not resume sessions, so a message that is not
+    /// acknowledged when the connection is lost is lost.
+    AtLeastOnce = 1,
+}
+
+impl QoS {
+    fn from_bits(bits: u8) -> Result<Self, DecodeError> {
+        match bits {
+            0 => Ok(QoS::AtMostOnce),
+            1 => Ok(QoS::AtLeastOnce),
+            2 => Err(DecodeError::Unsupported),
+            _ => Err(DecodeError::Malformed),
+        }
+    }
+}
+
+/// Answer of the broker to a CONNECT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectReturnCode {
+    Accepted,
+    UnacceptableProtocolVersion,
+    IdentifierRejected,
+    ServerUnavailable,
+    BadUserNameOrPassword,
+    NotAuthorized,
+}
+
+impl ConnectReturnCode {
+    fn from_u8(code: u8) -> Result<Self, DecodeError> {
+        Ok(match code {
+            0 => ConnectReturnCode::Accepted,
+            1 => ConnectReturnCode::UnacceptableProtocolVersion,
+            2 => ConnectReturnCode::IdentifierRejected,
+            3 => ConnectReturnCode::ServerUnavailable,
+            4 => ConnectReturnCode::BadUserNameOrPassword,
+            5 => ConnectReturnCode::NotAuthorized,
+            _ => return Err(DecodeError::Malformed),
+        })
+    }
+}
+
+/// The first packet of a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connect<'a> {
+    pub client_id: &'a str,
+    /// Maximum time between two packets of the client, in seconds, 0 to disable.
+    pub keep_alive: u16,
+    /// Start a new session, forgetting the subscriptions and the messages in flight.
+    pub clean_session: bool,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a [u8]>,
+}
+
+impl<'a> Connect<'a> {
+    /// A clean session with a keep alive of 60 seconds and no credentials.
+    pub const fn new(client_id: &'a str) -> Self {
+        Self {
+            client_id,
+            keep_alive: 60,
+            clean_session: true,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+/// An application message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Publish<'a> {
+    pub topic: &'a str,
+    pub payload: &'a [u8],
+    pub qos: QoS,
+    /// The broker keeps the message for the clients that subscribe later.
+    pub retain: bool,
+    /// The message is sent again.
+    pub dup: bool,
+    /// Identifier of a QoS 1 message, acknowledged by the PUBACK with the same one.
+    pub packet_id: Option<u16>,
+}
+
+/// The topic filters of a SUBSCRIBE, with the maximum QoS of each one.
+///
+/// Filters are equal when they have the same items, whether they were created from a
+/// slice or decoded.
+#[derive(Debug, Clone, Copy)]
+pub struct Filters<'a> {
+    filters: FilterList<'a>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FilterList<'a> {
+    Slice(&'a [(&'a str, QoS)]),
+    /// The payload of a decoded packet, already checked.
+    Encoded(&'a [u8]),
+}
+
+impl<'a> Filters<'a> {
+    pub const fn new(filters: &'a [(&'a str, QoS)]) -> Self {
+        Self {
+            filters: FilterList::Slice(filters),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, QoS)> + 'a {
+        let (slice, mut encoded): (&[(&str, QoS)], &[u8]) = match self.filters {
+            FilterList::Slice(slice) => (slice, &[]),
+            FilterList::Encoded(encoded) => (&[], encoded),
+        };
+        let decoded = core::iter::from_fn(move || {
+            let mut reader = Reader::new(encoded);
+            let filter = reader.string().ok()?;
+            let qos = QoS::from_bits(reader.u8().ok()?).ok()?;
+            encoded = reader.rest();
+            Some((filter, qos))
+        });
+        slice.iter().copied().chain(decoded)
+    }
+}
+
+impl PartialEq for Filters<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Filters<'_> {}
+
+/// A control packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packet<'a> {
+    Connect(Connect<'a>),
+    ConnAck {
+        session_present: bool,
+        code: ConnectReturnCode,
+    },
+    Publish(Publish<'a>),
+    PubAck(u16),
+    Subscribe {
+        packet_id: u16,
+        filters: Filters<'a>,
+    },
+    /// The granted QoS of every filter, in order, or `0x80` if the broker refused it.
+    SubAck {
+        packet_id: u16,
+        granted: &'a [u8],
+    },
+    PingReq,
+    PingResp,
+    Disconnect,
+}
+
+/// Error returned by [`Packet::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The packet does not fit in the buffer.
+    BufferTooSmall,
+    /// A string is longer than 65535 bytes, a QoS 1 message has no packet
+    /// identifier or the packet is longer than 256 MB.
+    Invalid,
+}
+
+/// Error returned by [`Packet::decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The packet has not been completely received.
+    Incomplete,
+    /// The packet does not follow the specification.
+    Malformed,
+    /// A QoS 2 message or a packet type that is not supported, like UNSUBSCRIBE.
+    Unsupported,
+}
+
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const PUBACK: u8 = 4;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const PINGREQ: u8 = 12;
+const PINGRESP: u8 = 13;
+const DISCONNECT: u8 = 14;
+
+/// Protocol level of MQTT 3.1.1.
+const PROTOCOL_LEVEL: u8 = 4;
+
+/// Maximum value of the remaining length, 4 bytes of 7 bits.
+const MAX_REMAINING_LEN: usize = 268_435_455;
+
+impl<'a> Packet<'a> {
+    /// Writes the packet at the start of `buffer` and returns its length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use utils::mqtt::{Packet, Publish, QoS};
+    ///
+    /// let publish = Packet::Publish(Publish {
+    ///     topic: "board/temperature",
+    ///     payload: b"23.5",
+    ///     qos: QoS::AtLeastOnce,
+    ///     retain: false,
+    ///     dup: false,
+    ///     packet_id: Some(7),
+    /// });
+    /// let mut buffer = [0; 64];
+    /// let len = publish.encode(&mut buffer).unwrap();
+    /// assert_eq!(&buffer[..4], [0x32, 25, 0, 17]);
+    ///
+    /// assert_eq!(Packet::decode(&buffer[..len]), Ok((publish, len)));
+    /// ```
+    pub fn encode(&self, buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        // The fixed header ends with the length of the rest of the packet, so the
+        // packet is measured before it is written.
+        let mut counter = Writer::counter();
+        let flags = self.write_body(&mut counter)?;
+        let remaining = counter.len;
+        if remaining > MAX_REMAINING_LEN {
+            return Err(EncodeError::Invalid);
+        }
+
+        let mut writer = Writer::new(buffer);
+        writer.u8(flags)?;
+        let mut rest = remaining;
+        loop {
+            let byte = (rest % 128) as u8;
+            rest /= 128;
+            if rest == 0 {
+                writer.u8(byte)?;
+                break;
+            }
+            writer.u8(byte | 0x80)?;
+        }
+        self.write_body(&mut writer)?;
+        Ok(writer.len)
+    }
+
+    /// Writes the variable header and the payload, and returns the first byte of the
+    /// fixed header.
+    fn write_body(&self, writer: &mut Writer<'_>) -> Result<u8, EncodeError> {
+        Ok(match self {
+            Packet::Connect(connect) => {
+                writer.string("MQTT")?;
+                writer.u8(PROTOCOL_LEVEL)?;
+                let mut flags = 0;
+                if connect.clean_session {
+                    flags |= 1 << 1;
+                }
+                if connect.password.is_some() {
+                    flags |= 1 << 6;
+                }
+                if connect.username.is_some() {
+                    flags |= 1 << 7;
+                }
+                writer.u8(flags)?;
+                writer.u16(connect.keep_alive)?;
+                writer.string(connect.client_id)?;
+                if let Some(username) = connect.username {
+                    writer.string(username)?;
+                }
+                if let Some(password) = connect.password {
+                    writer.binary(password)?;
+                }
+                CONNECT << 4
+            }
+            Packet::ConnAck {
+                session_present,
+                code,
+            } => {
+                writer.u8(*session_present as u8)?;
+                writer.u8(*code as u8)?;
+                CONNACK << 4
+            }
+            Packet::Publish(publish) => {
+                writer.string(publish.topic)?;
+                match (publish.qos, publish.packet_id) {
+                    (QoS::AtMostOnce, _) => {}
+                    (QoS::AtLeastOnce, Some(id)) => writer.u16(id)?,
+                    (QoS::AtLeastOnce, None) => return Err(EncodeError::Invalid),
+                }
+                writer.bytes(publish.payload)?;
+                (PUBLISH << 4)
+                    | ((publish.dup as u8) << 3)
+                    | ((publish.qos as u8) << 1)
+                    | publish.retain as u8
+            }
+            Packet::PubAck(packet_id) => {
+                writer.u16(*packet_id)?;
+                PUBACK << 4
+            }
+            Packet::Subscribe { packet_id, filters } => {
+                writer.u16(*packet_id)?;
+                for (filter, qos) in filters.iter() {
+                    writer.string(filter)?;
+                    writer.u8(qos as u8)?;
+                }
+                // The flags of SUBSCRIBE are reserved and set to 0b0010.
+                (SUBSCRIBE << 4) | 0b0010
+            }
+            Packet::SubAck { packet_id, granted } => {
+                writer.u16(*packet_id)?;
+                writer.bytes(granted)?;
+                SUBACK << 4
+            }
+            Packet::PingReq => PINGREQ << 4,
+            Packet::PingResp => PINGRESP << 4,
+            Packet::Disconnect => DISCONNECT << 4,
+        })
+    }
+
+    /// Reads the packet at the start of `buffer`. Returns the packet and its length,
+    /// so that the next packet starts right after it.
+    pub fn decode(buffer: &'a [u8]) -> Result<(Self, usize), DecodeError> {
+        let first = *buffer.first().ok_or(DecodeError::Incomplete)?;
+        let mut remaining = 0;
+        let mut header_len = 1;
+        loop {
+            let byte = *buffer.get(header_len).ok_or(DecodeError::Incomplete)?;
+            remaining |= ((byte & 0x7f) as usize) << (7 * (header_len - 1));
+            header_len += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            if header_len == 5 {
+                return Err(DecodeError::Malformed);
+            }
+        }
+        let len = header_len + remaining;
+        let body = buffer.get(header_len..len).ok_or(DecodeError::Incomplete)?;
+        let mut reader = Reader::new(body);
+
+        let flags = first & 0x0f;
+        let packet = match first >> 4 {
+            CONNECT => {
+                if reader.string()? != "MQTT" {
+                    return Err(DecodeError::Malformed);
+                }
+                if reader.u8()? != PROTOCOL_LEVEL {
+                    return Err(DecodeError::Unsupported);
+                }
+                let connect_flags = reader.u8()?;
+                let keep_alive = reader.u16()?;
+                let client_id = reader.string()?;
+                // A will message is not supported.
+                if connect_flags & (1 << 2) != 0 {
+                    return Err(DecodeError::Unsupported);
+                }
+                let username = match connect_flags & (1 << 7) {
+                    0 => None,
+                    _ => Some(reader.string()?),
+                };
+                let password = match connect_flags & (1 << 6) {
+                    0 => None,
+                    _ => Some(reader.binary()?),
+                };
+                Packet::Connect(Connect {
+                    client_id,
+                    keep_alive,
+                    clean_session: connect_flags & (1 << 1) != 0,
+                    username,
+                    password,
+                })
+            }
+            CONNACK => Packet::ConnAck {
+                session_present: reader.u8()? & 1 != 0,
+                code: ConnectReturnCode::from_u8(reader.u8()?)?,
+            },
+            PUBLISH => {
+                let qos = QoS::from_bits((flags >> 1) & 0b11)?;
+                let topic = reader.string()?;
+                let packet_id = match qos {
+                    QoS::AtMostOnce => None,
+                    QoS::AtLeastOnce => Some(reader.u16()?),
+                };
+                Packet::Publish(Publish {
+                    topic,
+                    payload: reader.rest(),
+                    qos,
+                    retain: flags & 1 != 0,
+                    dup: flags & (1 << 3) != 0,
+                    packet_id,
+                })
+            }
+            PUBACK => Packet::PubAck(reader.u16()?),
+            SUBSCRIBE => {
+                let packet_id = reader.u16()?;
+                let encoded = reader.rest();
+                // Checks the filters once, so that iterating over them cannot fail.
+                let mut filters = Reader::new(encoded);
+                while !filters.rest().is_empty() {
+                    filters.string()?;
+                    QoS::from_bits(filters.u8()?)?;
+                }
+                if encoded.is_empty() {
+                    return Err(DecodeError::Malformed);
+                }
+                Packet::Subscribe {
+                    packet_id,
+                    filters: Filters {
+                        filters: FilterList::Encoded(encoded),
+                    },
+                }
+            }
+            SUBACK => Packet::SubAck {
+                packet_id: reader.u16()?,
+                granted: reader.rest(),
+            },
+            PINGREQ => Packet::PingReq,
+            PINGRESP => Packet::PingResp,
+            DISCONNECT => Packet::Disconnect,
+            0 | 15 => return Err(DecodeError::Malformed),
+            _ => return Err(DecodeError::Unsupported),
+        };
+        Ok((packet, len))
+    }
+}
+
+/// Returns `true` if `topic` matches the subscription `filter`, where `+` matches one
+/// level and `#` all the levels that follow.
+///
+/// # Example
+///
+/// ```
+/// use utils::mqtt::topic_matches;
+///
+/// assert!(topic_matches("board/+/set", "board/led/set"));
+/// assert!(topic_matches("board/#", "board/led/set"));
+/// assert!(!topic_matches("board/+", "board/led/set"));
+/// ```
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(filter), Some(topic)) if filter == topic => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+struct Writer<'b> {
+    /// `None` to only count the bytes.
+    buffer: Option<&'b mut [u8]>,
+    len: usize,
+}
+
+impl<'b> Writer<'b> {
+    fn new(buffer: &'b mut [u8]) -> Self {
+        Self {
+            buffer: Some(buffer),
+            len: 0,
+        }
+    }
+
+    fn counter() -> Self {
+        Self {
+            buffer: None,
+            len: 0,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        let end = self.len + bytes.len();
+        if let Some(buffer) = self.buffer.as_deref_mut() {
+            buffer
+                .get_mut(self.len..end)
+                .ok_or(EncodeError::BufferTooSmall)?
+                .copy_from_slice(bytes);
+        }
+        self.len = end;
+        Ok(())
+    }
+
+    fn u8(&mut self, value: u8) -> Result<(), EncodeError> {
+        self.bytes(&[value])
+    }
+
+    fn u16(&mut self, value: u16) -> Result<(), EncodeError> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn binary(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        let len = u16::try_from(bytes.len()).map_err(|_| EncodeError::Invalid)?;
+        self.u16(len)?;
+        self.bytes(bytes)
+    }
+
+    fn string(&mut self, text: &str) -> Result<(), EncodeError> {
+        self.binary(text.as_bytes())
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.data.len() < len {
+            return Err(DecodeError::Malformed);
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn binary(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<&'a str, DecodeError> {
+        core::str::from_utf8(self.binary()?).map_err(|_| DecodeError::Malformed)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// A broker stood in for with the codec: it answers the packets of one client
+    /// and sends it back the messages of its subscriptions.
+    struct Broker {
+        /// The bytes sent to the client.
+        to_client: Vec<u8>,
+        filters: Vec<(std::string::String, QoS)>,
+        next_packet_id: u16,
+    }
+
+    impl Broker {
+        fn new() -> Self {
+            Self {
+                to_client: Vec::new(),
+                filters: Vec::new(),
+                next_packet_id: 1,
+            }
+        }
+
+        /// Handles the bytes sent by the client, which must hold whole packets.
+        fn receive(&mut self, mut data: &[u8]) {
+            while !data.is_empty() {
+                let (packet, len) = Packet::decode(data).unwrap();
+                self.handle(packet);
+                data = &data[len..];
+            }
+        }
+
+        fn handle(&mut self, packet: Packet<'_>) {
+            match packet {
+                Packet::Connect(connect) => {
+                    let code = if connect.client_id.is_empty() {
+                        ConnectReturnCode::IdentifierRejected
+                    } else {
+                        ConnectReturnCode::Accepted
+                    };
+                    self.send(Packet::ConnAck {
+                        session_present: false,
+                        code,
+                    });
+                }
+                Packet::Subscribe { packet_id, filters } => {
+                    let granted: Vec<u8> = filters.iter().map(|(_, qos)| qos as u8).collect();
+                    self.filters
+                        .extend(filters.iter().map(|(filter, qos)| (filter.into(), qos)));
+                    self.send(Packet::SubAck {
+                        packet_id,
+                        granted: &granted,
+                    });
+                }
+                Packet::Publish(publish) => {
+                    if let Some(id) = publish.packet_id {
+                        self.send(Packet::PubAck(id));
+                    }
+                    let granted = self
+                        .filters
+                        .iter()
+                        .filter(|(filter, _)| topic_matches(filter, publish.topic))
+                        .map(|(_, qos)| *qos)
+                        .max();
+                    if let Some(granted) = granted {
+                        let qos = publish.qos.min(granted);
+                        let packet_id = (qos == QoS::AtLeastOnce).then(|| self.packet_id());
+                        self.send(Packet::Publish(Publish {
+                            qos,
+                            retain: false,
+                            dup: false,
+                            packet_id,
+                            ..publish
+                        }));
+                    }
+                }
+                Packet::PubAck(_) | Packet::Disconnect => {}
+                Packet::PingReq => self.send(Packet::PingResp),
+                packet => panic!("a client does not send {packet:?}"),
+            }
+        }
+
+        fn send(&mut self, packet: Packet<'_>) {
+            let mut buffer = [0; 128];
+            let len = packet.encode(&mut buffer).unwrap();
+            self.to_client.extend_from_slice(&buffer[..len]);
+        }
+
+        fn packet_id(&mut self) -> u16 {
+            let id = self.next_packet_id;
+            self.next_packet_id += 1;
+            id
+        }
+    }
+
+    /// Encodes the packets of a client, one after the other.
+    fn client_bytes(packets: &[Packet<'_>]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for packet in packets {
+            let mut buffer = [0; 128];
+            let len = packet.encode(&mut buffer).unwrap();
+            bytes.extend_from_slice(&buffer[..len]);
+        }
+        bytes
+    }
+
+    /// Decodes the packets received by a client, a byte at a time like a slow
+    /// connection, and returns them with their length.
+    fn client_decode(bytes: &[u8]) -> Vec<(Packet<'_>, usize)> {
+        let mut packets = Vec::new();
+        let mut start = 0;
+        for end in 0..=bytes.len() {
+            match Packet::decode(&bytes[start..end]) {
+                Ok((packet, len)) => {
+                    assert_eq!(len, end - start);
+                    packets.push((packet, len));
+                    start = end;
+                }
+                Err(DecodeError::Incomplete) => {}
+                Err(err) => panic!("{err:?}"),
+            }
+        }
+        assert_eq!(start, bytes.len(), "incomplete packet");
+        packets
+    }
+
+    #[test]
+    fn session_with_a_stand_in_broker() {
+        let mut broker = Broker::new();
+        let filters = [
+            ("board/+/set", QoS::AtLeastOnce),
+            ("board/info", QoS::AtMostOnce),
+        ];
+        broker.receive(&client_bytes(&[
+            Packet::Connect(Connect::new("pico")),
+            Packet::Subscribe {
+                packet_id: 1,
+                filters: Filters::new(&filters),
+            },
+            Packet::Publish(Publish {
+                topic: "board/led/set",
+                payload: b"on",
+                qos: QoS::AtLeastOnce,
+                retain: false,
+                dup: false,
+                packet_id: Some(2),
+            }),
+            Packet::Publish(Publish {
+                topic: "board/info",
+                payload: b"v1",
+                qos: QoS::AtLeastOnce,
+                retain: true,
+                dup: false,
+                packet_id: Some(3),
+            }),
+            Packet::Publish(Publish {
+                topic: "board/light",
+                payload: b"1234",
+                qos: QoS::AtMostOnce,
+                retain: false,
+                dup: false,
+                packet_id: None,
+            }),
+            Packet::PingReq,
+        ]));
+
+        let received: Vec<Packet<'_>> = client_decode(&broker.to_client)
+            .into_iter()
+            .map(|(packet, _)| packet)
+            .collect();
+        assert_eq!(
+            received,
+            [
+                Packet::ConnAck {
+                    session_present: false,
+                    code: ConnectReturnCode::Accepted,
+                },
+                Packet::SubAck {
+                    packet_id: 1,
+                    granted: &[1, 0],
+                },
+                Packet::PubAck(2),
+                Packet::Publish(Publish {
+                    topic: "board/led/set",
+                    payload: b"on",
+                    qos: QoS::AtLeastOnce,
+                    retain: false,
+                    dup: false,
+                    packet_id: Some(1),
+                }),
+                Packet::PubAck(3),
+                // Downgraded to the QoS of the subscription.
+                Packet::Publish(Publish {
+                    topic: "board/info",
+                    payload: b"v1",
+                    qos: QoS::AtMostOnce,
+                    retain: false,
+                    dup: false,
+                    packet_id: None,
+                }),
+                Packet::PingResp,
+            ]
+        );
+    }
+
+    #[test]
+    fn stand_in_broker_refuses_an_empty_client_id() {
+        let mut broker = Broker::new();
+        broker.receive(&client_bytes(&[Packet::Connect(Connect::new(""))]));
+        assert_eq!(
+            Packet::decode(&broker.to_client),
+            Ok((
+                Packet::ConnAck {
+                    session_present: false,
+                    code: ConnectReturnCode::IdentifierRejected,
+                },
+                4
+            ))
+        );
+    }
+
+    #[test]
+    fn decoded_filters_equal_the_encoded_ones() {
+        let filters = [
+            ("board/+/set", QoS::AtLeastOnce),
+            ("board/#", QoS::AtMostOnce),
+        ];
+        let subscribe = Packet::Subscribe {
+            packet_id: 9,
+            filters: Filters::new(&filters),
+        };
+        let mut buffer = [0; 64];
+        let len = subscribe.encode(&mut buffer).unwrap();
+        let (decoded, _) = Packet::decode(&buffer[..len]).unwrap();
+        assert_eq!(decoded, subscribe);
+
+        let Packet::Subscribe {
+            filters: decoded, ..
+        } = decoded
+        else {
+            unreachable!()
+        };
+        assert_ne!(decoded, Filters::new(&filters[..1]));
+        assert_ne!(
+            decoded,
+            Filters::new(&[
+                ("board/+/set", QoS::AtLeastOnce),
+                ("board/#", QoS::AtLeastOnce)
+            ])
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! SNTP client on an `embassy-net` UDP socket, and the clock of the board.
+
+use core::cell::Cell;
+
+use embassy_net::driver::Driver;
+use embassy_net::udp::{BindError, PacketMetadata, RecvError, SendError, UdpSocket};
+use embassy_net::{IpAddress, IpEndpoint, Stack};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+use log::{info, warn};
+
+use super::sntp::{self, local_timestamp, Packet, ReplyError, Sample, PACKET_SIZE};
+use super::{Adjustment, Clock, DateTime, Timestamp};
+use crate::wifi::{Backoff, WifiStack};
+
+/// The clock of the board, set by [`sntp_task`].
+pub static CLOCK: BlockingMutex<CriticalSectionRawMutex, Cell<Clock>> =
+    BlockingMutex::new(Cell::new(Clock::new()));
+
+/// Returns the current UTC date and time, or `None` before the first synchronization.
+pub fn now() -> Option<DateTime> {
+    CLOCK.lock(|clock| clock.get()).datetime(Instant::now())
+}
+
+/// Returns the current number of microseconds since 1970-01-01T00:00:00Z, or `None`
+/// before the first synchronization.
+pub fn unix_micros() -> Option<u64> {
+    CLOCK.lock(|clock| clock.get()).unix_micros(Instant::now())
+}
+
+/// Returns the current time for the logs, see [`Timestamp`].
+///
+/// ```ignore
+/// info!("{} temperature {}", utils::time::timestamp(), temperature);
+/// ```
+pub fn timestamp() -> Timestamp {
+    CLOCK.lock(|clock| clock.get()).timestamp(Instant::now())
+}
+
+/// Error of an SNTP [`request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Bind(BindError),
+    Send(SendError),
+    Receive(RecvError),
+    /// No valid reply arrived in time.
+    Timeout,
+    /// The server sent an invalid reply, or refused to answer.
+    Reply(ReplyError),
+}
+
+/// Server and timing of the [`sntp_task`].
+#[derive(Debug, Clone)]
+pub struct SntpConfig {
+    pub server: IpEndpoint,
+    /// Time between two synchronizations.
+    pub interval: Duration,
+    /// How long to wait for a reply.
+    pub timeout: Duration,
+}
+
+impl SntpConfig {
+    /// Synchronizes with `server` every 15 minutes.
+    pub fn new(server: IpAddress) -> Self {
+        Self {
+            server: IpEndpoint::new(server, sntp::PORT),
+            interval: Duration::from_secs(15 * 60),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Sends one request to `server` and measures the offset of the local clock.
+///
+/// The replies that do not answer this request are ignored.
+pub async fn request<D: Driver>(
+    stack: &Stack<D>,
+    server: IpEndpoint,
+    timeout: Duration,
+) -> Result<Sample, Error> {
+    let mut rx_meta = [PacketMetadata::EMPTY; 2];
+    let mut rx_buffer = [0; 2 * PACKET_SIZE];
+    let mut tx_meta = [PacketMetadata::EMPTY; 2];
+    let mut tx_buffer = [0; 2 * PACKET_SIZE];
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+    // Any local port
+    socket.bind(0).map_err(Error::Bind)?;
+
+    let sent = Instant::now();
+    let request = Packet::request(local_timestamp(sent)).encode();
+    socket
+        .send_to(&request, server)
+        .await
+        .map_err(Error::Send)?;
+
+    let reply = async {
+        let mut message = [0; PACKET_SIZE];
+        loop {
+            let (n, from) = socket
+                .recv_from(&mut message)
+                .await
+                .map_err(Error::Receive)?;
+            let received = Instant::now();
+            if from != server {
+                continue;
+            }
+            let reply = Packet::decode(&message[..n]).map_err(Error::Reply)?;
+            match Sample::new(&reply, sent, received) {
+                Err(ReplyError::WrongOrigin) => continue,
+                result => return result.map_err(Error::Reply),
+            }
+        }
+    };
+    with_timeout(timeout, reply)
+        .await
+        .map_err(|_| Error::Timeout)?
+}
+
+/// Synchronizes [`CLOCK`] with an SNTP server, when the network is up.
+///
+/// After a failed request, the task tries again with an exponential backoff up to
+/// the interval. When the server refuses to answer, with a kiss-o'-death, it waits
+/// for the whole interval.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = SntpConfig::new(IpAddress::v4(162, 159, 200, 1));
+/// spawner.spawn(sntp_task(stack, config)).unwrap();
+///
+/// info!("{} starting", utils::time::timestamp());
+/// ```
+#[embassy_executor::task]
+pub async fn sntp_task(stack: &'static WifiStack, config: SntpConfig) -> ! {
+    let mut backoff = Backoff::new(Duration::from_secs(2), config.interval);
+    loop {
+        stack.wait_config_up().await;
+        let delay = match request(stack, config.server, config.timeout).await {
+            Ok(sample) => {
+                let adjustment = CLOCK.lock(|clock| {
+                    let mut updated = clock.get();
+                    let adjustment = updated.update(&sample);
+                    clock.set(updated);
+                    adjustment
+                });
+                match adjustment {
+                    Adjustment::Set => info!("Clock set, {}", timestamp()),
+                    Adjustment::Corrected { error } => info!(
+                        "Clock corrected by {} us, drift {} ppb",
+                        error,
+                        CLOCK.lock(|clock| clock.get().drift_ppb())
+                    ),
+                    Adjustment::Stepped { error } => {
+                        warn!("Clock off by {} us, set to {}", error, timestamp())
+                    }
+                }
+                backoff.reset();
+                config.interval
+            }
+            Err(Error::Reply(ReplyError::KissOfDeath(code))) => {
+                warn!("SNTP server refused to answer: {:?}", code);
+                config.interval
+            }
+            Err(err) => {
+                warn!("SNTP error: {:?}", err);
+                backoff.next_delay()
+            }
+        };
+        Timer::after(delay).await;
+    }
+}
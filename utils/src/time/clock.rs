@@ -0,0 +1,384 @@
+//! Wall clock: the time of `embassy_time`, corrected with the SNTP samples.
+
+use core::fmt;
+
+use embassy_time::{Duration, Instant};
+
+use super::{DateTime, Sample};
+
+/// Errors larger than this are corrected with a step, and the drift is not measured
+/// from them: the server changed, or the board was not synchronized for a long time.
+pub const STEP_THRESHOLD: Duration = Duration::from_millis(128);
+
+/// Maximum drift of the local clock, in parts per billion. Like NTP, 500 ppm: more
+/// than this is a measurement error.
+pub const MAX_DRIFT_PPB: i64 = 500_000;
+
+/// Minimum time over which the drift is measured. Over a shorter interval, the jitter
+/// of the network delay is larger than the drift.
+const MIN_DRIFT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What [`Clock::update`] did with a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjustment {
+    /// First sample, the clock is set.
+    Set,
+    /// The clock was off by `error` microseconds. It is corrected, and so is the
+    /// drift once a minute has passed since it was last measured.
+    Corrected { error: i64 },
+    /// The clock was off by more than [`STEP_THRESHOLD`] and jumped by `error`
+    /// microseconds.
+    Stepped { error: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Reference {
+    at: Instant,
+    offset: i64,
+}
+
+impl From<&Sample> for Reference {
+    fn from(sample: &Sample) -> Self {
+        Self {
+            at: sample.at,
+            offset: sample.offset,
+        }
+    }
+}
+
+/// The offset between the local time, [`Instant`], and the Unix time, measured by
+/// SNTP [`Sample`]s.
+///
+/// The local crystal is not exact: the clock measures how fast the offset changes,
+/// its drift, and corrects the time between the samples with it. The drift is
+/// measured over at least a minute, however often the samples come. The time can go
+/// back by a few milliseconds when a sample corrects it.
+///
+/// # Example
+///
+/// The board starts at 2024-03-01T12:00:00Z and its crystal is 100 ppm slow: the
+/// server counts 1000.1 seconds when the board counts 1000.
+///
+/// ```
+/// use embassy_time::{Duration, Instant};
+/// use utils::time::sntp::Sample;
+/// use utils::time::{Adjustment, Clock};
+///
+/// const START: i64 = 1_709_294_400_000_000;
+///
+/// // A sample without network delay, at `seconds` of local time
+/// fn sample(seconds: u64) -> Sample {
+///     let at = Instant::from_secs(seconds);
+///     Sample {
+///         offset: START + at.as_micros() as i64 / 10_000,
+///         delay: 0,
+///         at,
+///     }
+/// }
+///
+/// let mut clock = Clock::new();
+/// assert_eq!(clock.datetime(Instant::from_secs(1)), None);
+///
+/// assert_eq!(clock.update(&sample(1000)), Adjustment::Set);
+/// let time = clock.datetime(Instant::from_secs(1000)).unwrap();
+/// assert_eq!(format!("{}", time), "2024-03-01T12:16:40.100Z");
+///
+/// // 15 minutes later, the clock is 90 ms late, from which it knows the drift
+/// assert_eq!(clock.update(&sample(1900)), Adjustment::Corrected { error: 90_000 });
+/// assert_eq!(clock.drift_ppb(), 100_000);
+///
+/// // And a day later, it is still exact
+/// let time = clock.datetime(Instant::from_secs(1900 + 86_400)).unwrap();
+/// assert_eq!(format!("{}", time), "2024-03-02T12:31:48.830Z");
+///
+/// // An error larger than the step threshold
+/// let late = Sample { offset: START + 1_000_000, ..sample(2000) };
+/// assert!(matches!(clock.update(&late), Adjustment::Stepped { .. }));
+/// assert_eq!(clock.drift_ppb(), 100_000);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Clock {
+    /// The last sample, from which the time is predicted.
+    reference: Option<Reference>,
+    /// The sample from which the drift is measured next.
+    drift_reference: Option<Reference>,
+    /// In parts per billion, positive when the local clock is slow.
+    drift: i64,
+}
+
+impl Clock {
+    /// Returns a clock that is not synchronized.
+    pub const fn new() -> Self {
+        Self {
+            reference: None,
+            drift_reference: None,
+            drift: 0,
+        }
+    }
+
+    /// Returns `true` if the clock received a sample.
+    pub fn is_synced(&self) -> bool {
+        self.reference.is_some()
+    }
+
+    /// Returns the local time of the last sample.
+    pub fn last_sync(&self) -> Option<Instant> {
+        self.reference.map(|reference| reference.at)
+    }
+
+    /// Returns the measured drift of the local clock, in parts per billion, positive
+    /// when it is slow.
+    pub fn drift_ppb(&self) -> i64 {
+        self.drift
+    }
+
+    /// Corrects the clock with a sample.
+    pub fn update(&mut self, sample: &Sample) -> Adjustment {
+        let (Some(drift_reference), Some(predicted)) =
+            (self.drift_reference, self.offset(sample.at))
+        else {
+            self.set(sample);
+            return Adjustment::Set;
+        };
+
+        let error = sample.offset - predicted;
+        if error.unsigned_abs() > STEP_THRESHOLD.as_micros() {
+            self.set(sample);
+            return Adjustment::Stepped { error };
+        }
+
+        // The drift is measured from an older sample than the last one, so that samples
+        // that come more often than `MIN_DRIFT_INTERVAL` still measure it.
+        let elapsed = sample.at.as_micros() as i64 - drift_reference.at.as_micros() as i64;
+        if elapsed >= MIN_DRIFT_INTERVAL.as_micros() as i64 {
+            let predicted = drift_reference.offset as i128
+                + elapsed as i128 * self.drift as i128 / 1_000_000_000;
+            let drift_error = sample.offset as i128 - predicted;
+            let correction = drift_error * 1_000_000_000 / elapsed as i128;
+            self.drift = (self.drift as i128 + correction)
+                .clamp(-MAX_DRIFT_PPB as i128, MAX_DRIFT_PPB as i128)
+                as i64;
+            self.drift_reference = Some(Reference::from(sample));
+        }
+        self.reference = Some(Reference::from(sample));
+        Adjustment::Corrected { error }
+    }
+
+    /// Returns the Unix time minus the local time at `at`, in microseconds.
+    pub fn offset(&self, at: Instant) -> Option<i64> {
+        let reference = self.reference?;
+        let elapsed = at.as_micros() as i64 - reference.at.as_micros() as i64;
+        let drift = elapsed as i128 * self.drift as i128 / 1_000_000_000;
+        Some(reference.offset + drift as i64)
+    }
+
+    /// Returns the number of microseconds since 1970-01-01T00:00:00Z at the local
+    /// time `at`.
+    pub fn unix_micros(&self, at: Instant) -> Option<u64> {
+        let micros = at.as_micros() as i64 + self.offset(at)?;
+        Some(micros.max(0) as u64)
+    }
+
+    /// Returns the UTC date and time at the local time `at`.
+    pub fn datetime(&self, at: Instant) -> Option<DateTime> {
+        self.unix_micros(at).map(DateTime::from_unix_micros)
+    }
+
+    /// Returns the date and time at the local time `at`, or the time since boot if
+    /// the clock is not synchronized.
+    pub fn timestamp(&self, at: Instant) -> Timestamp {
+        match self.datetime(at) {
+            Some(datetime) => Timestamp::Utc(datetime),
+            None => Timestamp::Uptime(at),
+        }
+    }
+
+    /// Restarts from `sample`, keeping the drift.
+    fn set(&mut self, sample: &Sample) {
+        self.reference = Some(Reference::from(sample));
+        self.drift_reference = self.reference;
+    }
+}
+
+/// A time for the logs: the UTC date and time like `2024-03-01T12:30:05.250Z`, or
+/// the time since boot like `+12.345s` before the clock is synchronized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    Utc(DateTime),
+    Uptime(Instant),
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timestamp::Utc(datetime) => datetime.fmt(f),
+            Timestamp::Uptime(at) => {
+                let millis = at.as_millis();
+                write!(f, "+{}.{:03}s", millis / 1000, millis % 1000)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::format;
+
+    use super::*;
+
+    /// 2024-03-01T12:00:00Z
+    const START: i64 = 1_709_294_400_000_000;
+
+    /// A sample at `seconds` of local time, from a board whose clock is `ppm` slow.
+    fn sample(seconds: u64, ppm: i64) -> Sample {
+        let at = Instant::from_secs(seconds);
+        Sample {
+            offset: START + at.as_micros() as i64 * ppm / 1_000_000,
+            delay: 0,
+            at,
+        }
+    }
+
+    /// A sample at `seconds`, `error` microseconds away from a perfect clock.
+    fn off_by(seconds: u64, error: i64) -> Sample {
+        Sample {
+            offset: START + error,
+            ..sample(seconds, 0)
+        }
+    }
+
+    fn synced() -> Clock {
+        let mut clock = Clock::new();
+        assert_eq!(clock.update(&sample(0, 0)), Adjustment::Set);
+        clock
+    }
+
+    #[test]
+    fn first_sample_sets_the_clock() {
+        let mut clock = Clock::new();
+        assert!(!clock.is_synced());
+        assert_eq!(clock.datetime(Instant::from_secs(10)), None);
+        assert_eq!(clock.update(&sample(10, 0)), Adjustment::Set);
+        assert!(clock.is_synced());
+        assert_eq!(clock.last_sync(), Some(Instant::from_secs(10)));
+        let time = clock.datetime(Instant::from_secs(10)).unwrap();
+        assert_eq!(format!("{}", time), "2024-03-01T12:00:10.000Z");
+    }
+
+    #[test]
+    fn errors_up_to_the_threshold_are_corrected() {
+        let threshold = STEP_THRESHOLD.as_micros() as i64;
+        for error in [threshold, -threshold] {
+            let mut clock = synced();
+            assert_eq!(
+                clock.update(&off_by(10, error)),
+                Adjustment::Corrected { error }
+            );
+            assert_eq!(clock.offset(Instant::from_secs(10)), Some(START + error));
+        }
+    }
+
+    #[test]
+    fn errors_above_the_threshold_step_the_clock() {
+        let threshold = STEP_THRESHOLD.as_micros() as i64;
+        for error in [threshold + 1, -threshold - 1] {
+            let mut clock = synced();
+            assert_eq!(
+                clock.update(&off_by(120, error)),
+                Adjustment::Stepped { error }
+            );
+            assert_eq!(clock.offset(Instant::from_secs(120)), Some(START + error));
+            // A step does not measure the drift.
+            assert_eq!(clock.drift_ppb(), 0);
+        }
+    }
+
+    #[test]
+    fn drift_is_measured_from_a_minute_of_samples() {
+        let mut clock = synced();
+        // 100 ppm slow, a sample every 16 seconds: 1.6 ms late every time.
+        for seconds in [16, 32, 48] {
+            let adjustment = clock.update(&sample(seconds, 100));
+            assert_eq!(adjustment, Adjustment::Corrected { error: 1600 });
+            assert_eq!(clock.drift_ppb(), 0);
+        }
+        // 64 seconds after the first sample.
+        assert_eq!(
+            clock.update(&sample(64, 100)),
+            Adjustment::Corrected { error: 1600 }
+        );
+        assert_eq!(clock.drift_ppb(), 100_000);
+        assert_eq!(
+            clock.update(&sample(80, 100)),
+            Adjustment::Corrected { error: 0 }
+        );
+    }
+
+    #[test]
+    fn step_restarts_the_drift_measurement() {
+        let mut clock = synced();
+        clock.update(&sample(50, 100));
+        assert!(matches!(
+            clock.update(&off_by(55, 1_000_000)),
+            Adjustment::Stepped { .. }
+        ));
+        // A minute after the first sample, but only 10 seconds after the step.
+        clock.update(&off_by(65, 1_001_000));
+        assert_eq!(clock.drift_ppb(), 0);
+        clock.update(&off_by(115, 1_006_000));
+        assert_eq!(clock.drift_ppb(), 100_000);
+    }
+
+    #[test]
+    fn drift_is_clamped() {
+        // 2 ms per second, more than the crystal can drift.
+        for (error, drift) in [(120_000, MAX_DRIFT_PPB), (-120_000, -MAX_DRIFT_PPB)] {
+            let mut clock = synced();
+            clock.update(&off_by(60, error));
+            assert_eq!(clock.drift_ppb(), drift);
+            // And the clamped drift stays clamped.
+            clock.update(&off_by(120, 2 * error));
+            assert_eq!(clock.drift_ppb(), drift);
+        }
+    }
+
+    #[test]
+    fn drift_corrects_the_time_between_samples() {
+        let mut clock = synced();
+        clock.update(&sample(100, -50));
+        assert_eq!(clock.drift_ppb(), -50_000);
+        let later = Instant::from_secs(100 + 1000);
+        assert_eq!(clock.offset(later), Some(sample(1100, -50).offset));
+    }
+
+    #[test]
+    fn time_before_1970_is_clamped() {
+        let mut clock = Clock::new();
+        clock.update(&Sample {
+            offset: -1_000_000_000,
+            delay: 0,
+            at: Instant::from_secs(10),
+        });
+        assert_eq!(clock.unix_micros(Instant::from_secs(10)), Some(0));
+    }
+
+    #[test]
+    fn uptime_before_the_first_sample() {
+        let clock = Clock::new();
+        let timestamp = |millis| format!("{}", clock.timestamp(Instant::from_millis(millis)));
+        assert_eq!(timestamp(0), "+0.000s");
+        assert_eq!(timestamp(5), "+0.005s");
+        assert_eq!(timestamp(12_345), "+12.345s");
+        assert_eq!(timestamp(3_600_000), "+3600.000s");
+    }
+
+    #[test]
+    fn utc_after_the_first_sample() {
+        let clock = synced();
+        let timestamp = clock.timestamp(Instant::from_millis(1_250));
+        assert!(matches!(timestamp, Timestamp::Utc(_)));
+        assert_eq!(format!("{}", timestamp), "2024-03-01T12:00:01.250Z");
+    }
+}
@@ -0,0 +1,225 @@
+//! UTC calendar date and time.
+
+use core::fmt;
+
+const MICROS_PER_SECOND: u64 = 1_000_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A UTC date and time, to the millisecond, after 1970. Leap seconds are not counted,
+/// like in Unix time.
+///
+/// It is displayed in the ISO 8601 format, like `2024-03-01T12:30:05.250Z`, for logs
+/// and telemetry. [`DateTime::date`] and [`DateTime::time`] display the date and the
+/// time alone, for the screen.
+///
+/// # Example
+///
+/// ```
+/// use utils::time::DateTime;
+///
+/// let moment = DateTime::from_unix_seconds(1_709_296_205);
+/// assert_eq!((moment.year, moment.month, moment.day), (2024, 3, 1));
+/// assert_eq!((moment.hour, moment.minute, moment.second), (12, 30, 5));
+/// assert_eq!(moment.to_unix_seconds(), 1_709_296_205);
+///
+/// let moment = DateTime::from_unix_micros(1_709_296_205_250_000);
+/// assert_eq!(format!("{}", moment), "2024-03-01T12:30:05.250Z");
+/// assert_eq!(format!("{}", moment.date()), "2024-03-01");
+/// assert_eq!(format!("{}", moment.time()), "12:30:05");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    pub year: u16,
+    /// From 1 to 12.
+    pub month: u8,
+    /// From 1 to 31.
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl DateTime {
+    /// Returns the date and time `seconds` after 1970-01-01T00:00:00Z.
+    pub const fn from_unix_seconds(seconds: u64) -> Self {
+        Self::from_unix_micros(seconds * MICROS_PER_SECOND)
+    }
+
+    /// Returns the date and time `micros` microseconds after 1970-01-01T00:00:00Z.
+    pub const fn from_unix_micros(micros: u64) -> Self {
+        let seconds = micros / MICROS_PER_SECOND;
+        let (year, month, day) = civil_from_days(seconds / SECONDS_PER_DAY);
+        let time = seconds % SECONDS_PER_DAY;
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+            millisecond: (micros % MICROS_PER_SECOND / 1000) as u16,
+        }
+    }
+
+    /// Returns the number of seconds since 1970-01-01T00:00:00Z.
+    pub const fn to_unix_seconds(&self) -> u64 {
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64
+    }
+
+    /// Returns the date, displayed like `2024-03-01`.
+    pub fn date(&self) -> impl fmt::Display {
+        let (year, month, day) = (self.year, self.month, self.day);
+        Formatted(move |f: &mut fmt::Formatter| write!(f, "{:04}-{:02}-{:02}", year, month, day))
+    }
+
+    /// Returns the time of day, displayed like `12:30:05`.
+    pub fn time(&self) -> impl fmt::Display {
+        let (hour, minute, second) = (self.hour, self.minute, self.second);
+        Formatted(move |f: &mut fmt::Formatter| {
+            write!(f, "{:02}:{:02}:{:02}", hour, minute, second)
+        })
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}T{}.{:03}Z",
+            self.date(),
+            self.time(),
+            self.millisecond
+        )
+    }
+}
+
+/// Displays with a closure.
+struct Formatted<F>(F);
+
+impl<F: Fn(&mut fmt::Formatter) -> fmt::Result> fmt::Display for Formatted<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+/// Returns the year, month and day of the day `days` after 1970-01-01.
+///
+/// This is the algorithm of Howard Hinnant's `civil_from_days`, for days after the
+/// epoch only: the years are counted in eras of 400 years, starting in March.
+const fn civil_from_days(days: u64) -> (u16, u8, u8) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // From March (0) to February (11)
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year as u16, month as u8, day as u8)
+}
+
+/// Returns the number of days from 1970-01-01 to a date after it, the inverse of
+/// [`civil_from_days`].
+const fn days_from_civil(year: u16, month: u8, day: u8) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year } as u64;
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let month = month as u64;
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + day as u64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::format;
+
+    use super::*;
+
+    fn date(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+        }
+    }
+
+    /// Returns the date of the day after `date`.
+    fn next_day(date: DateTime) -> DateTime {
+        DateTime::from_unix_seconds(date.to_unix_seconds() + SECONDS_PER_DAY)
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(DateTime::from_unix_seconds(0), date(1970, 1, 1));
+        assert_eq!(date(1970, 1, 1).to_unix_seconds(), 0);
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(next_day(date(2024, 2, 28)), date(2024, 2, 29));
+        assert_eq!(next_day(date(2024, 2, 29)), date(2024, 3, 1));
+        assert_eq!(next_day(date(2023, 2, 28)), date(2023, 3, 1));
+        // Divisible by 400 is a leap year, by 100 only is not.
+        assert_eq!(next_day(date(2000, 2, 28)), date(2000, 2, 29));
+        assert_eq!(next_day(date(2100, 2, 28)), date(2100, 3, 1));
+        assert_eq!(
+            date(2025, 1, 1).to_unix_seconds() - date(2024, 1, 1).to_unix_seconds(),
+            366 * SECONDS_PER_DAY
+        );
+    }
+
+    #[test]
+    fn month_ends() {
+        for (year, month, last) in [
+            (2024, 1, 31),
+            (2024, 4, 30),
+            (2024, 6, 30),
+            (2024, 7, 31),
+            (2024, 8, 31),
+            (2024, 9, 30),
+            (2024, 11, 30),
+        ] {
+            assert_eq!(next_day(date(year, month, last)), date(year, month + 1, 1));
+        }
+        assert_eq!(next_day(date(2024, 12, 31)), date(2025, 1, 1));
+    }
+
+    #[test]
+    fn every_day_round_trips() {
+        // From 1970 to 2100, through the leap years and the end of the eras.
+        let mut previous = DateTime::from_unix_seconds(0);
+        for day in 1..47_847 {
+            let current = DateTime::from_unix_seconds(day * SECONDS_PER_DAY);
+            assert_eq!(current.to_unix_seconds(), day * SECONDS_PER_DAY);
+            assert!(current > previous);
+            assert!((1..=12).contains(&current.month) && (1..=31).contains(&current.day));
+            previous = current;
+        }
+        assert_eq!(previous, date(2100, 12, 31));
+    }
+
+    #[test]
+    fn time_of_day() {
+        let moment = DateTime::from_unix_micros(1_709_337_599_999_999);
+        assert_eq!(format!("{}", moment), "2024-03-01T23:59:59.999Z");
+        assert_eq!(format!("{}", next_day(moment).date()), "2024-03-02");
+        let moment = DateTime::from_unix_micros(1_709_251_200_001_000);
+        assert_eq!(format!("{}", moment), "2024-03-01T00:00:00.001Z");
+        assert_eq!(format!("{}", moment.time()), "00:00:00");
+    }
+}
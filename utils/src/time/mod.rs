@@ -0,0 +1,22 @@
+//! Wall-clock time: UTC dates and the SNTP client that sets the clock of the board.
+//!
+//! The local time of `embassy_time` starts at 0 when the board boots. [`sntp_task`]
+//! asks an NTP server for the time periodically, and the [`Clock`] keeps the offset
+//! between the local time and the Unix time, with the drift of the crystal. [`now`]
+//! and [`timestamp`] return the current UTC time, for the logs, the display and the
+//! telemetry.
+//!
+//! The SNTP messages, the [`Clock`] and [`DateTime`] do not depend on the chip and
+//! are also available without the `rp2040` feature.
+
+#[cfg(feature = "rp2040")]
+mod client;
+mod clock;
+mod datetime;
+pub mod sntp;
+
+#[cfg(feature = "rp2040")]
+pub use client::{now, request, sntp_task, timestamp, unix_micros, Error, SntpConfig, CLOCK};
+pub use clock::{Adjustment, Clock, Timestamp, MAX_DRIFT_PPB, STEP_THRESHOLD};
+pub use datetime::DateTime;
+pub use sntp::Sample;
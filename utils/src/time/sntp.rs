@@ -0,0 +1,464 @@
+//! SNTP messages (RFC 4330) and the clock offset measured by one exchange.
+//!
+//! The client sends a request with its own time in the transmit timestamp. The server
+//! copies it in the origin timestamp of its reply, with the times at which it
+//! received the request and sent the reply. With the time at which the reply
+//! arrived, the client knows the offset between its clock and the server's, and the
+//! round trip delay.
+
+use embassy_time::Instant;
+
+/// UDP port of the NTP servers.
+pub const PORT: u16 = 123;
+
+/// Length of an SNTP message without authentication.
+pub const PACKET_SIZE: usize = 48;
+
+/// Seconds from the NTP epoch, 1900-01-01, to the Unix epoch, 1970-01-01.
+const UNIX_EPOCH: u64 = 2_208_988_800;
+
+const MICROS_PER_SECOND: u64 = 1_000_000;
+
+/// The leap indicator of a server that is not synchronized.
+const LEAP_UNSYNCHRONIZED: u8 = 3;
+
+/// Error of [`Packet::decode`] and [`Sample::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyError {
+    /// The message is too short, or has an unknown version or mode.
+    Malformed,
+    /// The message is not a reply of a server.
+    NotServer,
+    /// The message is not the reply to the last request: another request, a late
+    /// reply or a spoofed one.
+    WrongOrigin,
+    /// The server is not synchronized to a reference clock.
+    Unsynchronized,
+    /// A timestamp of the reply is before 1970, or the server sent the reply before
+    /// it received the request.
+    InvalidTimestamp,
+    /// The server refuses to answer, with a kiss code like `RATE` (too many requests)
+    /// or `DENY`. The client should stop sending requests to this server, or at least
+    /// send them less often.
+    KissOfDeath([u8; 4]),
+}
+
+/// A time in NTP format: seconds since 1900-01-01 and fractions of a second, in
+/// units of 2^-32 seconds.
+///
+/// The seconds wrap around in 2036. As recommended by RFC 4330, timestamps with the
+/// most significant bit cleared are after 2036, so the timestamps cover 1968 to 2104,
+/// and [`NtpTimestamp::to_unix_micros`] the part of them after 1970.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NtpTimestamp {
+    pub seconds: u32,
+    pub fraction: u32,
+}
+
+impl NtpTimestamp {
+    pub const ZERO: Self = Self {
+        seconds: 0,
+        fraction: 0,
+    };
+
+    /// Returns the timestamp `micros` microseconds after 1970-01-01T00:00:00Z.
+    pub const fn from_unix_micros(micros: u64) -> Self {
+        let seconds = micros / MICROS_PER_SECOND + UNIX_EPOCH;
+        let fraction = ((micros % MICROS_PER_SECOND) << 32) / MICROS_PER_SECOND;
+        Self {
+            // Wraps around in 2036, on purpose.
+            seconds: seconds as u32,
+            fraction: fraction as u32,
+        }
+    }
+
+    /// Returns the number of microseconds since 1970-01-01T00:00:00Z, or `None` for
+    /// the timestamps of 1968 and 1969.
+    pub const fn to_unix_micros(self) -> Option<u64> {
+        let seconds = if self.seconds & 0x8000_0000 != 0 {
+            self.seconds as u64
+        } else {
+            self.seconds as u64 + (1 << 32)
+        };
+        let Some(seconds) = seconds.checked_sub(UNIX_EPOCH) else {
+            return None;
+        };
+        let micros = (self.fraction as u64 * MICROS_PER_SECOND + (1 << 31)) >> 32;
+        Some(seconds * MICROS_PER_SECOND + micros)
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            seconds: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            fraction: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        }
+    }
+
+    fn write(self, bytes: &mut [u8]) {
+        bytes[..4].copy_from_slice(&self.seconds.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.fraction.to_be_bytes());
+    }
+}
+
+/// Role of the sender of a [`Packet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Client = 3,
+    Server = 4,
+    Broadcast = 5,
+}
+
+/// An SNTP message.
+///
+/// # Example
+///
+/// ```
+/// use utils::time::sntp::{Mode, NtpTimestamp, Packet, PACKET_SIZE};
+///
+/// let request = Packet::request(NtpTimestamp::from_unix_micros(1_500_000));
+/// let bytes = request.encode();
+/// assert_eq!(bytes.len(), PACKET_SIZE);
+/// // Version 4, client
+/// assert_eq!(bytes[0], 0x23);
+///
+/// let decoded = Packet::decode(&bytes).unwrap();
+/// assert_eq!(decoded, request);
+/// assert_eq!(decoded.mode, Mode::Client);
+/// assert_eq!(decoded.transmit.to_unix_micros(), Some(1_500_000));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    /// Leap second warning, `3` if the server is not synchronized.
+    pub leap: u8,
+    /// Version of the protocol, from 1 to 4.
+    pub version: u8,
+    pub mode: Mode,
+    /// Distance to the reference clock: `1` for a server with a reference clock,
+    /// `0` for a kiss-o'-death message.
+    pub stratum: u8,
+    /// Maximum interval between two messages, in log2 seconds.
+    pub poll: i8,
+    /// Precision of the clock of the server, in log2 seconds.
+    pub precision: i8,
+    /// Round trip delay to the reference clock, in 1/65536 seconds.
+    pub root_delay: u32,
+    /// Maximum error relative to the reference clock, in 1/65536 seconds.
+    pub root_dispersion: u32,
+    /// The reference clock, or the kiss code when the stratum is `0`.
+    pub reference_id: [u8; 4],
+    /// Last time the server was synchronized.
+    pub reference: NtpTimestamp,
+    /// Transmit timestamp of the request, copied in the reply.
+    pub origin: NtpTimestamp,
+    /// Time at which the request arrived at the server.
+    pub receive: NtpTimestamp,
+    /// Time at which the message left its sender.
+    pub transmit: NtpTimestamp,
+}
+
+impl Packet {
+    /// Returns a client request sent at `transmit`.
+    pub const fn request(transmit: NtpTimestamp) -> Self {
+        Self {
+            leap: 0,
+            version: 4,
+            mode: Mode::Client,
+            stratum: 0,
+            poll: 0,
+            precision: 0,
+            root_delay: 0,
+            root_dispersion: 0,
+            reference_id: [0; 4],
+            reference: NtpTimestamp::ZERO,
+            origin: NtpTimestamp::ZERO,
+            receive: NtpTimestamp::ZERO,
+            transmit,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; PACKET_SIZE] {
+        let mut bytes = [0; PACKET_SIZE];
+        bytes[0] = (self.leap & 0b11) << 6 | (self.version & 0b111) << 3 | self.mode as u8;
+        bytes[1] = self.stratum;
+        bytes[2] = self.poll as u8;
+        bytes[3] = self.precision as u8;
+        bytes[4..8].copy_from_slice(&self.root_delay.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.root_dispersion.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.reference_id);
+        self.reference.write(&mut bytes[16..24]);
+        self.origin.write(&mut bytes[24..32]);
+        self.receive.write(&mut bytes[32..40]);
+        self.transmit.write(&mut bytes[40..48]);
+        bytes
+    }
+
+    /// Decodes a message. The authentication fields that may follow it are ignored.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplyError> {
+        if bytes.len() < PACKET_SIZE {
+            return Err(ReplyError::Malformed);
+        }
+        let version = bytes[0] >> 3 & 0b111;
+        let mode = match bytes[0] & 0b111 {
+            3 => Mode::Client,
+            4 => Mode::Server,
+            5 => Mode::Broadcast,
+            _ => return Err(ReplyError::Malformed),
+        };
+        if !(1..=4).contains(&version) {
+            return Err(ReplyError::Malformed);
+        }
+        let word = |at: usize| {
+            u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        Ok(Self {
+            leap: bytes[0] >> 6,
+            version,
+            mode,
+            stratum: bytes[1],
+            poll: bytes[2] as i8,
+            precision: bytes[3] as i8,
+            root_delay: word(4),
+            root_dispersion: word(8),
+            reference_id: [bytes[12], bytes[13], bytes[14], bytes[15]],
+            reference: NtpTimestamp::read(&bytes[16..24]),
+            origin: NtpTimestamp::read(&bytes[24..32]),
+            receive: NtpTimestamp::read(&bytes[32..40]),
+            transmit: NtpTimestamp::read(&bytes[40..48]),
+        })
+    }
+}
+
+/// Returns the transmit timestamp of a request sent at `sent`: the local time, as if
+/// the board had started in 1970, which the reply gives back as its origin.
+pub const fn local_timestamp(sent: Instant) -> NtpTimestamp {
+    NtpTimestamp::from_unix_micros(sent.as_micros())
+}
+
+/// The result of one exchange with a server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Unix time minus the local time, in microseconds.
+    pub offset: i64,
+    /// Round trip delay of the messages, in microseconds, without the time spent in
+    /// the server.
+    pub delay: u64,
+    /// Local time at which the reply arrived.
+    pub at: Instant,
+}
+
+impl Sample {
+    /// Checks the `reply` to the request [`sent`](local_timestamp) at `sent`, which
+    /// arrived at `received`, and measures the offset.
+    ///
+    /// The offset is the mean of the differences between the clocks at the request
+    /// and at the reply, so the delays of the two messages are assumed to be equal.
+    pub fn new(reply: &Packet, sent: Instant, received: Instant) -> Result<Self, ReplyError> {
+        if reply.mode != Mode::Server && reply.mode != Mode::Broadcast {
+            return Err(ReplyError::NotServer);
+        }
+        if reply.origin != local_timestamp(sent) {
+            return Err(ReplyError::WrongOrigin);
+        }
+        if reply.stratum == 0 {
+            return Err(ReplyError::KissOfDeath(reply.reference_id));
+        }
+        if reply.leap == LEAP_UNSYNCHRONIZED || reply.stratum > 15 || reply.transmit.seconds == 0 {
+            return Err(ReplyError::Unsynchronized);
+        }
+
+        let (Some(server_received), Some(server_sent)) = (
+            reply.receive.to_unix_micros(),
+            reply.transmit.to_unix_micros(),
+        ) else {
+            return Err(ReplyError::InvalidTimestamp);
+        };
+        if server_sent < server_received {
+            return Err(ReplyError::InvalidTimestamp);
+        }
+
+        // The times are below 2^53 microseconds, so they fit in an i64.
+        let client_sent = sent.as_micros() as i64;
+        let client_received = received.as_micros() as i64;
+        let server_received = server_received as i64;
+        let server_sent = server_sent as i64;
+        let offset = ((server_received - client_sent) + (server_sent - client_received)) / 2;
+        let delay = (client_received - client_sent) - (server_sent - server_received);
+        Ok(Self {
+            offset,
+            delay: delay.max(0) as u64,
+            at: received,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_time::Duration;
+
+    use super::*;
+
+    /// 2024-03-01T12:00:00Z, when the board started.
+    const START: u64 = 1_709_294_400_000_000;
+
+    /// A server that answers at the local time `now`, `START` after the local clock.
+    fn fake_server(request: &[u8], now: Instant) -> Packet {
+        let request = Packet::decode(request).unwrap();
+        let time = NtpTimestamp::from_unix_micros(START + now.as_micros());
+        let mut reply = Packet::request(time);
+        reply.mode = Mode::Server;
+        reply.stratum = 1;
+        reply.reference_id = *b"GPS\0";
+        reply.origin = request.transmit;
+        reply.receive = time;
+        Packet::decode(&reply.encode()).unwrap()
+    }
+
+    /// A request sent 10 minutes after the start, answered after 20 ms.
+    fn exchange() -> (Packet, Instant) {
+        let sent = Instant::from_secs(600);
+        let request = Packet::request(local_timestamp(sent)).encode();
+        (
+            fake_server(&request, sent + Duration::from_millis(20)),
+            sent,
+        )
+    }
+
+    #[test]
+    fn unix_micros_round_trip() {
+        for micros in [0, 1, 999_999, 1_500_000, START, 4_000_000_000_000_000] {
+            let timestamp = NtpTimestamp::from_unix_micros(micros);
+            assert_eq!(timestamp.to_unix_micros(), Some(micros));
+        }
+    }
+
+    #[test]
+    fn unix_micros_of_the_eras() {
+        let epoch = NtpTimestamp {
+            seconds: UNIX_EPOCH as u32,
+            fraction: 0,
+        };
+        assert_eq!(epoch.to_unix_micros(), Some(0));
+        let half = NtpTimestamp {
+            seconds: UNIX_EPOCH as u32,
+            fraction: 0x8000_0000,
+        };
+        assert_eq!(half.to_unix_micros(), Some(500_000));
+        // 2036-02-07T06:28:16Z, the first second of the next era
+        assert_eq!(
+            NtpTimestamp::ZERO.to_unix_micros(),
+            Some(((1 << 32) - UNIX_EPOCH) * MICROS_PER_SECOND)
+        );
+        // 2104, the end of the next era
+        let last = NtpTimestamp {
+            seconds: 0x7fff_ffff,
+            fraction: 0,
+        };
+        assert_eq!(
+            last.to_unix_micros(),
+            Some(((1 << 32) + 0x7fff_ffff - UNIX_EPOCH) * MICROS_PER_SECOND)
+        );
+    }
+
+    #[test]
+    fn timestamps_before_1970_have_no_unix_time() {
+        for seconds in [0x8000_0000, UNIX_EPOCH as u32 - 1] {
+            let timestamp = NtpTimestamp {
+                seconds,
+                fraction: 0,
+            };
+            assert_eq!(timestamp.to_unix_micros(), None);
+        }
+    }
+
+    #[test]
+    fn sample_of_a_reply() {
+        let (reply, sent) = exchange();
+        let received = sent + Duration::from_millis(40);
+        let sample = Sample::new(&reply, sent, received).unwrap();
+        assert_eq!(sample.offset, START as i64);
+        assert_eq!(sample.delay, 40_000);
+        assert_eq!(sample.at, received);
+    }
+
+    #[test]
+    fn time_spent_in_the_server_is_not_delay() {
+        let (mut reply, sent) = exchange();
+        reply.transmit =
+            NtpTimestamp::from_unix_micros(reply.transmit.to_unix_micros().unwrap() + 5_000);
+        let sample = Sample::new(&reply, sent, sent + Duration::from_millis(45)).unwrap();
+        assert_eq!(sample.offset, START as i64);
+        assert_eq!(sample.delay, 40_000);
+    }
+
+    #[test]
+    fn reply_to_an_older_request() {
+        let (reply, sent) = exchange();
+        let older = sent - Duration::from_secs(1);
+        assert_eq!(
+            Sample::new(&reply, older, sent + Duration::from_millis(40)),
+            Err(ReplyError::WrongOrigin)
+        );
+    }
+
+    #[test]
+    fn rejected_replies() {
+        let (reply, sent) = exchange();
+        let received = sent + Duration::from_millis(40);
+        let check = |change: fn(&mut Packet), error| {
+            let mut reply = reply;
+            change(&mut reply);
+            assert_eq!(Sample::new(&reply, sent, received), Err(error));
+        };
+        check(|reply| reply.mode = Mode::Client, ReplyError::NotServer);
+        check(
+            |reply| {
+                reply.stratum = 0;
+                reply.reference_id = *b"RATE";
+            },
+            ReplyError::KissOfDeath(*b"RATE"),
+        );
+        check(
+            |reply| reply.leap = LEAP_UNSYNCHRONIZED,
+            ReplyError::Unsynchronized,
+        );
+        check(|reply| reply.stratum = 16, ReplyError::Unsynchronized);
+        check(
+            |reply| reply.transmit = NtpTimestamp::ZERO,
+            ReplyError::Unsynchronized,
+        );
+    }
+
+    #[test]
+    fn replies_before_1970_are_invalid() {
+        let (reply, sent) = exchange();
+        let received = sent + Duration::from_millis(40);
+        let in_1968 = NtpTimestamp {
+            seconds: 0x8000_0000,
+            fraction: 0,
+        };
+
+        let mut before = reply;
+        before.transmit = in_1968;
+        assert_eq!(
+            Sample::new(&before, sent, received),
+            Err(ReplyError::InvalidTimestamp)
+        );
+        let mut before = reply;
+        before.receive = in_1968;
+        assert_eq!(
+            Sample::new(&before, sent, received),
+            Err(ReplyError::InvalidTimestamp)
+        );
+    }
+
+    #[test]
+    fn reply_sent_before_the_request_arrived_is_invalid() {
+        let (mut reply, sent) = exchange();
+        reply.receive =
+            NtpTimestamp::from_unix_micros(reply.transmit.to_unix_micros().unwrap() + 1);
+        assert_eq!(
+            Sample::new(&reply, sent, sent + Duration::from_millis(40)),
+            Err(ReplyError::InvalidTimestamp)
+        );
+    }
+}
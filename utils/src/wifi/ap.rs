@@ -0,0 +1,498 @@
+//! Access point mode: WPA2 or open network started by the Pico W, and tracking of the
+//! stations (phones, laptops) connected to it.
+//!
+//! The CYW43439 reports the stations that associate and leave with events, but the
+//! `cyw43` driver does not make them available to the application. The stations are
+//! detected by the [`dhcp`](super::dhcp) server instead: a station joins when it gets
+//! a lease and leaves when it releases it or when the lease expires. The count of
+//! stations is therefore a count of leases: most phones leave without releasing their
+//! address, so they are only seen leaving when the lease expires, up to
+//! [`DEFAULT_LEASE_TIME`](super::dhcp::DEFAULT_LEASE_TIME) (two minutes) after they
+//! are gone. Any other source can [`report`] the [`StationEvent`]s it detects.
+
+use heapless::{String, Vec};
+
+/// Maximum number of stations tracked by the access point, one per address of the
+/// DHCP pool.
+pub const MAX_STATIONS: usize = 16;
+
+/// Error returned by [`ApConfig`] for values the chip does not accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApConfigError {
+    /// The SSID is empty or longer than 32 bytes.
+    Ssid,
+    /// A WPA2 passphrase has 8 to 63 characters.
+    Passphrase,
+    /// The 2.4 GHz channels are 1 to 13.
+    Channel,
+}
+
+/// Network started by the access point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApConfig {
+    pub ssid: String<32>,
+    /// The WPA2 passphrase, empty for an open network.
+    pub passphrase: String<63>,
+    pub channel: u8,
+}
+
+impl ApConfig {
+    /// A WPA2 network.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use utils::wifi::ap::{ApConfig, ApConfigError};
+    ///
+    /// assert!(ApConfig::wpa2("Pico", "password", 5).is_ok());
+    /// assert_eq!(ApConfig::wpa2("Pico", "short", 5), Err(ApConfigError::Passphrase));
+    /// assert_eq!(ApConfig::wpa2("Pico", "password", 14), Err(ApConfigError::Channel));
+    /// ```
+    pub fn wpa2(ssid: &str, passphrase: &str, channel: u8) -> Result<Self, ApConfigError> {
+        if !(8..=63).contains(&passphrase.len()) {
+            return Err(ApConfigError::Passphrase);
+        }
+        Self::new(ssid, passphrase, channel)
+    }
+
+    /// An open network, without passphrase.
+    pub fn open(ssid: &str, channel: u8) -> Result<Self, ApConfigError> {
+        Self::new(ssid, "", channel)
+    }
+
+    fn new(ssid: &str, passphrase: &str, channel: u8) -> Result<Self, ApConfigError> {
+        if !(1..=13).contains(&channel) {
+            return Err(ApConfigError::Channel);
+        }
+        Ok(Self {
+            ssid: String::try_from(ssid)
+                .ok()
+                .filter(|ssid| !ssid.is_empty())
+                .ok_or(ApConfigError::Ssid)?,
+            passphrase: String::try_from(passphrase).map_err(|_| ApConfigError::Passphrase)?,
+            channel,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.passphrase.is_empty()
+    }
+}
+
+/// A station joined or left the access point, identified by its MAC address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StationEvent {
+    Joined([u8; 6]),
+    Left([u8; 6]),
+}
+
+/// A change of the connected stations, returned by [`Stations::apply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StationChange {
+    pub event: StationEvent,
+    /// Number of stations after the change, that is of DHCP leases, see the
+    /// [module documentation](self).
+    pub count: usize,
+}
+
+impl StationChange {
+    /// Returns `true` if the first station joined.
+    pub fn is_first_joined(&self) -> bool {
+        matches!(self.event, StationEvent::Joined(_)) && self.count == 1
+    }
+
+    /// Returns `true` if the last station left.
+    pub fn is_last_left(&self) -> bool {
+        matches!(self.event, StationEvent::Left(_)) && self.count == 0
+    }
+}
+
+/// The stations connected to the access point, up to `N`.
+///
+/// # Example
+///
+/// ```
+/// use utils::wifi::ap::{StationEvent, Stations};
+///
+/// let phone = [2, 0, 0, 0, 0, 1];
+/// let mut stations = Stations::<4>::new();
+///
+/// let change = stations.apply(StationEvent::Joined(phone)).unwrap();
+/// assert!(change.is_first_joined());
+/// // Joining again does not change anything.
+/// assert_eq!(stations.apply(StationEvent::Joined(phone)), None);
+///
+/// let change = stations.apply(StationEvent::Left(phone)).unwrap();
+/// assert!(change.is_last_left());
+/// assert_eq!(stations.count(), 0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Stations<const N: usize> {
+    stations: Vec<[u8; 6], N>,
+}
+
+impl<const N: usize> Stations<N> {
+    pub const fn new() -> Self {
+        Self {
+            stations: Vec::new(),
+        }
+    }
+
+    /// Updates the stations. Returns the change, or `None` if the station already
+    /// joined, already left, or if `N` stations are already tracked.
+    pub fn apply(&mut self, event: StationEvent) -> Option<StationChange> {
+        match event {
+            StationEvent::Joined(mac) => {
+                if self.contains(mac) {
+                    return None;
+                }
+                self.stations.push(mac).ok()?;
+            }
+            StationEvent::Left(mac) => {
+                let index = self.stations.iter().position(|known| *known == mac)?;
+                self.stations.swap_remove(index);
+            }
+        }
+        Some(StationChange {
+            event,
+            count: self.count(),
+        })
+    }
+
+    /// Brings the stations in line with the `present` ones and calls `on_change` for
+    /// every station that joined or left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use utils::wifi::ap::{StationEvent, Stations};
+    ///
+    /// let laptop = [2, 0, 0, 0, 0, 1];
+    /// let phone = [2, 0, 0, 0, 0, 2];
+    /// let mut stations = Stations::<4>::new();
+    /// stations.sync([laptop], |_| {});
+    ///
+    /// let mut events = Vec::new();
+    /// stations.sync([phone], |change| events.push(change.event));
+    /// assert_eq!(events, [StationEvent::Left(laptop), StationEvent::Joined(phone)]);
+    /// ```
+    pub fn sync<I>(&mut self, present: I, mut on_change: impl FnMut(StationChange))
+    where
+        I: IntoIterator<Item = [u8; 6]> + Clone,
+    {
+        let mut index = 0;
+        while let Some(&mac) = self.stations.get(index) {
+            if present.clone().into_iter().any(|other| other == mac) {
+                index += 1;
+            } else if let Some(change) = self.apply(StationEvent::Left(mac)) {
+                on_change(change);
+            }
+        }
+        for mac in present {
+            if let Some(change) = self.apply(StationEvent::Joined(mac)) {
+                on_change(change);
+            }
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.stations.len()
+    }
+
+    pub fn contains(&self, mac: [u8; 6]) -> bool {
+        self.stations.contains(&mac)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8; 6]> {
+        self.stations.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    const A: [u8; 6] = [2, 0, 0, 0, 0, 1];
+    const B: [u8; 6] = [2, 0, 0, 0, 0, 2];
+    const C: [u8; 6] = [2, 0, 0, 0, 0, 3];
+    const D: [u8; 6] = [2, 0, 0, 0, 0, 4];
+
+    fn stations<const N: usize>(macs: &[[u8; 6]]) -> Stations<N> {
+        let mut stations = Stations::new();
+        for &mac in macs {
+            stations.apply(StationEvent::Joined(mac)).unwrap();
+        }
+        stations
+    }
+
+    fn sync<const N: usize>(
+        stations: &mut Stations<N>,
+        present: &[[u8; 6]],
+    ) -> Vec<(StationEvent, usize)> {
+        let mut changes = Vec::new();
+        stations.sync(present.iter().copied(), |change| {
+            changes.push((change.event, change.count))
+        });
+        changes
+    }
+
+    #[test]
+    fn first_joined_and_last_left() {
+        let mut stations = Stations::<4>::new();
+        let first = stations.apply(StationEvent::Joined(A)).unwrap();
+        assert!(first.is_first_joined() && !first.is_last_left());
+        let second = stations.apply(StationEvent::Joined(B)).unwrap();
+        assert!(!second.is_first_joined());
+        assert_eq!(second.count, 2);
+        assert!(!stations
+            .apply(StationEvent::Left(A))
+            .unwrap()
+            .is_last_left());
+        assert!(stations
+            .apply(StationEvent::Left(B))
+            .unwrap()
+            .is_last_left());
+    }
+
+    #[test]
+    fn leaving_twice_or_unknown_station_changes_nothing() {
+        let mut stations = stations::<4>(&[A]);
+        assert_eq!(stations.apply(StationEvent::Left(B)), None);
+        assert!(stations.apply(StationEvent::Left(A)).is_some());
+        assert_eq!(stations.apply(StationEvent::Left(A)), None);
+        assert_eq!(stations.count(), 0);
+    }
+
+    #[test]
+    fn full_list_rejects_joins() {
+        let mut stations = stations::<2>(&[A, B]);
+        assert_eq!(stations.apply(StationEvent::Joined(C)), None);
+        assert_eq!(stations.count(), 2);
+        assert!(!stations.contains(C));
+
+        // There is room again once a station left.
+        stations.apply(StationEvent::Left(A)).unwrap();
+        let change = stations.apply(StationEvent::Joined(C)).unwrap();
+        assert_eq!(change.count, 2);
+        assert!(stations.contains(B) && stations.contains(C));
+    }
+
+    #[test]
+    fn sync_removes_and_adds_in_one_pass() {
+        let mut stations = stations::<4>(&[A, B, C]);
+        let changes = sync(&mut stations, &[B, D]);
+        assert_eq!(
+            changes,
+            [
+                (StationEvent::Left(A), 2),
+                (StationEvent::Left(C), 1),
+                (StationEvent::Joined(D), 2),
+            ]
+        );
+        assert!(stations.iter().eq(&[B, D]));
+    }
+
+    #[test]
+    fn sync_removes_before_adding_to_a_full_list() {
+        let mut stations = stations::<2>(&[A, B]);
+        let changes = sync(&mut stations, &[C, D]);
+        assert_eq!(
+            changes,
+            [
+                (StationEvent::Left(A), 1),
+                (StationEvent::Left(B), 0),
+                (StationEvent::Joined(C), 1),
+                (StationEvent::Joined(D), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn sync_with_the_same_stations_changes_nothing() {
+        let mut stations = stations::<4>(&[A, B]);
+        assert!(sync(&mut stations, &[B, A]).is_empty());
+        assert_eq!(stations.count(), 2);
+    }
+
+    #[test]
+    fn sync_ignores_duplicate_stations() {
+        let mut stations = stations::<4>(&[A]);
+        let changes = sync(&mut stations, &[A, B, A, B]);
+        assert_eq!(changes, [(StationEvent::Joined(B), 2)]);
+        assert!(sync(&mut stations, &[B, B]) == [(StationEvent::Left(A), 1)]);
+        assert!(stations.iter().eq(&[B]));
+    }
+
+    #[test]
+    fn sync_tracks_at_most_n_stations() {
+        let mut stations = Stations::<2>::new();
+        let changes = sync(&mut stations, &[A, B, C]);
+        assert_eq!(
+            changes,
+            [(StationEvent::Joined(A), 1), (StationEvent::Joined(B), 2)]
+        );
+        assert!(!stations.contains(C));
+    }
+}
+
+#[cfg(feature = "rp2040")]
+pub use rp2040::{
+    report, start, station_count, station_led_task, stations, StationMonitor, MAX_MONITORS,
+    STATION_CHANGES,
+};
+
+#[cfg(feature = "rp2040")]
+mod rp2040 {
+    use core::cell::RefCell;
+
+    use cyw43::Control;
+    use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+    use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+    use embassy_sync::pubsub::{PubSubChannel, Subscriber, WaitResult};
+    use log::warn;
+
+    use super::{ApConfig, StationChange, StationEvent, Stations, MAX_STATIONS};
+    use crate::wifi::SharedControl;
+
+    /// Maximum number of [`StationMonitor`]s that can exist at once.
+    pub const MAX_MONITORS: usize = 4;
+
+    /// Number of changes kept for a [`StationMonitor`] that does not keep up.
+    const CHANGES_LEN: usize = 8;
+
+    type Changes =
+        PubSubChannel<CriticalSectionRawMutex, StationChange, CHANGES_LEN, MAX_MONITORS, 0>;
+
+    /// Every change of the stations, received by all the [`StationMonitor`]s.
+    pub static STATION_CHANGES: Changes = PubSubChannel::new();
+
+    /// The stations connected to the access point.
+    static STATIONS: BlockingMutex<CriticalSectionRawMutex, RefCell<Stations<MAX_STATIONS>>> =
+        BlockingMutex::new(RefCell::new(Stations::new()));
+
+    /// Records that a station joined or left, and publishes the change on
+    /// [`STATION_CHANGES`]. Called by the
+    /// [`dhcp_server_task`](crate::wifi::dhcp::dhcp_server_task).
+    ///
+    /// Returns the change, or `None` if the station already joined or already left.
+    pub fn report(event: StationEvent) -> Option<StationChange> {
+        let change = STATIONS.lock(|stations| {
+            let mut stations = stations.borrow_mut();
+            let change = stations.apply(event);
+            if let StationEvent::Joined(mac) = event {
+                if change.is_none() && !stations.contains(mac) {
+                    warn!(
+                        "More than {} stations, {:02x?} is not tracked",
+                        MAX_STATIONS, mac
+                    );
+                }
+            }
+            change
+        })?;
+        STATION_CHANGES
+            .immediate_publisher()
+            .publish_immediate(change);
+        Some(change)
+    }
+
+    /// Returns the number of stations connected to the access point.
+    ///
+    /// A station that leaves without releasing its address is counted until its DHCP
+    /// lease expires.
+    pub fn station_count() -> usize {
+        STATIONS.lock(|stations| stations.borrow().count())
+    }
+
+    /// Returns the stations connected to the access point, see [`station_count`].
+    pub fn stations() -> Stations<MAX_STATIONS> {
+        STATIONS.lock(|stations| stations.borrow().clone())
+    }
+
+    /// Starts the access point.
+    ///
+    /// The network stack needs a static IPv4 configuration, and a
+    /// [`dhcp_server_task`](crate::wifi::dhcp::dhcp_server_task) to give addresses to
+    /// the stations.
+    pub async fn start(control: &mut Control<'_>, config: &ApConfig) {
+        if config.is_open() {
+            control.start_ap_open(&config.ssid, config.channel).await;
+        } else {
+            control
+                .start_ap_wpa2(&config.ssid, &config.passphrase, config.channel)
+                .await;
+        }
+    }
+
+    /// Receives the changes of the stations from [`STATION_CHANGES`].
+    ///
+    /// Every monitor receives all the changes, up to [`MAX_MONITORS`] monitors can run
+    /// at once, for example a [`station_led_task`] and a task of the application. A
+    /// station that leaves without releasing its address is only reported when its DHCP
+    /// lease expires.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut monitor = StationMonitor::new().unwrap();
+    /// loop {
+    ///     let change = monitor.next_change().await;
+    ///     info!("{} stations", change.count);
+    ///     if change.is_last_left() {
+    ///         control.lock().await.gpio_set(0, false).await;
+    ///     }
+    /// }
+    /// ```
+    pub struct StationMonitor {
+        changes: Subscriber<
+            'static,
+            CriticalSectionRawMutex,
+            StationChange,
+            CHANGES_LEN,
+            MAX_MONITORS,
+            0,
+        >,
+    }
+
+    impl StationMonitor {
+        /// Returns `None` if [`MAX_MONITORS`] monitors already exist.
+        pub fn new() -> Option<Self> {
+            Some(Self {
+                changes: STATION_CHANGES.subscriber().ok()?,
+            })
+        }
+
+        /// Waits until a station joins or leaves.
+        ///
+        /// A monitor that falls behind by more than 8 changes misses the oldest ones,
+        /// the `count` of the next change is still the current one.
+        pub async fn next_change(&mut self) -> StationChange {
+            loop {
+                match self.changes.next_message().await {
+                    WaitResult::Message(change) => return change,
+                    WaitResult::Lagged(missed) => warn!("{} station changes missed", missed),
+                }
+            }
+        }
+    }
+
+    /// Turns on the LED of the Pico W when the first station joins, and turns it off
+    /// when the last one leaves.
+    ///
+    /// Uses one of the [`MAX_MONITORS`] monitors.
+    #[embassy_executor::task]
+    pub async fn station_led_task(control: &'static SharedControl) -> ! {
+        let mut monitor = StationMonitor::new().expect("too many station monitors");
+        loop {
+            let change = monitor.next_change().await;
+            if change.is_first_joined() {
+                control.lock().await.gpio_set(0, true).await;
+            } else if change.is_last_left() {
+                control.lock().await.gpio_set(0, false).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Exponential backoff between two connection attempts.
+
+use embassy_time::Duration;
+
+/// Exponential backoff between two attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    /// Creates a backoff that starts at `min` and doubles up to `max`.
+    ///
+    /// If `min` is larger than `max`, every delay is `max`.
+    pub const fn new(min: Duration, max: Duration) -> Self {
+        let min = if min.as_ticks() > max.as_ticks() {
+            max
+        } else {
+            min
+        };
+        Self {
+            min,
+            max,
+            next: min,
+        }
+    }
+
+    /// Returns the delay before the next attempt and doubles the one after it.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next =
+            Duration::from_ticks(delay.as_ticks().saturating_mul(2).min(self.max.as_ticks()));
+        delay
+    }
+
+    /// Starts again from the minimum delay, after a successful attempt.
+    pub fn reset(&mut self) {
+        self.next = self.min;
+    }
+}
+
+impl Default for Backoff {
+    /// From 1 second up to 1 minute.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delays(backoff: &mut Backoff, count: usize) -> [u64; 8] {
+        let mut delays = [0; 8];
+        for delay in delays.iter_mut().take(count) {
+            *delay = backoff.next_delay().as_secs();
+        }
+        delays
+    }
+
+    #[test]
+    fn delay_doubles_up_to_max() {
+        let mut backoff = Backoff::default();
+        assert_eq!(delays(&mut backoff, 8), [1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+
+    #[test]
+    fn max_that_is_not_a_power_of_two_of_min() {
+        let mut backoff = Backoff::new(Duration::from_secs(3), Duration::from_secs(10));
+        assert_eq!(delays(&mut backoff, 4), [3, 6, 10, 10, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn reset_starts_again_from_min() {
+        let mut backoff = Backoff::default();
+        delays(&mut backoff, 5);
+        backoff.reset();
+        assert_eq!(delays(&mut backoff, 3), [1, 2, 4, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn min_larger_than_max_gives_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(30), Duration::from_secs(5));
+        assert_eq!(delays(&mut backoff, 3), [5, 5, 5, 0, 0, 0, 0, 0]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn equal_min_and_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(2), Duration::from_secs(2));
+        assert_eq!(delays(&mut backoff, 3), [2, 2, 2, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn doubling_does_not_overflow() {
+        let mut backoff = Backoff::new(Duration::from_ticks(u64::MAX / 2 + 1), Duration::MAX);
+        backoff.next_delay();
+        assert_eq!(backoff.next_delay(), Duration::MAX);
+    }
+}
@@ -0,0 +1,140 @@
+//! Bring-up of the CYW43439 chip: SPI bus, firmware and runner task.
+
+use cyw43::{Control, NetDriver, PowerManagementMode, Runner};
+use cyw43_pio::PioSpi;
+use embassy_executor::{SpawnError, Spawner};
+use embassy_rp::gpio::{Level, Output};
+use embassy_rp::interrupt::typelevel::{Binding, PIO0_IRQ_0};
+use embassy_rp::peripherals::{DMA_CH0, PIN_23, PIN_24, PIN_25, PIN_29, PIO0};
+use embassy_rp::pio::{InterruptHandler, Pio};
+use static_cell::StaticCell;
+
+/// Firmware of the CYW43439 chip.
+pub static FIRMWARE: &[u8] = include_bytes!(concat!(env!("CYW43_FIRMWARE_DIR"), "/43439A0.bin"));
+
+/// Country Locale Matrix of the CYW43439 chip, loaded by [`Control::init`].
+pub static CLM: &[u8] = include_bytes!(concat!(env!("CYW43_FIRMWARE_DIR"), "/43439A0_clm.bin"));
+
+/// The SPI bus between the RP2040 and the WiFi chip.
+pub type WifiSpi = PioSpi<'static, PIO0, 0, DMA_CH0>;
+
+/// The runner that drives the WiFi chip, see [`runner_task`].
+pub type WifiRunner = Runner<'static, Output<'static>, WifiSpi>;
+
+/// The pins that connect the WiFi chip to the RP2040 on the Raspberry Pi Pico W.
+pub struct WifiPins {
+    /// Power on.
+    pub pwr: PIN_23,
+    /// SPI data.
+    pub dio: PIN_24,
+    /// SPI chip select.
+    pub cs: PIN_25,
+    /// SPI clock.
+    pub clk: PIN_29,
+}
+
+/// Sets up the SPI bus of the WiFi chip and loads its [`FIRMWARE`].
+///
+/// Returns the network device, the control handle and the runner. The runner has to
+/// be running, for example with [`runner_task`], before the control handle can be used,
+/// starting with `control.init(CLM)`. [`init`] does all of this.
+///
+/// # Panics
+///
+/// If invoked more than once.
+pub async fn new(
+    pins: WifiPins,
+    pio: PIO0,
+    dma: DMA_CH0,
+    irqs: impl Binding<PIO0_IRQ_0, InterruptHandler<PIO0>>,
+) -> (NetDriver<'static>, Control<'static>, WifiRunner) {
+    let pwr = Output::new(pins.pwr, Level::Low);
+    let cs = Output::new(pins.cs, Level::High);
+    let mut pio = Pio::new(pio, irqs);
+    let spi = PioSpi::new(
+        &mut pio.common,
+        pio.sm0,
+        pio.irq0,
+        cs,
+        pins.dio,
+        pins.clk,
+        dma,
+    );
+
+    static STATE: StaticCell<cyw43::State> = StaticCell::new();
+    let state = STATE.init(cyw43::State::new());
+
+    cyw43::new(state, pwr, spi, FIRMWARE).await
+}
+
+/// Drives the WiFi chip. Spawned by [`init`].
+#[embassy_executor::task]
+pub async fn runner_task(runner: WifiRunner) -> ! {
+    runner.run().await
+}
+
+/// Initializes the WiFi chip: sets up its SPI bus, loads the firmware, spawns
+/// [`runner_task`], loads the [`CLM`] and sets the power management mode.
+///
+/// Returns the network device, to be given to the `embassy_net` stack, and the control
+/// handle, used to join or start a network.
+///
+/// # Panics
+///
+/// If invoked more than once.
+///
+/// # Example
+///
+/// ```ignore
+/// use embassy_executor::Spawner;
+/// use embassy_rp::bind_interrupts;
+/// use embassy_rp::peripherals::PIO0;
+/// use embassy_rp::pio::InterruptHandler;
+/// use utils::wifi::WifiPins;
+///
+/// // Bind interrupts to their handlers.
+/// bind_interrupts!(struct Irqs {
+///    PIO0_IRQ_0 => InterruptHandler<PIO0>;
+/// });
+///
+/// #[embassy_executor::main]
+/// async fn main(spawner: Spawner) {
+///     let peripherals = embassy_rp::init(Default::default());
+///
+///     let pins = WifiPins {
+///         pwr: peripherals.PIN_23,
+///         dio: peripherals.PIN_24,
+///         cs: peripherals.PIN_25,
+///         clk: peripherals.PIN_29,
+///     };
+///     let (net_device, mut control) = utils::wifi::init(
+///         spawner,
+///         pins,
+///         peripherals.PIO0,
+///         peripherals.DMA_CH0,
+///         Irqs,
+///         cyw43::PowerManagementMode::PowerSave,
+///     )
+///     .await
+///     .unwrap();
+///
+///     // Turn on the LED of the Pico W.
+///     control.gpio_set(0, true).await;
+/// }
+/// ```
+pub async fn init(
+    spawner: Spawner,
+    pins: WifiPins,
+    pio: PIO0,
+    dma: DMA_CH0,
+    irqs: impl Binding<PIO0_IRQ_0, InterruptHandler<PIO0>>,
+    power_management: PowerManagementMode,
+) -> Result<(NetDriver<'static>, Control<'static>), SpawnError> {
+    let (net_device, mut control, runner) = new(pins, pio, dma, irqs).await;
+    spawner.spawn(runner_task(runner))?;
+
+    control.init(CLM).await;
+    control.set_power_management(power_management).await;
+
+    Ok((net_device, control))
+}
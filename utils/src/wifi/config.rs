@@ -0,0 +1,268 @@
+//! Network configuration stored in a reserved flash sector.
+//!
+//! A [`NetworkConfig`] holds the credentials of the access point and the IPv4
+//! configuration, DHCP or static. [`ConfigStore`] keeps it in the last 4 KiB sector
+//! of the flash, which `memory.x` leaves out of the `FLASH` region, so flashing a new
+//! program does not overwrite it. The format of the record is described in
+//! [`record`](super::record). It is written with the `provision` binary of
+//! `lab08_ex1_2`, see [`provision`](super::provision).
+//!
+//! Boards that have never been provisioned, and CI builds, use
+//! [`NetworkConfig::fallback`], which is read from environment variables at
+//! compile time.
+
+use core::str::FromStr;
+
+use embassy_net::{ConfigV4, DhcpConfig, Ipv4Address, Ipv4Cidr, StaticConfigV4};
+use embedded_storage::nor_flash::NorFlash;
+use heapless::Vec;
+use log::{info, warn};
+
+use super::record::{Ipv4Record, Record, StaticIpv4};
+pub use super::record::{RecordError, RECORD_SIZE};
+use super::Credentials;
+
+/// Size of the flash sector that holds the configuration.
+pub const CONFIG_SECTOR_SIZE: u32 = 4096;
+
+/// Offset of the configuration sector from the start of the flash: the last sector
+/// of the 2 MiB flash of the Raspberry Pi Pico W.
+pub const CONFIG_OFFSET: u32 = 2048 * 1024 - CONFIG_SECTOR_SIZE;
+
+/// The credentials and the IPv4 configuration used to connect to the network.
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    pub credentials: Credentials,
+    pub ipv4: ConfigV4,
+}
+
+impl NetworkConfig {
+    /// The configuration given at compile time, used when nothing is stored in flash.
+    ///
+    /// | Variable | Value | Default |
+    /// |---|---|---|
+    /// | `WIFI_NETWORK` | SSID | `TEST` |
+    /// | `WIFI_PASSWORD` | WPA2 passphrase, empty for an open network | `test` |
+    /// | `WIFI_IPV4` | `dhcp` or a static configuration, see [`parse_ipv4`] | `dhcp` |
+    ///
+    /// ```shell
+    /// WIFI_NETWORK=lab WIFI_PASSWORD=secret WIFI_IPV4="192.168.1.50/24 gw 192.168.1.1" cargo build
+    /// ```
+    ///
+    /// An invalid `WIFI_IPV4` value is logged and replaced by DHCP.
+    pub fn fallback() -> Self {
+        let ssid = option_env!("WIFI_NETWORK").unwrap_or("TEST");
+        let passphrase = option_env!("WIFI_PASSWORD").unwrap_or("test");
+        let ipv4 = option_env!("WIFI_IPV4").unwrap_or("dhcp");
+
+        Self {
+            credentials: Credentials::wpa2(ssid, passphrase),
+            ipv4: parse_ipv4(ipv4).unwrap_or_else(|err| {
+                warn!("invalid WIFI_IPV4 {:?}: {}, using DHCP", ipv4, err);
+                ConfigV4::Dhcp(DhcpConfig::default())
+            }),
+        }
+    }
+
+    /// Encodes the configuration into `buf` and returns the length of the record,
+    /// see [`Record`].
+    pub fn encode(&self, buf: &mut [u8; RECORD_SIZE]) -> usize {
+        Record::from(self).encode(buf)
+    }
+
+    /// Decodes a record written by [`NetworkConfig::encode`].
+    pub fn decode(record: &[u8]) -> Result<Self, RecordError> {
+        Record::decode(record).map(Self::from)
+    }
+}
+
+impl From<&NetworkConfig> for Record {
+    fn from(config: &NetworkConfig) -> Self {
+        let ipv4 = match &config.ipv4 {
+            ConfigV4::None => Ipv4Record::None,
+            ConfigV4::Dhcp(_) => Ipv4Record::Dhcp,
+            ConfigV4::Static(config) => Ipv4Record::Static(StaticIpv4 {
+                address: config.address.address().0,
+                prefix_len: config.address.prefix_len(),
+                gateway: config.gateway.map(|gateway| gateway.0),
+                dns_servers: config.dns_servers.iter().map(|dns| dns.0).collect(),
+            }),
+        };
+        Self {
+            ssid: config.credentials.ssid.clone(),
+            passphrase: config.credentials.passphrase.clone(),
+            ipv4,
+        }
+    }
+}
+
+impl From<Record> for NetworkConfig {
+    fn from(record: Record) -> Self {
+        let ipv4 = match record.ipv4 {
+            Ipv4Record::None => ConfigV4::None,
+            Ipv4Record::Dhcp => ConfigV4::Dhcp(DhcpConfig::default()),
+            Ipv4Record::Static(config) => ConfigV4::Static(StaticConfigV4 {
+                address: Ipv4Cidr::new(Ipv4Address(config.address), config.prefix_len),
+                gateway: config.gateway.map(Ipv4Address),
+                dns_servers: config.dns_servers.into_iter().map(Ipv4Address).collect(),
+            }),
+        };
+        Self {
+            credentials: Credentials {
+                ssid: record.ssid,
+                passphrase: record.passphrase,
+            },
+            ipv4,
+        }
+    }
+}
+
+/// Parses an IPv4 configuration: `dhcp`, `none`, or a static configuration like
+/// `192.168.1.50/24 gw 192.168.1.1 dns 192.168.1.1 dns 8.8.8.8`, where the gateway
+/// and the DNS servers are optional.
+///
+/// # Example
+///
+/// ```
+/// use embassy_net::{ConfigV4, Ipv4Address};
+/// use utils::wifi::config::parse_ipv4;
+///
+/// let ConfigV4::Static(config) = parse_ipv4("192.168.1.50/24 gw 192.168.1.1").unwrap() else {
+///     panic!("not a static configuration");
+/// };
+/// assert_eq!(config.address.prefix_len(), 24);
+/// assert_eq!(config.gateway, Some(Ipv4Address::new(192, 168, 1, 1)));
+/// assert!(config.dns_servers.is_empty());
+///
+/// assert!(parse_ipv4("192.168.1.50").is_err());
+/// ```
+pub fn parse_ipv4(value: &str) -> Result<ConfigV4, &'static str> {
+    let mut words = value.split_whitespace();
+    let config = match words.next() {
+        Some("dhcp") => ConfigV4::Dhcp(DhcpConfig::default()),
+        Some("none") => ConfigV4::None,
+        Some(address) => {
+            let address =
+                Ipv4Cidr::from_str(address).map_err(|_| "expected an address like 10.0.0.2/24")?;
+            let mut config = StaticConfigV4 {
+                address,
+                gateway: None,
+                dns_servers: Vec::new(),
+            };
+            while let Some(option) = words.next() {
+                let value = parse_address(words.next().unwrap_or_default())?;
+                match option {
+                    "gw" => config.gateway = Some(value),
+                    "dns" => config
+                        .dns_servers
+                        .push(value)
+                        .map_err(|_| "too many DNS servers")?,
+                    _ => return Err("expected `gw` or `dns`"),
+                }
+            }
+            return Ok(ConfigV4::Static(config));
+        }
+        None => return Err("empty configuration"),
+    };
+
+    match words.next() {
+        Some(_) => Err("unexpected value"),
+        None => Ok(config),
+    }
+}
+
+/// Parses an IPv4 address like `192.168.1.2`, the format of the gateway and the DNS
+/// servers of [`parse_ipv4`].
+///
+/// # Example
+///
+/// ```
+/// use embassy_net::Ipv4Address;
+/// use utils::wifi::config::parse_address;
+///
+/// assert_eq!(parse_address("192.168.1.2"), Ok(Ipv4Address::new(192, 168, 1, 2)));
+/// assert!(parse_address("192.168.1").is_err());
+/// assert!(parse_address("192.168.1.2/24").is_err());
+/// ```
+pub fn parse_address(value: &str) -> Result<Ipv4Address, &'static str> {
+    Ipv4Address::from_str(value).map_err(|_| "expected an IPv4 address")
+}
+
+/// Reads and writes the [`NetworkConfig`] record in a flash sector.
+///
+/// # Example
+///
+/// ```ignore
+/// let flash = Flash::<_, Blocking, { 2048 * 1024 }>::new_blocking(peripherals.FLASH);
+/// let config = ConfigStore::new(flash).load_or_fallback();
+/// let manager = ConnectionManager::new(config.credentials, config.ipv4.clone());
+/// ```
+pub struct ConfigStore<F> {
+    flash: F,
+    offset: u32,
+}
+
+impl<F: NorFlash> ConfigStore<F> {
+    /// Uses the sector at [`CONFIG_OFFSET`].
+    pub fn new(flash: F) -> Self {
+        Self::with_offset(flash, CONFIG_OFFSET)
+    }
+
+    /// Uses the [`CONFIG_SECTOR_SIZE`] bytes at `offset`, which has to be
+    /// aligned to the erase size of the flash.
+    pub fn with_offset(flash: F, offset: u32) -> Self {
+        Self { flash, offset }
+    }
+
+    /// Reads the stored configuration.
+    ///
+    /// Returns `Ok(Err(_))` if the sector does not hold a valid record.
+    pub fn load(&mut self) -> Result<Result<NetworkConfig, RecordError>, F::Error> {
+        let mut record = [0; RECORD_SIZE];
+        self.flash.read(self.offset, &mut record)?;
+        Ok(NetworkConfig::decode(&record))
+    }
+
+    /// Reads the stored configuration, or returns [`NetworkConfig::fallback`] if
+    /// there is none.
+    pub fn load_or_fallback(&mut self) -> NetworkConfig {
+        match self.load() {
+            Ok(Ok(config)) => {
+                info!("using the network configuration stored in flash");
+                config
+            }
+            Ok(Err(RecordError::NotFound)) => {
+                info!("no network configuration in flash, using the built-in one");
+                NetworkConfig::fallback()
+            }
+            Ok(Err(err)) => {
+                warn!(
+                    "invalid network configuration in flash ({:?}), using the built-in one",
+                    err
+                );
+                NetworkConfig::fallback()
+            }
+            Err(err) => {
+                warn!(
+                    "cannot read the flash ({:?}), using the built-in network configuration",
+                    err
+                );
+                NetworkConfig::fallback()
+            }
+        }
+    }
+
+    /// Replaces the stored configuration.
+    pub fn save(&mut self, config: &NetworkConfig) -> Result<(), F::Error> {
+        let mut record = [0xff; RECORD_SIZE];
+        config.encode(&mut record);
+        self.erase()?;
+        self.flash.write(self.offset, &record)
+    }
+
+    /// Erases the stored configuration, so that the fallback is used again.
+    pub fn erase(&mut self) -> Result<(), F::Error> {
+        self.flash
+            .erase(self.offset, self.offset + CONFIG_SECTOR_SIZE)
+    }
+}
@@ -0,0 +1,80 @@
+//! The name and passphrase of the network to join.
+
+use heapless::String;
+
+use super::record::{MAX_PASSPHRASE_LEN, MAX_SSID_LEN};
+
+/// The network name and passphrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub ssid: String<MAX_SSID_LEN>,
+    /// The WPA2 passphrase, empty for open networks.
+    pub passphrase: String<MAX_PASSPHRASE_LEN>,
+}
+
+impl Credentials {
+    /// Credentials for a WPA2 network.
+    ///
+    /// The values are truncated if they are longer than [`MAX_SSID_LEN`] and
+    /// [`MAX_PASSPHRASE_LEN`], without splitting a UTF-8 character.
+    pub fn wpa2(ssid: &str, passphrase: &str) -> Self {
+        Self {
+            ssid: truncated(ssid),
+            passphrase: truncated(passphrase),
+        }
+    }
+
+    /// Credentials for an open network.
+    pub fn open(ssid: &str) -> Self {
+        Self::wpa2(ssid, "")
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.passphrase.is_empty()
+    }
+}
+
+fn truncated<const N: usize>(value: &str) -> String<N> {
+    let mut end = value.len().min(N);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    // Cannot fail, the value fits.
+    String::try_from(&value[..end]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn short_values_are_kept() {
+        let credentials = Credentials::wpa2("lab", "secret");
+        assert_eq!(credentials.ssid, "lab");
+        assert_eq!(credentials.passphrase, "secret");
+        assert!(!credentials.is_open());
+        assert!(Credentials::open("lab").is_open());
+    }
+
+    #[test]
+    fn long_values_are_truncated() {
+        let ssid = "s".repeat(MAX_SSID_LEN + 1);
+        let passphrase = "p".repeat(MAX_PASSPHRASE_LEN + 1);
+        let credentials = Credentials::wpa2(&ssid, &passphrase);
+        assert_eq!(credentials.ssid, ssid[..MAX_SSID_LEN]);
+        assert_eq!(credentials.passphrase, passphrase[..MAX_PASSPHRASE_LEN]);
+    }
+
+    #[test]
+    fn truncation_does_not_split_a_character() {
+        // "é" takes two bytes, the limit falls in the middle of the second one.
+        assert_eq!(truncated::<4>("aéé"), "aé");
+        assert_eq!(truncated::<3>("aéé"), "aé");
+        assert_eq!(truncated::<2>("aéé"), "a");
+        assert_eq!(truncated::<1>("éa"), "");
+        // A three byte character that ends exactly at the limit.
+        assert_eq!(truncated::<4>("a€b"), "a€");
+    }
+}
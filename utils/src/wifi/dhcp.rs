@@ -0,0 +1,1091 @@
+//! DHCPv4 server, so that phones and laptops get an address when they join the
+//! access point of the Pico W.
+//!
+//! [`LeaseTable`] hands out the addresses of the pool and [`DhcpServer`] answers the
+//! DHCP messages (RFC 2131). Both work on plain bytes and can be used on the host.
+//! [`dhcp_server_task`] runs the server on a UDP socket of the network stack.
+//!
+//! The server answers `DHCPDISCOVER`, `DHCPREQUEST`, `DHCPRELEASE` and `DHCPDECLINE`.
+//! Relay agents and `DHCPINFORM` are not supported.
+
+use embassy_time::{Duration, Instant};
+
+/// UDP port of the server.
+pub const SERVER_PORT: u16 = 67;
+
+/// UDP port of the clients.
+pub const CLIENT_PORT: u16 = 68;
+
+/// Size of the buffer that holds a message. Messages are at least 300 bytes long and
+/// clients accept at least 576 bytes.
+pub const MESSAGE_SIZE: usize = 576;
+
+/// How long an offered address stays reserved for a client that does not request it.
+pub const OFFER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Lease time of [`DhcpServerConfig::new`]. The stations of the access point are
+/// tracked with their leases, so a short lease makes the stations that leave without
+/// releasing their address disappear sooner, at the cost of more renewals.
+pub const DEFAULT_LEASE_TIME: Duration = Duration::from_secs(120);
+
+const OP_REQUEST: u8 = 1;
+const OP_REPLY: u8 = 2;
+const HTYPE_ETHERNET: u8 = 1;
+const FLAG_BROADCAST: u16 = 0x8000;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Length of the fixed part of a message, up to the magic cookie.
+const HEADER_LEN: usize = 236;
+/// Replies are padded to the minimal length of a BOOTP message.
+const MIN_MESSAGE_LEN: usize = 300;
+
+const OPTION_PAD: u8 = 0;
+const OPTION_SUBNET_MASK: u8 = 1;
+const OPTION_ROUTER: u8 = 3;
+const OPTION_DNS: u8 = 6;
+const OPTION_REQUESTED_IP: u8 = 50;
+const OPTION_LEASE_TIME: u8 = 51;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_RENEWAL_TIME: u8 = 58;
+const OPTION_REBINDING_TIME: u8 = 59;
+const OPTION_END: u8 = 255;
+
+/// Type of a DHCP message, option 53.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Discover = 1,
+    Offer = 2,
+    Request = 3,
+    Decline = 4,
+    Ack = 5,
+    Nak = 6,
+    Release = 7,
+    Inform = 8,
+}
+
+impl MessageType {
+    fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            1 => Self::Discover,
+            2 => Self::Offer,
+            3 => Self::Request,
+            4 => Self::Decline,
+            5 => Self::Ack,
+            6 => Self::Nak,
+            7 => Self::Release,
+            8 => Self::Inform,
+            _ => return None,
+        })
+    }
+}
+
+/// State of a [`Lease`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseState {
+    /// Offered to the client, waiting for its request.
+    Offered,
+    /// Acknowledged, the client uses the address.
+    Bound,
+    /// Declined by the client because another host uses it.
+    Declined,
+}
+
+/// An address of the pool given to a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lease {
+    /// Hardware address of the client.
+    pub mac: [u8; 6],
+    pub address: [u8; 4],
+    pub state: LeaseState,
+    pub expires: Instant,
+}
+
+/// The leases of a pool of `N` consecutive addresses.
+///
+/// Addresses that have never been leased are given first, then the expired leases
+/// are reused, starting with the oldest one.
+///
+/// # Example
+///
+/// ```
+/// use embassy_time::{Duration, Instant};
+/// use utils::wifi::dhcp::LeaseTable;
+///
+/// let laptop = [2, 0, 0, 0, 0, 1];
+/// let phone = [2, 0, 0, 0, 0, 2];
+/// let mut leases = LeaseTable::<2>::new([192, 168, 4, 100], Duration::from_secs(3600));
+/// let now = Instant::from_secs(0);
+///
+/// let address = leases.offer(laptop, None, now).unwrap();
+/// assert_eq!(address, [192, 168, 4, 100]);
+/// assert!(leases.bind(laptop, address, now));
+///
+/// // The phone asks for the address of the laptop and gets the next one.
+/// assert_eq!(leases.offer(phone, Some(address), now), Some([192, 168, 4, 101]));
+/// assert!(!leases.bind(phone, address, now));
+/// assert!(leases.bind(phone, [192, 168, 4, 101], now));
+///
+/// // The pool is full until the lease of the laptop expires.
+/// let other = [2, 0, 0, 0, 0, 3];
+/// assert_eq!(leases.offer(other, None, now), None);
+/// let later = now + Duration::from_secs(3600);
+/// assert_eq!(leases.offer(other, None, later), Some([192, 168, 4, 100]));
+/// ```
+pub struct LeaseTable<const N: usize> {
+    first: [u8; 4],
+    lease_time: Duration,
+    slots: [Option<Lease>; N],
+    /// Number of slots used, see [`LeaseTable::capacity`].
+    len: usize,
+}
+
+impl<const N: usize> LeaseTable<N> {
+    /// Creates an empty table for the addresses starting at `first`, which are leased
+    /// for `lease_time`.
+    ///
+    /// The pool does not cross the last byte of the address: if `first[3] + N` is above
+    /// 255, only the addresses up to `.254` are leased, see [`LeaseTable::capacity`].
+    pub const fn new(first: [u8; 4], lease_time: Duration) -> Self {
+        let room = 255 - first[3] as usize;
+        Self {
+            first,
+            lease_time,
+            slots: [None; N],
+            len: if room < N { room } else { N },
+        }
+    }
+
+    /// Returns the number of addresses of the pool, `N` unless the pool was clamped.
+    pub fn capacity(&self) -> usize {
+        self.len
+    }
+
+    /// How long an address is leased for.
+    pub fn lease_time(&self) -> Duration {
+        self.lease_time
+    }
+
+    /// Reserves an address for `mac` for [`OFFER_TIMEOUT`] and returns it.
+    ///
+    /// The client gets its current address if it has one, else the `requested` address
+    /// if it is free, else the first free address. Returns `None` if the pool is full.
+    pub fn offer(
+        &mut self,
+        mac: [u8; 6],
+        requested: Option<[u8; 4]>,
+        now: Instant,
+    ) -> Option<[u8; 4]> {
+        let index = self
+            .position_of(mac)
+            .or_else(|| {
+                requested
+                    .and_then(|address| self.index(address))
+                    .filter(|&index| self.is_free(index, now))
+            })
+            .or_else(|| (0..self.len).find(|&index| self.slots[index].is_none()))
+            .or_else(|| {
+                (0..self.len)
+                    .filter(|&index| self.is_free(index, now))
+                    .min_by_key(|&index| self.slots[index].map(|lease| lease.expires))
+            })?;
+
+        let address = self.address(index);
+        let bound = matches!(
+            self.slots[index],
+            Some(lease) if lease.state == LeaseState::Bound && lease.expires > now
+        );
+        if !bound {
+            self.slots[index] = Some(Lease {
+                mac,
+                address,
+                state: LeaseState::Offered,
+                expires: now + OFFER_TIMEOUT,
+            });
+        }
+        Some(address)
+    }
+
+    /// Leases `address` to `mac`, or extends its lease.
+    ///
+    /// Returns `false` if the address is not in the pool or is used by another client.
+    pub fn bind(&mut self, mac: [u8; 6], address: [u8; 4], now: Instant) -> bool {
+        let Some(index) = self.index(address) else {
+            return false;
+        };
+        match self.slots[index] {
+            Some(lease) if lease.mac == mac && lease.state != LeaseState::Declined => {}
+            _ if self.is_free(index, now) => {}
+            _ => return false,
+        }
+
+        // A client has a single address.
+        for slot in &mut self.slots {
+            if matches!(slot, Some(lease) if lease.mac == mac && lease.state != LeaseState::Declined)
+            {
+                *slot = None;
+            }
+        }
+        self.slots[index] = Some(Lease {
+            mac,
+            address,
+            state: LeaseState::Bound,
+            expires: now + self.lease_time,
+        });
+        true
+    }
+
+    /// Frees the address of `mac`.
+    pub fn release(&mut self, mac: [u8; 6], address: [u8; 4]) {
+        if let Some(index) = self.index(address) {
+            if matches!(self.slots[index], Some(lease) if lease.mac == mac) {
+                self.slots[index] = None;
+            }
+        }
+    }
+
+    /// Marks `address` as used by another host, so that it is not given again before
+    /// the lease time elapses.
+    pub fn decline(&mut self, mac: [u8; 6], address: [u8; 4], now: Instant) {
+        if let Some(index) = self.index(address) {
+            if matches!(self.slots[index], Some(lease) if lease.mac == mac) {
+                self.slots[index] = Some(Lease {
+                    mac,
+                    address,
+                    state: LeaseState::Declined,
+                    expires: now + self.lease_time,
+                });
+            }
+        }
+    }
+
+    /// Returns the active lease of `mac`.
+    pub fn lease(&self, mac: [u8; 6], now: Instant) -> Option<&Lease> {
+        self.active(now).find(|lease| lease.mac == mac)
+    }
+
+    /// Returns the leases that have not expired.
+    pub fn active(&self, now: Instant) -> impl Iterator<Item = &Lease> {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(move |lease| lease.expires > now)
+    }
+
+    /// Returns the hardware addresses of the clients that use an address: their lease
+    /// is bound and has not expired.
+    pub fn clients(&self, now: Instant) -> impl Iterator<Item = [u8; 6]> + Clone + '_ {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(move |lease| lease.state == LeaseState::Bound && lease.expires > now)
+            .map(|lease| lease.mac)
+    }
+
+    fn position_of(&self, mac: [u8; 6]) -> Option<usize> {
+        self.slots.iter().position(|slot| {
+            matches!(slot, Some(lease) if lease.mac == mac && lease.state != LeaseState::Declined)
+        })
+    }
+
+    fn is_free(&self, index: usize, now: Instant) -> bool {
+        match self.slots[index] {
+            Some(lease) => lease.expires <= now,
+            None => true,
+        }
+    }
+
+    fn index(&self, address: [u8; 4]) -> Option<usize> {
+        if address[..3] != self.first[..3] {
+            return None;
+        }
+        let index = address[3].checked_sub(self.first[3])? as usize;
+        (index < self.len).then_some(index)
+    }
+
+    fn address(&self, index: usize) -> [u8; 4] {
+        let mut address = self.first;
+        address[3] += index as u8;
+        address
+    }
+}
+
+/// Settings of a [`DhcpServer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DhcpServerConfig {
+    /// Address of the server, on the access point interface.
+    pub address: [u8; 4],
+    pub netmask: [u8; 4],
+    /// First address of the pool, in the network of `address`. The pool stops before
+    /// `address` if it comes after `pool_start`, see [`DhcpServer::new`].
+    pub pool_start: [u8; 4],
+    pub lease_time: Duration,
+    /// Default gateway given to the clients, none for an access point without uplink.
+    pub router: Option<[u8; 4]>,
+    pub dns: Option<[u8; 4]>,
+}
+
+impl DhcpServerConfig {
+    /// Serves the addresses from `pool_start` in the `address/prefix_len` network, for
+    /// [`DEFAULT_LEASE_TIME`], without gateway or DNS server.
+    pub fn new(address: [u8; 4], prefix_len: u8, pool_start: [u8; 4]) -> Self {
+        let mask = u32::MAX
+            .checked_shl(32 - prefix_len.min(32) as u32)
+            .unwrap_or(0);
+        Self {
+            address,
+            netmask: mask.to_be_bytes(),
+            pool_start,
+            lease_time: DEFAULT_LEASE_TIME,
+            router: None,
+            dns: None,
+        }
+    }
+
+    pub fn with_lease_time(mut self, lease_time: Duration) -> Self {
+        self.lease_time = lease_time;
+        self
+    }
+
+    pub fn with_router(mut self, router: [u8; 4]) -> Self {
+        self.router = Some(router);
+        self
+    }
+
+    pub fn with_dns(mut self, dns: [u8; 4]) -> Self {
+        self.dns = Some(dns);
+        self
+    }
+}
+
+/// A reply of the server, written into the buffer given to [`DhcpServer::handle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reply {
+    /// Length of the message.
+    pub len: usize,
+    pub message_type: MessageType,
+    /// Where to send the reply, on [`CLIENT_PORT`]: the client, or `255.255.255.255`
+    /// if it does not have an address yet.
+    pub destination: [u8; 4],
+}
+
+/// The fields of a client message used by the server.
+struct Request {
+    message_type: MessageType,
+    xid: [u8; 4],
+    flags: u16,
+    ciaddr: [u8; 4],
+    chaddr: [u8; 16],
+    requested: Option<[u8; 4]>,
+    server_id: Option<[u8; 4]>,
+}
+
+impl Request {
+    fn parse(message: &[u8]) -> Option<Self> {
+        if message.len() < HEADER_LEN + MAGIC_COOKIE.len()
+            || message[0] != OP_REQUEST
+            || message[1] != HTYPE_ETHERNET
+            || message[2] != 6
+            || message[HEADER_LEN..HEADER_LEN + 4] != MAGIC_COOKIE
+        {
+            return None;
+        }
+
+        let mut message_type = None;
+        let mut requested = None;
+        let mut server_id = None;
+        let mut options = &message[HEADER_LEN + 4..];
+        while let [code, rest @ ..] = options {
+            match *code {
+                OPTION_PAD => {
+                    options = rest;
+                    continue;
+                }
+                OPTION_END => break,
+                _ => {}
+            }
+            let (&len, rest) = rest.split_first()?;
+            let value = rest.get(..len as usize)?;
+            match (*code, value) {
+                (OPTION_MESSAGE_TYPE, &[value]) => message_type = MessageType::from_u8(value),
+                (OPTION_REQUESTED_IP, &[a, b, c, d]) => requested = Some([a, b, c, d]),
+                (OPTION_SERVER_ID, &[a, b, c, d]) => server_id = Some([a, b, c, d]),
+                _ => {}
+            }
+            options = &rest[len as usize..];
+        }
+
+        let field = |offset: usize| -> [u8; 4] {
+            [
+                message[offset],
+                message[offset + 1],
+                message[offset + 2],
+                message[offset + 3],
+            ]
+        };
+        let mut chaddr = [0; 16];
+        chaddr.copy_from_slice(&message[28..44]);
+        Some(Self {
+            message_type: message_type?,
+            xid: field(4),
+            flags: u16::from_be_bytes([message[10], message[11]]),
+            ciaddr: field(12),
+            chaddr,
+            requested,
+            server_id,
+        })
+    }
+
+    fn mac(&self) -> [u8; 6] {
+        let mut mac = [0; 6];
+        mac.copy_from_slice(&self.chaddr[..6]);
+        mac
+    }
+}
+
+/// Answers the DHCP messages of the clients with the addresses of a [`LeaseTable`]
+/// of `N` addresses.
+///
+/// # Example
+///
+/// ```
+/// use embassy_time::Instant;
+/// use utils::wifi::dhcp::{DhcpServer, DhcpServerConfig, MessageType, MESSAGE_SIZE};
+///
+/// let config = DhcpServerConfig::new([192, 168, 4, 1], 24, [192, 168, 4, 100]);
+/// let mut server = DhcpServer::<8>::new(config);
+///
+/// // A DHCPDISCOVER from 02:00:00:00:00:01
+/// let mut discover = [0; 244];
+/// discover[..4].copy_from_slice(&[1, 1, 6, 0]);
+/// discover[4..8].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+/// discover[28..34].copy_from_slice(&[2, 0, 0, 0, 0, 1]);
+/// discover[236..240].copy_from_slice(&[99, 130, 83, 99]);
+/// discover[240..].copy_from_slice(&[53, 1, 1, 255]);
+///
+/// let mut reply = [0; MESSAGE_SIZE];
+/// let offer = server.handle(&discover, Instant::from_secs(0), &mut reply).unwrap();
+/// assert_eq!(offer.message_type, MessageType::Offer);
+/// assert_eq!(offer.destination, [255, 255, 255, 255]);
+/// // The offered address, in `yiaddr`.
+/// assert_eq!(reply[16..20], [192, 168, 4, 100]);
+/// ```
+pub struct DhcpServer<const N: usize> {
+    config: DhcpServerConfig,
+    leases: LeaseTable<N>,
+}
+
+impl<const N: usize> DhcpServer<N> {
+    /// Creates a server for the pool of `config`.
+    ///
+    /// The pool is clamped so that it stays in the network of the server and stops
+    /// before the address of the server and before the broadcast address. A pool that
+    /// starts outside the network, or on the network address, is empty. See
+    /// [`LeaseTable::capacity`].
+    pub fn new(config: DhcpServerConfig) -> Self {
+        let mut leases = LeaseTable::new(config.pool_start, config.lease_time);
+        leases.len = leases.len.min(Self::pool_limit(&config));
+        Self { leases, config }
+    }
+
+    /// Returns the number of addresses from `pool_start` that can be leased.
+    fn pool_limit(config: &DhcpServerConfig) -> usize {
+        let address = u32::from_be_bytes(config.address);
+        let mask = u32::from_be_bytes(config.netmask);
+        let start = u32::from_be_bytes(config.pool_start);
+        let network = address & mask;
+        let broadcast = network | !mask;
+        if start & mask != network || start == network {
+            return 0;
+        }
+        let mut limit = broadcast - start;
+        if address >= start {
+            limit = limit.min(address - start);
+        }
+        limit as usize
+    }
+
+    pub fn config(&self) -> &DhcpServerConfig {
+        &self.config
+    }
+
+    pub fn leases(&self) -> &LeaseTable<N> {
+        &self.leases
+    }
+
+    /// Handles a message received on [`SERVER_PORT`] and writes the reply, if any,
+    /// into `reply`.
+    pub fn handle(
+        &mut self,
+        message: &[u8],
+        now: Instant,
+        reply: &mut [u8; MESSAGE_SIZE],
+    ) -> Option<Reply> {
+        let request = Request::parse(message)?;
+        let mac = request.mac();
+
+        let (message_type, address) = match request.message_type {
+            MessageType::Discover => {
+                let address = self.leases.offer(mac, request.requested, now)?;
+                (MessageType::Offer, address)
+            }
+            MessageType::Request => {
+                if request
+                    .server_id
+                    .is_some_and(|server| server != self.config.address)
+                {
+                    // The client accepted the offer of another server.
+                    if let Some(lease) = self.leases.lease(mac, now).copied() {
+                        if lease.state == LeaseState::Offered {
+                            self.leases.release(mac, lease.address);
+                        }
+                    }
+                    return None;
+                }
+                let address = request.requested.unwrap_or(request.ciaddr);
+                if self.leases.bind(mac, address, now) {
+                    (MessageType::Ack, address)
+                } else {
+                    (MessageType::Nak, [0; 4])
+                }
+            }
+            MessageType::Release => {
+                self.leases.release(mac, request.ciaddr);
+                return None;
+            }
+            MessageType::Decline => {
+                if let Some(address) = request.requested {
+                    self.leases.decline(mac, address, now);
+                }
+                return None;
+            }
+            _ => return None,
+        };
+
+        let len = self.write_reply(&request, message_type, address, reply);
+        // A NAK is broadcast, the client may not be able to receive its address.
+        let destination = if message_type != MessageType::Nak
+            && request.flags & FLAG_BROADCAST == 0
+            && request.ciaddr != [0; 4]
+        {
+            request.ciaddr
+        } else {
+            [255; 4]
+        };
+        Some(Reply {
+            len,
+            message_type,
+            destination,
+        })
+    }
+
+    fn write_reply(
+        &self,
+        request: &Request,
+        message_type: MessageType,
+        address: [u8; 4],
+        reply: &mut [u8; MESSAGE_SIZE],
+    ) -> usize {
+        reply.fill(0);
+        reply[0] = OP_REPLY;
+        reply[1] = HTYPE_ETHERNET;
+        reply[2] = 6;
+        reply[4..8].copy_from_slice(&request.xid);
+        reply[10..12].copy_from_slice(&request.flags.to_be_bytes());
+        if message_type == MessageType::Ack {
+            reply[12..16].copy_from_slice(&request.ciaddr);
+        }
+        reply[16..20].copy_from_slice(&address);
+        reply[28..44].copy_from_slice(&request.chaddr);
+        reply[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&MAGIC_COOKIE);
+
+        let mut options = Options {
+            buf: reply,
+            len: HEADER_LEN + 4,
+        };
+        options.push(OPTION_MESSAGE_TYPE, &[message_type as u8]);
+        options.push(OPTION_SERVER_ID, &self.config.address);
+        if message_type != MessageType::Nak {
+            let lease_time = self.leases.lease_time().as_secs() as u32;
+            options.push(OPTION_LEASE_TIME, &lease_time.to_be_bytes());
+            options.push(OPTION_RENEWAL_TIME, &(lease_time / 2).to_be_bytes());
+            options.push(OPTION_REBINDING_TIME, &(lease_time / 8 * 7).to_be_bytes());
+            options.push(OPTION_SUBNET_MASK, &self.config.netmask);
+            if let Some(router) = self.config.router {
+                options.push(OPTION_ROUTER, &router);
+            }
+            if let Some(dns) = self.config.dns {
+                options.push(OPTION_DNS, &dns);
+            }
+        }
+        options.buf[options.len] = OPTION_END;
+        (options.len + 1).max(MIN_MESSAGE_LEN)
+    }
+}
+
+struct Options<'a> {
+    buf: &'a mut [u8; MESSAGE_SIZE],
+    len: usize,
+}
+
+impl Options<'_> {
+    fn push(&mut self, code: u8, value: &[u8]) {
+        self.buf[self.len] = code;
+        self.buf[self.len + 1] = value.len() as u8;
+        self.buf[self.len + 2..self.len + 2 + value.len()].copy_from_slice(value);
+        self.len += 2 + value.len();
+    }
+}
+
+#[cfg(feature = "rp2040")]
+pub use rp2040::{dhcp_server_task, MAX_LEASES};
+
+#[cfg(feature = "rp2040")]
+mod rp2040 {
+    use embassy_net::udp::{PacketMetadata, UdpSocket};
+    use embassy_net::{IpAddress, IpEndpoint};
+    use embassy_time::{with_timeout, Duration, Instant};
+    use log::{info, warn};
+
+    use super::{
+        DhcpServer, DhcpServerConfig, MessageType, CLIENT_PORT, MESSAGE_SIZE, SERVER_PORT,
+    };
+    use crate::wifi::ap::{self, Stations, MAX_STATIONS};
+    use crate::wifi::WifiStack;
+
+    /// How often the expired leases are checked when no message is received.
+    const EXPIRY_CHECK: Duration = Duration::from_secs(1);
+
+    /// Number of addresses served by [`dhcp_server_task`], one per station that the
+    /// access point tracks.
+    pub const MAX_LEASES: usize = MAX_STATIONS;
+
+    /// Runs a [`DhcpServer`] with [`MAX_LEASES`] addresses on the network stack.
+    ///
+    /// The stack needs a static IPv4 configuration with `config.address`.
+    ///
+    /// The clients that get a lease, release it or let it expire are reported to the
+    /// access point with [`ap::report`]. A client that leaves without releasing its
+    /// address is only reported when its lease expires, after `config.lease_time`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// control.start_ap_wpa2("Pico", "password", 5).await;
+    ///
+    /// let config = DhcpServerConfig::new([192, 168, 4, 1], 24, [192, 168, 4, 100]);
+    /// spawner.spawn(dhcp_server_task(stack, config)).unwrap();
+    /// ```
+    #[embassy_executor::task]
+    pub async fn dhcp_server_task(stack: &'static WifiStack, config: DhcpServerConfig) -> ! {
+        let mut server = DhcpServer::<MAX_LEASES>::new(config);
+        if server.leases().capacity() < MAX_LEASES {
+            warn!(
+                "DHCP pool clamped to {} addresses, to fit the network before the server",
+                server.leases().capacity()
+            );
+        }
+        let mut stations = Stations::<MAX_LEASES>::new();
+
+        let mut rx_meta = [PacketMetadata::EMPTY; 4];
+        let mut rx_buffer = [0; 2 * MESSAGE_SIZE];
+        let mut tx_meta = [PacketMetadata::EMPTY; 4];
+        let mut tx_buffer = [0; 2 * MESSAGE_SIZE];
+        let mut socket = UdpSocket::new(
+            stack,
+            &mut rx_meta,
+            &mut rx_buffer,
+            &mut tx_meta,
+            &mut tx_buffer,
+        );
+        // Cannot fail, the socket is not bound and the port is not 0.
+        socket.bind(SERVER_PORT).unwrap();
+
+        let mut message = [0; MESSAGE_SIZE];
+        let mut reply = [0; MESSAGE_SIZE];
+        loop {
+            let received = with_timeout(EXPIRY_CHECK, socket.recv_from(&mut message)).await;
+            let answer = match received {
+                Ok(Ok((n, _))) => server.handle(&message[..n], Instant::now(), &mut reply),
+                Ok(Err(err)) => {
+                    warn!("DHCP receive error: {:?}", err);
+                    None
+                }
+                Err(_) => None,
+            };
+
+            if let Some(answer) = answer {
+                if answer.message_type == MessageType::Ack {
+                    info!("DHCP lease of {:?}", &reply[16..20]);
+                }
+
+                let [a, b, c, d] = answer.destination;
+                let destination = IpEndpoint::new(IpAddress::v4(a, b, c, d), CLIENT_PORT);
+                if let Err(err) = socket.send_to(&reply[..answer.len], destination).await {
+                    warn!("DHCP send error: {:?}", err);
+                }
+            }
+
+            stations.sync(server.leases().clients(Instant::now()), |change| {
+                ap::report(change.event);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    const LAPTOP: [u8; 6] = [2, 0, 0, 0, 0, 1];
+    const PHONE: [u8; 6] = [2, 0, 0, 0, 0, 2];
+    const TABLET: [u8; 6] = [2, 0, 0, 0, 0, 3];
+    const LEASE_TIME: Duration = Duration::from_secs(3600);
+    const SERVER: [u8; 4] = [192, 168, 4, 1];
+
+    fn pool(last: u8) -> [u8; 4] {
+        [192, 168, 4, last]
+    }
+
+    fn at(secs: u64) -> Instant {
+        Instant::from_secs(secs)
+    }
+
+    #[test]
+    fn lease_expires_after_the_lease_time() {
+        let mut leases = LeaseTable::<1>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        assert_eq!(
+            leases.lease(LAPTOP, at(0)).unwrap().state,
+            LeaseState::Bound
+        );
+        assert!(leases.clients(at(3599)).eq([LAPTOP]));
+        assert_eq!(leases.offer(PHONE, None, at(3599)), None);
+
+        assert!(leases.lease(LAPTOP, at(3600)).is_none());
+        assert_eq!(leases.clients(at(3600)).count(), 0);
+        assert_eq!(leases.offer(PHONE, None, at(3600)), Some(pool(100)));
+    }
+
+    #[test]
+    fn renewal_extends_the_lease() {
+        let mut leases = LeaseTable::<1>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        assert!(leases.bind(LAPTOP, pool(100), at(1800)));
+        assert!(leases.clients(at(3600)).eq([LAPTOP]));
+        assert_eq!(leases.offer(LAPTOP, None, at(3600)), Some(pool(100)));
+        // An offer to the client does not shorten its lease
+        assert_eq!(
+            leases.lease(LAPTOP, at(0)).unwrap().state,
+            LeaseState::Bound
+        );
+    }
+
+    #[test]
+    fn offer_is_reserved_until_the_offer_timeout() {
+        let mut leases = LeaseTable::<1>::new(pool(100), LEASE_TIME);
+        assert_eq!(leases.offer(LAPTOP, None, at(0)), Some(pool(100)));
+        assert_eq!(leases.offer(PHONE, None, at(59)), None);
+        assert!(!leases.bind(PHONE, pool(100), at(59)));
+
+        let timeout = at(0) + OFFER_TIMEOUT;
+        assert_eq!(leases.offer(PHONE, None, timeout), Some(pool(100)));
+        assert!(!leases.bind(LAPTOP, pool(100), timeout));
+    }
+
+    #[test]
+    fn declined_address_is_not_given_again() {
+        let mut leases = LeaseTable::<2>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        leases.decline(LAPTOP, pool(100), at(10));
+        assert_eq!(
+            leases.lease(LAPTOP, at(10)).unwrap().state,
+            LeaseState::Declined
+        );
+        assert_eq!(leases.clients(at(10)).count(), 0);
+
+        // The client gets another address, nobody gets the declined one
+        assert_eq!(leases.offer(LAPTOP, None, at(10)), Some(pool(101)));
+        assert!(leases.bind(LAPTOP, pool(101), at(10)));
+        assert_eq!(leases.offer(PHONE, Some(pool(100)), at(20)), None);
+        assert_eq!(leases.offer(PHONE, None, at(3610)), Some(pool(100)));
+    }
+
+    #[test]
+    fn decline_of_another_client_is_ignored() {
+        let mut leases = LeaseTable::<1>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        leases.decline(PHONE, pool(100), at(0));
+        leases.decline(LAPTOP, pool(200), at(0));
+        assert_eq!(
+            leases.lease(LAPTOP, at(0)).unwrap().state,
+            LeaseState::Bound
+        );
+    }
+
+    #[test]
+    fn released_address_is_free_at_once() {
+        let mut leases = LeaseTable::<1>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        // Only the client can release its address
+        leases.release(PHONE, pool(100));
+        assert_eq!(leases.offer(PHONE, None, at(1)), None);
+
+        leases.release(LAPTOP, pool(100));
+        assert!(leases.lease(LAPTOP, at(1)).is_none());
+        assert_eq!(leases.offer(PHONE, None, at(1)), Some(pool(100)));
+    }
+
+    #[test]
+    fn client_has_a_single_address() {
+        let mut leases = LeaseTable::<2>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(0)));
+        assert!(leases.bind(LAPTOP, pool(101), at(0)));
+        assert_eq!(leases.active(at(0)).count(), 1);
+        assert_eq!(leases.offer(PHONE, None, at(0)), Some(pool(100)));
+    }
+
+    #[test]
+    fn addresses_out_of_the_pool_are_refused() {
+        let mut leases = LeaseTable::<2>::new(pool(100), LEASE_TIME);
+        assert!(!leases.bind(LAPTOP, pool(99), at(0)));
+        assert!(!leases.bind(LAPTOP, pool(102), at(0)));
+        assert!(!leases.bind(LAPTOP, [192, 168, 5, 100], at(0)));
+        // A request for an address out of the pool gets one of the pool
+        assert_eq!(
+            leases.offer(LAPTOP, Some(pool(102)), at(0)),
+            Some(pool(100))
+        );
+    }
+
+    #[test]
+    fn full_pool_reuses_the_oldest_expired_lease() {
+        let mut leases = LeaseTable::<2>::new(pool(100), LEASE_TIME);
+        assert!(leases.bind(LAPTOP, pool(100), at(10)));
+        assert!(leases.bind(PHONE, pool(101), at(0)));
+        assert_eq!(leases.offer(TABLET, None, at(3000)), None);
+
+        // Both have expired, the phone's lease is the oldest
+        assert_eq!(leases.offer(TABLET, None, at(4000)), Some(pool(101)));
+        assert!(leases.bind(TABLET, pool(101), at(4000)));
+        // The laptop comes back and gets its old address
+        assert_eq!(leases.offer(LAPTOP, None, at(4000)), Some(pool(100)));
+        assert_eq!(leases.offer(PHONE, None, at(4000)), None);
+    }
+
+    #[test]
+    fn pool_is_clamped_before_the_broadcast_address() {
+        let mut leases = LeaseTable::<16>::new(pool(250), LEASE_TIME);
+        assert_eq!(leases.capacity(), 5);
+        for last in 250..=254 {
+            let mac = [2, 0, 0, 0, 1, last];
+            assert_eq!(leases.offer(mac, None, at(0)), Some(pool(last)));
+        }
+        assert_eq!(leases.offer(LAPTOP, None, at(0)), None);
+        assert!(!leases.bind(LAPTOP, pool(255), at(0)));
+
+        assert_eq!(LeaseTable::<16>::new(pool(255), LEASE_TIME).capacity(), 0);
+        assert_eq!(LeaseTable::<16>::new(pool(100), LEASE_TIME).capacity(), 16);
+    }
+
+    #[test]
+    fn pool_is_clamped_to_the_network_of_the_server() {
+        let capacity = |address: [u8; 4], prefix_len: u8, pool_start: [u8; 4]| {
+            let config = DhcpServerConfig::new(address, prefix_len, pool_start);
+            DhcpServer::<16>::new(config).leases().capacity()
+        };
+        // The server comes before the pool, or after its end.
+        assert_eq!(capacity(SERVER, 24, pool(100)), 16);
+        assert_eq!(capacity(pool(200), 24, pool(100)), 16);
+        // The pool stops before the server.
+        assert_eq!(capacity(pool(105), 24, pool(100)), 5);
+        assert_eq!(capacity(pool(100), 24, pool(100)), 0);
+        // The pool stops before the broadcast address of a small network.
+        assert_eq!(capacity([192, 168, 4, 1], 28, [192, 168, 4, 4]), 11);
+        // Outside the network, or on the network address.
+        assert_eq!(capacity(SERVER, 24, [192, 168, 5, 100]), 0);
+        assert_eq!(capacity([192, 168, 4, 1], 28, [192, 168, 4, 16]), 0);
+        assert_eq!(capacity([192, 168, 4, 17], 28, [192, 168, 4, 16]), 0);
+    }
+
+    #[test]
+    fn server_address_is_never_offered() {
+        let config = DhcpServerConfig::new(pool(102), 24, pool(100));
+        let mut server = DhcpServer::<8>::new(config);
+        let mut reply = [0; MESSAGE_SIZE];
+        for last in 0..3 {
+            let mac = [2, 0, 0, 0, 1, last];
+            let discover = message(MessageType::Discover, mac, [0; 4], &[]);
+            let offered = server
+                .handle(&discover, at(0), &mut reply)
+                .map(|_| reply[16..20].try_into().unwrap());
+            let expected = [Some(pool(100)), Some(pool(101)), None][last as usize];
+            assert_eq!(offered, expected);
+        }
+    }
+
+    /// Returns a client message of `message_type` from `mac`, with `options`.
+    fn message(
+        message_type: MessageType,
+        mac: [u8; 6],
+        ciaddr: [u8; 4],
+        options: &[u8],
+    ) -> Vec<u8> {
+        let mut message = std::vec![0; HEADER_LEN];
+        message[..4].copy_from_slice(&[OP_REQUEST, HTYPE_ETHERNET, 6, 0]);
+        message[4..8].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        message[12..16].copy_from_slice(&ciaddr);
+        message[28..34].copy_from_slice(&mac);
+        message.extend_from_slice(&MAGIC_COOKIE);
+        message.extend_from_slice(&[OPTION_MESSAGE_TYPE, 1, message_type as u8]);
+        message.extend_from_slice(options);
+        message.push(OPTION_END);
+        message
+    }
+
+    fn request(mac: [u8; 6], address: [u8; 4]) -> Vec<u8> {
+        let mut options = std::vec![OPTION_REQUESTED_IP, 4];
+        options.extend_from_slice(&address);
+        options.extend_from_slice(&[OPTION_SERVER_ID, 4]);
+        options.extend_from_slice(&SERVER);
+        message(MessageType::Request, mac, [0; 4], &options)
+    }
+
+    /// Returns the value of the option `code` of a reply.
+    fn option(reply: &[u8], code: u8) -> Option<&[u8]> {
+        let mut options = &reply[HEADER_LEN + 4..];
+        while let [option, len, rest @ ..] = options {
+            if *option == code {
+                return Some(&rest[..*len as usize]);
+            }
+            options = &rest[*len as usize..];
+        }
+        None
+    }
+
+    fn server() -> DhcpServer<2> {
+        let config = DhcpServerConfig::new(SERVER, 24, pool(100)).with_router(SERVER);
+        DhcpServer::new(config)
+    }
+
+    #[test]
+    fn request_is_acknowledged() {
+        let mut server = server();
+        let mut reply = [0; MESSAGE_SIZE];
+        let discover = message(MessageType::Discover, LAPTOP, [0; 4], &[]);
+        let offer = server.handle(&discover, at(0), &mut reply).unwrap();
+        assert_eq!(offer.message_type, MessageType::Offer);
+
+        let ack = server
+            .handle(&request(LAPTOP, pool(100)), at(1), &mut reply)
+            .unwrap();
+        assert_eq!(ack.message_type, MessageType::Ack);
+        assert_eq!(ack.destination, [255; 4]);
+        assert_eq!(ack.len, MIN_MESSAGE_LEN);
+        assert_eq!(reply[..4], [OP_REPLY, HTYPE_ETHERNET, 6, 0]);
+        assert_eq!(reply[4..8], [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(reply[16..20], pool(100));
+        assert_eq!(reply[28..34], LAPTOP);
+        assert_eq!(option(&reply, OPTION_MESSAGE_TYPE), Some(&[5][..]));
+        assert_eq!(option(&reply, OPTION_SERVER_ID), Some(&SERVER[..]));
+        assert_eq!(
+            option(&reply, OPTION_SUBNET_MASK),
+            Some(&[255, 255, 255, 0][..])
+        );
+        assert_eq!(option(&reply, OPTION_ROUTER), Some(&SERVER[..]));
+        assert_eq!(
+            option(&reply, OPTION_LEASE_TIME),
+            Some(&120u32.to_be_bytes()[..])
+        );
+        assert_eq!(option(&reply, OPTION_DNS), None);
+        assert!(server.leases().clients(at(1)).eq([LAPTOP]));
+
+        // A renewal from the client's address is sent back to it
+        let renew = message(MessageType::Request, LAPTOP, pool(100), &[]);
+        let ack = server.handle(&renew, at(1800), &mut reply).unwrap();
+        assert_eq!(ack.message_type, MessageType::Ack);
+        assert_eq!(ack.destination, pool(100));
+        assert_eq!(reply[12..16], pool(100));
+    }
+
+    #[test]
+    fn request_for_a_used_address_is_refused() {
+        let mut server = server();
+        let mut reply = [0; MESSAGE_SIZE];
+        server
+            .handle(&request(LAPTOP, pool(100)), at(0), &mut reply)
+            .unwrap();
+
+        for address in [pool(100), pool(102), [10, 0, 0, 2]] {
+            let nak = server
+                .handle(&request(PHONE, address), at(1), &mut reply)
+                .unwrap();
+            assert_eq!(nak.message_type, MessageType::Nak);
+            assert_eq!(nak.destination, [255; 4]);
+            assert_eq!(reply[16..20], [0; 4]);
+            assert_eq!(option(&reply, OPTION_MESSAGE_TYPE), Some(&[6][..]));
+            assert_eq!(option(&reply, OPTION_LEASE_TIME), None);
+        }
+        assert!(server.leases().clients(at(1)).eq([LAPTOP]));
+    }
+
+    #[test]
+    fn request_to_another_server_frees_the_offer() {
+        let mut server = server();
+        let mut reply = [0; MESSAGE_SIZE];
+        let discover = message(MessageType::Discover, LAPTOP, [0; 4], &[]);
+        server.handle(&discover, at(0), &mut reply).unwrap();
+
+        let mut options = std::vec![OPTION_SERVER_ID, 4, 192, 168, 4, 2];
+        options.extend_from_slice(&[OPTION_REQUESTED_IP, 4, 192, 168, 4, 50]);
+        let other = message(MessageType::Request, LAPTOP, [0; 4], &options);
+        assert_eq!(server.handle(&other, at(1), &mut reply), None);
+        assert!(server.leases().lease(LAPTOP, at(1)).is_none());
+    }
+
+    #[test]
+    fn release_and_decline_are_not_answered() {
+        let mut server = server();
+        let mut reply = [0; MESSAGE_SIZE];
+        server
+            .handle(&request(LAPTOP, pool(100)), at(0), &mut reply)
+            .unwrap();
+        let release = message(MessageType::Release, LAPTOP, pool(100), &[]);
+        assert_eq!(server.handle(&release, at(1), &mut reply), None);
+        assert!(server.leases().lease(LAPTOP, at(1)).is_none());
+
+        server
+            .handle(&request(PHONE, pool(101)), at(2), &mut reply)
+            .unwrap();
+        let mut options = std::vec![OPTION_REQUESTED_IP, 4];
+        options.extend_from_slice(&pool(101));
+        let decline = message(MessageType::Decline, PHONE, [0; 4], &options);
+        assert_eq!(server.handle(&decline, at(3), &mut reply), None);
+        let lease = server.leases().lease(PHONE, at(3)).unwrap();
+        assert_eq!(lease.state, LeaseState::Declined);
+    }
+
+    #[test]
+    fn malformed_messages_are_ignored() {
+        let mut server = server();
+        let mut reply = [0; MESSAGE_SIZE];
+        let discover = message(MessageType::Discover, LAPTOP, [0; 4], &[]);
+
+        // Truncated, a reply, a wrong magic cookie, a truncated option
+        let mut reply_op = discover.clone();
+        reply_op[0] = OP_REPLY;
+        let mut cookie = discover.clone();
+        cookie[HEADER_LEN] = 0;
+        let mut option = discover[..discover.len() - 1].to_vec();
+        option.extend_from_slice(&[OPTION_REQUESTED_IP, 4, 192]);
+        let no_type =
+            message(MessageType::Discover, LAPTOP, [0; 4], &[])[..HEADER_LEN + 4].to_vec();
+        for message in [&discover[..100], &reply_op, &cookie, &option, &no_type] {
+            assert_eq!(server.handle(message, at(0), &mut reply), None);
+        }
+        assert_eq!(server.leases().active(at(0)).count(), 0);
+    }
+}
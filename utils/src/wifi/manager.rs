@@ -0,0 +1,267 @@
+//! Connection manager: joins the network, waits for an IP address, watches the
+//! connection and re-joins when it is lost.
+
+use core::cell::Cell;
+
+use cyw43::{Control, NetDriver};
+use embassy_net::{ConfigV4, Stack};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex as BlockingMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_sync::pubsub::{PubSubChannel, Subscriber};
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Timer};
+use log::{info, warn};
+
+use super::{Backoff, Credentials};
+
+/// Maximum number of tasks that can be subscribed to the [`CONNECTION`] state at once.
+pub const MAX_SUBSCRIBERS: usize = 4;
+
+/// The WiFi control handle, shared between the connection manager and the application.
+pub type SharedControl = Mutex<CriticalSectionRawMutex, Control<'static>>;
+
+/// The network stack on top of the WiFi chip.
+pub type WifiStack = Stack<NetDriver<'static>>;
+
+/// State of the connection to the access point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Not connected, waiting before the next attempt.
+    Disconnected,
+    /// Joining the access point.
+    Joining,
+    /// Joined, waiting for DHCP (or for the static configuration to be applied).
+    WaitingForIp,
+    /// Connected, with an IP address.
+    Up,
+}
+
+/// Receives every change of the [`ConnectionState`], see [`ConnectionStatus::subscriber`].
+pub type ConnectionSubscriber<'a> =
+    Subscriber<'a, CriticalSectionRawMutex, ConnectionState, 1, MAX_SUBSCRIBERS, 0>;
+
+/// Current [`ConnectionState`], published by the connection manager.
+pub struct ConnectionStatus {
+    state: BlockingMutex<CriticalSectionRawMutex, Cell<ConnectionState>>,
+    changes: PubSubChannel<CriticalSectionRawMutex, ConnectionState, 1, MAX_SUBSCRIBERS, 0>,
+    link_lost: Signal<CriticalSectionRawMutex, ()>,
+}
+
+/// The connection state of the WiFi chip.
+pub static CONNECTION: ConnectionStatus = ConnectionStatus::new();
+
+impl ConnectionStatus {
+    const fn new() -> Self {
+        Self {
+            state: BlockingMutex::new(Cell::new(ConnectionState::Disconnected)),
+            changes: PubSubChannel::new(),
+            link_lost: Signal::new(),
+        }
+    }
+
+    /// Returns the current state.
+    pub fn get(&self) -> ConnectionState {
+        self.state.lock(|state| state.get())
+    }
+
+    /// Returns `true` if the connection is up.
+    pub fn is_up(&self) -> bool {
+        self.get() == ConnectionState::Up
+    }
+
+    /// Returns a subscriber that receives every state change, with
+    /// `subscriber.next_message_pure().await`. A subscriber that falls behind
+    /// only receives the latest state.
+    ///
+    /// Returns `None` if [`MAX_SUBSCRIBERS`] subscribers already exist.
+    pub fn subscriber(&self) -> Option<ConnectionSubscriber<'_>> {
+        self.changes.subscriber().ok()
+    }
+
+    /// Waits until the connection reaches `state`.
+    pub async fn wait_for(&self, state: ConnectionState) {
+        let Some(mut subscriber) = self.subscriber() else {
+            // All the subscribers are taken, poll instead.
+            while self.get() != state {
+                Timer::after_millis(100).await;
+            }
+            return;
+        };
+
+        // Subscribe before reading the state, so no change is missed.
+        while self.get() != state {
+            subscriber.next_message_pure().await;
+        }
+    }
+
+    /// Waits until the connection is up.
+    pub async fn wait_up(&self) {
+        self.wait_for(ConnectionState::Up).await
+    }
+
+    /// Tells the connection manager that the connection does not work anymore, for
+    /// example after repeated socket timeouts, so that it re-joins the network.
+    ///
+    /// The manager detects the loss of the DHCP lease by itself, but the chip
+    /// does not report when the access point goes away.
+    pub fn report_link_lost(&self) {
+        self.link_lost.signal(());
+    }
+
+    fn set(&self, state: ConnectionState) {
+        let previous = self.state.lock(|current| current.replace(state));
+        if previous != state {
+            self.changes.immediate_publisher().publish_immediate(state);
+        }
+    }
+}
+
+/// Keeps the WiFi chip connected to an access point.
+///
+/// The manager joins the network with exponential [`Backoff`] between failed attempts,
+/// applies the IPv4 configuration and waits for it to be up. While connected, it
+/// checks the IP configuration every [`ConnectionManager::CHECK_INTERVAL`] and
+/// re-joins when it is lost, or when the application calls
+/// [`ConnectionStatus::report_link_lost`].
+///
+/// The chip does not report when the access point goes away, so a lost link is only
+/// detected once the DHCP lease cannot be renewed (a static configuration is never
+/// lost), or when the application reports it, for example after repeated socket
+/// timeouts.
+///
+/// Every state change is published on [`CONNECTION`].
+///
+/// # Example
+///
+/// ```ignore
+/// static CONTROL: StaticCell<SharedControl> = StaticCell::new();
+/// let control = &*CONTROL.init(Mutex::new(control));
+///
+/// let manager = ConnectionManager::new(
+///     Credentials::wpa2(WIFI_NETWORK, WIFI_PASSWORD),
+///     ConfigV4::Dhcp(Default::default()),
+/// );
+/// spawner.spawn(connection_task(manager, control, stack)).unwrap();
+///
+/// CONNECTION.wait_up().await;
+/// ```
+pub struct ConnectionManager {
+    credentials: Credentials,
+    ipv4: ConfigV4,
+    backoff: Backoff,
+    ip_timeout: Duration,
+}
+
+impl ConnectionManager {
+    /// Interval between two checks of the connection.
+    pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Creates a manager that joins the network with `credentials` and then applies
+    /// the `ipv4` configuration (DHCP or static).
+    pub fn new(credentials: Credentials, ipv4: ConfigV4) -> Self {
+        Self {
+            credentials,
+            ipv4,
+            backoff: Backoff::default(),
+            ip_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the delays between failed attempts.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets how long to wait for an IP address before re-joining.
+    pub fn with_ip_timeout(mut self, timeout: Duration) -> Self {
+        self.ip_timeout = timeout;
+        self
+    }
+
+    /// Runs the manager forever.
+    pub async fn run(mut self, control: &SharedControl, stack: &WifiStack) -> ! {
+        loop {
+            if self.connect(control, stack).await {
+                self.backoff.reset();
+                self.monitor(stack).await;
+                warn!("connection to {} lost", self.credentials.ssid);
+            }
+
+            control.lock().await.leave().await;
+            CONNECTION.set(ConnectionState::Disconnected);
+
+            let delay = self.backoff.next_delay();
+            info!("re-joining in {} ms", delay.as_millis());
+            Timer::after(delay).await;
+        }
+    }
+
+    /// Joins the network and waits for the IP configuration. Returns `false` on failure.
+    async fn connect(&mut self, control: &SharedControl, stack: &WifiStack) -> bool {
+        CONNECTION.set(ConnectionState::Joining);
+        info!("joining {}", self.credentials.ssid);
+
+        let joined = {
+            let mut control = control.lock().await;
+            if self.credentials.is_open() {
+                control.join_open(&self.credentials.ssid).await
+            } else {
+                control
+                    .join_wpa2(&self.credentials.ssid, &self.credentials.passphrase)
+                    .await
+            }
+        };
+        if let Err(err) = joined {
+            warn!("join failed with status {}", err.status);
+            return false;
+        }
+
+        // Applying the configuration again restarts DHCP, so a new lease is requested
+        // after every join.
+        CONNECTION.set(ConnectionState::WaitingForIp);
+        CONNECTION.link_lost.reset();
+        stack.set_config_v4(self.ipv4.clone());
+        if with_timeout(self.ip_timeout, stack.wait_config_up())
+            .await
+            .is_err()
+        {
+            warn!("no IP address after {} s", self.ip_timeout.as_secs());
+            return false;
+        }
+
+        info!("connected, {:?}", stack.config_v4());
+        CONNECTION.set(ConnectionState::Up);
+        true
+    }
+
+    /// Returns when the connection is lost.
+    async fn monitor(&self, stack: &WifiStack) {
+        loop {
+            // The timeout elapsing is the normal case, the signal means that the
+            // application reported the link as lost.
+            if with_timeout(Self::CHECK_INTERVAL, CONNECTION.link_lost.wait())
+                .await
+                .is_ok()
+            {
+                return;
+            }
+            // `cyw43` never sets the link down once joined, only the loss of the
+            // DHCP lease tells that the access point is gone.
+            if !stack.is_config_up() {
+                return;
+            }
+        }
+    }
+}
+
+/// Runs a [`ConnectionManager`].
+#[embassy_executor::task]
+pub async fn connection_task(
+    manager: ConnectionManager,
+    control: &'static SharedControl,
+    stack: &'static WifiStack,
+) -> ! {
+    manager.run(control, stack).await
+}
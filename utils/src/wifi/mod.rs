@@ -0,0 +1,54 @@
+//! Utilities for WiFi chip initialization and management.
+//!
+//! The CYW43439 chip of the Raspberry Pi Pico W is connected to the RP2040 through a
+//! SPI bus driven by `PIO0` and `DMA_CH0`. [`init`] sets up the bus, loads the firmware,
+//! spawns the task that drives the chip and returns the handles used by the network
+//! stack and the application.
+//!
+//! [`ConnectionManager`] keeps the chip connected to an access point and publishes
+//! the [`ConnectionState`] on [`CONNECTION`]. The network to join and the IPv4
+//! configuration are read from flash by [`ConfigStore`], and written with
+//! [`provision`].
+//!
+//! The firmware is embedded at build time from the directory set by
+//! `build_script::cyw43_firmware`: the `cyw43-firmware` folder of this repository, or
+//! the `CYW43_FIRMWARE_DIR` environment variable if it is set.
+//!
+//! In access point mode, [`ap`] starts the network and keeps track of the stations
+//! connected to it, and [`dhcp`] gives them addresses.
+//!
+//! The scan results, [`AccessPoint`] and [`ScanResults`], the [`Credentials`] and the
+//! [`Backoff`] of the connection manager, the station tracking, the DHCP server logic
+//! and the flash [`record`] format do not depend on the chip and are also available
+//! without the `rp2040` feature.
+
+pub mod ap;
+mod backoff;
+#[cfg(feature = "rp2040")]
+mod chip;
+#[cfg(feature = "rp2040")]
+pub mod config;
+mod credentials;
+pub mod dhcp;
+#[cfg(feature = "rp2040")]
+mod manager;
+#[cfg(feature = "rp2040")]
+pub mod provision;
+pub mod record;
+mod scan;
+
+pub use backoff::Backoff;
+#[cfg(feature = "rp2040")]
+pub use chip::{init, new, runner_task, WifiPins, WifiRunner, WifiSpi, CLM, FIRMWARE};
+#[cfg(feature = "rp2040")]
+pub use config::{ConfigStore, NetworkConfig};
+pub use credentials::Credentials;
+#[cfg(feature = "rp2040")]
+pub use manager::{
+    connection_task, ConnectionManager, ConnectionState, ConnectionStatus, ConnectionSubscriber,
+    SharedControl, WifiStack, CONNECTION, MAX_SUBSCRIBERS,
+};
+pub use record::{MAX_PASSPHRASE_LEN, MAX_SSID_LEN};
+#[cfg(feature = "rp2040")]
+pub use scan::{scan, ScanConfig};
+pub use scan::{AccessPoint, ScanResults, Security};
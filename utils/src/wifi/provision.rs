@@ -0,0 +1,282 @@
+//! Writes the [`NetworkConfig`] into flash from a serial terminal on the USB port.
+//!
+//! [`usb_serial`] makes the board show up as a USB serial port (CDC ACM) and reads one
+//! command per line:
+//!
+//! | Command | Effect |
+//! |---|---|
+//! | `wifi <ssid> [passphrase]` | WPA2 network, or open network without a passphrase |
+//! | `ip dhcp` | Get the address with DHCP |
+//! | `ip <address>/<prefix> [gw <gateway>] [dns <server>]...` | Static address |
+//! | `show` | Print the configuration being edited |
+//! | `save` | Write it into flash |
+//! | `erase` | Erase the stored configuration, the fallback is used again |
+//!
+//! Values with spaces are written between double quotes: `wifi "Lab Network" secret`.
+//!
+//! ```shell
+//! picocom /dev/ttyACM0
+//! ```
+
+use core::fmt::Write;
+
+use embassy_futures::join::join;
+use embassy_net::ConfigV4;
+use embassy_rp::peripherals::USB;
+use embassy_rp::usb::Driver;
+use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use embassy_usb::driver::EndpointError;
+use embassy_usb::{Builder, Config};
+use embedded_storage::nor_flash::{NorFlash, NorFlashError};
+use heapless::{String, Vec};
+
+use super::config::{parse_ipv4, ConfigStore, NetworkConfig};
+use super::Credentials;
+
+/// Maximum length of a command line.
+pub const MAX_LINE_LEN: usize = 160;
+
+/// Maximum length of the reply to a command.
+const MAX_REPLY_LEN: usize = 512;
+
+const PACKET_SIZE: u16 = 64;
+
+const HELP: &str = "commands:\r
+  wifi <ssid> [passphrase]\r
+  ip dhcp\r
+  ip <address>/<prefix> [gw <gateway>] [dns <server>]...\r
+  show\r
+  save\r
+  erase\r
+";
+
+/// Applies the provisioning commands to a [`NetworkConfig`].
+pub struct Provisioner {
+    config: NetworkConfig,
+}
+
+impl Provisioner {
+    /// Starts editing `config`, usually the one that is stored in flash.
+    pub fn new(config: NetworkConfig) -> Self {
+        Self { config }
+    }
+
+    /// The configuration being edited.
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    /// Runs one command line and writes the reply into `out`.
+    pub fn handle<F: NorFlash>(
+        &mut self,
+        line: &str,
+        store: &mut ConfigStore<F>,
+        out: &mut impl Write,
+    ) -> core::fmt::Result {
+        let mut words: Vec<&str, 4> = Vec::new();
+        let mut rest = line.trim();
+        // Only the `wifi` command has values that may hold spaces.
+        while words.len() < 3 {
+            let Some((word, tail)) = next_word(rest) else {
+                break;
+            };
+            // Cannot fail, there is room for one more word.
+            let _ = words.push(word);
+            rest = tail;
+            if words[0] != "wifi" && words.len() == 2 {
+                break;
+            }
+        }
+
+        match words.as_slice() {
+            [] => Ok(()),
+            ["help"] => out.write_str(HELP),
+            ["show"] => self.show(out),
+            ["wifi", ssid] if rest.is_empty() => self.set_credentials(Credentials::open(ssid), out),
+            ["wifi", ssid, passphrase] if rest.is_empty() => {
+                self.set_credentials(Credentials::wpa2(ssid, passphrase), out)
+            }
+            ["ip", first] => {
+                let mut value: String<MAX_LINE_LEN> = String::new();
+                let _ = write!(value, "{} {}", first, rest);
+                match parse_ipv4(&value) {
+                    Ok(ipv4) => {
+                        self.config.ipv4 = ipv4;
+                        write!(out, "ok\r\n")
+                    }
+                    Err(err) => write!(out, "error: {}\r\n", err),
+                }
+            }
+            ["save"] => match store.save(&self.config) {
+                Ok(()) => write!(out, "saved, reset the board to use it\r\n"),
+                Err(err) => write!(out, "error: cannot write the flash ({:?})\r\n", err.kind()),
+            },
+            ["erase"] => match store.erase() {
+                Ok(()) => write!(out, "erased, the built-in configuration will be used\r\n"),
+                Err(err) => write!(out, "error: cannot erase the flash ({:?})\r\n", err.kind()),
+            },
+            _ => write!(out, "error: unknown command, type `help`\r\n"),
+        }
+    }
+
+    fn set_credentials(
+        &mut self,
+        credentials: Credentials,
+        out: &mut impl Write,
+    ) -> core::fmt::Result {
+        self.config.credentials = credentials;
+        write!(out, "ok\r\n")
+    }
+
+    fn show(&self, out: &mut impl Write) -> core::fmt::Result {
+        let credentials = &self.config.credentials;
+        if credentials.is_open() {
+            write!(out, "wifi {:?} (open)\r\n", credentials.ssid.as_str())?;
+        } else {
+            write!(out, "wifi {:?} ********\r\n", credentials.ssid.as_str())?;
+        }
+        match &self.config.ipv4 {
+            ConfigV4::None => write!(out, "ip none\r\n"),
+            ConfigV4::Dhcp(_) => write!(out, "ip dhcp\r\n"),
+            ConfigV4::Static(config) => {
+                write!(out, "ip {}", config.address)?;
+                if let Some(gateway) = config.gateway {
+                    write!(out, " gw {}", gateway)?;
+                }
+                for dns in &config.dns_servers {
+                    write!(out, " dns {}", dns)?;
+                }
+                write!(out, "\r\n")
+            }
+        }
+    }
+}
+
+/// Splits the first word, or the first value between double quotes, from `line`.
+fn next_word(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if line.is_empty() {
+        return None;
+    }
+    let (word, rest) = match line.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+        None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+    };
+    Some((word, rest.trim_start()))
+}
+
+/// Runs the provisioning commands on a USB serial port, forever.
+///
+/// The USB port cannot be used by `embassy_usb_logger` at the same time.
+///
+/// # Example
+///
+/// ```ignore
+/// let flash = Flash::<_, Blocking, { 2048 * 1024 }>::new_blocking(peripherals.FLASH);
+/// let driver = Driver::new(peripherals.USB, Irqs);
+/// utils::wifi::provision::usb_serial(driver, ConfigStore::new(flash)).await;
+/// ```
+pub async fn usb_serial<F: NorFlash>(driver: Driver<'_, USB>, mut store: ConfigStore<F>) -> ! {
+    let mut config = Config::new(0xc0de, 0xcafe);
+    config.manufacturer = Some("Embassy");
+    config.product = Some("Network provisioning");
+    config.serial_number = Some("12345678");
+    config.max_power = 100;
+    config.max_packet_size_0 = 64;
+
+    let mut device_descriptor = [0; 256];
+    let mut config_descriptor = [0; 256];
+    let mut bos_descriptor = [0; 256];
+    let mut control_buf = [0; 64];
+    let mut state = State::new();
+
+    let mut builder = Builder::new(
+        driver,
+        config,
+        &mut device_descriptor,
+        &mut config_descriptor,
+        &mut bos_descriptor,
+        &mut [],
+        &mut control_buf,
+    );
+    let mut class = CdcAcmClass::new(&mut builder, &mut state, PACKET_SIZE);
+    let mut usb = builder.build();
+
+    let initial = store.load_or_fallback();
+    let mut provisioner = Provisioner::new(initial);
+
+    let shell = async {
+        loop {
+            class.wait_connection().await;
+            // The host closed the port, wait for the next connection.
+            let _ = session(&mut class, &mut provisioner, &mut store).await;
+        }
+    };
+
+    join(usb.run(), shell).await.0
+}
+
+/// Reads and runs command lines until the port is closed.
+async fn session<'d, F: NorFlash>(
+    class: &mut CdcAcmClass<'d, Driver<'d, USB>>,
+    provisioner: &mut Provisioner,
+    store: &mut ConfigStore<F>,
+) -> Result<(), EndpointError> {
+    let mut packet = [0; PACKET_SIZE as usize];
+    let mut line: Vec<u8, MAX_LINE_LEN> = Vec::new();
+    let mut reply: String<MAX_REPLY_LEN> = String::new();
+    let mut previous = 0;
+
+    write_all(class, b"network provisioning, type `help`\r\n> ").await?;
+    loop {
+        let n = class.read_packet(&mut packet).await?;
+        for &byte in &packet[..n] {
+            match byte {
+                // The second half of a `\r\n` line ending.
+                b'\n' if previous == b'\r' => {}
+                b'\r' | b'\n' => {
+                    reply.clear();
+                    let _ = write!(reply, "\r\n");
+                    match core::str::from_utf8(&line) {
+                        Ok(command) => {
+                            // A reply that does not fit is truncated.
+                            let _ = provisioner.handle(command, store, &mut reply);
+                        }
+                        Err(_) => {
+                            let _ = reply.push_str("error: invalid UTF-8\r\n");
+                        }
+                    }
+                    let _ = reply.push_str("> ");
+                    write_all(class, reply.as_bytes()).await?;
+                    line.clear();
+                }
+                // Backspace and delete
+                0x08 | 0x7f => {
+                    if line.pop().is_some() {
+                        write_all(class, b"\x08 \x08").await?;
+                    }
+                }
+                _ => {
+                    if line.push(byte).is_ok() {
+                        write_all(class, &[byte]).await?;
+                    }
+                }
+            }
+            previous = byte;
+        }
+    }
+}
+
+async fn write_all<'d>(
+    class: &mut CdcAcmClass<'d, Driver<'d, USB>>,
+    data: &[u8],
+) -> Result<(), EndpointError> {
+    for chunk in data.chunks(PACKET_SIZE as usize) {
+        class.write_packet(chunk).await?;
+    }
+    // A full packet does not end the transfer for the host.
+    if data.len() % PACKET_SIZE as usize == 0 {
+        class.write_packet(&[]).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,417 @@
+//! Binary format of the network configuration stored in flash.
+//!
+//! A [`Record`] only holds plain values, so that it can be encoded and checked without
+//! the network stack. [`NetworkConfig`](super::NetworkConfig) converts from and to it
+//! when [`ConfigStore`](super::ConfigStore) reads or writes the flash.
+//!
+//! The record starts with a header (`NCFG`, the format version and the length of the
+//! payload) and ends with the CRC-32 of everything before it. The payload holds the
+//! SSID and the passphrase, each preceded by its length, then the IPv4 mode and, for
+//! a static configuration, the address, the prefix length, the gateway and the DNS
+//! servers.
+
+use heapless::{String, Vec};
+
+/// Maximum length of an SSID, in bytes.
+pub const MAX_SSID_LEN: usize = 32;
+
+/// Maximum length of a WPA2 passphrase, in bytes.
+pub const MAX_PASSPHRASE_LEN: usize = 64;
+
+/// Maximum number of DNS servers of a static configuration, as in `embassy-net`.
+pub const MAX_DNS_SERVERS: usize = 3;
+
+/// Size of the buffer that holds an encoded record, large enough for the longest one.
+pub const RECORD_SIZE: usize = 256;
+
+const MAGIC: [u8; 4] = *b"NCFG";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 7;
+const CRC_LEN: usize = 4;
+
+const MODE_NONE: u8 = 0;
+const MODE_DHCP: u8 = 1;
+const MODE_STATIC: u8 = 2;
+
+/// Error returned when a record cannot be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordError {
+    /// The sector is erased or holds something else.
+    NotFound,
+    /// The record was written by a newer version.
+    UnsupportedVersion(u8),
+    /// The checksum does not match, the record is corrupted.
+    Checksum,
+    /// The record is truncated or holds invalid values.
+    Invalid,
+}
+
+/// The credentials and the IPv4 configuration of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub ssid: String<MAX_SSID_LEN>,
+    /// The WPA2 passphrase, empty for open networks.
+    pub passphrase: String<MAX_PASSPHRASE_LEN>,
+    pub ipv4: Ipv4Record,
+}
+
+/// How the IPv4 address is configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ipv4Record {
+    None,
+    Dhcp,
+    Static(StaticIpv4),
+}
+
+/// A static IPv4 configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticIpv4 {
+    pub address: [u8; 4],
+    /// Length of the network prefix, at most 32.
+    pub prefix_len: u8,
+    pub gateway: Option<[u8; 4]>,
+    pub dns_servers: Vec<[u8; 4], MAX_DNS_SERVERS>,
+}
+
+impl Record {
+    /// Encodes the record into `buf` and returns its length.
+    pub fn encode(&self, buf: &mut [u8; RECORD_SIZE]) -> usize {
+        let mut writer = Writer {
+            buf,
+            len: HEADER_LEN,
+        };
+
+        writer.bytes_with_len(self.ssid.as_bytes());
+        writer.bytes_with_len(self.passphrase.as_bytes());
+        match &self.ipv4 {
+            Ipv4Record::None => writer.byte(MODE_NONE),
+            Ipv4Record::Dhcp => writer.byte(MODE_DHCP),
+            Ipv4Record::Static(config) => {
+                writer.byte(MODE_STATIC);
+                writer.bytes(&config.address);
+                writer.byte(config.prefix_len);
+                match config.gateway {
+                    Some(gateway) => {
+                        writer.byte(1);
+                        writer.bytes(&gateway);
+                    }
+                    None => writer.byte(0),
+                }
+                writer.byte(config.dns_servers.len() as u8);
+                for dns in &config.dns_servers {
+                    writer.bytes(dns);
+                }
+            }
+        }
+
+        let payload_len = (writer.len - HEADER_LEN) as u16;
+        let len = writer.len;
+        buf[..4].copy_from_slice(&MAGIC);
+        buf[4] = VERSION;
+        buf[5..HEADER_LEN].copy_from_slice(&payload_len.to_le_bytes());
+        let crc = crc32(&buf[..len]);
+        buf[len..len + CRC_LEN].copy_from_slice(&crc.to_le_bytes());
+        len + CRC_LEN
+    }
+
+    /// Decodes a record written by [`Record::encode`]. The bytes after the record
+    /// are ignored, so `record` can be the whole sector.
+    pub fn decode(record: &[u8]) -> Result<Self, RecordError> {
+        if record.len() < HEADER_LEN || record[..4] != MAGIC {
+            return Err(RecordError::NotFound);
+        }
+        if record[4] != VERSION {
+            return Err(RecordError::UnsupportedVersion(record[4]));
+        }
+        let payload_len = u16::from_le_bytes([record[5], record[6]]) as usize;
+        let len = HEADER_LEN + payload_len;
+        if len + CRC_LEN > record.len().min(RECORD_SIZE) {
+            return Err(RecordError::Invalid);
+        }
+        let crc = u32::from_le_bytes([
+            record[len],
+            record[len + 1],
+            record[len + 2],
+            record[len + 3],
+        ]);
+        if crc32(&record[..len]) != crc {
+            return Err(RecordError::Checksum);
+        }
+
+        let mut reader = Reader {
+            buf: &record[HEADER_LEN..len],
+        };
+        let ssid = reader.string()?;
+        let passphrase = reader.string()?;
+        let ipv4 = match reader.byte()? {
+            MODE_NONE => Ipv4Record::None,
+            MODE_DHCP => Ipv4Record::Dhcp,
+            MODE_STATIC => {
+                let address = reader.address()?;
+                let prefix_len = reader.byte()?;
+                if prefix_len > 32 {
+                    return Err(RecordError::Invalid);
+                }
+                let gateway = match reader.byte()? {
+                    0 => None,
+                    _ => Some(reader.address()?),
+                };
+                let mut dns_servers = Vec::new();
+                for _ in 0..reader.byte()? {
+                    dns_servers
+                        .push(reader.address()?)
+                        .map_err(|_| RecordError::Invalid)?;
+                }
+                Ipv4Record::Static(StaticIpv4 {
+                    address,
+                    prefix_len,
+                    gateway,
+                    dns_servers,
+                })
+            }
+            _ => return Err(RecordError::Invalid),
+        };
+
+        Ok(Self {
+            ssid,
+            passphrase,
+            ipv4,
+        })
+    }
+}
+
+struct Writer<'a> {
+    buf: &'a mut [u8; RECORD_SIZE],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn byte(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.buf[self.len..self.len + value.len()].copy_from_slice(value);
+        self.len += value.len();
+    }
+
+    fn bytes_with_len(&mut self, value: &[u8]) {
+        self.byte(value.len() as u8);
+        self.bytes(value);
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], RecordError> {
+        if len > self.buf.len() {
+            return Err(RecordError::Invalid);
+        }
+        let (value, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(value)
+    }
+
+    fn byte(&mut self) -> Result<u8, RecordError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn address(&mut self) -> Result<[u8; 4], RecordError> {
+        let bytes = self.bytes(4)?;
+        Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn string<const N: usize>(&mut self) -> Result<String<N>, RecordError> {
+        let len = self.byte()? as usize;
+        let value = core::str::from_utf8(self.bytes(len)?).map_err(|_| RecordError::Invalid)?;
+        String::try_from(value).map_err(|_| RecordError::Invalid)
+    }
+}
+
+/// CRC-32 (IEEE 802.3), computed bit by bit, as the record is small.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    fn dhcp() -> Record {
+        Record {
+            ssid: String::try_from("lab").unwrap(),
+            passphrase: String::try_from("secret").unwrap(),
+            ipv4: Ipv4Record::Dhcp,
+        }
+    }
+
+    fn static_ipv4() -> Record {
+        Record {
+            ssid: String::try_from("lab").unwrap(),
+            passphrase: String::new(),
+            ipv4: Ipv4Record::Static(StaticIpv4 {
+                address: [192, 168, 1, 50],
+                prefix_len: 24,
+                gateway: Some([192, 168, 1, 1]),
+                dns_servers: Vec::from_slice(&[[192, 168, 1, 1], [8, 8, 8, 8]]).unwrap(),
+            }),
+        }
+    }
+
+    fn encode(record: &Record) -> [u8; RECORD_SIZE] {
+        // Like an erased sector after the record
+        let mut buf = [0xff; RECORD_SIZE];
+        record.encode(&mut buf);
+        buf
+    }
+
+    /// Returns a record with the `payload`, and a valid header and checksum.
+    fn sealed(payload: &[u8]) -> std::vec::Vec<u8> {
+        let mut record = std::vec::Vec::from(MAGIC);
+        record.push(VERSION);
+        record.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        record.extend_from_slice(payload);
+        let crc = crc32(&record);
+        record.extend_from_slice(&crc.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn layout() {
+        let mut buf = [0; RECORD_SIZE];
+        let len = dhcp().encode(&mut buf);
+        let payload = b"\x03lab\x06secret\x01";
+        assert_eq!(len, HEADER_LEN + payload.len() + CRC_LEN);
+        assert_eq!(&buf[..len], sealed(payload));
+    }
+
+    #[test]
+    fn round_trip() {
+        let none = Record {
+            ipv4: Ipv4Record::None,
+            ..dhcp()
+        };
+        let longest = Record {
+            ssid: String::try_from("s".repeat(MAX_SSID_LEN).as_str()).unwrap(),
+            passphrase: String::try_from("p".repeat(MAX_PASSPHRASE_LEN).as_str()).unwrap(),
+            ipv4: Ipv4Record::Static(StaticIpv4 {
+                address: [10, 0, 0, 2],
+                prefix_len: 32,
+                gateway: None,
+                dns_servers: Vec::from_slice(&[[1, 1, 1, 1]; MAX_DNS_SERVERS]).unwrap(),
+            }),
+        };
+        for record in [dhcp(), none, static_ipv4(), longest] {
+            assert_eq!(Record::decode(&encode(&record)), Ok(record));
+        }
+    }
+
+    #[test]
+    fn erased_sector_is_not_found() {
+        assert_eq!(
+            Record::decode(&[0xff; RECORD_SIZE]),
+            Err(RecordError::NotFound)
+        );
+        assert_eq!(Record::decode(&[]), Err(RecordError::NotFound));
+        assert_eq!(Record::decode(b"NCF"), Err(RecordError::NotFound));
+    }
+
+    #[test]
+    fn newer_version_is_unsupported() {
+        let mut record = encode(&dhcp());
+        record[4] = VERSION + 1;
+        assert_eq!(
+            Record::decode(&record),
+            Err(RecordError::UnsupportedVersion(VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn corrupted_payload_fails_the_checksum() {
+        let mut record = [0; RECORD_SIZE];
+        let len = static_ipv4().encode(&mut record);
+        for i in HEADER_LEN..len - CRC_LEN {
+            let mut corrupted = record;
+            corrupted[i] ^= 0x10;
+            assert_eq!(Record::decode(&corrupted), Err(RecordError::Checksum));
+        }
+    }
+
+    #[test]
+    fn corrupted_crc_fails_the_checksum() {
+        let mut record = [0; RECORD_SIZE];
+        let len = dhcp().encode(&mut record);
+        record[len - 1] ^= 0x80;
+        assert_eq!(Record::decode(&record), Err(RecordError::Checksum));
+    }
+
+    #[test]
+    fn truncated_record_is_invalid() {
+        let mut buf = [0; RECORD_SIZE];
+        let len = static_ipv4().encode(&mut buf);
+        assert!(Record::decode(&buf[..len]).is_ok());
+        for cut in HEADER_LEN..len {
+            assert_eq!(Record::decode(&buf[..cut]), Err(RecordError::Invalid));
+        }
+        // A length that does not fit in a sector
+        let mut record = encode(&dhcp());
+        record[5..HEADER_LEN].copy_from_slice(&(RECORD_SIZE as u16).to_le_bytes());
+        assert_eq!(Record::decode(&record), Err(RecordError::Invalid));
+    }
+
+    #[test]
+    fn truncated_payload_is_invalid() {
+        // Valid checksums over payloads that end too early
+        for payload in [
+            &b""[..],
+            b"\x03la",
+            b"\x03lab\x06secret",
+            b"\x03lab\x00\x02\xc0\xa8\x01",
+            b"\x03lab\x00\x02\xc0\xa8\x01\x32\x18\x01\xc0\xa8",
+            b"\x03lab\x00\x02\xc0\xa8\x01\x32\x18\x00\x02\x08\x08\x08\x08",
+        ] {
+            assert_eq!(Record::decode(&sealed(payload)), Err(RecordError::Invalid));
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_invalid() {
+        for payload in [
+            // Unknown IPv4 mode
+            &b"\x03lab\x00\x03"[..],
+            // Prefix longer than 32 bits
+            b"\x03lab\x00\x02\x0a\x00\x00\x02\x21\x00\x00",
+            // More DNS servers than a configuration holds
+            b"\x03lab\x00\x02\x0a\x00\x00\x02\x18\x00\x04\
+              \x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01",
+            // SSID that is not UTF-8
+            b"\x02\xc3\x28\x00\x01",
+            // SSID longer than MAX_SSID_LEN
+            b"\x21xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx\x00\x01",
+        ] {
+            assert_eq!(Record::decode(&sealed(payload)), Err(RecordError::Invalid));
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Scan for access points, without duplicates, from the strongest to the weakest.
+
+use core::fmt;
+
+use heapless::{String, Vec};
+
+/// Security of an access point, as far as the scan results tell.
+///
+/// The chip only reports the privacy bit of the beacon, so WEP, WPA and WPA2 networks
+/// are all [`Security::Protected`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    Open,
+    Protected,
+}
+
+/// An access point found by [`scan`](super::scan).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    /// The network name, empty for hidden networks. Bytes that are not valid UTF-8
+    /// are replaced by `?`.
+    pub ssid: String<32>,
+    pub bssid: [u8; 6],
+    pub channel: u8,
+    /// Received signal strength, in dBm.
+    pub rssi: i16,
+    pub security: Security,
+}
+
+impl AccessPoint {
+    /// Builds an access point from a raw SSID, which may not be valid UTF-8.
+    pub fn new(ssid: &[u8], bssid: [u8; 6], channel: u8, rssi: i16, security: Security) -> Self {
+        let mut text = String::new();
+        let mut rest = &ssid[..ssid.len().min(32)];
+        // Pushing cannot fail, `?` is not longer than the bytes it replaces.
+        while !rest.is_empty() {
+            match core::str::from_utf8(rest) {
+                Ok(valid) => {
+                    let _ = text.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    let _ = text.push_str(core::str::from_utf8(valid).unwrap_or_default());
+                    let _ = text.push('?');
+                    rest = &invalid[err.error_len().unwrap_or(invalid.len())..];
+                }
+            }
+        }
+        Self {
+            ssid: text,
+            bssid,
+            channel,
+            rssi,
+            security,
+        }
+    }
+
+    /// Returns the BSSID as `aa:bb:cc:dd:ee:ff`.
+    pub fn bssid(&self) -> impl fmt::Display + '_ {
+        MacAddress(&self.bssid)
+    }
+}
+
+struct MacAddress<'a>(&'a [u8; 6]);
+
+impl fmt::Display for MacAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Up to `N` access points, sorted by signal strength, the strongest first.
+///
+/// The chip reports an access point several times during a scan, once for every
+/// beacon or probe response it receives. [`ScanResults::insert`] keeps a single entry
+/// per BSSID, with the strongest signal, and when the list is full it only keeps
+/// the `N` strongest access points.
+///
+/// # Example
+///
+/// ```
+/// use utils::wifi::{AccessPoint, ScanResults, Security};
+///
+/// let ap = |last: u8, rssi: i16| {
+///     AccessPoint::new(b"lab", [2, 0, 0, 0, 0, last], 6, rssi, Security::Protected)
+/// };
+///
+/// let mut results = ScanResults::<2>::new();
+/// assert!(results.insert(ap(1, -70)));
+/// assert!(results.insert(ap(2, -50)));
+/// // The same access point again, with a stronger signal.
+/// assert!(!results.insert(ap(1, -60)));
+/// // Weaker than all the access points of the full list.
+/// assert!(!results.insert(ap(3, -80)));
+///
+/// let rssi: Vec<_> = results.iter().map(|ap| (ap.bssid[5], ap.rssi)).collect();
+/// assert_eq!(rssi, [(2, -50), (1, -60)]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScanResults<const N: usize> {
+    access_points: Vec<AccessPoint, N>,
+}
+
+impl<const N: usize> ScanResults<N> {
+    pub const fn new() -> Self {
+        Self {
+            access_points: Vec::new(),
+        }
+    }
+
+    /// Adds an access point to the list, or updates its signal strength if it is
+    /// already in the list.
+    ///
+    /// Returns `true` if the access point was not in the list and has been added.
+    pub fn insert(&mut self, access_point: AccessPoint) -> bool {
+        if let Some(index) = self
+            .access_points
+            .iter()
+            .position(|known| known.bssid == access_point.bssid)
+        {
+            // A weaker or equal signal leaves the entry where it is.
+            if access_point.rssi > self.access_points[index].rssi {
+                self.access_points.remove(index);
+                self.insert_sorted(access_point);
+            }
+            return false;
+        }
+
+        // With the same signal as the weakest one, the access point found first is kept.
+        if self.access_points.is_full() {
+            match self.access_points.last() {
+                Some(weakest) if weakest.rssi < access_point.rssi => {
+                    self.access_points.pop();
+                }
+                _ => return false,
+            }
+        }
+        self.insert_sorted(access_point);
+        true
+    }
+
+    fn insert_sorted(&mut self, access_point: AccessPoint) {
+        // Access points with the same signal stay in the order they were found.
+        let index = self
+            .access_points
+            .iter()
+            .position(|known| known.rssi < access_point.rssi)
+            .unwrap_or(self.access_points.len());
+        // Cannot fail, there is room for one more access point.
+        let _ = self.access_points.insert(index, access_point);
+    }
+
+    pub fn len(&self) -> usize {
+        self.access_points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.access_points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.access_points.clear();
+    }
+
+    /// Returns the access points, the strongest first.
+    pub fn as_slice(&self) -> &[AccessPoint] {
+        &self.access_points
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AccessPoint> {
+        self.access_points.iter()
+    }
+
+    pub fn into_inner(self) -> Vec<AccessPoint, N> {
+        self.access_points
+    }
+}
+
+#[cfg(feature = "rp2040")]
+pub use rp2040::{scan, ScanConfig};
+
+#[cfg(feature = "rp2040")]
+mod rp2040 {
+    use cyw43::{BssInfo, Control, ScanOptions};
+    use embassy_time::{with_timeout, Duration};
+
+    use super::{AccessPoint, ScanResults, Security};
+
+    /// The privacy bit of the capability field of a beacon.
+    const CAPABILITY_PRIVACY: u16 = 1 << 4;
+
+    impl From<&BssInfo> for AccessPoint {
+        fn from(bss: &BssInfo) -> Self {
+            let ssid_len = (bss.ssid_len as usize).min(bss.ssid.len());
+            let security = if bss.capability & CAPABILITY_PRIVACY != 0 {
+                Security::Protected
+            } else {
+                Security::Open
+            };
+            AccessPoint::new(
+                &bss.ssid[..ssid_len],
+                bss.bssid,
+                // The low byte of the channel specification is the channel number.
+                (bss.chanspec & 0xff) as u8,
+                bss.rssi,
+                security,
+            )
+        }
+    }
+
+    /// Options of [`scan`].
+    #[derive(Clone)]
+    pub struct ScanConfig {
+        /// Options given to the chip, like active or passive scanning.
+        pub options: ScanOptions,
+        /// Stop when this number of different access points has been added to the
+        /// results. With a full list, weaker access points are not counted.
+        pub stop_after: Option<usize>,
+        /// Stop after this time, even if the chip has not finished.
+        pub timeout: Option<Duration>,
+    }
+
+    impl Default for ScanConfig {
+        /// An active scan of all the channels, the default of the chip, stopped after
+        /// 10 seconds. Set `options.scan_type` to `ScanType::Passive` to only listen
+        /// for beacons.
+        fn default() -> Self {
+            Self {
+                options: ScanOptions::default(),
+                stop_after: None,
+                timeout: Some(Duration::from_secs(10)),
+            }
+        }
+    }
+
+    /// Scans for access points and returns the `N` strongest ones, without duplicates.
+    ///
+    /// The scan ends when the chip has scanned all the channels, when
+    /// [`ScanConfig::stop_after`] different access points have been found or when
+    /// [`ScanConfig::timeout`] elapses, whichever comes first.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = ScanConfig {
+    ///     stop_after: Some(10),
+    ///     ..Default::default()
+    /// };
+    /// let results = utils::wifi::scan::<16>(&mut control, config).await;
+    /// for ap in results.iter() {
+    ///     info!("{} {} channel {} {} dBm", ap.ssid, ap.bssid(), ap.channel, ap.rssi);
+    /// }
+    /// ```
+    pub async fn scan<const N: usize>(
+        control: &mut Control<'_>,
+        config: ScanConfig,
+    ) -> ScanResults<N> {
+        let mut results = ScanResults::new();
+        let mut found = 0;
+
+        let mut scanner = control.scan(config.options).await;
+        let collect = async {
+            while let Some(bss) = scanner.next().await {
+                if results.insert(AccessPoint::from(&bss)) {
+                    found += 1;
+                }
+                if config.stop_after.is_some_and(|limit| found >= limit) {
+                    break;
+                }
+            }
+        };
+
+        match config.timeout {
+            Some(timeout) => {
+                // The results found so far are returned when the scan times out.
+                let _ = with_timeout(timeout, collect).await;
+            }
+            None => collect.await,
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ap(last: u8, rssi: i16) -> AccessPoint {
+        AccessPoint::new(b"lab", [2, 0, 0, 0, 0, last], 6, rssi, Security::Protected)
+    }
+
+    fn entries<const N: usize>(results: &ScanResults<N>) -> Vec<(u8, i16), N> {
+        results.iter().map(|ap| (ap.bssid[5], ap.rssi)).collect()
+    }
+
+    #[test]
+    fn sorted_from_the_strongest() {
+        let mut results = ScanResults::<4>::new();
+        assert!(results.insert(ap(1, -70)));
+        assert!(results.insert(ap(2, -50)));
+        assert!(results.insert(ap(3, -90)));
+        assert!(results.insert(ap(4, -60)));
+        assert_eq!(entries(&results), [(2, -50), (4, -60), (1, -70), (3, -90)]);
+    }
+
+    #[test]
+    fn equal_signals_keep_the_order_they_were_found_in() {
+        let mut results = ScanResults::<4>::new();
+        results.insert(ap(1, -60));
+        results.insert(ap(2, -60));
+        results.insert(ap(3, -50));
+        results.insert(ap(4, -60));
+        assert_eq!(entries(&results), [(3, -50), (1, -60), (2, -60), (4, -60)]);
+    }
+
+    #[test]
+    fn stronger_duplicate_moves_up() {
+        let mut results = ScanResults::<4>::new();
+        results.insert(ap(1, -50));
+        results.insert(ap(2, -60));
+        results.insert(ap(3, -70));
+        assert!(!results.insert(ap(3, -55)));
+        assert_eq!(entries(&results), [(1, -50), (3, -55), (2, -60)]);
+    }
+
+    #[test]
+    fn weaker_duplicate_keeps_its_position() {
+        let mut results = ScanResults::<4>::new();
+        results.insert(ap(1, -50));
+        results.insert(ap(2, -60));
+        results.insert(ap(3, -60));
+        assert!(!results.insert(ap(2, -80)));
+        assert!(!results.insert(ap(2, -60)));
+        assert_eq!(entries(&results), [(1, -50), (2, -60), (3, -60)]);
+    }
+
+    #[test]
+    fn duplicate_in_a_full_list_is_updated() {
+        let mut results = ScanResults::<2>::new();
+        results.insert(ap(1, -50));
+        results.insert(ap(2, -60));
+        assert!(!results.insert(ap(2, -40)));
+        assert_eq!(entries(&results), [(2, -40), (1, -50)]);
+    }
+
+    #[test]
+    fn full_list_evicts_the_weakest() {
+        let mut results = ScanResults::<3>::new();
+        results.insert(ap(1, -50));
+        results.insert(ap(2, -70));
+        results.insert(ap(3, -60));
+        assert!(results.insert(ap(4, -65)));
+        assert_eq!(entries(&results), [(1, -50), (3, -60), (4, -65)]);
+        assert!(results.insert(ap(5, -40)));
+        assert_eq!(entries(&results), [(5, -40), (1, -50), (3, -60)]);
+    }
+
+    #[test]
+    fn full_list_rejects_weaker_and_equal_signals() {
+        let mut results = ScanResults::<2>::new();
+        results.insert(ap(1, -50));
+        results.insert(ap(2, -60));
+        assert!(!results.insert(ap(3, -70)));
+        assert!(!results.insert(ap(3, -60)));
+        assert_eq!(entries(&results), [(1, -50), (2, -60)]);
+    }
+
+    #[test]
+    fn clear_empties_the_list() {
+        let mut results = ScanResults::<2>::new();
+        results.insert(ap(1, -50));
+        assert_eq!(results.len(), 1);
+        results.clear();
+        assert!(results.is_empty());
+        assert!(results.insert(ap(1, -50)));
+    }
+
+    fn ssid(raw: &[u8]) -> String<32> {
+        AccessPoint::new(raw, [0; 6], 1, -50, Security::Open).ssid
+    }
+
+    #[test]
+    fn valid_ssid_is_kept() {
+        assert_eq!(ssid(b"lab"), "lab");
+        assert_eq!(ssid("café".as_bytes()), "café");
+        assert_eq!(ssid(b""), "");
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        assert_eq!(ssid(b"ab\xffcd"), "ab?cd");
+        assert_eq!(ssid(b"\xff\xfe"), "??");
+        // A character cut at the end of the SSID.
+        assert_eq!(ssid(b"ab\xe2\x82"), "ab?");
+        // An invalid sequence followed by valid characters.
+        assert_eq!(ssid(b"\xe2\x28\xa1x"), "?(?x");
+    }
+
+    #[test]
+    fn long_ssid_is_truncated() {
+        assert_eq!(ssid(&[b'a'; 40]).as_bytes(), [b'a'; 32]);
+        // The 32nd byte starts a two byte character.
+        let mut raw = [b'a'; 33];
+        raw[31..].copy_from_slice("é".as_bytes());
+        let mut expected = [b'a'; 32];
+        expected[31] = b'?';
+        assert_eq!(ssid(&raw).as_bytes(), expected);
+    }
+
+    #[test]
+    fn bssid_is_formatted() {
+        extern crate std;
+        use std::string::ToString;
+
+        let ap = AccessPoint::new(b"", [0x02, 0xab, 0, 0x10, 0xff, 1], 1, -50, Security::Open);
+        assert_eq!(ap.bssid().to_string(), "02:ab:00:10:ff:01");
+    }
+}